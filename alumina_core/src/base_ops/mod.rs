@@ -8,13 +8,14 @@ use crate::errors::{ExecutionError, GradientError, ShapePropError};
 use crate::graph::NodeID;
 use crate::{
 	errors::OpBuildError,
-	exec::ExecutionContext,
+	exec::{execute_op, ExecutionContext},
 	grad::GradientContext,
 	graph::{merge_node_graphs, Graph, Node, Op},
 	shape_prop::ShapePropContext,
 };
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
+use ndarray::{ArcArray, IxDyn};
 use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
@@ -89,6 +90,63 @@ pub trait OpSpecification: Any + Sized {
 
 		Ok(graph.new_op(Arc::new(instance)).set_name(name))
 	}
+
+	/// Like `build()`, but leaves the `Op` with the default name rather than generating a unique one.
+	///
+	/// Generating unique names requires formatting and looking up names in the graph, which can dominate the cost of
+	/// building graphs with many `Op`s.
+	fn build_unnamed(self) -> Result<Op, OpBuildError> {
+		let graph = merge_node_graphs(self.inputs().into_iter().chain(self.outputs()));
+
+		let instance = self.build_instance()?;
+
+		Ok(graph.new_op(Arc::new(instance)))
+	}
+
+	/// Like `build()`, but if the `Op` has no inputs, i.e. its outputs depend only on its own configuration, the outputs
+	/// are evaluated immediately and stored as `Node` values.
+	///
+	/// As `Node`s with values are treated as inputs when executing, the `Op` is not re-executed on each `calc()`. If
+	/// the `Op` has inputs this is the same as `build()`.
+	fn build_constant(self) -> Result<Op, OpBuildError> {
+		let op = self.build()?;
+
+		if op.instance().inputs().is_empty() {
+			let values = execute_op(&op, IndexMap::<Node, ArcArray<f32, IxDyn>>::new())
+				.map_err(|err| format!("Constant Op `{}` could not be evaluated at build time: {}", op, err))?;
+			for (node, value) in values {
+				node.set_value(value);
+			}
+		}
+
+		Ok(op)
+	}
+
+	/// Like `build()`, but marks the selected inputs so that no gradient flows back to them through this `Op`.
+	///
+	/// This is useful for inputs which are logically non-differentiable, such as a condition or mask, and saves each
+	/// `Op` from having to special case them. See `Op::block_gradient(..)`.
+	fn build_gradient_blocked<I, T>(self, blocked: T) -> Result<Op, OpBuildError>
+	where
+		I: Into<Node>,
+		T: IntoIterator<Item = I>,
+	{
+		let inputs = self.inputs();
+		let blocked: Vec<Node> = blocked.into_iter().map(Into::into).collect();
+		if let Some(node) = blocked.iter().find(|node| !inputs.contains(*node)) {
+			return Err(format!(
+				"Node `{}` can't have its gradient blocked as it is not an input to the Op",
+				node
+			)
+			.into());
+		}
+
+		let op = self.build()?;
+		for node in blocked {
+			op.block_gradient(node);
+		}
+		Ok(op)
+	}
 }
 
 /// An OpInstance should not behave as though it contains internal state, i.e. state as as an optimisation only.
@@ -108,6 +166,22 @@ pub trait OpInstance: fmt::Debug + Any + Send + Sync {
 	/// Returns a list of `Node`s this `Op` may need to write to when executed
 	fn outputs(&self) -> IndexSet<NodeID>;
 
+	/// Returns the inputs in the order of the arguments they were declared as, for when argument order matters.
+	///
+	/// Unlike `inputs()` a `Node` used for several arguments is repeated. The default implementation returns
+	/// `inputs()`, so the insertion order of `inputs()` must be the argument order.
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		self.inputs().into_iter().collect()
+	}
+
+	/// Returns the outputs in the order of the arguments they were declared as, for when argument order matters.
+	///
+	/// Unlike `outputs()` a `Node` used for several arguments is repeated. The default implementation returns
+	/// `outputs()`, so the insertion order of `outputs()` must be the argument order.
+	fn ordered_outputs(&self) -> Vec<NodeID> {
+		self.outputs().into_iter().collect()
+	}
+
 	/// Test
 	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError>;
 
@@ -116,6 +116,47 @@ impl GradError {
 	}
 }
 
+/// Returned from `integrated_gradients(..)`.
+#[derive(Debug, Fail)]
+pub enum AttributionError {
+	/// Returned when constructing the gradient of the output w.r.t. the input fails.
+	#[fail(
+		display = "AttributionError::Grad Constructing the gradient returned error: {}",
+		error
+	)]
+	Grad { error: GradError },
+
+	/// Returned when executing the gradient at one of the interpolated inputs fails.
+	#[fail(display = "AttributionError::Exec Executing the gradient returned error: {}", error)]
+	Exec { error: ExecError },
+
+	/// Returned when the input node has no value to attribute.
+	#[fail(
+		display = "AttributionError::InputWithoutValue The input node ({}) has no value",
+		node
+	)]
+	InputWithoutValue { node: Node },
+
+	/// Returned when the baseline can't be broadcast to the shape of the input value.
+	#[fail(
+		display = "AttributionError::IncompatibleBaseline The baseline shape {:?} can't be broadcast to the input shape {:?}",
+		baseline, input
+	)]
+	IncompatibleBaseline { baseline: Vec<usize>, input: Vec<usize> },
+}
+
+impl From<GradError> for AttributionError {
+	fn from(error: GradError) -> Self {
+		AttributionError::Grad { error }
+	}
+}
+
+impl From<ExecError> for AttributionError {
+	fn from(error: ExecError) -> Self {
+		AttributionError::Exec { error }
+	}
+}
+
 // impl ::std::fmt::Display for GradError {
 // 	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
 // 		write!(f, "The following ops errored when producing their gradient: [\n")?;
@@ -273,6 +314,25 @@ pub enum ExecError {
 		node
 	)]
 	SubGraphNotExecutable { node: Node },
+
+	/// Returned when accumulation checking is enabled and an `Op` overwrites, rather than accumulates into, an output
+	/// which already held a value written by another `Op`.
+	#[fail(
+		display = "ExecError::AccumulationCheck The op ({}) overwrote rather than accumulated into the node ({}), which already held a value written by another op.",
+		op, node
+	)]
+	AccumulationCheck { op: Op, node: Node },
+
+	/// Returned from `Node::calc()` when nodes required to calculate the output have no value and are not written to
+	/// by any `Op`s.
+	#[fail(
+		display = "ExecError::InputsWithoutValues Calculating node ({}) requires a value to be set for the following input nodes: {}",
+		output, nodes
+	)]
+	InputsWithoutValues {
+		output: Node,
+		nodes: IterDisplay<Node, Vec<Node>>,
+	},
 }
 
 /// Fail type returned when extraction of an execution subgraph
@@ -313,3 +373,30 @@ pub struct CyclicGraphError {
 	pub unsorted_nodes: IterDisplay<Node, Vec<Node>>,
 	pub unsorted_ops: IterDisplay<Op, Vec<Op>>,
 }
+
+/// A single problem found by `Graph::validate()`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Fail)]
+pub enum ValidationProblem {
+	/// The graph contains a cycle, shape propagation is not checked.
+	#[fail(display = "ValidationProblem::Cycle {}", error)]
+	Cycle { error: CyclicGraphError },
+
+	/// A node has no parent ops and no value, and so would have to be supplied as an input.
+	#[fail(
+		display = "ValidationProblem::UnsetInput The node ({}) has no parent ops and no value, so must be supplied as an input.",
+		node
+	)]
+	UnsetInput { node: Node },
+
+	/// An op failed shape propagation. Ops downstream of the failed op are not checked.
+	#[fail(display = "ValidationProblem::Shape {}", error)]
+	Shape { error: ShapesError },
+}
+
+/// `Fail` type returned from `Graph::validate()` containing every problem found.
+#[derive(Debug, Fail)]
+#[fail(display = "Graph validation found the following problems:\n{}", problems)]
+pub struct ValidationError {
+	pub problems: IterDisplay<ValidationProblem, Vec<ValidationProblem>>,
+}
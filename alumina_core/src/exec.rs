@@ -2,20 +2,43 @@
 use crate::{
 	errors::ExecError,
 	graph::{Node, NodeID, Op, OpID},
-	shape_prop::cached_shapes_inner,
+	shape_prop::{cached_shapes_inner, shapes_inner},
 	subgraph::{execution_subgraph, SubGraph},
 };
-use indexmap::{IndexMap, IndexSet};
+use indexmap::{indexset, IndexMap, IndexSet};
 use lru::LruCache;
-use ndarray::{ArcArray, ArrayViewD, ArrayViewMutD, Dimension, IxDyn};
+use ndarray::{ArcArray, ArrayD, ArrayViewD, ArrayViewMutD, Dimension, IxDyn};
+use rayon::prelude::*;
 use std::time::Instant;
 use std::{
 	borrow::Borrow,
 	cell::{RefCell, UnsafeCell},
 	hash::Hash,
+	sync::atomic::{AtomicUsize, Ordering},
 };
 use sysinfo::{ProcessorExt, RefreshKind, SystemExt};
 
+/// The default for `par_threshold()`.
+pub const DEFAULT_PAR_THRESHOLD: usize = 4096;
+
+static PAR_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_PAR_THRESHOLD);
+
+/// The number of elements below which Ops are executed serially on the calling thread rather than on the rayon thread
+/// pool, where the overhead of splitting up the work would dominate for small arrays.
+///
+/// This is used by executions which don't set `ExecutionPlan::par_threshold(..)`.
+///
+/// Default: `DEFAULT_PAR_THRESHOLD`
+pub fn par_threshold() -> usize {
+	PAR_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Sets the global `par_threshold()`. A threshold of 0 always executes in parallel, and `usize::MAX` never does.
+pub fn set_par_threshold(threshold: usize) {
+	PAR_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+#[derive(Clone)]
 enum DataState<T> {
 	Unallocated {
 		writers_remaining: usize,
@@ -67,6 +90,17 @@ impl<T> DataState<T> {
 		}
 	}
 
+	fn readers_remaining_mut(&mut self) -> Option<&mut usize> {
+		match self {
+			DataState::Unallocated { readers_remaining, .. }
+			| DataState::Writable { readers_remaining, .. }
+			| DataState::Readable { readers_remaining, .. }
+			| DataState::Input { readers_remaining, .. }
+			| DataState::BroadcastInput { readers_remaining, .. } => Some(readers_remaining),
+			DataState::Deallocated => None,
+		}
+	}
+
 	fn deallocatable(&self) -> bool {
 		match self {
 			DataState::Unallocated {
@@ -104,6 +138,11 @@ pub struct ExecutionContext {
 	current_op: Option<Op>,
 	current_inputs: IndexSet<Node>,
 	current_outputs: IndexSet<Node>,
+
+	pool: Option<RefCell<BufferPool>>,
+	par_threshold: usize,
+	rng_state: Option<RefCell<RngState>>,
+	execution_seed: u64,
 }
 
 impl ExecutionContext {
@@ -120,9 +159,64 @@ impl ExecutionContext {
 			current_op: None,
 			current_inputs: IndexSet::new(),
 			current_outputs: IndexSet::new(),
+
+			pool: None,
+			par_threshold: par_threshold(),
+			rng_state: None,
+			execution_seed: rand::random(),
 		}
 	}
 
+	fn pool(mut self, pool: Option<RefCell<BufferPool>>) -> Self {
+		self.pool = pool;
+		self
+	}
+
+	fn par_threshold(mut self, par_threshold: usize) -> Self {
+		self.par_threshold = par_threshold;
+		self
+	}
+
+	fn rng_state(mut self, rng_state: Option<RefCell<RngState>>) -> Self {
+		self.rng_state = rng_state;
+		self
+	}
+
+	fn execution_seed(mut self, execution_seed: u64) -> Self {
+		self.execution_seed = execution_seed;
+		self
+	}
+
+	/// Returns the seed an Op should draw its random values from during this execution, given the seed it was built
+	/// with.
+	///
+	/// Without an `RngState` (see `ExecutionPlan::rng_state(..)`) this is `seed` itself, so every execution draws the
+	/// same values. With one, the result also depends on the number of earlier executions which drew from `seed`, and
+	/// is the same for every Op built with `seed` within this execution, e.g. an Op and its backward Op.
+	pub fn seed(&self, seed: u64) -> u64 {
+		match self.rng_state {
+			Some(ref rng_state) => rng_state.borrow_mut().draw(seed),
+			None => seed,
+		}
+	}
+
+	/// As `seed(..)`, except that without an `RngState` a new seed is drawn for each execution, so every execution
+	/// draws different values.
+	///
+	/// Within an execution the result is still the same for every Op built with `seed`.
+	pub fn fresh_seed(&self, seed: u64) -> u64 {
+		match self.rng_state {
+			Some(ref rng_state) => rng_state.borrow_mut().draw(seed),
+			None => splitmix64(seed ^ splitmix64(self.execution_seed)),
+		}
+	}
+
+	/// Returns true if an Op processing `len` elements should split the work over the rayon thread pool, or false if
+	/// it should run serially on the calling thread. See `par_threshold()`.
+	pub fn is_parallel(&self, len: usize) -> bool {
+		len >= self.par_threshold
+	}
+
 	/// Returns the `OpInner` to an `OpInstance` inside its `execute()` method
 	pub fn current_op(&self) -> &Op {
 		self.current_op
@@ -233,6 +327,214 @@ impl ExecutionContext {
 		Ok((self, !output_required)) // skip the op if no outputs are required
 	}
 
+	/// Executes the current op, checking that it accumulates into, rather than overwrites, any output which already
+	/// holds a value written by an earlier op.
+	///
+	/// If there are such outputs the op is first executed with them zeroed to obtain its contribution, then the outputs
+	/// are restored and the op is executed again normally. Inputs are prevented from being taken during the first
+	/// execution so that they are still available for the second.
+	fn execute_with_accumulation_check(&self, op: &Op) -> Result<(), ExecError> {
+		// These references must not escape the current method.
+		let value_map = unsafe { &mut *self.value_map.get() };
+		let borrows = unsafe { &mut *self.borrows.get() };
+
+		let accumulating: Vec<Node> = self
+			.current_outputs
+			.iter()
+			.filter(|node| self.is_required_output(node) && matches!(value_map[*node], DataState::Writable { .. }))
+			.cloned()
+			.collect();
+
+		let execute = || {
+			op.instance().execute(self).map_err(|e| ExecError::Op {
+				error: e,
+				op: op.clone(),
+			})
+		};
+
+		if accumulating.is_empty() {
+			return execute();
+		}
+
+		let snapshot: Vec<(Node, DataState<f32>)> = self
+			.current_outputs
+			.iter()
+			.map(|node| (node.clone(), value_map[node].clone()))
+			.collect();
+
+		for node in &accumulating {
+			if let DataState::Writable { ref mut data, .. } = value_map[node] {
+				*data = ArcArray::zeros(data.shape());
+			}
+		}
+		for node in &self.current_inputs {
+			if let Some(readers_remaining) = value_map[node].readers_remaining_mut() {
+				*readers_remaining += 1;
+			}
+		}
+
+		execute()?;
+
+		let contributions: Vec<ArcArray<f32, IxDyn>> = accumulating
+			.iter()
+			.map(|node| match value_map[node] {
+				DataState::Writable { ref data, .. } => data.clone(),
+				_ => unreachable!(),
+			})
+			.collect();
+
+		for (node, value) in snapshot {
+			value_map[&node] = value;
+		}
+		for node in &self.current_inputs {
+			if let Some(readers_remaining) = value_map[node].readers_remaining_mut() {
+				*readers_remaining -= 1;
+			}
+		}
+		borrows.clear();
+
+		let priors: Vec<ArcArray<f32, IxDyn>> = accumulating
+			.iter()
+			.map(|node| match value_map[node] {
+				DataState::Writable { ref data, .. } => data.clone(),
+				_ => unreachable!(),
+			})
+			.collect();
+
+		execute()?;
+
+		for ((node, prior), contribution) in accumulating.iter().zip(priors).zip(contributions) {
+			let result = match value_map[node] {
+				DataState::Writable { ref data, .. } => data,
+				_ => unreachable!(),
+			};
+			let accumulated = result
+				.iter()
+				.zip(prior.iter())
+				.zip(contribution.iter())
+				.all(|((&r, &p), &c)| {
+					// exact equality is needed for infinite sums, where the difference is NaN
+					r == p + c || (r - (p + c)).abs() <= 1e-4 * (p.abs() + c.abs()) + f32::MIN_POSITIVE
+				});
+			if !accumulated {
+				return Err(ExecError::AccumulationCheck {
+					op: op.clone(),
+					node: node.clone(),
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Executes a group of `Op`s which have no data dependencies between them, running them concurrently.
+	///
+	/// Each `Op` is given its own `ExecutionContext` holding only the nodes it reads and writes. Nodes read by more
+	/// than one `Op` of the group are shared between the contexts, all other nodes are moved in and moved back out
+	/// afterwards. If any `Op` fails the error of the first failing `Op` in the group is returned.
+	fn execute_wave(mut self, ops: &[Op]) -> Result<Self, ExecError> {
+		self.finalise_current_op();
+
+		if let [op] = ops {
+			let (mut ctx, skip) = self.set_next_op(op)?;
+			if !skip {
+				op.instance().execute(&ctx).map_err(|e| ExecError::Op {
+					error: e,
+					op: op.clone(),
+				})?;
+			}
+			ctx.finalise_current_op();
+			return Ok(ctx);
+		}
+
+		let mut shared: IndexMap<Node, usize> = IndexMap::new();
+		for op in ops {
+			for node in op.parent_nodes() {
+				*shared.entry(node).or_insert(0) += 1;
+			}
+		}
+		shared.retain(|_, count| *count > 1);
+
+		// Upgrade shared inputs once here, rather than once per copy
+		for node in shared.keys() {
+			let upgrade = matches!(
+				self.value_map.get_mut().get(node),
+				Some(DataState::Writable {
+					writers_remaining: 0,
+					..
+				}) | Some(DataState::BroadcastInput { .. })
+			);
+			if upgrade {
+				unsafe {
+					self.allocate_readable_or_input(node);
+				}
+			}
+		}
+
+		let mut contexts = Vec::with_capacity(ops.len());
+		for op in ops {
+			let value_map = self.value_map.get_mut();
+			let mut op_value_map = IndexMap::new();
+			let mut op_shape_map = IndexMap::new();
+			for node in op.parent_nodes().into_iter().chain(op.child_nodes()) {
+				if op_value_map.contains_key(&node) {
+					continue;
+				}
+				if let Some(value) = value_map.get_mut(&node) {
+					let value = if shared.contains_key(&node) {
+						value.clone()
+					} else {
+						::std::mem::replace(value, DataState::Deallocated)
+					};
+					op_shape_map.insert(node.id(), self.shape_map[&node.id()].clone());
+					op_value_map.insert(node, value);
+				}
+			}
+			contexts.push(
+				ExecutionContext::new(op_value_map, op_shape_map)
+					.par_threshold(self.par_threshold)
+					.execution_seed(self.execution_seed),
+			);
+		}
+
+		let results: Vec<Result<ExecutionContext, ExecError>> = contexts
+			.into_par_iter()
+			.zip(ops.par_iter())
+			.map(|(ctx, op)| {
+				let (mut ctx, skip) = ctx.set_next_op(op)?;
+				if !skip {
+					op.instance().execute(&ctx).map_err(|e| ExecError::Op {
+						error: e,
+						op: op.clone(),
+					})?;
+				}
+				ctx.finalise_current_op();
+				Ok(ctx)
+			})
+			.collect();
+
+		let value_map = self.value_map.get_mut();
+		for result in results {
+			for (node, value) in result?.value_map.into_inner() {
+				if !shared.contains_key(&node) {
+					value_map[&node] = value;
+				}
+			}
+		}
+
+		for (node, count) in &shared {
+			let value = &mut value_map[node];
+			if let Some(readers_remaining) = value.readers_remaining_mut() {
+				*readers_remaining -= count;
+			}
+			if value.deallocatable() {
+				*value = DataState::Deallocated;
+			}
+		}
+
+		Ok(self)
+	}
+
 	fn finalise_current_op(&mut self) {
 		if self.current_op.is_some() {
 			// This reference must not escape the current method.
@@ -249,7 +551,7 @@ impl ExecutionContext {
 					DataState::Deallocated => {}
 				}
 				if value.deallocatable() {
-					::std::mem::swap(value, &mut DataState::Deallocated);
+					deallocate(&self.pool, value);
 				}
 			}
 
@@ -264,7 +566,7 @@ impl ExecutionContext {
 						| DataState::BroadcastInput { .. } => {}
 					}
 					if value.deallocatable() {
-						::std::mem::swap(value, &mut DataState::Deallocated);
+						deallocate(&self.pool, value);
 					}
 				}
 			}
@@ -453,6 +755,9 @@ impl ExecutionContext {
 	/// Must check borrows to ensure the node hasnt been borrowed in any way before calling to avoid creating a
 	/// duplicate mutable reference.
 	///
+	/// Newly allocated arrays are zeroed, as Ops accumulate (`+=`) into their outputs. Arrays are only reused between
+	/// executions if a `BufferPool` is supplied.
+	///
 	/// # Panics
 	/// if data has already been deallocated
 	/// if data is already readable
@@ -465,7 +770,10 @@ impl ExecutionContext {
 			x @ &mut DataState::Unallocated { .. } => {
 				// upgrade to writable
 
-				let data = ArcArray::<f32, IxDyn>::zeros(self.shape_map[node].slice());
+				let data = match self.pool {
+					Some(ref pool) => pool.borrow_mut().zeros(&self.shape_map[node]),
+					None => ArcArray::<f32, IxDyn>::zeros(self.shape_map[node].slice()),
+				};
 
 				let mut new_value = DataState::Writable {
 					writers_remaining: x.writers_remaining(),
@@ -751,6 +1059,77 @@ impl ExecutionContext {
 	}
 }
 
+/// The random number state of Ops with randomness, such as dropout, allowing them to draw different values on each
+/// execution while remaining reproducible.
+///
+/// Pass the same state to `ExecutionPlan::rng_state(..)` on each iteration of a training loop. Each stream of random
+/// values is identified by the seed an Op was built with, which it shares with its backward Op so that both draw the
+/// same values within an execution, and each stream drawn from is stepped forward once the execution succeeds.
+///
+/// To checkpoint the state clone it, or save `steps()` and restore them with `set_step(..)`. Resuming from the
+/// checkpoint reproduces the same sequence of random values.
+#[derive(Clone, Debug, Default)]
+pub struct RngState {
+	steps: IndexMap<u64, u64>,
+	drawn: IndexSet<u64>,
+}
+
+impl RngState {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The number of executions which have drawn from the stream identified by `seed`.
+	pub fn step(&self, seed: u64) -> u64 {
+		self.steps.get(&seed).cloned().unwrap_or(0)
+	}
+
+	/// Sets the number of executions which have drawn from the stream identified by `seed`.
+	pub fn set_step(&mut self, seed: u64, step: u64) {
+		self.steps.insert(seed, step);
+	}
+
+	/// Returns the `(seed, step)` pairs of every stream which has been drawn from.
+	pub fn steps(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+		self.steps.iter().map(|(&seed, &step)| (seed, step))
+	}
+
+	/// Returns the seed for the current step of the stream, which is `seed` itself for the first step.
+	fn draw(&mut self, seed: u64) -> u64 {
+		self.drawn.insert(seed);
+		match self.step(seed) {
+			0 => seed,
+			step => splitmix64(seed ^ splitmix64(step)),
+		}
+	}
+
+	/// Steps forward each stream drawn from since the last call.
+	fn advance(&mut self) {
+		for seed in self.drawn.drain(..) {
+			*self.steps.entry(seed).or_insert(0) += 1;
+		}
+	}
+}
+
+/// A bijective mixing function, used to derive well separated seeds from nearby inputs.
+fn splitmix64(x: u64) -> u64 {
+	let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	z ^ (z >> 31)
+}
+
+/// Marks a value as deallocated, returning arrays allocated during execution to the pool if there is one.
+fn deallocate(pool: &Option<RefCell<BufferPool>>, value: &mut DataState<f32>) {
+	if let DataState::Writable { data, .. } | DataState::Readable { data, .. } =
+		::std::mem::replace(value, DataState::Deallocated)
+	{
+		if let Some(pool) = pool {
+			pool.borrow_mut().recycle(data);
+		}
+	}
+}
+
 fn form_output_map(
 	outputs: IndexSet<Node>,
 	mut value_map: IndexMap<Node, DataState<f32>>,
@@ -794,12 +1173,86 @@ pub struct OpPerf {
 	pub cumulative_time: f32,
 }
 
+/// Holds arrays freed during execution so that later executions can reuse them rather than allocating new ones.
+///
+/// Pass the same pool to `ExecutionPlan::buffer_pool(..)` on each iteration of a training loop. Arrays for
+/// intermediate nodes are returned to the pool as soon as they are no longer required, and reused arrays are zeroed
+/// before being handed to an `Op`. Arrays returned as outputs, such as gradients, can be handed back with `recycle()`
+/// once they have been used.
+#[derive(Default, Debug)]
+pub struct BufferPool {
+	buffers: IndexMap<IxDyn, Vec<ArrayD<f32>>>,
+	allocations: usize,
+	reuses: usize,
+}
+
+impl BufferPool {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds an array to the pool for reuse.
+	///
+	/// Arrays which share their data, e.g. a clone is still held elsewhere, or don't have a standard layout are
+	/// dropped instead.
+	pub fn recycle(&mut self, array: ArcArray<f32, IxDyn>) {
+		if let Ok(array) = array.try_into_owned_nocopy() {
+			if array.is_standard_layout() {
+				self.buffers.entry(array.raw_dim()).or_default().push(array);
+			}
+		}
+	}
+
+	/// The number of arrays allocated by executions using this pool, because no array of the right shape was held.
+	pub fn allocations(&self) -> usize {
+		self.allocations
+	}
+
+	/// The number of arrays taken from this pool by executions, rather than allocated.
+	pub fn reuses(&self) -> usize {
+		self.reuses
+	}
+
+	/// The number of arrays currently held by the pool.
+	pub fn len(&self) -> usize {
+		self.buffers.values().map(Vec::len).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Drops all arrays held by the pool.
+	pub fn clear(&mut self) {
+		self.buffers.clear();
+	}
+
+	fn zeros(&mut self, shape: &IxDyn) -> ArcArray<f32, IxDyn> {
+		match self.buffers.get_mut(shape).and_then(Vec::pop) {
+			Some(mut array) => {
+				self.reuses += 1;
+				array.fill(0.0);
+				array.into_shared()
+			}
+			None => {
+				self.allocations += 1;
+				ArcArray::zeros(shape.slice())
+			}
+		}
+	}
+}
+
 pub struct ExecutionPlan<'a> {
 	inputs: IndexMap<Node, ArcArray<f32, IxDyn>>,
 	outputs: IndexSet<Node>,
 	ignore_node_values: bool,
 	subgraph: Option<&'a SubGraph>,
 	perf_records: Option<&'a mut IndexMap<Op, OpPerf>>,
+	buffer_pool: Option<&'a mut BufferPool>,
+	rng_state: Option<&'a mut RngState>,
+	check_accumulation: bool,
+	parallel: bool,
+	par_threshold: Option<usize>,
 }
 
 impl<'a> ExecutionPlan<'a> {
@@ -816,6 +1269,11 @@ impl<'a> ExecutionPlan<'a> {
 			ignore_node_values: false,
 			subgraph: None,
 			perf_records: None,
+			buffer_pool: None,
+			rng_state: None,
+			check_accumulation: false,
+			parallel: false,
+			par_threshold: None,
 		}
 	}
 	/// Determines whether node values are ignored during execution.
@@ -842,6 +1300,61 @@ impl<'a> ExecutionPlan<'a> {
 		self
 	}
 
+	/// If Some, arrays are taken from the pool where possible, and arrays no longer required during execution are
+	/// returned to it. See `BufferPool`.
+	///
+	/// If execution fails any arrays held by the pool are dropped.
+	///
+	/// Default: None
+	pub fn buffer_pool(mut self, buffer_pool: Option<&'a mut BufferPool>) -> Self {
+		self.buffer_pool = buffer_pool;
+		self
+	}
+
+	/// If Some, Ops with randomness draw different values on each execution, and the state is advanced once the
+	/// execution succeeds. See `RngState`.
+	///
+	/// If execution fails the state is left unchanged.
+	///
+	/// Default: None
+	pub fn rng_state(mut self, rng_state: Option<&'a mut RngState>) -> Self {
+		self.rng_state = rng_state;
+		self
+	}
+
+	/// If true, Ops which write to a node that already holds a value from an earlier Op are checked to ensure they
+	/// accumulate (`+=`) into it rather than overwrite it, returning an `AccumulationCheck` error otherwise.
+	///
+	/// Nodes written by multiple Ops are common in gradient graphs, where a node which feeds several Ops receives a
+	/// gradient contribution from each. Checked Ops are executed twice, so this is intended for debugging and tests.
+	///
+	/// Default: false
+	pub fn check_accumulation(mut self, check_accumulation: bool) -> Self {
+		self.check_accumulation = check_accumulation;
+		self
+	}
+
+	/// If true, Ops with no data dependency between them are executed concurrently on the rayon thread pool.
+	///
+	/// Ops are grouped into waves, where each Op is placed in the earliest wave after every Op it must follow in the
+	/// subgraph order, and the Ops within each wave are run in parallel. This is ignored if `perf_records`,
+	/// `buffer_pool` or `rng_state` is Some, or `check_accumulation` is true, in which case Ops are executed one at a
+	/// time.
+	///
+	/// Default: false
+	pub fn parallel(mut self, parallel: bool) -> Self {
+		self.parallel = parallel;
+		self
+	}
+
+	/// If Some, overrides the global `par_threshold()` for this execution.
+	///
+	/// Default: None
+	pub fn par_threshold(mut self, par_threshold: Option<usize>) -> Self {
+		self.par_threshold = par_threshold;
+		self
+	}
+
 	/// Execution with a custom subgraph
 	///
 	/// Ops are executed in the order contained in the subgraph, if this order is not topological
@@ -851,6 +1364,8 @@ impl<'a> ExecutionPlan<'a> {
 	pub fn execute(&mut self) -> Result<IndexMap<Node, ArcArray<f32, IxDyn>>, ExecError> {
 		let perf_records = &mut self.perf_records;
 		let subgraph = self.subgraph.as_ref();
+		let check_accumulation = self.check_accumulation;
+		let par_threshold = self.par_threshold.unwrap_or_else(par_threshold);
 
 		let mut system = sysinfo::System::new_with_specifics(RefreshKind::new().with_cpu());
 
@@ -930,53 +1445,222 @@ impl<'a> ExecutionPlan<'a> {
 
 		// let mut perf_map = OP_PERF_DATA.lock().unwrap();
 
+		// Move the pooled arrays into the context for the duration of the execution
+		let pool = self.buffer_pool.as_mut().map(|pool| {
+			RefCell::new(BufferPool {
+				buffers: ::std::mem::take(&mut pool.buffers),
+				..BufferPool::default()
+			})
+		});
+
+		let rng_state = self.rng_state.as_deref().cloned().map(RefCell::new);
+
 		// Fold over ops executing those that arent skipped. No permanent references handed out
-		let mut context = subgraph
-			.ops
-			.iter()
-			.fold(Ok(ExecutionContext::new(value_map, shape_map)), |result, op| {
-				result.and_then(|ctx| {
-					let (ctx, skip) = ctx.set_next_op(op)?;
-
-					if !skip {
-						//assert!(config.perf_records.as_mut().and_then(|pr|pr.get_mut(op)).is_some());
-						if let Some(record) = perf_records.as_mut().and_then(|pr| pr.get_mut(op)) {
-							system.refresh_cpu();
-							//let _ = system.get_processors().iter().map(|p|p.get_cpu_usage()).sum::<f32>();
-							//let _ = system.get_global_processor_info().get_cpu_usage() as f32;
-							let start = Instant::now();
-							op.instance().execute(&ctx).map_err(|e| ExecError::Op {
-								error: e,
-								op: op.clone(),
-							})?;
-							system.refresh_cpu();
-							record.invocation_count += 1;
-							record.cumulative_usage += system.processors().iter().map(|p| p.cpu_usage()).sum::<f32>()
-								/ system.processors().len() as f32; //system.get_global_processor_info().get_cpu_usage() as f32;
-							record.cumulative_time += start.elapsed().as_micros() as f32;
-						} else {
-							op.instance().execute(&ctx).map_err(|e| ExecError::Op {
-								error: e,
-								op: op.clone(),
-							})?;
+		let mut context = if self.parallel
+			&& perf_records.is_none()
+			&& pool.is_none()
+			&& rng_state.is_none()
+			&& !check_accumulation
+		{
+			parallel_waves(&subgraph.ops).iter().try_fold(
+				ExecutionContext::new(value_map, shape_map).par_threshold(par_threshold),
+				|ctx, wave| ctx.execute_wave(wave),
+			)?
+		} else {
+			subgraph.ops.iter().fold(
+				Ok(ExecutionContext::new(value_map, shape_map)
+					.pool(pool)
+					.par_threshold(par_threshold)
+					.rng_state(rng_state)),
+				|result, op| {
+					result.and_then(|ctx| {
+						let (ctx, skip) = ctx.set_next_op(op)?;
+
+						if !skip && check_accumulation {
+							ctx.execute_with_accumulation_check(op)?;
+						} else if !skip {
+							//assert!(config.perf_records.as_mut().and_then(|pr|pr.get_mut(op)).is_some());
+							if let Some(record) = perf_records.as_mut().and_then(|pr| pr.get_mut(op)) {
+								system.refresh_cpu();
+								//let _ = system.get_processors().iter().map(|p|p.get_cpu_usage()).sum::<f32>();
+								//let _ = system.get_global_processor_info().get_cpu_usage() as f32;
+								let start = Instant::now();
+								op.instance().execute(&ctx).map_err(|e| ExecError::Op {
+									error: e,
+									op: op.clone(),
+								})?;
+								system.refresh_cpu();
+								record.invocation_count += 1;
+								record.cumulative_usage +=
+									system.processors().iter().map(|p| p.cpu_usage()).sum::<f32>()
+										/ system.processors().len() as f32; //system.get_global_processor_info().get_cpu_usage() as f32;
+								record.cumulative_time += start.elapsed().as_micros() as f32;
+							} else {
+								op.instance().execute(&ctx).map_err(|e| ExecError::Op {
+									error: e,
+									op: op.clone(),
+								})?;
+							}
 						}
-					}
 
-					Ok(ctx)
-				})
-			})?;
+						Ok(ctx)
+					})
+				},
+			)?
+		};
 
 		// This gets called by set_next_op for all ops except the last one.
 		context.finalise_current_op();
 
 		let ExecutionContext {
-			value_map, shape_map, ..
+			value_map,
+			shape_map,
+			pool,
+			rng_state,
+			..
 		} = context;
 
+		if let (Some(buffer_pool), Some(pool)) = (self.buffer_pool.as_mut(), pool) {
+			let pool = pool.into_inner();
+			buffer_pool.buffers = pool.buffers;
+			buffer_pool.allocations += pool.allocations;
+			buffer_pool.reuses += pool.reuses;
+		}
+
+		if let (Some(plan_rng_state), Some(rng_state)) = (self.rng_state.as_mut(), rng_state) {
+			let mut rng_state = rng_state.into_inner();
+			rng_state.advance();
+			**plan_rng_state = rng_state;
+		}
+
 		form_output_map(self.outputs.clone(), value_map.into_inner(), shape_map)
 	}
 }
 
+/// Executes a single `Op` on the supplied input values, returning freshly allocated values for each of its outputs.
+///
+/// This bypasses `ExecutionPlan`, so no subgraph is extracted and `Node` values are ignored. Every input of the `Op`
+/// must be supplied, and any other supplied values are ignored. The graph is left untouched, so this can be used to
+/// recompute the outputs of an `Op`, e.g. `node.parent_op()`, for gradient checkpointing or debugging.
+///
+/// The order of nodes in the result map is the same as `op.child_nodes()`.
+pub fn execute_op<I, T>(op: &Op, inputs: T) -> Result<IndexMap<Node, ArcArray<f32, IxDyn>>, ExecError>
+where
+	I: Into<Node>,
+	T: IntoIterator<Item = (I, ArcArray<f32, IxDyn>)>,
+{
+	let mut inputs: IndexMap<Node, ArcArray<f32, IxDyn>> = inputs.into_iter().map(|(n, v)| (n.into(), v)).collect();
+	let op_inputs = op.parent_nodes();
+	let op_outputs = op.child_nodes();
+
+	for node in &op_inputs {
+		if !inputs.contains_key(node) {
+			return Err(ExecError::InsufficientInputs {
+				node: node.clone(),
+				op: op.clone(),
+			});
+		}
+	}
+
+	let subgraph = SubGraph::new(
+		op_inputs.iter().chain(&op_outputs).cloned().collect::<IndexSet<Node>>(),
+		indexset![op.clone()],
+	);
+
+	let shape_map = shapes_inner(
+		&subgraph,
+		&op_inputs
+			.iter()
+			.map(|node| (node.id(), IxDyn(inputs[node].shape())))
+			.collect(),
+		false,
+	)
+	.map_err(|e| ExecError::Shape { error: e })?;
+
+	// each input is read once by the op, and each output once more when it is returned
+	let value_map: IndexMap<Node, DataState<f32>> = subgraph
+		.nodes
+		.iter()
+		.map(|node| {
+			let state = match inputs.swap_remove(node) {
+				Some(data) if op_inputs.contains(node) => {
+					if data.shape() == shape_map[&node.id()].slice() {
+						DataState::Input {
+							readers_remaining: 1,
+							data,
+						}
+					} else {
+						DataState::BroadcastInput {
+							readers_remaining: 1,
+							data,
+						}
+					}
+				}
+				_ => DataState::Unallocated {
+					writers_remaining: 1,
+					readers_remaining: 1,
+				},
+			};
+			(node.clone(), state)
+		})
+		.collect();
+
+	let (mut context, skip) = ExecutionContext::new(value_map, shape_map).set_next_op(op)?;
+	if !skip {
+		op.instance().execute(&context).map_err(|e| ExecError::Op {
+			error: e,
+			op: op.clone(),
+		})?;
+	}
+	context.finalise_current_op();
+
+	let ExecutionContext {
+		value_map, shape_map, ..
+	} = context;
+
+	form_output_map(op_outputs, value_map.into_inner(), shape_map)
+}
+
+/// Groups ops into waves which can be executed in order, with the ops of each wave executed concurrently.
+///
+/// Each op is placed in the wave after the latest wave containing an op which writes to one of its inputs, or which
+/// reads or writes one of its outputs. Any order problems in `ops` are therefore preserved, and reported by
+/// `set_next_op()` as they would be for sequential execution.
+fn parallel_waves(ops: &IndexSet<Op>) -> Vec<Vec<Op>> {
+	let mut last_write: IndexMap<Node, usize> = IndexMap::new();
+	let mut last_read: IndexMap<Node, usize> = IndexMap::new();
+	let mut waves: Vec<Vec<Op>> = vec![];
+
+	for op in ops {
+		let inputs = op.parent_nodes();
+		let outputs = op.child_nodes();
+
+		let wave = inputs
+			.iter()
+			.filter_map(|node| last_write.get(node))
+			.chain(outputs.iter().filter_map(|node| last_read.get(node)))
+			.chain(outputs.iter().filter_map(|node| last_write.get(node)))
+			.map(|&wave| wave + 1)
+			.max()
+			.unwrap_or(0);
+
+		for node in inputs {
+			let last = last_read.entry(node).or_insert(wave);
+			*last = (*last).max(wave);
+		}
+		for node in outputs {
+			last_write.insert(node, wave);
+		}
+
+		if waves.len() <= wave {
+			waves.resize_with(wave + 1, Vec::new);
+		}
+		waves[wave].push(op.clone());
+	}
+
+	waves
+}
+
 #[derive(Hash, PartialEq, Eq, Clone)]
 struct InputOutputCountCacheKey {
 	subgraph_nodes: Vec<NodeID>,
@@ -1047,15 +1731,324 @@ mod tests {
 	#![allow(non_snake_case)]
 
 	use crate::{
-		base_ops::{dummy::DummyOp, shape_constraint::same_shape, OpSpecification},
-		errors::{ExecutionSubgraphError, ShapesError},
-		exec::{ExecError, ExecutionPlan},
-		graph::Node,
+		base_ops::{
+			dummy::DummyOp,
+			fill::{fill_into, Fill},
+			shape_constraint::same_shape,
+			OpInstance, OpSpecification,
+		},
+		errors::{ExecutionError, ExecutionSubgraphError, GradientError, OpBuildError, ShapePropError, ShapesError},
+		exec::{execute_op, parallel_waves, BufferPool, ExecError, ExecutionContext, ExecutionPlan},
+		grad::GradientContext,
+		graph::{Graph, Node, NodeID, Op},
+		shape_prop::ShapePropContext,
 		subgraph::SubGraph,
 	};
 	use indexmap::indexset;
-	use indexmap::IndexMap;
-	use ndarray::arr0;
+	use indexmap::{IndexMap, IndexSet};
+	use ndarray::{arr0, ArcArray, IxDyn};
+	use std::any::Any;
+
+	/// Writes a value to its output without accumulating, i.e. an Op with a bug.
+	#[derive(Clone, Debug)]
+	struct Overwrite {
+		output: Node,
+		value: f32,
+	}
+
+	impl OpSpecification for Overwrite {
+		type InstanceType = OverwriteInstance;
+
+		fn type_name(&self) -> &'static str {
+			"Overwrite"
+		}
+
+		fn inputs(&self) -> IndexSet<Node> {
+			indexset![]
+		}
+
+		fn outputs(&self) -> IndexSet<Node> {
+			indexset![self.output.clone()]
+		}
+
+		fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+			Overwrite {
+				output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+				value: self.value,
+			}
+		}
+
+		fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+			Ok(OverwriteInstance {
+				output: self.output.id(),
+				value: self.value,
+			})
+		}
+	}
+
+	#[derive(Clone, Debug)]
+	struct OverwriteInstance {
+		output: NodeID,
+		value: f32,
+	}
+
+	impl OpInstance for OverwriteInstance {
+		fn type_name(&self) -> &'static str {
+			"Overwrite"
+		}
+
+		fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+			Box::new(Overwrite {
+				output: graph.node_from_id(self.output),
+				value: self.value,
+			})
+		}
+
+		fn inputs(&self) -> IndexSet<NodeID> {
+			indexset![]
+		}
+
+		fn outputs(&self) -> IndexSet<NodeID> {
+			indexset![self.output]
+		}
+
+		fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+			Ok(())
+		}
+
+		fn propagate_shapes(&self, _ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+			Ok(())
+		}
+
+		fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+			ctx.get_output(&self.output).fill(self.value);
+			Ok(())
+		}
+	}
+
+	/// Accumulates a scaled copy of its input into its output.
+	#[derive(Clone, Debug)]
+	struct Scale {
+		input: Node,
+		output: Node,
+		factor: f32,
+	}
+
+	impl OpSpecification for Scale {
+		type InstanceType = ScaleInstance;
+
+		fn type_name(&self) -> &'static str {
+			"Scale"
+		}
+
+		fn inputs(&self) -> IndexSet<Node> {
+			indexset![self.input.clone()]
+		}
+
+		fn outputs(&self) -> IndexSet<Node> {
+			indexset![self.output.clone()]
+		}
+
+		fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+			Scale {
+				input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+				output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+				factor: self.factor,
+			}
+		}
+
+		fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+			Ok(ScaleInstance {
+				input: self.input.id(),
+				output: self.output.id(),
+				factor: self.factor,
+			})
+		}
+	}
+
+	#[derive(Clone, Debug)]
+	struct ScaleInstance {
+		input: NodeID,
+		output: NodeID,
+		factor: f32,
+	}
+
+	impl OpInstance for ScaleInstance {
+		fn type_name(&self) -> &'static str {
+			"Scale"
+		}
+
+		fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+			Box::new(Scale {
+				input: graph.node_from_id(self.input),
+				output: graph.node_from_id(self.output),
+				factor: self.factor,
+			})
+		}
+
+		fn inputs(&self) -> IndexSet<NodeID> {
+			indexset![self.input]
+		}
+
+		fn outputs(&self) -> IndexSet<NodeID> {
+			indexset![self.output]
+		}
+
+		fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+			Ok(())
+		}
+
+		fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+			ctx.set_output_like(&self.output, &self.input)
+		}
+
+		fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+			let input = ctx.get_input(&self.input);
+			ctx.get_output(&self.output).scaled_add(self.factor, &input);
+			Ok(())
+		}
+	}
+
+	fn scale(input: &Node, output: &Node, factor: f32) -> Op {
+		Scale {
+			input: input.clone(),
+			output: output.clone(),
+			factor,
+		}
+		.build()
+		.unwrap()
+	}
+
+	#[test]
+	fn parallel_diamond() {
+		let x = Node::new(&[13, 7]).set_name("x");
+		let left = Node::new(&[13, 7]).set_name("left");
+		let left2 = Node::new(&[13, 7]).set_name("left2");
+		let right = Node::new(&[13, 7]).set_name("right");
+		let right2 = Node::new(&[13, 7]).set_name("right2");
+		let y = Node::new(&[13, 7]).set_name("y");
+
+		let x_value = ArcArray::from_shape_fn(IxDyn(&[13, 7]), |i| (i[0] * 7 + i[1]) as f32 * 0.1 - 3.0);
+
+		let ops = indexset![
+			scale(&x, &left, 2.0),
+			scale(&x, &right, -0.5),
+			scale(&left, &left2, 3.0),
+			scale(&right, &right2, 5.0),
+			scale(&left2, &y, 1.0),
+			scale(&right2, &y, 0.25),
+			scale(&x, &y, 1.5),
+		];
+
+		let waves = parallel_waves(&ops);
+		assert_eq!(
+			waves.iter().map(|wave| wave.len()).collect::<Vec<_>>(),
+			vec![2, 2, 1, 1, 1]
+		);
+		assert!(waves[0].contains(&ops[0]) && waves[0].contains(&ops[1]));
+		assert!(waves[1].contains(&ops[2]) && waves[1].contains(&ops[3]));
+
+		let subgraph = SubGraph::new(indexset![&x, &left, &left2, &right, &right2, &y], ops);
+
+		let sequential = ExecutionPlan::new(vec![(&x, x_value.clone())], &[&y, &left2])
+			.subgraph(Some(&subgraph))
+			.execute()
+			.unwrap();
+		let parallel = ExecutionPlan::new(vec![(&x, x_value.clone())], &[&y, &left2])
+			.subgraph(Some(&subgraph))
+			.parallel(true)
+			.execute()
+			.unwrap();
+
+		assert_eq!(sequential, parallel);
+		let expected = x_value.mapv(|e| e * 2.0 * 3.0 + e * -0.5 * 5.0 * 0.25 + e * 1.5);
+		assert!(parallel[&y]
+			.iter()
+			.zip(&expected)
+			.all(|(&p, &e)| (p - e).abs() <= 1e-5 * e.abs().max(1.0)));
+	}
+
+	#[test]
+	fn buffer_pool_reuse() {
+		let x = Node::new(&[13, 7]).set_name("x");
+		let a = Node::new(&[13, 7]).set_name("a");
+		let b = Node::new(&[13, 7]).set_name("b");
+		let y = Node::new(&[13, 7]).set_name("y");
+
+		scale(&x, &a, 2.0);
+		scale(&a, &b, -0.5);
+		scale(&b, &y, 3.0);
+
+		let x_value = ArcArray::from_shape_fn(IxDyn(&[13, 7]), |i| (i[0] * 7 + i[1]) as f32 * 0.1 - 3.0);
+		let fresh = ExecutionPlan::new(vec![(&x, x_value.clone())], &[&y]).execute().unwrap();
+
+		let mut pool = BufferPool::new();
+		for i in 0..3 {
+			let mut pooled = ExecutionPlan::new(vec![(&x, x_value.clone())], &[&y])
+				.buffer_pool(Some(&mut pool))
+				.execute()
+				.unwrap();
+			assert_eq!(pooled, fresh);
+
+			if i == 0 {
+				// `a` is no longer required by the time `y` is allocated, so its array is reused
+				assert_eq!(pool.allocations(), 2);
+				assert_eq!(pool.reuses(), 1);
+			}
+			pool.recycle(pooled.swap_remove(&y).unwrap());
+		}
+
+		// once warmed up, no further arrays are allocated
+		assert_eq!(pool.allocations(), 2);
+		assert_eq!(pool.reuses(), 7);
+		assert_eq!(pool.len(), 2);
+	}
+
+	#[test]
+	fn check_accumulation_passes() {
+		let x = Node::new(&[2, 3]).set_name("x");
+
+		fill_into(1.0, &x).unwrap();
+		fill_into(2.0, &x).unwrap();
+
+		// infinite sums are still accumulated correctly
+		let y = x.graph().new_node(x.shape()).set_name("y");
+		fill_into(f32::NEG_INFINITY, &y).unwrap();
+		fill_into(1.0, &y).unwrap();
+
+		let results = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&x, &y])
+			.check_accumulation(true)
+			.execute()
+			.unwrap();
+		assert!(results[&x].iter().all(|&e| (e - 3.0).abs() < f32::EPSILON));
+		assert!(results[&y].iter().all(|&e| e == f32::NEG_INFINITY));
+	}
+
+	#[test]
+	fn exec_error_AccumulationCheck() {
+		let x = Node::new(&[2, 3]).set_name("x");
+
+		// The overwrite is only a problem if it isn't the first op to write to x, so force the order
+		let op1 = Fill::new(&x, 1.0).build().unwrap();
+		let op2 = Overwrite {
+			output: x.clone(),
+			value: 2.0,
+		}
+		.build()
+		.unwrap();
+
+		let subgraph = SubGraph::new(indexset![&x], indexset![&op1, &op2]);
+
+		match ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&x])
+			.subgraph(Some(&subgraph))
+			.check_accumulation(true)
+			.execute()
+		{
+			Err(ExecError::AccumulationCheck { node, .. }) => assert_eq!(node, x),
+			Err(x) => panic!("{}", x),
+			Ok(_) => panic!("No Error"),
+		}
+	}
 
 	#[test]
 	fn exec_error_OpInputNotInSubgraph() {
@@ -1119,6 +2112,47 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn exec_error_InputsWithoutValues() {
+		let x = Node::new(&[2, 1]).set_name("x");
+		let w = Node::new(&[2, 1]).set_name("w").set_value(arr0(1.0));
+		let y = Node::new(&[2, 1]).set_name("y");
+
+		let _op = DummyOp::new().input(&x).input(&w).output(&y).build().unwrap();
+
+		let error = y.calc().unwrap_err();
+		assert!(format!("{}", error).contains("input nodes: [x]"), "{}", error);
+		match error {
+			ExecError::InputsWithoutValues { output, nodes } => {
+				assert_eq!(output, y);
+				assert_eq!(nodes.inner, vec![x]);
+			},
+			x => panic!("{}", x),
+		}
+	}
+
+	#[test]
+	fn execute_op_matches_calc() {
+		let x = Node::new(&[13, 7]).set_name("x");
+		let y = Node::new(&[13, 7]).set_name("y");
+
+		let x_value = ArcArray::from_shape_fn(IxDyn(&[13, 7]), |i| (i[0] * 7 + i[1]) as f32 * 0.1 - 3.0);
+		x.set_value(x_value.clone());
+
+		let op = scale(&x, &y, 2.5);
+
+		let result = execute_op(&y.parent_op(), vec![(&x, x_value)]).unwrap();
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[&y], y.calc().unwrap());
+		assert!(!y.has_value());
+
+		match execute_op(&op, Vec::<(Node, ArcArray<f32, IxDyn>)>::new()) {
+			Err(ExecError::InsufficientInputs { node, .. }) => assert_eq!(node, x),
+			Err(x) => panic!("{}", x),
+			Ok(_) => panic!("No Error"),
+		}
+	}
+
 	#[test]
 	fn exec_error_OutputNotComputable() {
 		let x = Node::new(&[2, 1]).set_name("x");
@@ -1,13 +1,14 @@
 //! Types and tools for constructing symbolic gradients.
 use crate::{
 	base_ops::{apply::Apply, fill::fill_into, shape_constraint::same_shape, OpSpecification},
-	errors::GradError,
+	errors::{AttributionError, GradError},
+	exec::ExecutionPlan,
 	graph::{IntoNodeValue, Node, NodeID},
 	subgraph::{backward_subgraph_from, forward_subgraph_from, SubGraph},
 	util::display::{Iter2Display, IterDisplay},
 };
 use indexmap::{indexmap, indexset, IndexMap, IndexSet};
-use ndarray::{ArcArray, ArrayViewMutD, IxDyn};
+use ndarray::{ArcArray, ArrayD, ArrayViewMutD, IxDyn};
 use std::{borrow::Borrow, sync::Arc};
 
 enum GradValue {
@@ -80,6 +81,10 @@ impl Grad {
 
 	/// Select the independant variables over which the dependant variables will be differentiated.
 	/// These will be included in the resuling output map.
+	///
+	/// These need not be inputs or parameters. Any intermediate node, e.g. an activation, can be selected to obtain the
+	/// gradient w.r.t. its value. Only the `Op`s downstream of the selected nodes contribute, so the gradient of an
+	/// intermediate doesn't depend on how its value was produced.
 	pub fn wrt<I, T>(mut self, nodes: T) -> Self
 	where
 		I: Into<Node>,
@@ -150,6 +155,12 @@ impl Grad {
 
 		// take the gradient of each op, and collect any errors
 		let errors = ops.iter().fold(IndexMap::new(), |mut errors, op| {
+			let outputs = op.instance().outputs();
+			context.blocked = op
+				.gradient_blocked()
+				.into_iter()
+				.filter(|node| !outputs.contains(node))
+				.collect();
 			op.instance().gradient(&mut context).unwrap_or_else(|e| {
 				errors.insert(op.clone(), e);
 			});
@@ -202,6 +213,86 @@ impl Grad {
 	}
 }
 
+/// Builds the gradients of a weighted sum of outputs, `w1 * y1 + w2 * y2 + ..`, w.r.t. each `x` in a single backward
+/// pass.
+///
+/// This is equivalent to summing the weighted gradients of separate backward passes for each output, as is common for
+/// multi-task losses. Each weight seeds the gradient of its output, and is broadcast to the shape of the output. If an
+/// output is listed more than once its weights are summed.
+///
+/// This is shorthand for `Grad::of_multi(..)` with a `grad_value(..)` for each output.
+///
+/// # Panics
+/// Panics if `outputs` is empty.
+pub fn grads_weighted<O, I, T>(outputs: &[(O, f32)], xs: T) -> Result<IndexMap<Node, Node>, GradError>
+where
+	O: Into<Node> + Clone,
+	I: Into<Node>,
+	T: IntoIterator<Item = I>,
+{
+	let mut weights: IndexMap<Node, f32> = IndexMap::new();
+	for (output, weight) in outputs {
+		*weights.entry(output.clone().into()).or_insert(0.0) += weight;
+	}
+
+	weights
+		.iter()
+		.fold(Grad::of_multi(weights.keys()), |grad, (output, &weight)| {
+			grad.grad_value(output.clone(), weight)
+		})
+		.wrt(xs)
+		.build()
+}
+
+/// Calculates the integrated gradients attribution of `output` to each element of `input`.
+///
+/// The gradient of `output` w.r.t. `input` is averaged over `steps` points evenly spaced on the straight line from the
+/// `baseline` to the current value of `input`, then multiplied by `input - baseline`. The `baseline` is broadcast to
+/// the shape of the input value, a typical choice being `0.0`. If `output` has more than one element the attribution is
+/// for their sum.
+///
+/// As with `Grad::wrt(..)` the input may be an intermediate node. All other nodes required to calculate the gradient
+/// must have values.
+///
+/// The sum of the attributions approximates `output(input) - output(baseline)`, and the approximation improves as
+/// `steps` is increased.
+///
+/// # Panics
+/// Panics if `steps` is zero.
+pub fn integrated_gradients(
+	output: &Node,
+	input: &Node,
+	baseline: impl IntoNodeValue,
+	steps: usize,
+) -> Result<ArrayD<f32>, AttributionError> {
+	assert!(steps > 0, "integrated_gradients requires at least one step");
+
+	let input_value = input
+		.value()
+		.ok_or_else(|| AttributionError::InputWithoutValue { node: input.clone() })?;
+	let baseline = baseline.into_value();
+	let baseline = baseline
+		.broadcast(input_value.shape())
+		.ok_or_else(|| AttributionError::IncompatibleBaseline {
+			baseline: baseline.shape().to_vec(),
+			input: input_value.shape().to_vec(),
+		})?;
+	let difference = &input_value - &baseline;
+
+	let grad = Grad::of(output).wrt(&[input]).build()?.swap_remove(input).unwrap();
+
+	let mut grad_sum = ArrayD::zeros(input_value.shape());
+	for step in 1..=steps {
+		let alpha = step as f32 / steps as f32;
+		let interpolated = (&baseline + &(&difference * alpha)).into_shared();
+
+		let mut results = ExecutionPlan::new(indexmap![input.clone() => interpolated], &[&grad]).execute()?;
+		grad_sum += &results.swap_remove(&grad).unwrap();
+	}
+
+	Ok(grad_sum * difference / steps as f32)
+}
+
 /// The subgraph should be the intersection of everything that the xs could affect, and everything that could affect y.
 /// With the addition of the xs regardless of whether they can affect y.
 fn grad_subgraph(ys: IndexSet<Node>, xs: IndexSet<Node>) -> SubGraph {
@@ -245,6 +336,7 @@ pub struct GradientContext {
 	y_names: String,
 	node_to_grad: IndexMap<NodeID, Node>,
 	nodes: IndexSet<Node>,
+	blocked: IndexSet<NodeID>,
 }
 
 impl GradientContext {
@@ -271,6 +363,7 @@ impl GradientContext {
 			y_names,
 			nodes: subgraph_nodes,
 			node_to_grad: IndexMap::new(),
+			blocked: IndexSet::new(),
 		}
 	}
 
@@ -282,11 +375,15 @@ impl GradientContext {
 	}
 
 	/// This lazily instantiates and returns gradient nodes corresponding to a non-gradient inner.
+	///
+	/// If the current `Op` has blocked the gradient of the inner, a new node is returned each call which is not
+	/// connected to the real gradient, so anything written to it is discarded.
 	pub fn grad_of(&mut self, inner: &NodeID) -> Node {
 		let &mut GradientContext {
 			ref mut node_to_grad,
 			ref nodes,
 			ref y_names,
+			ref blocked,
 			..
 		} = self;
 
@@ -298,6 +395,11 @@ impl GradientContext {
 			)
 		});
 
+		if blocked.contains(inner) {
+			let name = format!("blocked_d({})/d({})", y_names, node.name());
+			return node.graph().new_node(node.shape()).set_name_unique(&name);
+		}
+
 		node_to_grad
 			.entry(*inner)
 			.or_insert_with(|| {
@@ -308,6 +410,14 @@ impl GradientContext {
 			.clone()
 	}
 
+	/// Returns `true` if the node is part of the gradient subgraph.
+	///
+	/// `Op`s with multiple outputs can use this to skip outputs which don't affect the `y`s, as calling `grad_of()` or
+	/// `node()` on those panics.
+	pub fn contains(&self, inner: &NodeID) -> bool {
+		self.nodes.contains(inner)
+	}
+
 	/// Returns the full node for an inner.
 	pub fn node(&self, inner: &NodeID) -> Node {
 		self.nodes.get(inner).cloned().unwrap_or_else(|| {
@@ -34,12 +34,20 @@
 //! ```
 
 use crate::{
-	base_ops::OpInstance, errors::ExecError, exec::ExecutionPlan, init::Initialiser, shape::NodeShape,
-	util::display::IterDebug,
+	base_ops::OpInstance,
+	errors::{ExecError, ExecutionSubgraphError, ValidationError, ValidationProblem},
+	exec::ExecutionPlan,
+	init::Initialiser,
+	shape::NodeShape,
+	shape_prop::{shape_errors, shapes, ShapeMap},
+	subgraph::{execution_subgraph, SubGraph},
+	util::display::{IterDebug, IterDisplay},
 };
 use indexmap::{Equivalent, IndexMap, IndexSet};
 use ndarray::{arr0, arr1, ArcArray, ArrayBase, ArrayD, Data, Dimension, IxDyn, OwnedArcRepr, OwnedRepr, ViewRepr};
 use parking_lot::Mutex;
+use rand::{rngs::StdRng, SeedableRng};
+use rand_distr::{Distribution, Uniform};
 use smallvec::SmallVec;
 use std::{
 	borrow::Borrow,
@@ -163,8 +171,20 @@ impl Node {
 	}
 
 	/// Call `exec()` for this node only.
+	///
+	/// If any nodes required to calculate this node have no value an `InputsWithoutValues` error listing all of them is
+	/// returned.
 	pub fn calc(&self) -> Result<ArcArray<f32, IxDyn>, ExecError> {
+		let subgraph = execution_subgraph(IndexSet::<Node>::new(), &[self], false).map_err(|error| match error {
+			ExecutionSubgraphError::InsufficientInputs { parentless_nodes, .. } => ExecError::InputsWithoutValues {
+				output: self.clone(),
+				nodes: parentless_nodes,
+			},
+			error => ExecError::Subgraph { error },
+		})?;
+
 		Ok(ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&self])
+			.subgraph(Some(&subgraph))
 			.execute()?
 			.remove(self)
 			.unwrap())
@@ -294,6 +314,34 @@ impl Node {
 		self.clone()
 	}
 
+	/// Sets the value of the node to an array of the node's shape, drawn uniformly from `[low, high)`.
+	///
+	/// The same seed always gives the same value, which makes this convenient for tests.
+	///
+	/// # Panics
+	///
+	/// Panics if node shape is not fully determined, or if `low >= high`.
+	pub fn set_random(&self, low: f32, high: f32, seed: u64) -> Self {
+		assert!(
+			low < high,
+			"set_random requires low ({}) to be less than high ({}) for node ({})",
+			low,
+			high,
+			self
+		);
+		let shape = self.shape().to_data_shape().unwrap_or_else(|err| {
+			panic!(
+				"set_random requires the shape of node ({}) to be fully determined: {}",
+				self, err
+			)
+		});
+		let mut rng = StdRng::seed_from_u64(seed);
+		let range = Uniform::new(low, high);
+		let value = ArrayD::from_shape_simple_fn(shape, || range.sample(&mut rng));
+
+		self.set_value(value)
+	}
+
 	/// Returns the set of `Op`s that output to this `Node`.
 	pub fn parent_ops(&self) -> IndexSet<Op> {
 		self.graph.with_root_inner_mut(|graph, inner| {
@@ -364,6 +412,25 @@ impl Node {
 		data.shape.clone()
 	}
 
+	/// Returns the number of elements in a value of this node, or `None` if not all axes of the shape are known.
+	pub fn len(&self) -> Option<usize> {
+		self.shape().known_flat_size().ok()
+	}
+
+	/// Returns `Some(true)` if a value of this node has no elements, or `None` if not all axes of the shape are known.
+	pub fn is_empty(&self) -> Option<bool> {
+		self.len().map(|len| len == 0)
+	}
+
+	/// Returns the strides, in elements, of a standard (row-major) layout value of this node, or `None` if not all
+	/// axes of the shape are known.
+	///
+	/// Calculated values aren't guaranteed to be in standard layout, so use `as_standard_layout()` before copying them
+	/// into external buffers arranged with these strides.
+	pub fn standard_strides(&self) -> Option<IxDyn> {
+		self.shape().to_data_shape().ok().map(|shape| shape.default_strides())
+	}
+
 	/// Returns copy-on-write value of the `Node`, if one has been set.
 	pub fn value(&self) -> Option<ArcArray<f32, IxDyn>> {
 		let data = self.data.lock();
@@ -727,6 +794,32 @@ impl Op {
 		self.clone()
 	}
 
+	/// Marks an input so that no gradient flows back to it through this `Op`, as though it were a constant.
+	///
+	/// Must be called before `Grad::build(..)` to have an effect. Other inputs are unaffected, and the input still
+	/// receives gradient through any other `Op`s which use it.
+	///
+	/// # Panics
+	/// Panics if the `Node` is not an input to this `Op`.
+	pub fn block_gradient<I: Into<Node>>(&self, input: I) -> Self {
+		let input = input.into();
+		assert!(
+			self.instance.inputs().contains(&input.id()),
+			"Node `{}` can't have its gradient blocked as it is not an input to Op `{}`",
+			input,
+			self
+		);
+		self.data.lock().gradient_blocked.insert(input.id());
+
+		self.clone()
+	}
+
+	/// Returns the set of inputs marked by `block_gradient(..)`.
+	pub fn gradient_blocked(&self) -> IndexSet<NodeID> {
+		let data = self.data.lock();
+		data.gradient_blocked.clone()
+	}
+
 	/// Returns the set of `Node`s this `Op` uses as inputs in the `Graph`.
 	pub fn parent_nodes(&self) -> IndexSet<Node> {
 		self.graph.with_root_inner_mut(|graph, inner| {
@@ -905,6 +998,7 @@ impl Equivalent<Op> for OpID {
 pub struct OpInnerData {
 	pub name: String,
 	pub tags: IndexSet<OpTag>,
+	pub gradient_blocked: IndexSet<NodeID>,
 	pub instance: Arc<dyn OpInstance>,
 }
 
@@ -1065,6 +1159,7 @@ impl Graph {
 		let op_data = OpInnerData {
 			name: "Unnamed_Op".to_string(),
 			tags: IndexSet::new(),
+			gradient_blocked: IndexSet::new(),
 			instance,
 		};
 
@@ -1236,6 +1331,68 @@ impl Graph {
 		self.with_root_inner_mut(|_graph, inner| inner.ops.len())
 	}
 
+	/// Checks the whole graph for problems prior to execution, returning every problem found rather than only the first.
+	///
+	/// The following are checked:
+	///  * The graph is acyclic.
+	///  * Every `Node` without parent `Op`s has a value, otherwise it must be supplied as an input. Initialisers are not
+	///    considered, call `init_value()` on such nodes first.
+	///  * Shape propagation succeeds for every `Op`, using the shapes of `Node` values where available. This is skipped
+	///    if the graph contains a cycle.
+	pub fn validate(&self) -> Result<(), ValidationError> {
+		let mut problems = vec![];
+
+		let nodes = self.nodes();
+		for node in &nodes {
+			if node.parent_ops().is_empty() && !node.has_value() {
+				problems.push(ValidationProblem::UnsetInput { node: node.clone() });
+			}
+		}
+
+		match SubGraph::new(nodes, self.ops()).execution_order() {
+			Ok(subgraph) => {
+				problems.extend(
+					shape_errors(&subgraph, true)
+						.into_iter()
+						.map(|error| ValidationProblem::Shape { error }),
+				);
+			},
+			Err(error) => problems.push(ValidationProblem::Cycle { error }),
+		}
+
+		if problems.is_empty() {
+			Ok(())
+		} else {
+			Err(ValidationError {
+				problems: IterDisplay { inner: problems },
+			})
+		}
+	}
+
+	/// Propagates shapes through the `Op`s required to calculate `output`, without allocating or calculating any values.
+	///
+	/// The returned map contains the shape every required `Node` would have if `output.calc()` were called, using the
+	/// same `Node` values as inputs. Errors are returned as they would be by `calc()`.
+	pub fn dry_run<O: Into<Node>>(&self, output: O) -> Result<ShapeMap, ExecError> {
+		let output = output.into();
+		assert_eq!(
+			output.graph(),
+			self,
+			"Node ({}) passed to dry_run() is not a member of this Graph",
+			output
+		);
+
+		let subgraph = execution_subgraph(IndexSet::<Node>::new(), &[&output], false).map_err(|error| match error {
+			ExecutionSubgraphError::InsufficientInputs { parentless_nodes, .. } => ExecError::InputsWithoutValues {
+				output: output.clone(),
+				nodes: parentless_nodes,
+			},
+			error => ExecError::Subgraph { error },
+		})?;
+
+		shapes(&subgraph, IndexMap::new(), true).map_err(|error| ExecError::Shape { error })
+	}
+
 	// pub fn node_index(&self, node: NodeID) -> usize {
 	// 	self.with_root_inner_mut(|_graph, inner| {
 	// 		inner.nodes.get_full(&node).unwrap().0
@@ -2165,9 +2322,16 @@ impl From<String> for OpTag {
 #[cfg(test)]
 mod tests {
 	use crate::{
-		base_ops::noop::NoOpInstance,
+		base_ops::{
+			fill::fill_into,
+			noop::NoOpInstance,
+			shape_constraint::{same_shape, ShapeConstraint},
+			OpSpecification,
+		},
+		errors::{ShapesError, ValidationProblem},
 		graph::{Graph, Node, NodeTag},
 	};
+	use ndarray::{ArrayD, Dimension, IxDyn};
 	use std::sync::Arc;
 
 	#[test]
@@ -2185,8 +2349,51 @@ mod tests {
 		assert_eq!(format!("{}", g), "Graph { nodes: [n1, n2], ops: [o1] }".to_string());
 	}
 
+	#[test]
+	fn len_and_strides() {
+		let x = Node::new(&[2, 3, 4]).set_name("x");
+
+		assert_eq!(x.len(), Some(24));
+		assert_eq!(x.is_empty(), Some(false));
+		assert_eq!(x.standard_strides(), Some(IxDyn(&[12, 4, 1])));
+		assert_eq!(
+			x.standard_strides().unwrap().slice(),
+			ArrayD::<f32>::zeros(IxDyn(&[2, 3, 4]))
+				.strides()
+				.iter()
+				.map(|&s| s as usize)
+				.collect::<Vec<_>>()
+				.as_slice()
+		);
+
+		let y = Node::new(&[-1, 3]).set_name("y");
+		assert_eq!(y.len(), None);
+		assert_eq!(y.standard_strides(), None);
+	}
+
 	// Graph Tests
 
+	#[test]
+	fn dry_run_matches_calc() {
+		let x = Node::new(&[-1, 3])
+			.set_name("x")
+			.set_value(ArrayD::<f32>::zeros(IxDyn(&[2, 3])));
+		let y = Node::new(&[-1, -1]).set_name("y");
+		let unused = Node::new(&[-1, 5]).set_name("unused");
+
+		ShapeConstraint::new(&x, &y).multiple(2).build().unwrap();
+		fill_into(1.0, &y).unwrap();
+		same_shape(&y, &unused).unwrap();
+
+		let shapes = y.graph().dry_run(&y).unwrap();
+		assert_eq!(shapes.len(), 2);
+		assert_eq!(shapes[&x], IxDyn(&[2, 3]));
+		assert_eq!(shapes[&y], IxDyn(&[4, 6]));
+		assert!(!y.has_value());
+
+		assert_eq!(shapes[&y], IxDyn(y.calc().unwrap().shape()));
+	}
+
 	#[test]
 	fn graph_new() {
 		let g = Graph::new();
@@ -2282,6 +2489,68 @@ mod tests {
 		assert_eq!(iter.next(), None);
 	}
 
+	#[test]
+	fn node_set_random() {
+		let node1 = Node::new(&[13, 33]).set_random(-0.5, 2.0, 7);
+		let node2 = Node::new(&[13, 33]).set_random(-0.5, 2.0, 7);
+		let node3 = Node::new(&[13, 33]).set_random(-0.5, 2.0, 8);
+
+		let value1 = node1.value().unwrap();
+		assert_eq!(value1.shape(), &[13, 33]);
+		assert!(value1.iter().all(|&x| (-0.5..2.0).contains(&x)));
+
+		assert_eq!(value1, node2.value().unwrap());
+		assert_ne!(value1, node3.value().unwrap());
+	}
+
+	#[test]
+	#[should_panic]
+	fn node_set_random_unknown_shape() {
+		let _node = Node::new(&[-1, 3]).set_random(0.0, 1.0, 0);
+	}
+
+	#[test]
+	#[should_panic(expected = "set_random requires low (1) to be less than high (1)")]
+	fn node_set_random_empty_range() {
+		let _node = Node::new(&[13, 33]).set_random(1.0, 1.0, 0);
+	}
+
+	#[test]
+	fn graph_validate() {
+		let input = Node::new(&[2, 3]).set_name("input");
+		let a = Node::new(&[2, 3])
+			.set_name("a")
+			.set_value(ArrayD::zeros(IxDyn(&[2, 3])));
+		let b = Node::new(&[4, 3]).set_name("b");
+		let c = Node::new(&[2, 3]).set_name("c");
+		same_shape(&a, &b).unwrap();
+		same_shape(&input, &c).unwrap();
+		a.graph().merge(input.graph());
+
+		let problems = a.graph().validate().unwrap_err().problems.inner;
+		assert_eq!(problems.len(), 2, "{:?}", problems);
+		assert!(problems
+			.iter()
+			.any(|p| matches!(p, ValidationProblem::UnsetInput { node } if node == &input)));
+		assert!(problems.iter().any(|p| matches!(
+			p,
+			ValidationProblem::Shape {
+				error: ShapesError::ShapePropError { .. }
+			}
+		)));
+
+		input.set_value(ArrayD::zeros(IxDyn(&[2, 3])));
+		let problems = a.graph().validate().unwrap_err().problems.inner;
+		assert_eq!(problems.len(), 1, "{:?}", problems);
+
+		let e = Node::new(&[2, 3])
+			.set_name("e")
+			.set_value(ArrayD::zeros(IxDyn(&[2, 3])));
+		let f = Node::new(&[-1, 3]).set_name("f");
+		same_shape(&e, &f).unwrap();
+		assert!(e.graph().validate().is_ok());
+	}
+
 	// Op Tests
 }
 
@@ -0,0 +1,281 @@
+//! Structural comparison of graphs, for checking what a graph rewrite (e.g. constant folding) actually changed.
+//!
+//! `Node`s and `Op`s are compared by a canonical identity rather than by handle, so two separately constructed graphs
+//! can be compared. A `Node` is identified by its name and shape, and an `Op` by its name, type, and the names of its
+//! inputs and outputs in argument order. As a graph rewrite may modify a graph in place, a `GraphSnapshot` can be
+//! taken beforehand.
+//!
+//! ```rust
+//! # use alumina_core::graph::Node;
+//! # use alumina_core::graph_diff::{graph_diff, GraphSnapshot};
+//! let x = Node::new(&[2]).set_name("x");
+//! let before = GraphSnapshot::of(x.graph());
+//!
+//! let y = Node::new(&[2]).set_name("y");
+//! x.graph().merge(y.graph());
+//!
+//! let diff = graph_diff(before, x.graph());
+//! assert_eq!(format!("{}", diff), "+ node y[2]\n");
+//! ```
+
+use crate::graph::{Graph, Node, NodeID, Op};
+use indexmap::IndexMap;
+use std::{
+	fmt::{self, Display},
+	hash::Hash,
+};
+
+/// The canonical identity of a `Node`, used when comparing graphs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeIdentity {
+	pub name: String,
+	pub shape: Vec<String>,
+}
+
+impl NodeIdentity {
+	pub fn of(node: &Node) -> Self {
+		NodeIdentity {
+			name: node.name(),
+			shape: node
+				.shape()
+				.iter()
+				.map(|axis| format!("{}", axis).trim().to_string())
+				.collect(),
+		}
+	}
+}
+
+impl Display for NodeIdentity {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}[{}]", self.name, self.shape.join(", "))
+	}
+}
+
+/// The canonical identity of an `Op`, used when comparing graphs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OpIdentity {
+	pub name: String,
+	pub type_name: String,
+	pub inputs: Vec<String>,
+	pub outputs: Vec<String>,
+}
+
+impl OpIdentity {
+	pub fn of(op: &Op) -> Self {
+		OpIdentity {
+			name: op.name(),
+			type_name: op.type_name().to_string(),
+			inputs: names(op, op.instance().ordered_inputs()),
+			outputs: names(op, op.instance().ordered_outputs()),
+		}
+	}
+}
+
+fn names(op: &Op, nodes: Vec<NodeID>) -> Vec<String> {
+	nodes.into_iter().map(|id| op.graph().node_from_id(id).name()).collect()
+}
+
+impl Display for OpIdentity {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}: {}({}) -> ({})",
+			self.name,
+			self.type_name,
+			self.inputs.join(", "),
+			self.outputs.join(", ")
+		)
+	}
+}
+
+/// The canonical identities of the `Node`s and `Op`s of a `Graph` at a point in time.
+#[derive(Clone, Debug)]
+pub struct GraphSnapshot {
+	nodes: Vec<NodeIdentity>,
+	ops: Vec<OpIdentity>,
+}
+
+impl GraphSnapshot {
+	pub fn of(graph: &Graph) -> Self {
+		GraphSnapshot {
+			nodes: graph.nodes().iter().map(NodeIdentity::of).collect(),
+			ops: graph.ops().iter().map(OpIdentity::of).collect(),
+		}
+	}
+}
+
+impl<'a> From<&'a Graph> for GraphSnapshot {
+	fn from(graph: &'a Graph) -> Self {
+		GraphSnapshot::of(graph)
+	}
+}
+
+/// The `Node`s and `Op`s added and removed between two graphs, as returned by `graph_diff(..)`.
+///
+/// Displays as one line per change, prefixed by `+` or `-`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphDiff {
+	pub added_nodes: Vec<NodeIdentity>,
+	pub removed_nodes: Vec<NodeIdentity>,
+	pub added_ops: Vec<OpIdentity>,
+	pub removed_ops: Vec<OpIdentity>,
+}
+
+impl GraphDiff {
+	/// Returns true if the graphs were structurally identical.
+	pub fn is_empty(&self) -> bool {
+		self.added_nodes.is_empty()
+			&& self.removed_nodes.is_empty()
+			&& self.added_ops.is_empty()
+			&& self.removed_ops.is_empty()
+	}
+}
+
+impl Display for GraphDiff {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for node in &self.removed_nodes {
+			writeln!(f, "- node {}", node)?;
+		}
+		for node in &self.added_nodes {
+			writeln!(f, "+ node {}", node)?;
+		}
+		for op in &self.removed_ops {
+			writeln!(f, "- op {}", op)?;
+		}
+		for op in &self.added_ops {
+			writeln!(f, "+ op {}", op)?;
+		}
+		Ok(())
+	}
+}
+
+/// Compares two graphs, or snapshots of graphs, reporting the `Node`s and `Op`s added and removed.
+///
+/// If several `Node`s or `Op`s share an identity then only the difference in their number is reported.
+pub fn graph_diff<B, A>(before: B, after: A) -> GraphDiff
+where
+	B: Into<GraphSnapshot>,
+	A: Into<GraphSnapshot>,
+{
+	let before = before.into();
+	let after = after.into();
+	let (removed_nodes, added_nodes) = diff(before.nodes, after.nodes);
+	let (removed_ops, added_ops) = diff(before.ops, after.ops);
+	GraphDiff {
+		added_nodes,
+		removed_nodes,
+		added_ops,
+		removed_ops,
+	}
+}
+
+/// Returns (removed, added) treating before and after as multisets.
+fn diff<T: Clone + Eq + Hash>(before: Vec<T>, after: Vec<T>) -> (Vec<T>, Vec<T>) {
+	let mut counts: IndexMap<T, isize> = IndexMap::new();
+	for item in before {
+		*counts.entry(item).or_insert(0) -= 1;
+	}
+	for item in after {
+		*counts.entry(item).or_insert(0) += 1;
+	}
+
+	let mut removed = vec![];
+	let mut added = vec![];
+	for (item, count) in counts {
+		for _ in 0..count.abs() {
+			if count < 0 {
+				removed.push(item.clone());
+			} else {
+				added.push(item.clone());
+			}
+		}
+	}
+	(removed, added)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{graph_diff, GraphSnapshot, NodeIdentity, OpIdentity};
+	use crate::{
+		base_ops::{dummy::DummyOp, OpSpecification},
+		graph::Graph,
+	};
+	use ndarray::arr1;
+
+	/// Builds `d = f(c)` and `c = g(a, b)` where `a` and `b` are constants.
+	fn unfolded() -> Graph {
+		let graph = Graph::new();
+		let a = graph.new_node((&[2]).into()).set_name("a").set_value(arr1(&[1.0, 2.0]));
+		let b = graph.new_node((&[2]).into()).set_name("b").set_value(arr1(&[3.0, 4.0]));
+		let c = graph.new_node((&[2]).into()).set_name("c");
+		let d = graph.new_node((&[2]).into()).set_name("d");
+		DummyOp::new()
+			.input(&a)
+			.input(&b)
+			.output(&c)
+			.build()
+			.unwrap()
+			.set_name("g");
+		DummyOp::new().input(&c).output(&d).build().unwrap().set_name("f");
+		graph
+	}
+
+	/// Constant folding of `unfolded()`, `c` only depends on constants so becomes a constant and `a`, `b`, and `g` are
+	/// no longer required.
+	fn folded() -> Graph {
+		let graph = Graph::new();
+		let c = graph.new_node((&[2]).into()).set_name("c").set_value(arr1(&[4.0, 6.0]));
+		let d = graph.new_node((&[2]).into()).set_name("d");
+		DummyOp::new().input(&c).output(&d).build().unwrap().set_name("f");
+		graph
+	}
+
+	#[test]
+	fn constant_folding_diff() {
+		let before = unfolded();
+		let after = folded();
+
+		let diff = graph_diff(&before, &after);
+		assert_eq!(
+			diff.removed_nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(),
+			vec!["a", "b"]
+		);
+		assert!(diff.added_nodes.is_empty());
+		assert!(diff.added_ops.is_empty());
+		assert_eq!(
+			diff.removed_ops,
+			vec![OpIdentity {
+				name: "g".to_string(),
+				type_name: "DummyOp".to_string(),
+				inputs: vec!["a".to_string(), "b".to_string()],
+				outputs: vec!["c".to_string()],
+			}]
+		);
+		assert_eq!(
+			format!("{}", diff),
+			"- node a[2]\n- node b[2]\n- op g: DummyOp(a, b) -> (c)\n"
+		);
+
+		assert!(graph_diff(&after, &after).is_empty());
+	}
+
+	#[test]
+	fn snapshot_before_in_place_change() {
+		let graph = unfolded();
+		let before = GraphSnapshot::of(&graph);
+
+		let c = graph.node_named("c");
+		let e = graph.new_node((&[2, -1]).into()).set_name("e");
+		DummyOp::new().input(&c).output(&e).build().unwrap().set_name("h");
+
+		let diff = graph_diff(before, &graph);
+		assert_eq!(
+			diff.added_nodes,
+			vec![NodeIdentity {
+				name: "e".to_string(),
+				shape: vec!["2".to_string(), "-1".to_string()],
+			}]
+		);
+		assert_eq!(format!("{}", diff), "+ node e[2, -1]\n+ op h: DummyOp(c) -> (e)\n");
+	}
+}
@@ -3,6 +3,7 @@ pub mod errors;
 pub mod exec;
 pub mod grad;
 pub mod graph;
+pub mod graph_diff;
 pub mod init;
 pub mod shape;
 pub mod shape_prop;
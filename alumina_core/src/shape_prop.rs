@@ -13,12 +13,15 @@ use lru::LruCache;
 use ndarray::{Dimension, IxDyn};
 use std::cell::RefCell;
 
+/// The resolved shape of each `Node` in a `SubGraph`, as returned by `shapes()` and `Graph::dry_run()`.
+pub type ShapeMap = IndexMap<Node, IxDyn>;
+
 /// Computes the shapes of the `Node`s in a `SubGraph`, using the `Op`s to propagate from the supplied inputs.
 pub fn shapes(
 	execution_subgraph: &SubGraph,
 	inputs: IndexMap<Node, IxDyn>,
 	use_node_values: bool,
-) -> Result<IndexMap<Node, IxDyn>, ShapesError> {
+) -> Result<ShapeMap, ShapesError> {
 	let mut inner_map = shapes_inner(
 		execution_subgraph,
 		&inputs.into_iter().map(|(node, shape)| (node.id(), shape)).collect(),
@@ -134,6 +137,59 @@ fn op_update<'a>(
 	Ok(())
 }
 
+/// Propagates shapes through every `Op` in the `SubGraph`, collecting errors rather than stopping at the first.
+///
+/// `Op`s with an input written by an `Op` which failed are skipped, so that one problem isn't reported repeatedly.
+///
+/// # Contract
+/// Execution subgraph must be topologically sorted
+pub(crate) fn shape_errors(execution_subgraph: &SubGraph, use_node_values: bool) -> Vec<ShapesError> {
+	let (_input_errors, mut map) = input_update(execution_subgraph, &IndexMap::new(), use_node_values);
+	let mut map_completed = IndexMap::with_capacity(execution_subgraph.nodes.len());
+
+	let mut errors = vec![];
+	let mut failed_nodes = IndexSet::new();
+	for op in execution_subgraph.ops.iter() {
+		if op.parent_nodes().iter().any(|node| failed_nodes.contains(node)) {
+			failed_nodes.extend(op.child_nodes());
+			continue;
+		}
+
+		let result = {
+			let mut context = ShapePropContext {
+				subgraph: execution_subgraph,
+				map: &mut map,
+				map_completed: &mut map_completed,
+
+				current_op: op.clone(),
+				current_inputs: op.parent_nodes(),
+				current_outputs: op.child_nodes(),
+			};
+
+			context
+				.set_next_op(op.clone())
+				.map(|_| op.instance().propagate_shapes(&mut context))
+		};
+
+		let error = match result {
+			Ok(Ok(())) => continue,
+			Ok(Err(error)) => ShapesError::ShapePropError {
+				op: op.clone(),
+				error,
+				partial: map
+					.iter()
+					.map(|(k, v)| (execution_subgraph.nodes.get(k).unwrap().clone(), v.clone()))
+					.collect(),
+			},
+			Err(error) => error,
+		};
+		failed_nodes.extend(op.child_nodes());
+		errors.push(error);
+	}
+
+	errors
+}
+
 #[derive(Hash, PartialEq, Eq, Clone)]
 struct ShapeCacheKey {
 	subgraph_nodes: Vec<NodeID>,
@@ -174,6 +230,10 @@ thread_local! {
 
 /// Computes the shapes of the `Node`s in a `SubGraph`, using the `Op`s to propagate from the supplied inputs.
 ///
+/// Results are cached per thread, keyed by the `Node`s and `Op`s of the subgraph and the shapes of the inputs (and
+/// `Node` values if used). `Node` shapes and `Op`s can't change after creation, so a change in any input shape is
+/// sufficient to miss the cache and propagate shapes again.
+///
 /// #Contract
 /// Execution subgraph must be topologically sorted
 pub(crate) fn cached_shapes_inner(
@@ -288,6 +348,40 @@ impl<'a> ShapePropContext<'a> {
 		})
 	}
 
+	/// Returns an error naming the first pair of inputs found to have different shapes.
+	///
+	/// # Panics
+	/// If any of the `Node`s isn't listed as an input by the `Op`.
+	pub fn require_equal_shapes(&self, nodes: &[NodeID]) -> Result<(), ShapePropError> {
+		if let Some((first, rest)) = nodes.split_first() {
+			let first_shape = self.input_shape(first);
+			for node in rest {
+				let shape = self.input_shape(node);
+				if shape != first_shape {
+					return Err(format!(
+						"Op `{}` requires inputs of equal shape, but Node `{}` has shape {:?} and Node `{}` has shape {:?}",
+						self.current_op(),
+						self.node(first).name(),
+						first_shape.slice(),
+						self.node(node).name(),
+						shape.slice()
+					)
+					.into());
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Merges the shape of an input into the shape of an output, for outputs with the same shape as an input.
+	///
+	/// # Panics
+	/// Panics if the nodes aren't listed as an input and an output by the `Op`.
+	pub fn set_output_like(&mut self, output: &NodeID, input: &NodeID) -> Result<(), ShapePropError> {
+		let input_shape: NodeShape = self.input_shape(input).slice().iter().into();
+		self.merge_output_shape(output, &input_shape)
+	}
+
 	/// If output shape is not part of the graph, this does nothing.
 	///
 	/// # Panics
@@ -414,3 +508,60 @@ impl<'a> ShapePropContext<'a> {
 		self.current_op.clone()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		base_ops::{fill::fill_into, shape_constraint::ShapeConstraint, OpSpecification},
+		graph::Node,
+		shape_prop::{cached_shapes_inner, shapes_inner},
+		subgraph::execution_subgraph,
+	};
+	use indexmap::indexmap;
+	use ndarray::{ArcArray, IxDyn};
+
+	#[test]
+	fn cached_matches_uncached() {
+		let x = Node::new(&[-1, 3]).set_name("x");
+		let y = Node::new(&[-1, -1]).set_name("y");
+
+		ShapeConstraint::new(&x, &y).multiple(2).build().unwrap();
+
+		let subgraph = execution_subgraph(&[&x], &[&y], false).unwrap();
+		let inputs = indexmap![x.id() => IxDyn(&[2, 3])];
+
+		let uncached = shapes_inner(&subgraph, &inputs, false).unwrap();
+		let first = cached_shapes_inner(&subgraph, &inputs, false).unwrap();
+		let second = cached_shapes_inner(&subgraph, &inputs, false).unwrap();
+
+		assert_eq!(uncached[&y.id()], IxDyn(&[4, 6]));
+		assert_eq!(first, uncached);
+		assert_eq!(second, uncached);
+	}
+
+	#[test]
+	fn cache_invalidated_by_input_shape() {
+		let x = Node::new(&[-1, 3]).set_name("x");
+		let y = Node::new(&[-1, -1]).set_name("y");
+
+		ShapeConstraint::new(&x, &y).multiple(2).build().unwrap();
+
+		let subgraph = execution_subgraph(&[&x], &[&y], false).unwrap();
+
+		let small = cached_shapes_inner(&subgraph, &indexmap![x.id() => IxDyn(&[2, 3])], false).unwrap();
+		let large = cached_shapes_inner(&subgraph, &indexmap![x.id() => IxDyn(&[5, 3])], false).unwrap();
+		let small_again = cached_shapes_inner(&subgraph, &indexmap![x.id() => IxDyn(&[2, 3])], false).unwrap();
+
+		assert_eq!(small[&y.id()], IxDyn(&[4, 6]));
+		assert_eq!(large[&y.id()], IxDyn(&[10, 6]));
+		assert_eq!(small_again, small);
+
+		// the same applies when shapes come from Node values during repeated calc() calls
+		fill_into(1.0, &y).unwrap();
+		x.set_value(ArcArray::zeros(IxDyn(&[2, 3])));
+		assert_eq!(y.calc().unwrap().shape(), &[4, 6]);
+		assert_eq!(y.calc().unwrap().shape(), &[4, 6]);
+		x.set_value(ArcArray::zeros(IxDyn(&[5, 3])));
+		assert_eq!(y.calc().unwrap().shape(), &[10, 6]);
+	}
+}
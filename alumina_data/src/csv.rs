@@ -0,0 +1,247 @@
+use alumina_core::graph::Node;
+use indexmap::IndexMap;
+use ndarray::{ArcArray, Array2, Axis, IxDyn};
+use std::{
+	error::Error,
+	fmt,
+	fs::File,
+	io::{self, BufRead, BufReader, Read},
+	path::Path,
+};
+
+/// Error returned when reading or selecting from a `Csv`.
+///
+/// Line numbers are 1-based and include the header row if present.
+#[derive(Debug)]
+pub enum CsvError {
+	/// The underlying reader returned an error.
+	Io(io::Error),
+
+	/// A field was empty.
+	MissingValue { line: usize, column: String },
+
+	/// A field could not be parsed as a number.
+	Parse { line: usize, column: String, value: String },
+
+	/// A row did not have the same number of fields as the first row.
+	RowLength { line: usize, expected: usize, found: usize },
+
+	/// A requested column does not exist.
+	UnknownColumn { name: String },
+}
+
+impl fmt::Display for CsvError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CsvError::Io(err) => write!(f, "CsvError::Io Reading CSV failed: {}", err),
+			CsvError::MissingValue { line, column } => write!(
+				f,
+				"CsvError::MissingValue Line {} has no value for column '{}'",
+				line, column
+			),
+			CsvError::Parse { line, column, value } => write!(
+				f,
+				"CsvError::Parse Line {} has a non-numeric value '{}' for column '{}'",
+				line, value, column
+			),
+			CsvError::RowLength { line, expected, found } => write!(
+				f,
+				"CsvError::RowLength Line {} has {} fields, but {} were expected",
+				line, found, expected
+			),
+			CsvError::UnknownColumn { name } => write!(f, "CsvError::UnknownColumn No column named '{}'", name),
+		}
+	}
+}
+
+impl Error for CsvError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			CsvError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for CsvError {
+	fn from(err: io::Error) -> Self {
+		CsvError::Io(err)
+	}
+}
+
+/// Numeric data read from a comma separated values file.
+///
+/// Every field must be present and parseable as an `f32`. Columns are named by the header row, or if there is no
+/// header row, by their index (i.e. "0", "1", ...). Blank lines are ignored.
+#[derive(Clone, Debug)]
+pub struct Csv {
+	names: Vec<String>,
+	data: Array2<f32>,
+}
+
+impl Csv {
+	/// Opens and reads a CSV file.
+	pub fn open<P: AsRef<Path>>(path: P, has_header: bool) -> Result<Self, CsvError> {
+		Csv::read(File::open(path)?, has_header)
+	}
+
+	/// Reads CSV data from any reader.
+	pub fn read<R: Read>(reader: R, has_header: bool) -> Result<Self, CsvError> {
+		let mut names: Option<Vec<String>> = None;
+		let mut values = vec![];
+		let mut rows = 0;
+
+		for (i, line) in BufReader::new(reader).lines().enumerate() {
+			let line = line?;
+			let line_number = i + 1;
+			if line.trim().is_empty() {
+				continue;
+			}
+			let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+			let names = match names {
+				Some(ref names) => names,
+				None if has_header => {
+					names = Some(fields.iter().map(|s| s.to_string()).collect());
+					continue;
+				},
+				None => names.get_or_insert((0..fields.len()).map(|i| i.to_string()).collect()),
+			};
+
+			if fields.len() != names.len() {
+				return Err(CsvError::RowLength {
+					line: line_number,
+					expected: names.len(),
+					found: fields.len(),
+				});
+			}
+
+			for (field, name) in fields.iter().zip(names) {
+				if field.is_empty() {
+					return Err(CsvError::MissingValue {
+						line: line_number,
+						column: name.clone(),
+					});
+				}
+				values.push(field.parse::<f32>().map_err(|_| CsvError::Parse {
+					line: line_number,
+					column: name.clone(),
+					value: field.to_string(),
+				})?);
+			}
+			rows += 1;
+		}
+
+		let names = names.unwrap_or_default();
+		let data = Array2::from_shape_vec((rows, names.len()), values)
+			.expect("Alumina Bug: CSV values did not match the number of rows and columns");
+
+		Ok(Csv { names, data })
+	}
+
+	/// Returns the column names.
+	pub fn names(&self) -> &[String] {
+		&self.names
+	}
+
+	/// Returns all values as a [rows, columns] array.
+	pub fn data(&self) -> &Array2<f32> {
+		&self.data
+	}
+
+	/// Returns the number of rows, excluding the header.
+	pub fn rows(&self) -> usize {
+		self.data.nrows()
+	}
+
+	/// Returns the index of the named column.
+	pub fn column_index(&self, name: &str) -> Result<usize, CsvError> {
+		self.names
+			.iter()
+			.position(|n| n == name)
+			.ok_or_else(|| CsvError::UnknownColumn { name: name.to_string() })
+	}
+
+	/// Returns a [rows, columns.len()] array containing the named columns in the order given.
+	pub fn columns(&self, columns: &[&str]) -> Result<ArcArray<f32, IxDyn>, CsvError> {
+		let indices = columns
+			.iter()
+			.map(|name| self.column_index(name))
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok(self.data.select(Axis(1), &indices).into_dyn().into_shared())
+	}
+
+	/// Produces feeds for execution by mapping each node to an array of the named columns.
+	///
+	/// Each array has shape [rows, columns.len()].
+	pub fn feeds<'a, I, C, T>(&self, mapping: T) -> Result<IndexMap<Node, ArcArray<f32, IxDyn>>, CsvError>
+	where
+		I: Into<Node>,
+		C: AsRef<[&'a str]>,
+		T: IntoIterator<Item = (I, C)>,
+	{
+		mapping
+			.into_iter()
+			.map(|(node, columns)| Ok((node.into(), self.columns(columns.as_ref())?)))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Csv, CsvError};
+	use alumina_core::graph::Node;
+	use ndarray::{arr2, ArcArray, IxDyn};
+
+	#[test]
+	fn feeds_match_csv() {
+		let text = "x1, x2, label\n0.5, 1.0, 1\n-2, 3.25, 0\n\n4e-1,7,1\n";
+		let csv = Csv::read(text.as_bytes(), true).unwrap();
+		assert_eq!(csv.names(), &["x1", "x2", "label"]);
+		assert_eq!(csv.rows(), 3);
+
+		let input = Node::new(&[-1, 2]).set_name("input");
+		let labels = Node::new(&[-1, 1]).set_name("labels");
+
+		let feeds = csv
+			.feeds(vec![(&input, &["x1", "x2"][..]), (&labels, &["label"][..])])
+			.unwrap();
+		let expected_input: ArcArray<f32, IxDyn> =
+			arr2(&[[0.5, 1.0], [-2.0, 3.25], [0.4, 7.0]]).into_dyn().into_shared();
+		let expected_labels: ArcArray<f32, IxDyn> = arr2(&[[1.0], [0.0], [1.0]]).into_dyn().into_shared();
+		assert_eq!(feeds[&input], expected_input);
+		assert_eq!(feeds[&labels], expected_labels);
+	}
+
+	#[test]
+	fn no_header() {
+		let csv = Csv::read("1,2\n3,4\n".as_bytes(), false).unwrap();
+		assert_eq!(csv.names(), &["0", "1"]);
+		assert_eq!(csv.data(), &arr2(&[[1.0, 2.0], [3.0, 4.0]]));
+	}
+
+	#[test]
+	fn errors() {
+		match Csv::read("a,b\n1,\n".as_bytes(), true) {
+			Err(CsvError::MissingValue { line: 2, ref column }) if column == "b" => {},
+			x => panic!("{:?}", x),
+		}
+		match Csv::read("a,b\n1,x\n".as_bytes(), true) {
+			Err(CsvError::Parse { line: 2, .. }) => {},
+			x => panic!("{:?}", x),
+		}
+		match Csv::read("a,b\n1,2,3\n".as_bytes(), true) {
+			Err(CsvError::RowLength {
+				line: 2,
+				expected: 2,
+				found: 3,
+			}) => {},
+			x => panic!("{:?}", x),
+		}
+		let csv = Csv::read("a,b\n1,2\n".as_bytes(), true).unwrap();
+		match csv.columns(&["c"]) {
+			Err(CsvError::UnknownColumn { ref name }) if name == "c" => {},
+			x => panic!("{:?}", x),
+		}
+	}
+}
@@ -1,8 +1,11 @@
 pub mod cifar;
 pub mod crop;
+pub mod csv;
+pub mod loader;
 pub mod mnist;
 
 pub use crate::crop::{Crop, Cropping};
+pub use crate::loader::DataLoader;
 use alumina_core::graph::Node;
 use indexmap::IndexMap;
 use ndarray::{ArcArray, Axis, IxDyn};
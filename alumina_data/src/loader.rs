@@ -0,0 +1,175 @@
+use alumina_core::graph::Node;
+use indexmap::IndexMap;
+use ndarray::{ArcArray, Axis, IxDyn};
+use rand::{seq::SliceRandom, thread_rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+/// Yields batches of an in-memory dataset as feeds for execution, one epoch at a time.
+///
+/// Each component of the dataset is an array whose outermost axis indexes the elements, and is associated with the
+/// node it will be fed to. Each batch is a map from those nodes to arrays containing `batch_size` elements, suitable
+/// for use as the inputs of an `ExecutionPlan`. If the number of elements isn't divisible by the batch size the last
+/// batch of each epoch is smaller.
+///
+/// Every element appears exactly once per epoch. If shuffling is enabled the order of elements is reshuffled at the
+/// start of each epoch, for reproducible shuffling provide a seeded rng using `rng(..)`.
+pub struct DataLoader {
+	components: IndexMap<Node, ArcArray<f32, IxDyn>>,
+	batch_size: usize,
+	shuffle: bool,
+	rng: Box<dyn RngCore + Send>,
+	order: Vec<usize>,
+}
+
+impl DataLoader {
+	/// # Panics
+	/// Panics if `batch_size` is 0, if no components are provided, or if the components do not all have the same
+	/// length in the outermost axis.
+	pub fn new<I, T>(components: T, batch_size: usize) -> Self
+	where
+		I: Into<Node>,
+		T: IntoIterator<Item = (I, ArcArray<f32, IxDyn>)>,
+	{
+		assert!(batch_size > 0, "DataLoader batch_size must be greater than 0");
+		let components: IndexMap<Node, ArcArray<f32, IxDyn>> =
+			components.into_iter().map(|(node, arr)| (node.into(), arr)).collect();
+		assert!(!components.is_empty(), "DataLoader requires at least one component");
+
+		let len = components[0].shape().first().cloned().unwrap_or(0);
+		for (node, arr) in &components {
+			assert_eq!(
+				arr.shape().first().cloned().unwrap_or(0),
+				len,
+				"All DataLoader components must have the same outermost axis length ({}), but the component for {} has shape {:?}",
+				len,
+				node,
+				arr.shape()
+			);
+		}
+
+		DataLoader {
+			components,
+			batch_size,
+			shuffle: true,
+			rng: Box::new(Pcg64Mcg::from_rng(thread_rng()).unwrap()),
+			order: (0..len).collect(),
+		}
+	}
+
+	/// Whether the order of elements is shuffled at the start of each epoch.
+	///
+	/// Default: true
+	pub fn shuffle(mut self, shuffle: bool) -> Self {
+		self.shuffle = shuffle;
+		self
+	}
+
+	/// The rng used for shuffling, e.g. `Pcg64Mcg::seed_from_u64(0)` for reproducible epochs.
+	///
+	/// Default: `Pcg64Mcg` seeded from `thread_rng()`
+	pub fn rng<R: RngCore + 'static + Send>(mut self, rng: R) -> Self {
+		self.rng = Box::new(rng);
+		self
+	}
+
+	/// Returns the number of elements in the dataset.
+	pub fn len(&self) -> usize {
+		self.order.len()
+	}
+
+	/// Returns true if the dataset contains no elements.
+	pub fn is_empty(&self) -> bool {
+		self.order.is_empty()
+	}
+
+	/// Returns the number of batches produced by each epoch.
+	pub fn batches_per_epoch(&self) -> usize {
+		self.len().div_ceil(self.batch_size)
+	}
+
+	/// Begins a new epoch, returning an iterator over its batches.
+	pub fn epoch(&mut self) -> Epoch<'_> {
+		if self.shuffle {
+			self.order.shuffle(&mut self.rng);
+		}
+		Epoch {
+			loader: self,
+			next_i: 0,
+		}
+	}
+}
+
+/// Iterator over the batches of one epoch of a `DataLoader`.
+pub struct Epoch<'a> {
+	loader: &'a DataLoader,
+	next_i: usize,
+}
+
+impl<'a> Iterator for Epoch<'a> {
+	type Item = IndexMap<Node, ArcArray<f32, IxDyn>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let order = &self.loader.order;
+		if self.next_i >= order.len() {
+			return None;
+		}
+
+		let end = (self.next_i + self.loader.batch_size).min(order.len());
+		let indices = &order[self.next_i..end];
+		self.next_i = end;
+
+		Some(
+			self.loader
+				.components
+				.iter()
+				.map(|(node, arr)| (node.clone(), arr.select(Axis(0), indices).into_shared()))
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::DataLoader;
+	use alumina_core::graph::Node;
+	use ndarray::{ArcArray, Array, IxDyn};
+	use rand::SeedableRng;
+	use rand_pcg::Pcg64Mcg;
+
+	#[test]
+	fn every_sample_once_per_epoch() {
+		let x = Node::new(&[-1, 2]).set_name("x");
+		let y = Node::new(&[-1]).set_name("y");
+
+		let x_data: ArcArray<f32, IxDyn> =
+			Array::from_shape_fn(IxDyn(&[10, 2]), |idx| (idx[0] * 2 + idx[1]) as f32).into_shared();
+		let y_data: ArcArray<f32, IxDyn> = Array::from_shape_fn(IxDyn(&[10]), |idx| idx[0] as f32).into_shared();
+
+		let mut loader = DataLoader::new(vec![(&x, x_data), (&y, y_data)], 3).rng(Pcg64Mcg::seed_from_u64(0));
+		assert_eq!(loader.len(), 10);
+		assert_eq!(loader.batches_per_epoch(), 4);
+
+		let mut orders = vec![];
+		for _ in 0..2 {
+			let mut seen = vec![];
+			let mut batch_sizes = vec![];
+			for batch in loader.epoch() {
+				let x_batch = &batch[&x];
+				let y_batch = &batch[&y];
+				batch_sizes.push(y_batch.len());
+				for (x_row, &y) in x_batch.outer_iter().zip(y_batch) {
+					// components of each element must stay together
+					assert_eq!(x_row[0], y * 2.0);
+					assert_eq!(x_row[1], y * 2.0 + 1.0);
+					seen.push(y as usize);
+				}
+			}
+			assert_eq!(batch_sizes, vec![3, 3, 3, 1]);
+
+			orders.push(seen.clone());
+			seen.sort_unstable();
+			assert_eq!(seen, (0..10).collect::<Vec<_>>());
+		}
+		assert_ne!(orders[0], orders[1]);
+	}
+}
@@ -1,6 +1,7 @@
-use crate::{
-	elementwise::elementwise_single::{UnaryElementwise, UnaryFunc},
-	elementwise::{mul::Mul, sign::sign},
+use crate::elementwise::{
+	elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc},
+	mul::Mul,
+	sign::sign,
 };
 use alumina_core::{
 	base_ops::OpSpecification,
@@ -11,6 +12,8 @@ use alumina_core::{
 
 /// Returns the absolute (abs) of the input.
 ///
+/// The gradient at zero is taken to be zero, the subgradient which is smallest in magnitude.
+///
 /// The output node has the same shape as the input.
 pub fn abs<I>(input: I) -> Result<Node, OpBuildError>
 where
@@ -27,10 +30,13 @@ where
 
 pub type Abs = UnaryElementwise<AbsFunc>;
 
+pub type AbsBack = BinaryElementwise<AbsBackFunc>;
+
 #[derive(Clone, Debug, Default)]
 pub struct AbsFunc {}
 
 impl UnaryFunc for AbsFunc {
+	#[inline]
 	fn calc(&self, input: f32) -> f32 {
 		input.abs()
 	}
@@ -40,20 +46,60 @@ impl UnaryFunc for AbsFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		let _op = Mul::new_default(ctx.grad_of(output), sign(ctx.node(input))?, ctx.grad_of(input)).build()?;
+		AbsBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of abs
+/// input2 = grad of output of abs
+#[derive(Clone, Debug, Default)]
+pub struct AbsBackFunc {}
+
+impl BinaryFunc for AbsBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if input1 == 0.0 {
+			0.0
+		} else {
+			input2 * input1.signum()
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"AbsBackward"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		// output = input2 * sign(input1), which is flat in input1 so only input2 receives a gradient
+		let _op = Mul::new_default(ctx.grad_of(output), sign(ctx.node(input1))?, ctx.grad_of(input2)).build()?;
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::abs;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{abs, Abs, AbsBack};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::{duplicate, uniform, Initialiser},
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
-	use indexmap::indexset;
+	use indexmap::{indexmap, indexset};
 	use ndarray::arr0;
 
+	contract_case!(unary "abs", abs);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -80,4 +126,60 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-3).run();
 	}
+
+	#[test]
+	fn grad_numeric_zero_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(duplicate(0.0));
+		let output = abs(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.expect_zero(&input, ::std::f32::EPSILON)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		// alternate the sign without approaching zero, where the gradient is discontinuous
+		let alternating = Initialiser::new("alternating".to_string(), |mut arr| {
+			for (i, x) in arr.iter_mut().enumerate() {
+				*x = if i % 2 == 0 { 1.5 } else { -1.5 };
+			}
+		});
+		let input = Node::new(&[37, 33]).set_name("input").set_init(alternating);
+		let output = abs(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		GradNumericTest::new(&grad, &indexset![&input])
+			.expect_zero(&input, f32::EPSILON)
+			.run();
+
+		// the output grad is routed through the sign of the input
+		let output_grad = Node::new(&[37, 33]).set_name("output_grad");
+		let input_grad = Node::new(&[37, 33]).set_name("input_grad");
+		merge_graphs(&[input.graph(), output_grad.graph(), input_grad.graph()]);
+
+		let _op = AbsBack::new_default(&input, &output_grad, &input_grad).build().unwrap();
+
+		GradNumericTest::new(&input_grad, &indexset![&input, &output_grad])
+			.expect_zero(&input, f32::EPSILON)
+			.run();
+	}
+
+	#[test]
+	fn clone_with_nodes_changed_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = Node::new(&[13, 33]).set_name("output");
+		let new_input = Node::new(&[13, 33]).set_name("new_input");
+
+		let op =
+			Abs::new_default(&input, &output).clone_with_nodes_changed(&indexmap![input.clone() => new_input.clone()]);
+
+		assert_eq!(op.inputs(), indexset![new_input]);
+		assert_eq!(op.outputs(), indexset![output]);
+	}
 }
@@ -0,0 +1,220 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{merge_graphs, Node, NodeID},
+};
+
+/// Returns the four quadrant arctangent of y and x element-wise, i.e. the angle of the point (x, y) in radians.
+///
+/// `let output = y.atan2(x)`
+///
+/// The gradient is undefined at the origin, so a small epsilon is added to `x^2 + y^2` when calculating it. To
+/// change the epsilon use `Atan2::epsilon(..)`.
+///
+/// The output node has the same shape as the inputs.
+pub fn atan2<I1, I2>(y: I1, x: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let y = y.into();
+	let x = x.into();
+	merge_graphs(&[y.graph(), x.graph()]);
+	let output = y
+		.graph()
+		.new_node(y.shape())
+		.set_name_unique(&format!("atan2({},{})", y, x));
+	let _op = Atan2::new_default(y, x, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Atan2 = BinaryElementwise<Atan2Func>;
+
+impl Atan2 {
+	/// Added to `x^2 + y^2` when calculating the gradient, keeping it finite at the origin.
+	///
+	/// Default: 1e-8
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon >= 0.0, "epsilon {} must not be negative", epsilon);
+		self.func_mut().epsilon = epsilon;
+		self
+	}
+}
+
+pub type Atan2Back = TernaryElementwise<Atan2BackFunc>;
+
+#[derive(Clone, Debug)]
+pub struct Atan2Func {
+	epsilon: f32,
+}
+
+impl Default for Atan2Func {
+	fn default() -> Self {
+		Self { epsilon: 1e-8 }
+	}
+}
+
+impl BinaryFunc for Atan2Func {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input1.atan2(input2)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Atan2"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		Atan2Back::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input1),
+			Atan2BackFunc {
+				wrt_x: false,
+				epsilon: self.epsilon,
+			},
+		)
+		.build()?;
+		Atan2Back::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input2),
+			Atan2BackFunc {
+				wrt_x: true,
+				epsilon: self.epsilon,
+			},
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = y of atan2
+/// input2 = x of atan2
+/// input3 = grad of output of atan2
+///
+/// If `wrt_x` is true the gradient of x is produced, otherwise the gradient of y.
+#[derive(Clone, Debug)]
+pub struct Atan2BackFunc {
+	wrt_x: bool,
+	epsilon: f32,
+}
+
+impl Default for Atan2BackFunc {
+	fn default() -> Self {
+		Self {
+			wrt_x: false,
+			epsilon: 1e-8,
+		}
+	}
+}
+
+impl TernaryFunc for Atan2BackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		let denom = input1 * input1 + input2 * input2 + self.epsilon;
+		if self.wrt_x {
+			-input3 * input1 / denom
+		} else {
+			input3 * input2 / denom
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		if self.wrt_x {
+			"Atan2XBackward"
+		} else {
+			"Atan2YBackward"
+		}
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_input3: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{atan2, Atan2};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::{arr0, arr1};
+	use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+	contract_case!(binary "atan2", atan2);
+
+	#[test]
+	fn forward_test() {
+		let y = Node::new(&[4]).set_name("y").set_value(arr1(&[1.0, 1.0, -2.0, 0.0]));
+		let x = Node::new(&[4]).set_name("x").set_value(arr1(&[1.0, 0.0, -2.0, -1.0]));
+
+		let output = atan2(&y, &x).unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(
+			&arr1(&[FRAC_PI_4, FRAC_PI_2, -3.0 * FRAC_PI_4, PI]),
+			::std::f32::EPSILON
+		));
+	}
+
+	#[test]
+	fn grad_origin_test() {
+		let y = Node::new(&[13, 33]).set_name("y").set_value(arr0(0.0));
+		let x = Node::new(&[13, 33]).set_name("x").set_value(arr0(0.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Atan2::new_default(&y, &x, &output).epsilon(1e-4).build().unwrap();
+
+		let grads = Grad::of(&output).wrt(&[&y, &x]).build().unwrap();
+
+		// the epsilon keeps the gradient finite, rather than NaN, at the origin
+		assert!(grads[&y].calc().unwrap().iter().all(|&g| g == 0.0));
+		assert!(grads[&x].calc().unwrap().iter().all(|&g| g == 0.0));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		// keep inputs in the first quadrant, away from the origin and the branch cut along the negative x axis
+		let y = Node::new(&[13, 33]).set_name("y").set_init(uniform(0.5, 2.0));
+		let x = Node::new(&[13, 33]).set_name("x").set_init(uniform(0.5, 2.0));
+
+		let output = atan2(&y, &x).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&y, &x]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn grad_shared_input_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(-1.5));
+
+		// atan2(x, x) is constant away from the origin, so the contributions of both inputs cancel
+		let output = atan2(&input, &input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		assert!(grad.calc().unwrap().iter().all(|&g| g == 0.0));
+	}
+}
@@ -0,0 +1,193 @@
+use crate::elementwise::{
+	elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc},
+	mul::Mul,
+};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{merge_graphs, Node, NodeID},
+};
+
+/// Returns a smooth blend of `a` and `b` element-wise, weighted by `alpha`.
+///
+/// `let output = alpha * a + (1 - alpha) * b`
+///
+/// `alpha` would typically lie in `[0, 1]`, selecting `a` where it is 1 and `b` where it is 0. Unlike a hard select
+/// on a mask, gradients are produced for all three inputs, including `alpha`.
+///
+/// The output node has the same shape as the inputs.
+pub fn blend<I1, I2, I3>(a: I1, b: I2, alpha: I3) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+	I3: Into<Node>,
+{
+	let a = a.into();
+	let b = b.into();
+	let alpha = alpha.into();
+	merge_graphs(&[a.graph(), b.graph(), alpha.graph()]);
+	let output = a
+		.graph()
+		.new_node(a.shape())
+		.set_name_unique(&format!("blend({},{},{})", a, b, alpha));
+	let _op = Blend::new_default(a, b, alpha, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Blend = TernaryElementwise<BlendFunc>;
+
+pub type BlendBack = BinaryElementwise<BlendBackFunc>;
+
+pub type BlendAlphaBack = TernaryElementwise<BlendAlphaBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct BlendFunc {}
+
+impl TernaryFunc for BlendFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		input3 * input1 + (1.0 - input3) * input2
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Blend"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		input3: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		let _op = Mul::new_default(ctx.grad_of(output), ctx.node(input3), ctx.grad_of(input1)).build()?;
+		let _op = BlendBack::new_default(ctx.node(input3), ctx.grad_of(output), ctx.grad_of(input2)).build()?;
+		let _op = BlendAlphaBack::new_default(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input3),
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = alpha of blend
+/// input2 = grad of output of blend
+///
+/// Produces the gradient of b.
+#[derive(Clone, Debug, Default)]
+pub struct BlendBackFunc {}
+
+impl BinaryFunc for BlendBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		(1.0 - input1) * input2
+	}
+
+	fn type_name(&self) -> &'static str {
+		"BlendBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+/// input1 = a of blend
+/// input2 = b of blend
+/// input3 = grad of output of blend
+///
+/// Produces the gradient of alpha.
+#[derive(Clone, Debug, Default)]
+pub struct BlendAlphaBackFunc {}
+
+impl TernaryFunc for BlendAlphaBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		(input1 - input2) * input3
+	}
+
+	fn type_name(&self) -> &'static str {
+		"BlendAlphaBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_input3: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::blend;
+	use crate::tests::{contract_case, input};
+	use alumina_core::{grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::{arr0, arr1};
+
+	contract_case!("blend", || {
+		let (x, y, alpha) = (input("x", &[4, 5]), input("y", &[4, 5]), input("alpha", &[4, 5]));
+		(blend(&x, &y, &alpha).unwrap(), vec![x, y, alpha])
+	});
+
+	#[test]
+	fn forward_test() {
+		let a = Node::new(&[4]).set_name("a").set_value(arr1(&[1.0, 1.0, 1.0, -2.0]));
+		let b = Node::new(&[4]).set_name("b").set_value(arr1(&[3.0, 3.0, 3.0, 2.0]));
+		let alpha = Node::new(&[4])
+			.set_name("alpha")
+			.set_value(arr1(&[1.0, 0.0, 0.25, 0.5]));
+
+		let output = blend(&a, &b, &alpha).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[1.0, 3.0, 2.5, 0.0]), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let a = Node::new(&[13, 33]).set_name("a");
+		let b = Node::new(&[13, 33]).set_name("b");
+		let alpha = Node::new(&[13, 33]).set_name("alpha").set_init(uniform(0.0, 1.0));
+
+		let output = blend(&a, &b, &alpha).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a, &b, &alpha]).run();
+	}
+
+	#[test]
+	fn grad_shared_input_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(-1.5));
+		let alpha = Node::new(&[13, 33]).set_name("alpha").set_value(arr0(0.3));
+
+		// blending an input with itself returns it unchanged, so its gradient is one and alpha has no effect
+		let output = blend(&input, &input, &alpha).unwrap();
+		let grads = Grad::of(&output).wrt(&[&input, &alpha]).build().unwrap();
+
+		assert!(grads[&input]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.0), ::std::f32::EPSILON));
+		assert!(grads[&alpha].calc().unwrap().iter().all(|&g| g == 0.0));
+	}
+}
@@ -0,0 +1,238 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the input clamped (clipped) to lie within `[min, max]` element-wise.
+///
+/// The gradient is passed through only where the input lies strictly inside `(min, max)`, and is zero where the
+/// input is clamped.
+///
+/// The output node has the same shape as the input.
+///
+/// # Panics
+/// Panics if `min` is greater than `max`, or either is NaN.
+pub fn clamp<I>(input: I, min: f32, max: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("clamp({})", input));
+	let _op = Clamp::new_default(input, output.clone()).min(min).max(max).build()?;
+	Ok(output)
+}
+
+pub type Clamp = UnaryElementwise<ClampFunc>;
+
+impl Clamp {
+	/// The lower bound of the output.
+	///
+	/// Default: -inf
+	pub fn min(mut self, min: f32) -> Self {
+		assert!(
+			min <= self.func_mut().max,
+			"min {} must not be NaN or greater than max {}",
+			min,
+			self.func_mut().max
+		);
+		self.func_mut().min = min;
+		self
+	}
+
+	/// The upper bound of the output.
+	///
+	/// Default: inf
+	pub fn max(mut self, max: f32) -> Self {
+		assert!(
+			max >= self.func_mut().min,
+			"max {} must not be NaN or less than min {}",
+			max,
+			self.func_mut().min
+		);
+		self.func_mut().max = max;
+		self
+	}
+}
+
+pub type ClampBack = BinaryElementwise<ClampBackFunc>;
+
+#[derive(Clone, Debug)]
+pub struct ClampFunc {
+	min: f32,
+	max: f32,
+}
+
+impl Default for ClampFunc {
+	fn default() -> Self {
+		Self {
+			min: f32::NEG_INFINITY,
+			max: f32::INFINITY,
+		}
+	}
+}
+
+impl UnaryFunc for ClampFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.clamp(self.min, self.max)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Clamp"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		ClampBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			ClampBackFunc {
+				min: self.min,
+				max: self.max,
+			},
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of clamp
+/// input2 = grad of output of clamp
+#[derive(Clone, Debug)]
+pub struct ClampBackFunc {
+	min: f32,
+	max: f32,
+}
+
+impl Default for ClampBackFunc {
+	fn default() -> Self {
+		Self {
+			min: f32::NEG_INFINITY,
+			max: f32::INFINITY,
+		}
+	}
+}
+
+impl BinaryFunc for ClampBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if input1 > self.min && input1 < self.max {
+			input2
+		} else {
+			0.0
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ClampBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{clamp, Clamp};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::{indexmap, indexset};
+	use ndarray::{arr0, arr1};
+
+	contract_case!(unary "clamp", |x| clamp(x, 0.75, 1.75));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[5])
+			.set_name("input")
+			.set_value(arr1(&[-2.0, -0.5, 0.0, 0.7, 3.0]));
+
+		let output = clamp(&input, -1.0, 1.0).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[-1.0, -0.5, 0.0, 0.7, 1.0]), f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_saturated_test() {
+		let input = Node::new(&[5])
+			.set_name("input")
+			.set_value(arr1(&[-2.0, -1.0, 0.0, 1.0, 3.0]));
+
+		let output = clamp(&input, -1.0, 1.0).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		// the gradient vanishes where the input is clamped, including exactly at the bounds
+		assert_eq!(grad.calc().unwrap(), arr1(&[0.0, 0.0, 1.0, 0.0, 0.0]).into_dyn());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-0.9, 0.9));
+		let output = clamp(&input, -1.0, 1.0).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn bounds_round_trip_test() {
+		let input = Node::new(&[2]).set_name("input").set_value(arr1(&[-0.8, 0.8]));
+		let output = Node::new(&[2]).set_name("output");
+		let new_output = Node::new(&[2]).set_name("new_output");
+
+		let op = Clamp::new_default(&input, &output).min(-0.5).max(0.25).build().unwrap();
+
+		// both bounds must survive the instance to specification round trip, and the cloning of the specification
+		let spec = op
+			.instance()
+			.as_specification(op.graph())
+			.downcast::<Clamp>()
+			.unwrap()
+			.clone_with_nodes_changed(&indexmap![output.clone() => new_output.clone()]);
+		spec.build().unwrap();
+
+		assert!(new_output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[-0.5, 0.25]), f32::EPSILON));
+	}
+
+	#[test]
+	fn one_sided_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(-3.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Clamp::new_default(&input, &output).max(2.0).build().unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(-3.0), f32::EPSILON));
+	}
+
+	#[test]
+	#[should_panic]
+	fn inverted_bounds_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let _ = clamp(&input, 1.0, -1.0);
+	}
+}
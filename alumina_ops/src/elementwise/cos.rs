@@ -74,12 +74,15 @@ impl BinaryFunc for CosBackFunc {
 #[cfg(test)]
 mod tests {
 	use super::cos;
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "cos", cos);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -1,7 +1,7 @@
-use crate::{
-	elementwise::elementwise_single::{BinaryElementwise, BinaryFunc},
-	elementwise::mul::mul,
-	elementwise::{negative::negative, sqr::sqr},
+use crate::elementwise::{
+	elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc},
+	mul::mul,
+	scale::scale,
 };
 use alumina_core::{
 	base_ops::OpSpecification,
@@ -10,7 +10,9 @@ use alumina_core::{
 	graph::{merge_graphs, Node, NodeID},
 };
 
-/// Calculates the elementwise division (div) of input1 over input2.
+/// Calculates the elementwise division (div) of input1 over input2 plus epsilon, `input1 / (input2 + epsilon)`.
+///
+/// The default epsilon is zero, to change it use `Div::epsilon(..)`.
 ///
 /// The output node has the same shape as the inputs.
 pub fn div<I1, I2>(input1: I1, input2: I2) -> Result<Node, OpBuildError>
@@ -33,13 +35,40 @@ where
 
 pub type Div = BinaryElementwise<DivFunc>;
 
+impl Div {
+	/// Added to the denominator before dividing, preventing division by zero for denominators of zero.
+	///
+	/// Default: 0.0
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon >= 0.0, "epsilon {} must not be negative", epsilon);
+		self.func_mut().epsilon = epsilon;
+		self
+	}
+}
+
+pub type DivBack = TernaryElementwise<DivBackFunc>;
+
+/// As `div` but with an epsilon added to the denominator.
+fn div_epsilon(input1: Node, input2: Node, epsilon: f32) -> Result<Node, OpBuildError> {
+	let output = input1
+		.graph()
+		.new_node(input1.shape())
+		.set_name_unique(&format!("div({},{})", input1, input2));
+	let _op = Div::new_default(input1, input2, output.clone())
+		.epsilon(epsilon)
+		.build()?;
+	Ok(output)
+}
+
 #[derive(Clone, Debug, Default)]
-pub struct DivFunc {}
+pub struct DivFunc {
+	epsilon: f32,
+}
 
 impl BinaryFunc for DivFunc {
 	#[inline]
 	fn calc(&self, input1: f32, input2: f32) -> f32 {
-		input1 / input2
+		input1 / (input2 + self.epsilon)
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -53,26 +82,139 @@ impl BinaryFunc for DivFunc {
 		input2: &NodeID,
 		output: &NodeID,
 	) -> Result<(), GradientError> {
-		let _op = Div::new_default(ctx.grad_of(output), ctx.node(input2), ctx.grad_of(input1)).build()?;
-		let _op = Div::new_default(
-			mul(ctx.grad_of(output), ctx.node(input1))?,
-			negative(sqr(ctx.node(input2))?)?,
+		DivBack::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input1),
+			DivBackFunc {
+				wrt_denominator: false,
+				epsilon: self.epsilon,
+			},
+		)
+		.build()?;
+		DivBack::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
 			ctx.grad_of(input2),
+			DivBackFunc {
+				wrt_denominator: true,
+				epsilon: self.epsilon,
+			},
 		)
-		.build()?; // TODO specialised ternary func, or swap the mul for a square
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = numerator of div
+/// input2 = denominator of div
+/// input3 = grad of output of div
+///
+/// If `wrt_denominator` is true the gradient of the denominator is produced, otherwise the gradient of the numerator.
+#[derive(Clone, Debug, Default)]
+pub struct DivBackFunc {
+	wrt_denominator: bool,
+	epsilon: f32,
+}
+
+impl TernaryFunc for DivBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		let denominator = input2 + self.epsilon;
+		if self.wrt_denominator {
+			-input1 * input3 / (denominator * denominator)
+		} else {
+			input3 / denominator
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		if self.wrt_denominator {
+			"DivDenominatorBackward"
+		} else {
+			"DivNumeratorBackward"
+		}
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		input3: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		let denominator_back = DivBackFunc {
+			wrt_denominator: true,
+			epsilon: self.epsilon,
+		};
+		if self.wrt_denominator {
+			// output = -input1 * input3 / d^2, where d = input2 + epsilon
+			DivBack::new(
+				ctx.node(input3),
+				ctx.node(input2),
+				ctx.grad_of(output),
+				ctx.grad_of(input1),
+				denominator_back.clone(),
+			)
+			.build()?;
+			DivBack::new(
+				ctx.node(input1),
+				ctx.node(input2),
+				ctx.grad_of(output),
+				ctx.grad_of(input3),
+				denominator_back.clone(),
+			)
+			.build()?;
+			// 2 * input1 * input3 * grad / d^3
+			DivBack::new(
+				scale(
+					div_epsilon(mul(ctx.node(input1), ctx.node(input3))?, ctx.node(input2), self.epsilon)?,
+					-2.0,
+				)?,
+				ctx.node(input2),
+				ctx.grad_of(output),
+				ctx.grad_of(input2),
+				denominator_back,
+			)
+			.build()?;
+		} else {
+			// output = input3 / d, where d = input2 + epsilon
+			let _op = Div::new_default(ctx.grad_of(output), ctx.node(input2), ctx.grad_of(input3))
+				.epsilon(self.epsilon)
+				.build()?;
+			DivBack::new(
+				ctx.node(input3),
+				ctx.node(input2),
+				ctx.grad_of(output),
+				ctx.grad_of(input2),
+				denominator_back,
+			)
+			.build()?;
+		}
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::div;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{div, Div, DivBack, DivBackFunc};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::uniform,
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(binary "div", div);
+
 	#[test]
 	fn forward_test() {
 		let input1 = Node::new(&[13, 33]).set_name("input1");
@@ -109,6 +251,23 @@ mod tests {
 			.all_relatively_close(&arr0(1.0), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn epsilon_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_value(arr0(1.5));
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_value(arr0(0.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Div::new_default(&input1, &input2, &output)
+			.epsilon(0.5)
+			.build()
+			.unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(3.0), ::std::f32::EPSILON));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input1 = Node::new(&[13, 33]).set_name("input1");
@@ -134,4 +293,89 @@ mod tests {
 			.step_size(1e-3)
 			.run();
 	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1");
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_init(uniform(0.2, 3.0));
+
+		let output = div(&input1, &input2).unwrap();
+		let grads = Grad::of(&output).wrt(&[&input1, &input2]).build().unwrap();
+
+		GradNumericTest::new(&grads[&input1], &indexset![&input1, &input2])
+			.expect_zero(&input1, f32::EPSILON)
+			.step_size(1e-3)
+			.run();
+		GradNumericTest::new(&grads[&input2], &indexset![&input1, &input2])
+			.step_size(1e-3)
+			.tolerance(5e-4)
+			.run();
+	}
+
+	#[test]
+	fn back_grad_numeric_epsilon_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1");
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_init(uniform(0.2, 3.0));
+		let grad = Node::new(&[13, 33]).set_name("grad");
+		let numerator_grad = Node::new(&[13, 33]).set_name("numerator_grad");
+		let denominator_grad = Node::new(&[13, 33]).set_name("denominator_grad");
+		merge_graphs(&[
+			input1.graph(),
+			input2.graph(),
+			grad.graph(),
+			numerator_grad.graph(),
+			denominator_grad.graph(),
+		]);
+
+		let _op = DivBack::new(
+			&input1,
+			&input2,
+			&grad,
+			&numerator_grad,
+			DivBackFunc {
+				wrt_denominator: false,
+				epsilon: 0.1,
+			},
+		)
+		.build()
+		.unwrap();
+		let _op = DivBack::new(
+			&input1,
+			&input2,
+			&grad,
+			&denominator_grad,
+			DivBackFunc {
+				wrt_denominator: true,
+				epsilon: 0.1,
+			},
+		)
+		.build()
+		.unwrap();
+
+		GradNumericTest::new(&numerator_grad, &indexset![&input1, &input2, &grad])
+			.expect_zero(&input1, f32::EPSILON)
+			.step_size(1e-3)
+			.run();
+		GradNumericTest::new(&denominator_grad, &indexset![&input1, &input2, &grad])
+			.step_size(1e-3)
+			.tolerance(5e-4)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_epsilon_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1");
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_init(uniform(0.2, 3.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Div::new_default(&input1, &input2, &output)
+			.epsilon(0.1)
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input1, &input2])
+			.step_size(1e-3)
+			.tolerance(5e-4)
+			.run();
+	}
 }
@@ -26,6 +26,32 @@ use rayon::prelude::*;
 use std::any::Any;
 use std::fmt;
 
+#[cfg(test)]
+thread_local! {
+	/// The number of Binary and Ternary executions on this thread which chose to use rayon, so that tests can check
+	/// that the `par_threshold` is respected.
+	pub(crate) static PARALLEL_EXECUTIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Whether an execution over `len` elements should be split up using rayon. See `ExecutionContext::is_parallel(..)`.
+fn is_parallel(ctx: &ExecutionContext, len: usize) -> bool {
+	let parallel = ctx.is_parallel(len);
+	#[cfg(test)]
+	PARALLEL_EXECUTIONS.with(|count| count.set(count.get() + parallel as usize));
+	parallel
+}
+
+/// Calls `par_for_each` on the `Zip` if `parallel` is true, otherwise calls `for_each` on the calling thread.
+macro_rules! zip_for_each {
+	($parallel:expr, $zip:expr, $f:expr) => {
+		if $parallel {
+			$zip.par_for_each($f)
+		} else {
+			$zip.for_each($f)
+		}
+	};
+}
+
 pub trait NullaryDualFunc: Send + Sync + Clone + fmt::Debug + 'static {
 	fn calc(&self) -> (f32, f32);
 
@@ -224,7 +250,7 @@ impl<F: UnaryDualFunc> OpSpecification for UnaryElementwiseDual<F> {
 		Self {
 			output1: mapping.get(&self.output1).unwrap_or(&self.output1).clone(),
 			output2: mapping.get(&self.output2).unwrap_or(&self.output2).clone(),
-			input: mapping.get(&self.output2).unwrap_or(&self.input).clone(),
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
 			f: self.f.clone(),
 		}
 	}
@@ -433,8 +459,8 @@ impl<F: BinaryDualFunc> OpSpecification for BinaryElementwiseDual<F> {
 		Self {
 			output1: mapping.get(&self.output1).unwrap_or(&self.output1).clone(),
 			output2: mapping.get(&self.output2).unwrap_or(&self.output2).clone(),
-			input1: mapping.get(&self.output1).unwrap_or(&self.input1).clone(),
-			input2: mapping.get(&self.output2).unwrap_or(&self.input2).clone(),
+			input1: mapping.get(&self.input1).unwrap_or(&self.input1).clone(),
+			input2: mapping.get(&self.input2).unwrap_or(&self.input2).clone(),
 			f: self.f.clone(),
 		}
 	}
@@ -479,6 +505,10 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 		indexset![self.input1, self.input2]
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		vec![self.input1, self.input2]
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.output1, self.output2]
 	}
@@ -521,6 +551,9 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 			ctx.shape(&self.output2)
 		);
 
+		// small arrays are processed on the calling thread, where splitting them up would cost more than it saves
+		let parallel = is_parallel(ctx, ctx.shape(&self.output1).iter().product());
+
 		// Are the inputs the same node?
 		// Are the outputs the same node?
 		// What are the combinations of can_take and can_set?
@@ -531,81 +564,97 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 				// one input and one output
 				if ctx.can_take(&self.input1) && ctx.can_set(&self.output1) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1).par_for_each(|in1| {
+					zip_for_each!(parallel, Zip::from(&mut input1), |in1| {
 						let (o1, o2) = self.f.calc(*in1, *in1);
 						*in1 = o1 + o2;
 					});
 					ctx.set(&self.output1, input1);
 				} else {
-					Zip::from(ctx.get_input(&self.input1))
-						.and(ctx.get_output(&self.output1))
-						.par_for_each(|in1, output| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1)).and(ctx.get_output(&self.output1)),
+						|in1, output| {
 							let (o1, o2) = self.f.calc(*in1, *in1);
 							*output += o1 + o2;
-						});
+						}
+					);
 				}
 			}
 			(true, false) => {
 				// one input and two outputs
 				if ctx.can_take(&self.input1) && ctx.can_set(&self.output1) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1)
-						.and(ctx.get_output(&self.output2))
-						.par_for_each(|in1, out2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(&mut input1).and(ctx.get_output(&self.output2)),
+						|in1, out2| {
 							let (o1, o2) = self.f.calc(*in1, *in1);
 							*in1 = o1;
 							*out2 += o2;
-						});
+						}
+					);
 					ctx.set(&self.output1, input1);
 				} else if ctx.can_take(&self.input1) && ctx.can_set(&self.output2) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1)
-						.and(ctx.get_output(&self.output1))
-						.par_for_each(|in1, out1| {
+					zip_for_each!(
+						parallel,
+						Zip::from(&mut input1).and(ctx.get_output(&self.output1)),
+						|in1, out1| {
 							let (o1, o2) = self.f.calc(*in1, *in1);
 							*in1 = o2;
 							*out1 += o1;
-						});
+						}
+					);
 					ctx.set(&self.output2, input1);
 				} else {
-					Zip::from(ctx.get_input(&self.input1))
-						.and(ctx.get_output(&self.output1))
-						.and(ctx.get_output(&self.output2))
-						.par_for_each(|in1, output1, output2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1))
+							.and(ctx.get_output(&self.output1))
+							.and(ctx.get_output(&self.output2)),
+						|in1, output1, output2| {
 							let (o1, o2) = self.f.calc(*in1, *in1);
 							*output1 += o1;
 							*output2 += o2;
-						});
+						}
+					);
 				}
 			}
 			(false, true) => {
 				// two inputs and one output
 				if ctx.can_take(&self.input1) && ctx.can_set(&self.output1) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1)
-						.and(ctx.get_input(&self.input2))
-						.par_for_each(|in1, in2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(&mut input1).and(ctx.get_input(&self.input2)),
+						|in1, in2| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in1 = o1 + o2;
-						});
+						}
+					);
 					ctx.set(&self.output1, input1);
 				} else if ctx.can_take(&self.input2) && ctx.can_set(&self.output1) {
 					let mut input2 = ctx.take(&self.input2);
-					Zip::from(ctx.get_input(&self.input1))
-						.and(&mut input2)
-						.par_for_each(|in1, in2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1)).and(&mut input2),
+						|in1, in2| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in2 = o1 + o2;
-						});
+						}
+					);
 					ctx.set(&self.output1, input2);
 				} else {
-					Zip::from(ctx.get_input(&self.input1))
-						.and(ctx.get_input(&self.input2))
-						.and(ctx.get_output(&self.output1))
-						.par_for_each(|in1, in2, out1| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1))
+							.and(ctx.get_input(&self.input2))
+							.and(ctx.get_output(&self.output1)),
+						|in1, in2, out1| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*out1 += o1 + o2;
-						});
+						}
+					);
 				}
 			}
 			(false, false) => {
@@ -617,7 +666,7 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 				{
 					let mut input1 = ctx.take(&self.input1);
 					let mut input2 = ctx.take(&self.input2);
-					Zip::from(&mut input1).and(&mut input2).par_for_each(|in1, in2| {
+					zip_for_each!(parallel, Zip::from(&mut input1).and(&mut input2), |in1, in2| {
 						let (o1, o2) = self.f.calc(*in1, *in2);
 						*in1 = o1;
 						*in2 = o2;
@@ -626,58 +675,73 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 					ctx.set(&self.output2, input2);
 				} else if ctx.can_set(&self.output1) && ctx.can_take(&self.input1) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1)
-						.and(ctx.get_input(&self.input2))
-						.and(ctx.get_output(&self.output2))
-						.par_for_each(|in1, in2, out2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(&mut input1)
+							.and(ctx.get_input(&self.input2))
+							.and(ctx.get_output(&self.output2)),
+						|in1, in2, out2| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in1 = o1;
 							*out2 += o2;
-						});
+						}
+					);
 					ctx.set(&self.output1, input1);
 				} else if ctx.can_set(&self.output1) && ctx.can_take(&self.input2) {
 					let mut input2 = ctx.take(&self.input2);
-					Zip::from(ctx.get_input(&self.input1))
-						.and(&mut input2)
-						.and(ctx.get_output(&self.output2))
-						.par_for_each(|in1, in2, out2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1))
+							.and(&mut input2)
+							.and(ctx.get_output(&self.output2)),
+						|in1, in2, out2| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in2 = o1;
 							*out2 += o2;
-						});
+						}
+					);
 					ctx.set(&self.output1, input2);
 				} else if ctx.can_set(&self.output2) && ctx.can_take(&self.input1) {
 					let mut input1 = ctx.take(&self.input1);
-					Zip::from(&mut input1)
-						.and(ctx.get_input(&self.input2))
-						.and(ctx.get_output(&self.output1))
-						.par_for_each(|in1, in2, out1| {
+					zip_for_each!(
+						parallel,
+						Zip::from(&mut input1)
+							.and(ctx.get_input(&self.input2))
+							.and(ctx.get_output(&self.output1)),
+						|in1, in2, out1| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in1 = o2;
 							*out1 += o1;
-						});
+						}
+					);
 					ctx.set(&self.output2, input1);
 				} else if ctx.can_set(&self.output2) && ctx.can_take(&self.input2) {
 					let mut input2 = ctx.take(&self.input2);
-					Zip::from(ctx.get_input(&self.input1))
-						.and(&mut input2)
-						.and(ctx.get_output(&self.output1))
-						.par_for_each(|in1, in2, out1| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1))
+							.and(&mut input2)
+							.and(ctx.get_output(&self.output1)),
+						|in1, in2, out1| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*in2 = o2;
 							*out1 += o1;
-						});
+						}
+					);
 					ctx.set(&self.output2, input2);
 				} else {
-					Zip::from(ctx.get_input(&self.input1))
-						.and(ctx.get_input(&self.input2))
-						.and(ctx.get_output(&self.output1))
-						.and(ctx.get_output(&self.output2))
-						.par_for_each(|in1, in2, out1, out2| {
+					zip_for_each!(
+						parallel,
+						Zip::from(ctx.get_input(&self.input1))
+							.and(ctx.get_input(&self.input2))
+							.and(ctx.get_output(&self.output1))
+							.and(ctx.get_output(&self.output2)),
+						|in1, in2, out1, out2| {
 							let (o1, o2) = self.f.calc(*in1, *in2);
 							*out1 += o1;
 							*out2 += o2;
-						});
+						}
+					);
 				}
 			}
 		}
@@ -686,6 +750,327 @@ impl<F: BinaryDualFunc> OpInstance for BinaryElementwiseDualInstance<F> {
 	}
 }
 
+pub trait TernaryDualFunc: Send + Sync + Clone + fmt::Debug + 'static {
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> (f32, f32);
+
+	fn type_name(&self) -> &'static str;
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		input3: &NodeID,
+		output1: &NodeID,
+		output2: &NodeID,
+	) -> Result<(), GradientError>;
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct TernaryElementwiseDual<F: TernaryDualFunc> {
+	output1: Node,
+	output2: Node,
+	input1: Node,
+	input2: Node,
+	input3: Node,
+	f: F,
+}
+
+impl<F: TernaryDualFunc> TernaryElementwiseDual<F> {
+	pub fn new<I1, I2, I3, O1, O2>(input1: I1, input2: I2, input3: I3, output1: O1, output2: O2, f: F) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let input1 = input1.into();
+		let input2 = input2.into();
+		let input3 = input3.into();
+		let output1 = output1.into();
+		let output2 = output2.into();
+		TernaryElementwiseDual {
+			output1,
+			output2,
+			input1,
+			input2,
+			input3,
+			f,
+		}
+	}
+
+	pub fn new_default<I1, I2, I3, O1, O2>(input1: I1, input2: I2, input3: I3, output1: O1, output2: O2) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+		F: Default,
+	{
+		Self::new(input1, input2, input3, output1, output2, F::default())
+	}
+}
+
+impl<F: TernaryDualFunc> OpSpecification for TernaryElementwiseDual<F> {
+	type InstanceType = TernaryElementwiseDualInstance<F>;
+
+	fn type_name(&self) -> &'static str {
+		self.f.type_name()
+	}
+
+	/// Returns a list of `Node`s this `Op` may need to read when executed
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input1.clone(), self.input2.clone(), self.input3.clone()]
+	}
+
+	/// Returns a list of `Node`s this `Op` may need to write to when executed
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output1.clone(), self.output2.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			output1: mapping.get(&self.output1).unwrap_or(&self.output1).clone(),
+			output2: mapping.get(&self.output2).unwrap_or(&self.output2).clone(),
+			input1: mapping.get(&self.input1).unwrap_or(&self.input1).clone(),
+			input2: mapping.get(&self.input2).unwrap_or(&self.input2).clone(),
+			input3: mapping.get(&self.input3).unwrap_or(&self.input3).clone(),
+			f: self.f.clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(TernaryElementwiseDualInstance {
+			input1: self.input1.id(),
+			input2: self.input2.id(),
+			input3: self.input3.id(),
+			output1: self.output1.id(),
+			output2: self.output2.id(),
+			f: self.f,
+		})
+	}
+}
+
+/// ElementwiseDual Op, the value of the input is added to
+#[derive(Clone, Debug)]
+pub struct TernaryElementwiseDualInstance<F: TernaryDualFunc> {
+	input1: NodeID,
+	input2: NodeID,
+	input3: NodeID,
+	output1: NodeID,
+	output2: NodeID,
+	f: F,
+}
+
+impl<F: TernaryDualFunc> TernaryElementwiseDualInstance<F> {
+	/// Takes the input at `index` and overwrites it with one output, accumulating into the other output if it is
+	/// required.
+	///
+	/// If `set_first` is true the taken array becomes output1, otherwise output2.
+	fn execute_taking(&self, ctx: &ExecutionContext, parallel: bool, index: usize, set_first: bool) {
+		// select the argument order outside of the loop so that each loop body is branch free
+		let f = &self.f;
+		match (index, set_first) {
+			(0, true) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| f.calc(t, a, b)),
+			(0, false) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| swap(f.calc(t, a, b))),
+			(1, true) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| f.calc(a, t, b)),
+			(1, false) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| swap(f.calc(a, t, b))),
+			(_, true) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| f.calc(a, b, t)),
+			(_, false) => self.zip_taking(ctx, parallel, index, set_first, |t, a, b| swap(f.calc(a, b, t))),
+		}
+	}
+
+	/// `calc` takes the taken input followed by the other inputs in order, and returns the output to set followed by the
+	/// output to accumulate into.
+	fn zip_taking<C>(&self, ctx: &ExecutionContext, parallel: bool, index: usize, set_first: bool, calc: C)
+	where
+		C: Fn(f32, f32, f32) -> (f32, f32) + Sync,
+	{
+		let inputs = [self.input1, self.input2, self.input3];
+		let others: Vec<NodeID> = (0..3).filter(|&i| i != index).map(|i| inputs[i]).collect();
+		let (set_output, add_output) = if set_first {
+			(self.output1, self.output2)
+		} else {
+			(self.output2, self.output1)
+		};
+
+		let mut taken = ctx.take(&inputs[index]);
+		if ctx.is_required_output(&add_output) {
+			zip_for_each!(
+				parallel,
+				Zip::from(&mut taken)
+					.and(ctx.get_input(&others[0]))
+					.and(ctx.get_input(&others[1]))
+					.and(ctx.get_output(&add_output)),
+				|taken, &a, &b, out| {
+					let (set, add) = calc(*taken, a, b);
+					*taken = set;
+					*out += add;
+				}
+			);
+		} else {
+			zip_for_each!(
+				parallel,
+				Zip::from(&mut taken)
+					.and(ctx.get_input(&others[0]))
+					.and(ctx.get_input(&others[1])),
+				|taken, &a, &b| *taken = calc(*taken, a, b).0
+			);
+		}
+		ctx.set(&set_output, taken);
+	}
+}
+
+#[inline]
+fn swap((a, b): (f32, f32)) -> (f32, f32) {
+	(b, a)
+}
+
+impl<F: TernaryDualFunc> OpInstance for TernaryElementwiseDualInstance<F> {
+	fn type_name(&self) -> &'static str {
+		self.f.type_name()
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(TernaryElementwiseDual {
+			input1: graph.node_from_id(self.input1),
+			input2: graph.node_from_id(self.input2),
+			input3: graph.node_from_id(self.input3),
+			output1: graph.node_from_id(self.output1),
+			output2: graph.node_from_id(self.output2),
+			f: self.f.clone(),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input1, self.input2, self.input3]
+	}
+
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		vec![self.input1, self.input2, self.input3]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output1, self.output2]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		self.f.grad(
+			ctx,
+			&self.input1,
+			&self.input2,
+			&self.input3,
+			&self.output1,
+			&self.output2,
+		)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape1: NodeShape = ctx.input_shape(&self.input1).slice().iter().into();
+		let input_shape2: NodeShape = ctx.input_shape(&self.input2).slice().iter().into();
+		let input_shape3: NodeShape = ctx.input_shape(&self.input3).slice().iter().into();
+		let input_shape = input_shape1.merge(&input_shape2)?.merge(&input_shape3)?;
+		ctx.merge_output_shape(&self.output1, &input_shape)?;
+		ctx.merge_output_shape(&self.output2, &input_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		for input in &[self.input2, self.input3] {
+			assert_eq!(
+				ctx.shape(&self.input1),
+				ctx.shape(input),
+				"Alumina Bug: input1 shape: {:?} did not match input shape: {:?}",
+				ctx.shape(&self.input1),
+				ctx.shape(input)
+			);
+		}
+		let required1 = ctx.is_required_output(&self.output1);
+		let required2 = ctx.is_required_output(&self.output2);
+		for &(output, required) in &[(self.output1, required1), (self.output2, required2)] {
+			if required {
+				assert_eq!(
+					ctx.shape(&self.input1),
+					ctx.shape(&output),
+					"Alumina Bug: input1 shape: {:?} did not match output shape: {:?}",
+					ctx.shape(&self.input1),
+					ctx.shape(&output)
+				);
+			}
+		}
+
+		// small arrays are processed on the calling thread, where splitting them up would cost more than it saves
+		let parallel = is_parallel(ctx, ctx.shape(&self.input1).iter().product());
+
+		if self.output1 == self.output2 {
+			zip_for_each!(
+				parallel,
+				Zip::from(ctx.get_input(&self.input1))
+					.and(ctx.get_input(&self.input2))
+					.and(ctx.get_input(&self.input3))
+					.and(ctx.get_output(&self.output1)),
+				|&in1, &in2, &in3, out1| {
+					let (o1, o2) = self.f.calc(in1, in2, in3);
+					*out1 += o1 + o2;
+				}
+			);
+			return Ok(());
+		}
+
+		// An input can only be taken if no other input is the same node. The last input is tried first, as for
+		// backward Ops it is usually the output grad, which is less likely to be read by other Ops.
+		let inputs = [self.input1, self.input2, self.input3];
+		let takeable = (0..3)
+			.rev()
+			.find(|&i| ctx.can_take(&inputs[i]) && (0..3).all(|j| j == i || inputs[j] != inputs[i]));
+
+		match (takeable, required1, required2) {
+			(Some(index), true, _) if ctx.can_set(&self.output1) => self.execute_taking(ctx, parallel, index, true),
+			(Some(index), _, true) if ctx.can_set(&self.output2) => self.execute_taking(ctx, parallel, index, false),
+			(_, true, true) => {
+				zip_for_each!(
+					parallel,
+					Zip::from(ctx.get_input(&self.input1))
+						.and(ctx.get_input(&self.input2))
+						.and(ctx.get_input(&self.input3))
+						.and(ctx.get_output(&self.output1))
+						.and(ctx.get_output(&self.output2)),
+					|&in1, &in2, &in3, out1, out2| {
+						let (o1, o2) = self.f.calc(in1, in2, in3);
+						*out1 += o1;
+						*out2 += o2;
+					}
+				);
+			}
+			(_, true, false) => {
+				zip_for_each!(
+					parallel,
+					Zip::from(ctx.get_input(&self.input1))
+						.and(ctx.get_input(&self.input2))
+						.and(ctx.get_input(&self.input3))
+						.and(ctx.get_output(&self.output1)),
+					|&in1, &in2, &in3, out1| *out1 += self.f.calc(in1, in2, in3).0
+				);
+			}
+			(_, false, true) => {
+				zip_for_each!(
+					parallel,
+					Zip::from(ctx.get_input(&self.input1))
+						.and(ctx.get_input(&self.input2))
+						.and(ctx.get_input(&self.input3))
+						.and(ctx.get_output(&self.output2)),
+					|&in1, &in2, &in3, out2| *out2 += self.f.calc(in1, in2, in3).1
+				);
+			}
+			(_, false, false) => {}
+		}
+
+		Ok(())
+	}
+}
+
 pub trait NaryDualFunc: Send + Sync + Clone + fmt::Debug + 'static {
 	fn calc(&self, input: &[f32]) -> (f32, f32);
 
@@ -810,6 +1195,10 @@ impl<F: NaryDualFunc> OpInstance for NaryElementwiseDualInstance<F> {
 		self.inputs.iter().cloned().collect()
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		self.inputs.clone()
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.output1, self.output2]
 	}
@@ -4,7 +4,7 @@
 //!  * UnaryFunc is the trait which is unique to each implemented Op, defining the forward operation, and any relevant
 //!    gradients.
 //!  * UnaryElementwiseInstance<T: UnaryFunc> is the generic OpInstance
-//!  * UnaryElementwise<T: UnaryFunc> implements the
+//!  * UnaryElementwise<T: UnaryFunc> is the generic OpSpecification, used to build the OpInstance
 //!
 //! This optimised implementations are also available for Nullary, Binary, and Ternary, along with a less efficient
 //! N-ary family for any input number up to 64. All Ops constructed this way have a single output.
@@ -17,12 +17,11 @@ use alumina_core::{
 	exec::ExecutionContext,
 	grad::GradientContext,
 	graph::{Graph, Node, NodeID},
-	shape::NodeShape,
 	shape_prop::ShapePropContext,
 };
 use indexmap::{indexset, IndexMap, IndexSet};
 
-use ndarray::{Dimension, Zip};
+use ndarray::Zip;
 use rayon::prelude::*;
 use std::any::Any;
 use std::fmt;
@@ -138,6 +137,10 @@ pub trait UnaryFunc: Send + Sync + Clone + fmt::Debug + 'static {
 
 	fn type_name(&self) -> &'static str;
 
+	/// Builds the Ops which calculate the gradient of the input from the gradient of the output.
+	///
+	/// Where the derivative can be written in terms of the output, pass `ctx.node(output)` to the backward Op so that
+	/// the value calculated by the forward pass is reused rather than recalculated from the input.
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError>;
 }
 
@@ -168,6 +171,11 @@ impl<F: UnaryFunc> UnaryElementwise<F> {
 	{
 		Self::new(input, output, F::default())
 	}
+
+	/// Returns the function applied by this Op, so that builder methods can be implemented for specific functions.
+	pub fn func_mut(&mut self) -> &mut F {
+		&mut self.f
+	}
 }
 
 impl<F: UnaryFunc> OpSpecification for UnaryElementwise<F> {
@@ -190,7 +198,7 @@ impl<F: UnaryFunc> OpSpecification for UnaryElementwise<F> {
 	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
 		Self {
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
-			input: mapping.get(&self.output).unwrap_or(&self.input).clone(),
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
 			f: self.f.clone(),
 		}
 	}
@@ -238,8 +246,7 @@ impl<F: UnaryFunc> OpInstance for UnaryElementwiseInstance<F> {
 	}
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
-		let input_shape: NodeShape = ctx.input_shape(&self.input).slice().iter().into();
-		ctx.merge_output_shape(&self.output, &input_shape)
+		ctx.set_output_like(&self.output, &self.input)
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
@@ -322,6 +329,11 @@ impl<F: BinaryFunc> BinaryElementwise<F> {
 	{
 		Self::new(input1, input2, output, F::default())
 	}
+
+	/// Returns the function applied by this Op, so that builder methods can be implemented for specific functions.
+	pub fn func_mut(&mut self) -> &mut F {
+		&mut self.f
+	}
 }
 
 impl<F: BinaryFunc> OpSpecification for BinaryElementwise<F> {
@@ -344,8 +356,8 @@ impl<F: BinaryFunc> OpSpecification for BinaryElementwise<F> {
 	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
 		Self {
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
-			input1: mapping.get(&self.output).unwrap_or(&self.input1).clone(),
-			input2: mapping.get(&self.output).unwrap_or(&self.input2).clone(),
+			input1: mapping.get(&self.input1).unwrap_or(&self.input1).clone(),
+			input2: mapping.get(&self.input2).unwrap_or(&self.input2).clone(),
 			f: self.f.clone(),
 		}
 	}
@@ -387,6 +399,10 @@ impl<F: BinaryFunc> OpInstance for BinaryElementwiseInstance<F> {
 		indexset![self.input1, self.input2]
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		vec![self.input1, self.input2]
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.output]
 	}
@@ -396,9 +412,8 @@ impl<F: BinaryFunc> OpInstance for BinaryElementwiseInstance<F> {
 	}
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
-		let input_shape1: NodeShape = ctx.input_shape(&self.input1).slice().iter().into();
-		let input_shape2: NodeShape = ctx.input_shape(&self.input2).slice().iter().into();
-		ctx.merge_output_shape(&self.output, &input_shape1.merge(&input_shape2)?)
+		ctx.require_equal_shapes(&[self.input1, self.input2])?;
+		ctx.set_output_like(&self.output, &self.input1)
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
@@ -418,40 +433,68 @@ impl<F: BinaryFunc> OpInstance for BinaryElementwiseInstance<F> {
 			ctx.shape(&self.output)
 		);
 
+		// small arrays are processed on the calling thread, where splitting them up would cost more than it saves
+		let parallel = ctx.is_parallel(ctx.shape(&self.output).iter().product());
+
 		if ctx.can_take(&self.input1) && ctx.can_set(&self.output) {
 			// if output can be set using the input array, update inplace and do that.
 			let mut input1 = ctx.take(&self.input1);
 			if self.input1 == self.input2 {
-				Zip::from(&mut input1).par_for_each(|in1| {
+				let f = |in1: &mut f32| {
 					*in1 = self.f.calc(*in1, *in1);
-				});
+				};
+				if parallel {
+					Zip::from(&mut input1).par_for_each(f);
+				} else {
+					Zip::from(&mut input1).for_each(f);
+				}
 			} else {
 				let input2 = ctx.get_input(&self.input2);
-				Zip::from(&mut input1).and(input2).par_for_each(|in1, &in2| {
+				let f = |in1: &mut f32, &in2: &f32| {
 					*in1 = self.f.calc(*in1, in2);
-				});
+				};
+				if parallel {
+					Zip::from(&mut input1).and(input2).par_for_each(f);
+				} else {
+					Zip::from(&mut input1).and(input2).for_each(f);
+				}
 			}
 			ctx.set(&self.output, input1);
 		} else if ctx.can_take(&self.input2) && ctx.can_set(&self.output) {
 			let mut input2 = ctx.take(&self.input2);
 			if self.input1 == self.input2 {
-				Zip::from(&mut input2).par_for_each(|in2| {
+				let f = |in2: &mut f32| {
 					*in2 = self.f.calc(*in2, *in2);
-				});
+				};
+				if parallel {
+					Zip::from(&mut input2).par_for_each(f);
+				} else {
+					Zip::from(&mut input2).for_each(f);
+				}
 			} else {
 				let input1 = ctx.get_input(&self.input1);
-				Zip::from(&mut input2).and(input1).par_for_each(|in2, &in1| {
+				let f = |in2: &mut f32, &in1: &f32| {
 					*in2 = self.f.calc(in1, *in2);
-				});
+				};
+				if parallel {
+					Zip::from(&mut input2).and(input1).par_for_each(f);
+				} else {
+					Zip::from(&mut input2).and(input1).for_each(f);
+				}
 			}
 			ctx.set(&self.output, input2);
 		} else {
-			Zip::from(ctx.get_output(&self.output))
+			let zip = Zip::from(ctx.get_output(&self.output))
 				.and(ctx.get_input(&self.input1))
-				.and(ctx.get_input(&self.input2))
-				.par_for_each(|output, &input1, &input2| {
-					*output += self.f.calc(input1, input2);
-				});
+				.and(ctx.get_input(&self.input2));
+			let f = |output: &mut f32, &input1: &f32, &input2: &f32| {
+				*output += self.f.calc(input1, input2);
+			};
+			if parallel {
+				zip.par_for_each(f);
+			} else {
+				zip.for_each(f);
+			}
 		}
 
 		Ok(())
@@ -526,7 +569,7 @@ impl<F: TernaryFunc> OpSpecification for TernaryElementwise<F> {
 
 	/// Returns a list of `Node`s this `Op` may need to read when executed
 	fn inputs(&self) -> IndexSet<Node> {
-		indexset![self.input1.clone(), self.input2.clone()]
+		indexset![self.input1.clone(), self.input2.clone(), self.input3.clone()]
 	}
 
 	/// Returns a list of `Node`s this `Op` may need to write to when executed
@@ -537,9 +580,9 @@ impl<F: TernaryFunc> OpSpecification for TernaryElementwise<F> {
 	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
 		Self {
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
-			input1: mapping.get(&self.output).unwrap_or(&self.input1).clone(),
-			input2: mapping.get(&self.output).unwrap_or(&self.input2).clone(),
-			input3: mapping.get(&self.output).unwrap_or(&self.input3).clone(),
+			input1: mapping.get(&self.input1).unwrap_or(&self.input1).clone(),
+			input2: mapping.get(&self.input2).unwrap_or(&self.input2).clone(),
+			input3: mapping.get(&self.input3).unwrap_or(&self.input3).clone(),
 			f: self.f.clone(),
 		}
 	}
@@ -584,6 +627,10 @@ impl<F: TernaryFunc> OpInstance for TernaryElementwiseInstance<F> {
 		indexset![self.input1, self.input2, self.input3]
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		vec![self.input1, self.input2, self.input3]
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.output]
 	}
@@ -593,10 +640,8 @@ impl<F: TernaryFunc> OpInstance for TernaryElementwiseInstance<F> {
 	}
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
-		let input_shape1: NodeShape = ctx.input_shape(&self.input1).slice().iter().into();
-		let input_shape2: NodeShape = ctx.input_shape(&self.input2).slice().iter().into();
-		let input_shape3: NodeShape = ctx.input_shape(&self.input3).slice().iter().into();
-		ctx.merge_output_shape(&self.output, &input_shape1.merge(&input_shape2.merge(&input_shape3)?)?)
+		ctx.require_equal_shapes(&[self.input1, self.input2, self.input3])?;
+		ctx.set_output_like(&self.output, &self.input1)
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
@@ -824,6 +869,10 @@ impl<F: NaryFunc> OpInstance for NaryElementwiseInstance<F> {
 		self.inputs.iter().cloned().collect()
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		self.inputs.clone()
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.output]
 	}
@@ -834,12 +883,8 @@ impl<F: NaryFunc> OpInstance for NaryElementwiseInstance<F> {
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
 		if !self.inputs.is_empty() {
-			let mut input_shape: NodeShape = ctx.input_shape(&self.inputs[0]).slice().iter().into();
-			for input in &self.inputs[1..] {
-				let next_shape: NodeShape = ctx.input_shape(input).slice().iter().into();
-				input_shape = input_shape.merge(&next_shape)?;
-			}
-			ctx.merge_output_shape(&self.output, &input_shape)
+			ctx.require_equal_shapes(&self.inputs)?;
+			ctx.set_output_like(&self.output, &self.inputs[0])
 		} else {
 			Ok(())
 		}
@@ -0,0 +1,244 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+use std::f32::consts::FRAC_2_SQRT_PI;
+
+/// Complementary error function.
+///
+/// Uses the Chebyshev fitted rational approximation from Numerical Recipes, which has a fractional error below 1.2e-7
+/// everywhere.
+#[inline]
+pub fn erfc_approx(x: f32) -> f32 {
+	let z = x.abs();
+	let t = 1.0 / (1.0 + 0.5 * z);
+	let ans = t
+		* (-z * z - 1.265_512_2
+			+ t * (1.000_023_7
+				+ t * (0.374_091_96
+					+ t * (0.096_784_18
+						+ t * (-0.186_288_06
+							+ t * (0.278_868_07
+								+ t * (-1.135_204 + t * (1.488_515_9 + t * (-0.822_152_2 + t * 0.170_872_77)))))))))
+			.exp();
+	if x >= 0.0 {
+		ans
+	} else {
+		2.0 - ans
+	}
+}
+
+/// Error function.
+///
+/// Near zero a Maclaurin series is used to avoid the cancellation in `1 - erfc(x)`.
+#[inline]
+pub fn erf_approx(x: f32) -> f32 {
+	if x.abs() < 0.5 {
+		let x2 = x * x;
+		x * FRAC_2_SQRT_PI
+			* (1.0 + x2 * (-1.0 / 3.0 + x2 * (1.0 / 10.0 + x2 * (-1.0 / 42.0 + x2 * (1.0 / 216.0 - x2 / 1320.0)))))
+	} else {
+		1.0 - erfc_approx(x)
+	}
+}
+
+/// Returns the error function (erf) of the input.
+///
+/// The output node has the same shape as the input.
+pub fn erf<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("erf({})", input));
+	let _op = Erf::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+/// Returns the complementary error function (erfc) of the input.
+///
+/// This is more accurate than `1 - erf(x)` for large positive inputs.
+///
+/// The output node has the same shape as the input.
+pub fn erfc<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("erfc({})", input));
+	let _op = Erfc::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Erf = UnaryElementwise<ErfFunc>;
+
+pub type Erfc = UnaryElementwise<ErfcFunc>;
+
+pub type ErfBack = BinaryElementwise<ErfBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct ErfFunc {}
+
+impl UnaryFunc for ErfFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		erf_approx(input)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Erf"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		ErfBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			ErfBackFunc { negate: false },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ErfcFunc {}
+
+impl UnaryFunc for ErfcFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		erfc_approx(input)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Erfc"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		ErfBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			ErfBackFunc { negate: true },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of erf or erfc
+/// input2 = grad of output of erf or erfc
+///
+/// If `negate` is true the gradient of erfc is produced.
+#[derive(Clone, Debug, Default)]
+pub struct ErfBackFunc {
+	negate: bool,
+}
+
+impl BinaryFunc for ErfBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		let grad = input2 * FRAC_2_SQRT_PI * (-input1 * input1).exp();
+		if self.negate {
+			-grad
+		} else {
+			grad
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		if self.negate {
+			"ErfcBackward"
+		} else {
+			"ErfBackward"
+		}
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{erf, erfc};
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr1;
+
+	contract_case!(unary "erf", erf);
+	contract_case!(unary "erfc", erfc);
+
+	#[test]
+	fn erf_forward_test() {
+		let input = Node::new(&[8]).set_name("input");
+
+		let output = erf(&input).unwrap();
+
+		input.set_value(arr1(&[0.0, 0.1, 0.3, 0.5, 1.0, 1.25, -0.8, 3.0]));
+		assert!(output.calc().unwrap().all_relatively_close(
+			&arr1(&[
+				0.0,
+				0.112_462_916,
+				0.328_626_76,
+				0.520_499_9,
+				0.842_700_8,
+				0.922_900_1,
+				-0.742_100_96,
+				0.999_977_9
+			]),
+			1e-6
+		));
+	}
+
+	#[test]
+	fn erfc_forward_test() {
+		let input = Node::new(&[6]).set_name("input");
+
+		let output = erfc(&input).unwrap();
+
+		input.set_value(arr1(&[0.0, 0.5, 1.25, -0.8, 2.0, 3.0]));
+		let expected = [1.0, 0.479_500_12, 0.077_099_87, 1.742_101, 0.004_677_735, 2.209_05e-5];
+		// compare relative to the expected value as erfc becomes very small for large inputs
+		assert!(output
+			.calc()
+			.unwrap()
+			.iter()
+			.zip(expected.iter())
+			.all(|(&x, &y)| (x - y).abs() <= 1e-6 * y.abs()));
+	}
+
+	#[test]
+	fn erf_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-2.5, 2.5));
+		let output = erf(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn erfc_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-2.5, 2.5));
+		let output = erfc(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+}
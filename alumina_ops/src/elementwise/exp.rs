@@ -41,7 +41,8 @@ impl UnaryFunc for ExpFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		let _op = Mul::new_default(ctx.grad_of(output), exp(ctx.node(input))?, ctx.grad_of(input)).build()?;
+		// d/dx exp(x) = exp(x), so reuse the forward output
+		let _op = Mul::new_default(ctx.grad_of(output), ctx.node(output), ctx.grad_of(input)).build()?;
 		Ok(())
 	}
 }
@@ -49,11 +50,15 @@ impl UnaryFunc for ExpFunc {
 #[cfg(test)]
 mod tests {
 	use super::exp;
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
-	use ndarray::arr0;
+	use ndarray::{arr0, arr2};
+	use std::f32::consts::E;
+
+	contract_case!(unary "exp", exp);
 
 	#[test]
 	fn forward_test() {
@@ -74,6 +79,19 @@ mod tests {
 			.all_relatively_close(&arr0(0.449_328_96), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn forward_arr2_test() {
+		let input = Node::new(&[2, 3])
+			.set_name("input")
+			.set_value(arr2(&[[0.0, 1.0, -1.0], [2.5, -3.0, 0.5]]));
+
+		let output = exp(&input).unwrap();
+		assert_eq!(output.shape(), input.shape());
+
+		let expected = arr2(&[[1.0, E, 0.367_879_45], [12.182_494, 0.049_787_07, 1.648_721_3]]);
+		assert!(output.calc().unwrap().all_relatively_close(&expected, 1e-6));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[37, 33]).set_name("input");
@@ -0,0 +1,132 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the natural exponent of the input minus one (exp(x) - 1).
+///
+/// This is more accurate than `offset(exp(x), -1.0)` for inputs close to zero.
+///
+/// The output node has the same shape as the input.
+pub fn expm1<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("expm1({})", input));
+	let _op = Expm1::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Expm1 = UnaryElementwise<Expm1Func>;
+
+pub type Expm1Back = BinaryElementwise<Expm1BackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct Expm1Func {}
+
+impl UnaryFunc for Expm1Func {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.exp_m1()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Expm1"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		Expm1Back::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of expm1
+/// input2 = grad of output of expm1
+#[derive(Clone, Debug, Default)]
+pub struct Expm1BackFunc {}
+
+impl BinaryFunc for Expm1BackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * input1.exp()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Expm1Backward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::expm1;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "expm1", expm1);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = expm1(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(2.490_343), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.550_671_04), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn forward_small_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = expm1(&input).unwrap();
+
+		// exp(1e-5) - 1 computed naively in f32 loses most significant digits
+		input.set_value(arr0(1e-5));
+		assert!(output
+			.calc()
+			.unwrap()
+			.iter()
+			.all(|&x| (x - 1.000_005e-5).abs() <= 1e-6 * 1.000_005e-5));
+
+		input.set_value(arr0(-1e-7));
+		assert!(output.calc().unwrap().iter().all(|&x| (x + 1e-7).abs() <= 1e-6 * 1e-7));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = expm1(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
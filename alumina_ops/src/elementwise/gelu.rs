@@ -0,0 +1,189 @@
+use crate::elementwise::{
+	elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc},
+	erf::erf_approx,
+};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_2_SQRT_PI};
+
+/// `sqrt(2 / pi)`
+const SQRT_2_FRAC_PI: f32 = FRAC_2_SQRT_PI * FRAC_1_SQRT_2;
+
+/// Coefficient of the cubic term in the tanh approximation.
+const TANH_CUBIC: f32 = 0.044_715;
+
+/// Applies the Gaussian error linear unit (GELU) to each element of the input.
+///
+/// `let output = input * 0.5 * (1 + erf(input / sqrt(2)))`
+///
+/// The output node has the same shape as the input.
+pub fn gelu<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("gelu({})", input));
+	let _op = Gelu::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Gelu = UnaryElementwise<GeluFunc>;
+
+impl Gelu {
+	/// If true, use the tanh approximation used by many transformer models rather than the exact erf form.
+	///
+	/// `let output = 0.5 * input * (1 + tanh(sqrt(2 / pi) * (input + 0.044715 * input^3)))`
+	///
+	/// Default: false
+	pub fn approximate(mut self, approximate: bool) -> Self {
+		self.func_mut().approximate = approximate;
+		self
+	}
+}
+
+pub type GeluBack = BinaryElementwise<GeluBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct GeluFunc {
+	approximate: bool,
+}
+
+impl UnaryFunc for GeluFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		if self.approximate {
+			let inner = SQRT_2_FRAC_PI * (input + TANH_CUBIC * input * input * input);
+			0.5 * input * (1.0 + inner.tanh())
+		} else {
+			0.5 * input * (1.0 + erf_approx(input * FRAC_1_SQRT_2))
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Gelu"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		GeluBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			GeluBackFunc {
+				approximate: self.approximate,
+			},
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of gelu
+/// input2 = grad of output of gelu
+#[derive(Clone, Debug, Default)]
+pub struct GeluBackFunc {
+	approximate: bool,
+}
+
+impl BinaryFunc for GeluBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if self.approximate {
+			let x2 = input1 * input1;
+			let tanh = (SQRT_2_FRAC_PI * input1 * (1.0 + TANH_CUBIC * x2)).tanh();
+			let d_inner = SQRT_2_FRAC_PI * (1.0 + 3.0 * TANH_CUBIC * x2);
+			input2 * 0.5 * (1.0 + tanh + input1 * (1.0 - tanh * tanh) * d_inner)
+		} else {
+			// cdf(x) + x * pdf(x)
+			let cdf = 0.5 * (1.0 + erf_approx(input1 * FRAC_1_SQRT_2));
+			let pdf = 0.5 * FRAC_2_SQRT_PI * FRAC_1_SQRT_2 * (-0.5 * input1 * input1).exp();
+			input2 * (cdf + input1 * pdf)
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"GeluBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{gelu, Gelu};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr1;
+
+	contract_case!(unary "gelu", gelu);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[5])
+			.set_name("input")
+			.set_value(arr1(&[-2.0, -1.0, 0.0, 1.0, 2.0]));
+
+		let output = gelu(&input).unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(
+			&arr1(&[-0.045_500_264, -0.158_655_25, 0.0, 0.841_344_7, 1.954_499_7]),
+			1e-5
+		));
+	}
+
+	#[test]
+	fn forward_approximate_test() {
+		let input = Node::new(&[5])
+			.set_name("input")
+			.set_value(arr1(&[-2.0, -1.0, 0.0, 1.0, 2.0]));
+		let output = input
+			.graph()
+			.new_node(input.shape())
+			.set_name_unique(&format!("gelu({})", input));
+
+		Gelu::new_default(&input, &output).approximate(true).build().unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(
+			&arr1(&[-0.045_402_306, -0.158_808_01, 0.0, 0.841_192, 1.954_597_7]),
+			1e-5
+		));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-4.0, 4.0));
+		let output = gelu(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn grad_numeric_approximate_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-4.0, 4.0));
+		let output = input
+			.graph()
+			.new_node(input.shape())
+			.set_name_unique(&format!("gelu({})", input));
+
+		Gelu::new_default(&input, &output).approximate(true).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
@@ -105,13 +105,16 @@ impl UnaryFunc for IdentityFunc {
 
 #[cfg(test)]
 mod tests {
-	use super::identity;
+	use super::{add, identity};
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(binary "add", add);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -138,4 +141,19 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).run();
 	}
+
+	#[test]
+	fn add_shared_input_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = add(&input, &input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(2.5), ::std::f32::EPSILON));
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
 }
@@ -6,10 +6,10 @@ use alumina_core::{
 	graph::{Node, NodeID},
 };
 
-/// Returns the leaky rectified linear unit activation (leaky relu) of the input.
+/// Returns the leaky rectified linear unit activation (leaky relu) of the input, with a negative slope of 0.01.
 ///
 /// The output node has the same shape as the input.
-pub fn leaky_relu<I>(input: I, alpha: f32) -> Result<Node, OpBuildError>
+pub fn leaky_relu<I>(input: I) -> Result<Node, OpBuildError>
 where
 	I: Into<Node>,
 {
@@ -18,22 +18,48 @@ where
 		.graph()
 		.new_node(input.shape())
 		.set_name_unique(&format!("leaky_relu({})", input));
-	let _op = LeakyRelu::new(input, output.clone(), LeakyReluFunc { alpha }).build()?;
+	let _op = LeakyRelu::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+/// Returns the leaky rectified linear unit activation (leaky relu) of the input, with the given negative slope.
+///
+/// The output node has the same shape as the input.
+pub fn leaky_relu_with_slope<I>(input: I, slope: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("leaky_relu({})", input));
+	let _op = LeakyRelu::new_default(input, output.clone()).slope(slope).build()?;
 	Ok(output)
 }
 
 pub type LeakyRelu = UnaryElementwise<LeakyReluFunc>;
 
+impl LeakyRelu {
+	/// The gradient of the output with respect to the input, where the input is negative.
+	///
+	/// Default: 0.01
+	pub fn slope(mut self, slope: f32) -> Self {
+		self.func_mut().slope = slope;
+		self
+	}
+}
+
 pub type LeakyReluBack = BinaryElementwise<LeakyReluBackFunc>;
 
 #[derive(Clone, Debug)]
 pub struct LeakyReluFunc {
-	alpha: f32,
+	slope: f32,
 }
 
 impl Default for LeakyReluFunc {
 	fn default() -> Self {
-		Self { alpha: 0.1 }
+		Self { slope: 0.01 }
 	}
 }
 
@@ -47,7 +73,7 @@ impl UnaryFunc for LeakyReluFunc {
 		// 	alpha * input
 		// }
 
-		let half_grad_change_at_zero = (1.0 - self.alpha) * 0.5;
+		let half_grad_change_at_zero = (1.0 - self.slope) * 0.5;
 
 		input.abs() * half_grad_change_at_zero + input * (1.0 - half_grad_change_at_zero)
 		// TODO does this vectorize?
@@ -62,7 +88,7 @@ impl UnaryFunc for LeakyReluFunc {
 			ctx.node(input),
 			ctx.grad_of(output),
 			ctx.grad_of(input),
-			LeakyReluBackFunc { alpha: self.alpha },
+			LeakyReluBackFunc { slope: self.slope },
 		)
 		.build()?;
 		Ok(())
@@ -73,20 +99,23 @@ impl UnaryFunc for LeakyReluFunc {
 /// input2 = grad of output of leaky_relu
 #[derive(Clone, Debug)]
 pub struct LeakyReluBackFunc {
-	alpha: f32,
+	slope: f32,
 }
 
 impl Default for LeakyReluBackFunc {
 	fn default() -> Self {
-		Self { alpha: 0.1 }
+		Self { slope: 0.01 }
 	}
 }
 
 impl BinaryFunc for LeakyReluBackFunc {
 	#[inline]
 	fn calc(&self, input1: f32, input2: f32) -> f32 {
-		let sign = input1.signum();
-		input2 * ((1.0 + self.alpha) + sign * (1.0 - self.alpha)) * 0.5 // x.signum().max(0.0); <- this should be better but doesnt compile to maxps,
+		if input1 >= 0.0 {
+			input2
+		} else {
+			input2 * self.slope
+		}
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -106,18 +135,21 @@ impl BinaryFunc for LeakyReluBackFunc {
 
 #[cfg(test)]
 mod tests {
-	use super::leaky_relu;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{leaky_relu, leaky_relu_with_slope, LeakyRelu};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node, init::uniform};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
-	use indexmap::indexset;
+	use indexmap::{indexmap, indexset};
 	use ndarray::arr0;
 
+	contract_case!(unary "leaky_relu", leaky_relu);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
 
-		let output = leaky_relu(&input, 0.2).unwrap();
+		let output = leaky_relu_with_slope(&input, 0.2).unwrap();
 
 		input.set_value(arr0(1.25));
 		assert!(output
@@ -132,10 +164,46 @@ mod tests {
 			.all_relatively_close(&arr0(-0.16), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn forward_default_slope_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = leaky_relu(&input).unwrap();
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.008), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn slope_round_trip_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(-0.8));
+		let output = Node::new(&[13, 33]).set_name("output");
+		let new_output = Node::new(&[13, 33]).set_name("new_output");
+
+		let op = LeakyRelu::new_default(&input, &output).slope(0.1).build().unwrap();
+
+		// the slope must survive both the instance to specification round trip, and the cloning of the specification
+		let spec = op
+			.instance()
+			.as_specification(op.graph())
+			.downcast::<LeakyRelu>()
+			.unwrap()
+			.clone_with_nodes_changed(&indexmap![output.clone() => new_output.clone()]);
+		spec.build().unwrap();
+
+		assert!(new_output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.08), ::std::f32::EPSILON));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
-		let output = leaky_relu(&input, 0.2).unwrap();
+		let output = leaky_relu_with_slope(&input, 0.1).unwrap();
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
 	}
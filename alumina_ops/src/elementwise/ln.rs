@@ -49,12 +49,15 @@ impl UnaryFunc for LnFunc {
 #[cfg(test)]
 mod tests {
 	use super::ln;
+	use crate::tests::contract_case;
 	use alumina_core::{graph::Node, init::uniform};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "ln", ln);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -0,0 +1,132 @@
+use crate::elementwise::{
+	div::Div,
+	elementwise_single::{UnaryElementwise, UnaryFunc},
+};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the natural logarithm of the input plus a small epsilon, `ln(input + epsilon)`.
+///
+/// Unlike `ln(..)` the output stays finite for inputs of zero, such as probabilities fed to a cross entropy. To change
+/// the epsilon use `Log::epsilon(..)`.
+///
+/// The output node has the same shape as the input.
+pub fn log<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("log({})", input));
+	let _op = Log::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Log = UnaryElementwise<LogFunc>;
+
+impl Log {
+	/// Added to the input before the logarithm is taken, preventing an infinite output for inputs of zero.
+	///
+	/// Default: 1e-8
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon >= 0.0, "epsilon {} must not be negative", epsilon);
+		self.func_mut().epsilon = epsilon;
+		self
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct LogFunc {
+	epsilon: f32,
+}
+
+impl Default for LogFunc {
+	fn default() -> Self {
+		LogFunc { epsilon: 1e-8 }
+	}
+}
+
+impl UnaryFunc for LogFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		(input + self.epsilon).ln()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Log"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		let _op = Div::new_default(ctx.grad_of(output), ctx.node(input), ctx.grad_of(input))
+			.epsilon(self.epsilon)
+			.build()?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{log, Log};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "log", log);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = log(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(0.223_143_55), 1e-6));
+
+		// stays finite at zero
+		input.set_value(arr0(0.0));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(-18.420_681), 1e-6));
+	}
+
+	#[test]
+	fn epsilon_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(0.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Log::new_default(&input, &output).epsilon(0.5).build().unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(0.5f32.ln()), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(0.1, 1.0));
+		let output = log(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(1e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_epsilon_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(0.0, 1.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Log::new_default(&input, &output).epsilon(0.5).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(1e-3)
+			.run();
+	}
+}
@@ -0,0 +1,132 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the natural logarithm of one plus the input (ln(1 + x)).
+///
+/// This is more accurate than `ln(offset(x, 1.0))` for inputs close to zero.
+///
+/// The output node has the same shape as the input.
+pub fn log1p<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("log1p({})", input));
+	let _op = Log1p::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Log1p = UnaryElementwise<Log1pFunc>;
+
+pub type Log1pBack = BinaryElementwise<Log1pBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct Log1pFunc {}
+
+impl UnaryFunc for Log1pFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.ln_1p()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Log1p"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		Log1pBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of log1p
+/// input2 = grad of output of log1p
+#[derive(Clone, Debug, Default)]
+pub struct Log1pBackFunc {}
+
+impl BinaryFunc for Log1pBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 / (1.0 + input1)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Log1pBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::log1p;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "log1p", log1p);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = log1p(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.810_930_2), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-1.609_438), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn forward_small_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = log1p(&input).unwrap();
+
+		// ln(1 + 1e-5) computed naively in f32 loses most significant digits
+		input.set_value(arr0(1e-5));
+		assert!(output
+			.calc()
+			.unwrap()
+			.iter()
+			.all(|&x| (x - 9.999_95e-6).abs() <= 1e-6 * 9.999_95e-6));
+
+		input.set_value(arr0(-1e-7));
+		assert!(output.calc().unwrap().iter().all(|&x| (x + 1e-7).abs() <= 1e-6 * 1e-7));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-0.9, 5.0));
+		let output = log1p(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+}
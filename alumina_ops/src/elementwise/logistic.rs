@@ -28,6 +28,8 @@ pub type Logistic = UnaryElementwise<LogisticFunc>;
 
 pub type LogisticBack = BinaryElementwise<LogisticBackFunc>;
 
+pub type LogisticBackFromOutput = BinaryElementwise<LogisticBackFromOutputFunc>;
+
 #[derive(Clone, Debug, Default)]
 pub struct LogisticFunc {}
 
@@ -42,7 +44,7 @@ impl UnaryFunc for LogisticFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		LogisticBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		LogisticBackFromOutput::new_default(ctx.node(output), ctx.grad_of(output), ctx.grad_of(input)).build()?;
 		Ok(())
 	}
 }
@@ -74,15 +76,47 @@ impl BinaryFunc for LogisticBackFunc {
 	}
 }
 
+/// input1 = output of logistic
+/// input2 = grad of output of logistic
+#[derive(Clone, Debug, Default)]
+pub struct LogisticBackFromOutputFunc {}
+
+impl BinaryFunc for LogisticBackFromOutputFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * input1 * (1.0 - input1)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"LogisticBackwardFromOutput"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::logistic;
-	use alumina_core::graph::Node;
+	use super::{logistic, LogisticBack, LogisticBackFromOutput};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		graph::{merge_graphs, Node},
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "logistic", logistic);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -109,4 +143,25 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).run();
 	}
+
+	#[test]
+	fn back_from_output_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_random(-3.0, 3.0, 0);
+		let grad = Node::new(&[13, 33]).set_name("grad").set_random(-1.0, 1.0, 1);
+		let output = logistic(&input).unwrap();
+		let from_input = Node::new(&[13, 33]).set_name("from_input");
+		let from_output = Node::new(&[13, 33]).set_name("from_output");
+		merge_graphs(&[input.graph(), grad.graph(), from_input.graph(), from_output.graph()]);
+
+		// the back Op takes the input of logistic, and the FromOutput variant takes its output
+		let _op = LogisticBack::new_default(&input, &grad, &from_input).build().unwrap();
+		let _op = LogisticBackFromOutput::new_default(&output, &grad, &from_output)
+			.build()
+			.unwrap();
+
+		assert!(from_input
+			.calc()
+			.unwrap()
+			.all_relatively_close(&from_output.calc().unwrap(), 1e-5));
+	}
 }
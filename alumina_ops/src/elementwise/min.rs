@@ -1,4 +1,8 @@
-use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc};
+use crate::elementwise::{
+	elementwise_dual::{TernaryDualFunc, TernaryElementwiseDual},
+	elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc},
+	identity::Identity,
+};
 use alumina_core::{
 	base_ops::OpSpecification,
 	errors::{GradientError, OpBuildError},
@@ -27,10 +31,87 @@ where
 	Ok(output)
 }
 
+/// Calculates the elementwise minimum (min) of input1 and input2, without naming the output node or the Op.
+///
+/// Generating unique names can dominate the cost of building graphs with many thousands of Ops, this skips it and
+/// otherwise behaves the same as `min(..)`.
+///
+/// The output node has the same shape as the input.
+pub fn min_unnamed<I1, I2>(input1: I1, input2: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let input1 = input1.into();
+	let input2 = input2.into();
+	merge_graphs(&[input1.graph(), input2.graph()]);
+	let output = input1.graph().new_node(input1.shape());
+	let _op = Min::new_default(input1, input2, output.clone()).build_unnamed()?;
+	Ok(output)
+}
+
+/// Calculates the elementwise minimum (min) of input1 and input2, distributing the gradient according to `tie_break`
+/// where input1 and input2 are equal.
+///
+/// Unlike `min(..)`, which passes no gradient to either input at a tie, this can be used where ties are common, e.g.
+/// on quantised or clamped data.
+///
+/// The output node has the same shape as the input.
+pub fn min_with_tie_break<I1, I2>(input1: I1, input2: I2, tie_break: TieBreak) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let input1 = input1.into();
+	let input2 = input2.into();
+	merge_graphs(&[input1.graph(), input2.graph()]);
+	let output = input1
+		.graph()
+		.new_node(input1.shape())
+		.set_name_unique(&format!("min({},{})", input1, input2));
+	let _op = Min::new(input1, input2, output.clone(), MinFunc::with_tie_break(tie_break)).build()?;
+	Ok(output)
+}
+
+/// How the gradient of `min` or `max` is distributed between input1 and input2 where they are equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreak {
+	/// All of the gradient goes to input1.
+	First,
+
+	/// All of the gradient goes to input2.
+	Second,
+
+	/// The gradient is split equally between input1 and input2.
+	Split,
+}
+
+impl TieBreak {
+	/// Returns the share of the gradient for input1 and input2 respectively.
+	pub(crate) fn shares(tie_break: Option<TieBreak>) -> (f32, f32) {
+		match tie_break {
+			None => (0.0, 0.0),
+			Some(TieBreak::First) => (1.0, 0.0),
+			Some(TieBreak::Second) => (0.0, 1.0),
+			Some(TieBreak::Split) => (0.5, 0.5),
+		}
+	}
+}
+
 pub type Min = BinaryElementwise<MinFunc>;
 
 #[derive(Clone, Debug, Default)]
-pub struct MinFunc {}
+pub struct MinFunc {
+	tie_break: Option<TieBreak>,
+}
+
+impl MinFunc {
+	pub fn with_tie_break(tie_break: TieBreak) -> Self {
+		MinFunc {
+			tie_break: Some(tie_break),
+		}
+	}
+}
 
 impl BinaryFunc for MinFunc {
 	#[inline]
@@ -49,19 +130,19 @@ impl BinaryFunc for MinFunc {
 		input2: &NodeID,
 		output: &NodeID,
 	) -> Result<(), GradientError> {
-		// TODO combine into single backward
-		let _op = MinBack::new_default(
+		if input1 == input2 {
+			// min(x, x) = x, the MinBack ops would both see a tie and by default route no gradient
+			let _op = Identity::new_default(ctx.grad_of(output), ctx.grad_of(input1)).build()?;
+			return Ok(());
+		}
+		let (tie_share1, tie_share2) = TieBreak::shares(self.tie_break);
+		let _op = MinBackBoth::new(
 			ctx.node(input1),
 			ctx.node(input2),
 			ctx.grad_of(output),
 			ctx.grad_of(input1),
-		)
-		.build()?;
-		let _op = MinBack::new_default(
-			ctx.node(input2),
-			ctx.node(input1),
-			ctx.grad_of(output),
 			ctx.grad_of(input2),
+			MinBackBothFunc { tie_share1, tie_share2 },
 		)
 		.build()?;
 		Ok(())
@@ -74,13 +155,19 @@ pub type MinBack = TernaryElementwise<MinBackFunc>;
 /// input2 = an input of min
 /// input3 = grad of output of min
 /// returns grad for input1
+///
+/// `tie_share` is the proportion of the grad given to input1 where input1 and input2 are equal.
 #[derive(Clone, Debug, Default)]
-pub struct MinBackFunc {}
+pub struct MinBackFunc {
+	tie_share: f32,
+}
 impl TernaryFunc for MinBackFunc {
 	#[inline]
 	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
 		if input1 < input2 {
 			input3
+		} else if input1 == input2 {
+			input3 * self.tie_share
 		} else {
 			0.0
 		}
@@ -92,59 +179,166 @@ impl TernaryFunc for MinBackFunc {
 
 	fn grad(
 		&self,
-		_ctx: &mut GradientContext,
-		_input1: &NodeID,
-		_input2: &NodeID,
-		_input3: &NodeID,
-		_output: &NodeID,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		input3: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		// The gate is piecewise constant so the grads of input1 and input2 are zero, and the grad of input3 is gated
+		// the same way as input3 itself.
+		let _op = MinBack::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input3),
+			self.clone(),
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+pub type MinBackBoth = TernaryElementwiseDual<MinBackBothFunc>;
+
+/// input1 = input1 of min
+/// input2 = input2 of min
+/// input3 = grad of output of min
+/// returns grads for input1 and input2
+///
+/// Equivalent to a `MinBack` for each input, but calculates both grads in a single pass.
+///
+/// `tie_share1` and `tie_share2` are the proportions of the grad given to input1 and input2 where they are equal.
+#[derive(Clone, Debug, Default)]
+pub struct MinBackBothFunc {
+	tie_share1: f32,
+	tie_share2: f32,
+}
+
+impl TernaryDualFunc for MinBackBothFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> (f32, f32) {
+		if input1 < input2 {
+			(input3, 0.0)
+		} else if input1 > input2 {
+			(0.0, input3)
+		} else {
+			(input3 * self.tie_share1, input3 * self.tie_share2)
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MinBackBoth"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		input3: &NodeID,
+		output1: &NodeID,
+		output2: &NodeID,
 	) -> Result<(), GradientError> {
-		Err(GradientError::Unimplemented)
+		// As for MinBack, only input3 receives a grad, gated the same way as each output.
+		let gates = [
+			(input1, input2, output1, self.tie_share1),
+			(input2, input1, output2, self.tie_share2),
+		];
+		for &(gated, other, output, tie_share) in &gates {
+			if ctx.contains(output) {
+				let _op = MinBack::new(
+					ctx.node(gated),
+					ctx.node(other),
+					ctx.grad_of(output),
+					ctx.grad_of(input3),
+					MinBackFunc { tie_share },
+				)
+				.build()?;
+			}
+		}
+		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::min;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{min, min_unnamed, min_with_tie_break, MinBack, MinBackBoth, MinBackBothFunc, MinBackFunc, TieBreak};
+	use crate::elementwise::elementwise_dual::PARALLEL_EXECUTIONS;
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		exec::ExecutionPlan,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::{uniform, Initialiser},
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
-	use indexmap::indexset;
-	use ndarray::arr0;
+	use indexmap::{indexset, IndexMap};
+	use ndarray::{arr0, Array2, Zip};
+
+	contract_case!(binary "min", min);
 
 	#[test]
 	fn forward_test() {
-		let input1 = Node::new(&[13, 33]).set_name("input1");
-		let input2 = Node::new(&[13, 33]).set_name("input2");
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_random(-1.0, 1.0, 0);
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_random(-1.0, 1.0, 1);
 
 		let output = min(&input1, &input2).unwrap();
 
-		input1.set_value(arr0(1.25));
-		input2.set_value(arr0(1.25));
-		assert!(output
-			.calc()
-			.unwrap()
-			.all_relatively_close(&arr0(1.25), ::std::f32::EPSILON));
+		let expected = Zip::from(&input1.value().unwrap())
+			.and(&input2.value().unwrap())
+			.map_collect(|&input1: &f32, &input2| input1.min(input2));
+		assert!(output.calc().unwrap().all_relatively_close(&expected, f32::EPSILON));
 
-		input1.set_value(arr0(1.25));
-		input2.set_value(arr0(-0.8));
+		// ties
+		input2.set_value(input1.value().unwrap());
 		assert!(output
 			.calc()
 			.unwrap()
-			.all_relatively_close(&arr0(-0.8), ::std::f32::EPSILON));
+			.all_relatively_close(&input1.value().unwrap(), f32::EPSILON));
+	}
 
-		input1.set_value(arr0(-0.8));
-		input2.set_value(arr0(1.25));
-		assert!(output
-			.calc()
-			.unwrap()
-			.all_relatively_close(&arr0(-0.8), ::std::f32::EPSILON));
+	#[test]
+	fn unnamed_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_random(-1.0, 1.0, 0);
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_random(-1.0, 1.0, 1);
 
-		input1.set_value(arr0(-0.8));
-		input2.set_value(arr0(-0.8));
-		assert!(output
-			.calc()
-			.unwrap()
-			.all_relatively_close(&arr0(-0.8), ::std::f32::EPSILON));
+		let output = min_unnamed(&input1, &input2).unwrap();
+
+		assert_eq!(output.name(), "Unnamed_Node");
+		assert!(output.parent_ops().iter().all(|op| op.name() == "Unnamed_Op"));
+		assert_eq!(output.calc().unwrap(), min(&input1, &input2).unwrap().calc().unwrap());
+
+		// gradients are unaffected by the output being unnamed
+		let grads = Grad::of(&output).wrt(&[&input1, &input2]).build().unwrap();
+		let expected_grads = Grad::of(min(&input1, &input2).unwrap())
+			.wrt(&[&input1, &input2])
+			.build()
+			.unwrap();
+		for input in &[&input1, &input2] {
+			assert_eq!(grads[*input].calc().unwrap(), expected_grads[*input].calc().unwrap());
+		}
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input1 = Node::new(&[-1, 3])
+			.set_name("input1")
+			.set_value(Array2::<f32>::zeros((2, 3)));
+		let input2 = Node::new(&[-1, 3])
+			.set_name("input2")
+			.set_value(Array2::<f32>::zeros((4, 3)));
+
+		let output = min(&input1, &input2).unwrap();
+
+		let message = format!("{}", output.calc().unwrap_err());
+		assert!(
+			message.contains("Node `input1` has shape [2, 3] and Node `input2` has shape [4, 3]"),
+			"{}",
+			message
+		);
 	}
 
 	#[test]
@@ -172,17 +366,203 @@ mod tests {
 			.run();
 	}
 
-	// #[test]
-	// fn grad_numeric_shared_input_test() {
-	// 	let input1 = Node::new(&[13, 33])
-	// 		.set_name("input1")
-	// 		.set_init(Initialiser::uniform(-1.0, 1.0));
+	#[test]
+	fn grad_numeric_shared_input_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_init(uniform(-1.0, 1.0));
+
+		let output = min(&input1, &input1).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input1])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_init(uniform(-1.0, 1.0));
+		// alternate the gate without ever approaching a tie
+		let alternating = Initialiser::new("alternating".to_string(), |mut arr| {
+			for (i, x) in arr.iter_mut().enumerate() {
+				*x = if i % 2 == 0 { 2.0 } else { -2.0 };
+			}
+		});
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_init(alternating);
+		let grad = Node::new(&[13, 33]).set_name("grad");
+		let output = Node::new(&[13, 33]).set_name("output");
+		merge_graphs(&[input1.graph(), input2.graph(), grad.graph(), output.graph()]);
+
+		let _op = MinBack::new_default(&input1, &input2, &grad, &output).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input1, &input2, &grad])
+			.expect_zero(&input1, ::std::f32::EPSILON)
+			.expect_zero(&input2, ::std::f32::EPSILON)
+			.run();
+	}
+
+	#[test]
+	fn back_both_matches_two_pass_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_random(-1.0, 1.0, 0);
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_random(-1.0, 1.0, 1);
+		let grad = Node::new(&[13, 33]).set_name("grad").set_random(-1.0, 1.0, 2);
+		merge_graphs(&[input1.graph(), input2.graph(), grad.graph()]);
 
-	// 	let output = min(&input1, &input1);
+		// include ties so that the shares are exercised
+		let mut value2 = input2.value().unwrap();
+		let value1 = input1.value().unwrap();
+		for (i, (x2, &x1)) in value2.iter_mut().zip(value1.iter()).enumerate() {
+			if i % 5 == 0 {
+				*x2 = x1;
+			}
+		}
+		input2.set_value(value2);
 
-	// 	GradNumericTest::new(&output, &indexset![&input1])
-	// 		.step_size(1e-3)
-	// 		.tolerance(4e-3)
-	// 		.run();
-	// }
+		let single1 = Node::new(&[13, 33]).set_name("single1");
+		let single2 = Node::new(&[13, 33]).set_name("single2");
+		let two_pass1 = Node::new(&[13, 33]).set_name("two_pass1");
+		let two_pass2 = Node::new(&[13, 33]).set_name("two_pass2");
+		merge_graphs(&[
+			input1.graph(),
+			single1.graph(),
+			single2.graph(),
+			two_pass1.graph(),
+			two_pass2.graph(),
+		]);
+
+		let (tie_share1, tie_share2) = (0.25, 0.75);
+		let _op = MinBackBoth::new(
+			&input1,
+			&input2,
+			&grad,
+			&single1,
+			&single2,
+			MinBackBothFunc { tie_share1, tie_share2 },
+		)
+		.build()
+		.unwrap();
+		let _op = MinBack::new(
+			&input1,
+			&input2,
+			&grad,
+			&two_pass1,
+			MinBackFunc { tie_share: tie_share1 },
+		)
+		.build()
+		.unwrap();
+		let _op = MinBack::new(
+			&input2,
+			&input1,
+			&grad,
+			&two_pass2,
+			MinBackFunc { tie_share: tie_share2 },
+		)
+		.build()
+		.unwrap();
+
+		assert!(single1
+			.calc()
+			.unwrap()
+			.all_relatively_close(&two_pass1.calc().unwrap(), ::std::f32::EPSILON));
+		assert!(single2
+			.calc()
+			.unwrap()
+			.all_relatively_close(&two_pass2.calc().unwrap(), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn back_both_grad_numeric_second_order_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_init(uniform(-1.0, 1.0));
+		// alternate the gate without ever approaching a tie
+		let alternating = Initialiser::new("alternating".to_string(), |mut arr| {
+			for (i, x) in arr.iter_mut().enumerate() {
+				*x = if i % 2 == 0 { 2.0 } else { -2.0 };
+			}
+		});
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_init(alternating);
+		let grad = Node::new(&[13, 33]).set_name("grad");
+		let output1 = Node::new(&[13, 33]).set_name("output1");
+		let output2 = Node::new(&[13, 33]).set_name("output2");
+		merge_graphs(&[
+			input1.graph(),
+			input2.graph(),
+			grad.graph(),
+			output1.graph(),
+			output2.graph(),
+		]);
+
+		let _op = MinBackBoth::new_default(&input1, &input2, &grad, &output1, &output2)
+			.build()
+			.unwrap();
+
+		for output in &[&output1, &output2] {
+			GradNumericTest::new(*output, &indexset![&input1, &input2, &grad])
+				.expect_zero(&input1, ::std::f32::EPSILON)
+				.expect_zero(&input2, ::std::f32::EPSILON)
+				.run();
+		}
+	}
+
+	#[test]
+	fn tie_break_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_value(arr0(0.5));
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_value(arr0(0.5));
+
+		let policies = [
+			(None, 0.0, 0.0),
+			(Some(TieBreak::First), 1.0, 0.0),
+			(Some(TieBreak::Second), 0.0, 1.0),
+			(Some(TieBreak::Split), 0.5, 0.5),
+		];
+		for &(tie_break, expected1, expected2) in &policies {
+			let output = match tie_break {
+				Some(tie_break) => min_with_tie_break(&input1, &input2, tie_break).unwrap(),
+				None => min(&input1, &input2).unwrap(),
+			};
+			let grads = Grad::of(&output).wrt(&[&input1, &input2]).build().unwrap();
+
+			assert!(grads[&input1]
+				.calc()
+				.unwrap()
+				.all_relatively_close(&arr0(expected1), ::std::f32::EPSILON));
+			assert!(grads[&input2]
+				.calc()
+				.unwrap()
+				.all_relatively_close(&arr0(expected2), ::std::f32::EPSILON));
+		}
+	}
+
+	#[test]
+	fn par_threshold_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1").set_random(-1.0, 1.0, 0);
+		let input2 = Node::new(&[13, 33]).set_name("input2").set_random(-1.0, 1.0, 1);
+
+		let output = min(&input1, &input2).unwrap();
+		let grads = Grad::of(&output).wrt(&[&input1, &input2]).build().unwrap();
+		let outputs = indexset![output.clone(), grads[&input1].clone(), grads[&input2].clone()];
+
+		let mut expected = Array2::zeros([13, 33]).into_dyn();
+		Zip::from(&mut expected)
+			.and(&input1.value().unwrap())
+			.and(&input2.value().unwrap())
+			.for_each(|expected, &input1: &f32, &input2| *expected = input1.min(input2));
+
+		// thresholds of 0 and usize::MAX force parallel and serial execution respectively, which for the gradient is
+		// visible through the MinBackBoth executions counted on this thread
+		let parallel_executions = PARALLEL_EXECUTIONS.with(|count| count.get());
+		let parallel = ExecutionPlan::new(IndexMap::<Node, _>::new(), &outputs)
+			.par_threshold(Some(0))
+			.execute()
+			.unwrap();
+		assert_eq!(PARALLEL_EXECUTIONS.with(|count| count.get()) - parallel_executions, 1);
+
+		let parallel_executions = PARALLEL_EXECUTIONS.with(|count| count.get());
+		let serial = ExecutionPlan::new(IndexMap::<Node, _>::new(), &outputs)
+			.par_threshold(Some(usize::MAX))
+			.execute()
+			.unwrap();
+		assert_eq!(PARALLEL_EXECUTIONS.with(|count| count.get()), parallel_executions);
+
+		assert_eq!(parallel[&output], expected);
+		assert_eq!(parallel, serial);
+	}
 }
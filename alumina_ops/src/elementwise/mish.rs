@@ -0,0 +1,148 @@
+use crate::elementwise::elementwise_single::{TernaryElementwise, TernaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Applies the Mish activation function to each element of the input.
+///
+/// `let output = input * tanh(softplus(input))`
+///
+/// The output node has the same shape as the input.
+pub fn mish<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("mish({})", input));
+	let _op = Mish::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Mish = UnaryElementwise<MishFunc>;
+
+pub type MishBack = TernaryElementwise<MishBackFunc>;
+
+/// Returns `tanh(softplus(x))`.
+#[inline]
+fn tanh_softplus(x: f32) -> f32 {
+	(x.max(0.0) + (-x.abs()).exp().ln_1p()).tanh()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MishFunc {}
+
+impl UnaryFunc for MishFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input * tanh_softplus(input)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Mish"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		MishBack::new_default(
+			ctx.node(input),
+			ctx.node(output),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of mish
+/// input2 = output of mish
+/// input3 = grad of output of mish
+#[derive(Clone, Debug, Default)]
+pub struct MishBackFunc {}
+
+impl TernaryFunc for MishBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		// recover tanh(softplus(x)) from the output, except where dividing by the input would lose precision
+		let t = if input1.abs() > 1e-3 {
+			input2 / input1
+		} else {
+			tanh_softplus(input1)
+		};
+		let sigmoid = 1.0 / (1.0 + (-input1).exp());
+		input3 * (t + input1 * (1.0 - t * t) * sigmoid)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MishBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_input3: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::mish;
+	use crate::elementwise::{mul::mul, softplus::softplus, tanh::tanh};
+	use crate::tests::contract_case;
+	use alumina_core::{grad::Grad, graph::Node};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "mish", mish);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = mish(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(1.131_870_3), 1e-6));
+
+		input.set_value(arr0(-0.8));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(-0.283_963_26), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = mish(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn grad_reference_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_random(-6.0, 6.0, 0);
+
+		// the reference gradient recalculates tanh(softplus(x)) from the input
+		let output = mish(&input).unwrap();
+		let reference = mul(&input, tanh(softplus(&input).unwrap()).unwrap()).unwrap();
+
+		let grad = Grad::of(&output).wrt(&[&input]).build().unwrap()[&input]
+			.calc()
+			.unwrap();
+		let reference_grad = Grad::of(&reference).wrt(&[&input]).build().unwrap()[&input]
+			.calc()
+			.unwrap();
+
+		assert!(grad.all_relatively_close(&reference_grad, 1e-4));
+	}
+}
@@ -1,32 +1,50 @@
 pub mod abs;
+pub mod atan2;
+pub mod blend;
 pub mod ceil;
+pub mod clamp;
 pub mod cos;
 pub mod div;
 pub mod elementwise_dual;
 pub mod elementwise_single;
 pub mod elu;
+pub mod erf;
 pub mod exp;
+pub mod expm1;
 pub mod floor;
+pub mod gelu;
 pub mod identity;
 pub mod leaky_relu;
 pub mod ln;
+pub mod log;
+pub mod log1p;
 pub mod logistic;
 pub mod max;
 pub mod min;
+pub mod mish;
 pub mod mul;
+pub mod neg;
 pub mod negative;
 pub mod offset;
+pub mod one_minus;
+pub mod pow;
 pub mod reciprocal;
 pub mod relu;
 pub mod robust;
 pub mod round;
+pub mod scalar;
+pub mod scalar_pow;
 pub mod scale;
+pub mod sigmoid;
 pub mod sign;
+pub mod silu;
 pub mod sin;
 pub mod softplus;
 pub mod softsign;
 pub mod sqr;
 pub mod sqrt;
+pub mod square;
 pub mod srgb;
 pub mod subtract;
 pub mod tanh;
+pub mod trig;
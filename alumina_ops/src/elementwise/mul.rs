@@ -57,13 +57,17 @@ impl BinaryFunc for MulFunc {
 
 #[cfg(test)]
 mod tests {
-	use super::mul;
-	use alumina_core::graph::Node;
+	use super::{mul, Mul};
+	use crate::elementwise::{identity::add, sqr::sqr};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, exec::ExecutionPlan, grad::Grad, graph::Node};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
-	use indexmap::indexset;
+	use indexmap::{indexset, IndexMap};
 	use ndarray::arr0;
 
+	contract_case!(binary "mul", mul);
+
 	#[test]
 	fn forward_test() {
 		let input1 = Node::new(&[13, 33]).set_name("input1");
@@ -118,4 +122,48 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input1]).run();
 	}
+
+	#[test]
+	fn grad_fan_out_test() {
+		// x feeds two ops, so its gradient is the sum of the gradient from each path: a + 2x
+		let x = Node::new(&[13, 33]).set_name("x").set_value(arr0(1.25));
+		let a = Node::new(&[13, 33]).set_name("a").set_value(arr0(-0.8));
+
+		let output = add(mul(&x, &a).unwrap(), sqr(&x).unwrap()).unwrap();
+
+		let grads = Grad::of(&output).wrt(&[&x]).build().unwrap();
+		let results = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&grads[&x]])
+			.check_accumulation(true)
+			.execute()
+			.unwrap();
+		assert!(results[&grads[&x]].all_relatively_close(&arr0(1.7), 2.0 * ::std::f32::EPSILON));
+
+		GradNumericTest::new(&output, &indexset![&x, &a])
+			.check_accumulation(true)
+			.run();
+	}
+
+	#[test]
+	fn grad_blocked_test() {
+		let x = Node::new(&[13, 33]).set_name("x").set_value(arr0(1.25));
+		let a = Node::new(&[13, 33]).set_name("a").set_value(arr0(-0.8));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Mul::new_default(&x, &a, &output).build_gradient_blocked(&[&a]).unwrap();
+
+		let grads = Grad::of(&output).wrt(&[&x, &a]).build().unwrap();
+
+		// the blocked input receives no gradient while the other is unaffected
+		assert!(grads[&x]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.8), ::std::f32::EPSILON));
+		assert!(grads[&a].calc().unwrap().iter().all(|&g| g == 0.0));
+
+		// only inputs can be blocked
+		let other = Node::new(&[13, 33]).set_name("other");
+		assert!(Mul::new_default(&x, &a, &other)
+			.build_gradient_blocked(&[&output])
+			.is_err());
+	}
 }
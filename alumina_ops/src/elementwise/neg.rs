@@ -0,0 +1,61 @@
+//! Short alias for the `negative` op.
+//!
+//! Negation already has a dedicated op, `Negative`, with a backward pass of `-grad`, this module provides the
+//! conventional short name for it.
+
+pub use crate::elementwise::negative::{Negative, NegativeFunc};
+
+use crate::elementwise::negative::negative;
+use alumina_core::{errors::OpBuildError, graph::Node};
+
+/// Returns the negative (neg) of each element of the input.
+///
+/// Equivalent to `negative(input)`.
+///
+/// The output node has the same shape as the input.
+pub fn neg<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	negative(input)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::neg;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "neg", neg);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = neg(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-1.25), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.8), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = neg(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
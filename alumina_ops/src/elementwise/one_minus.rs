@@ -0,0 +1,101 @@
+use crate::elementwise::{
+	elementwise_single::{UnaryElementwise, UnaryFunc},
+	negative::Negative,
+};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the complement (one_minus) of the input, `1 - x`.
+///
+/// Commonly used for gating, e.g. `one_minus(gate)`.
+///
+/// The output node has the same shape as the input.
+pub fn one_minus<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("one_minus({})", input));
+	let _op = OneMinus::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type OneMinus = UnaryElementwise<OneMinusFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct OneMinusFunc {}
+
+impl UnaryFunc for OneMinusFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		1.0 - input
+	}
+
+	fn type_name(&self) -> &'static str {
+		"OneMinus"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		Negative::new_default(ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::one_minus;
+	use crate::tests::contract_case;
+	use alumina_core::{grad::Grad, graph::Node};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "one_minus", one_minus);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = one_minus(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.25), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.8), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(0.3));
+		let output = one_minus(&input).unwrap();
+
+		let grads = Grad::of(&output).wrt(&[&input]).build().unwrap();
+		assert!(grads[&input]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-1.0), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = one_minus(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
@@ -0,0 +1,207 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, TernaryElementwise, TernaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{merge_graphs, Node, NodeID},
+};
+
+/// Raises each element of base to the power of the corresponding element of exponent.
+///
+/// `let output = base.powf(exponent)`
+///
+/// The gradient w.r.t. exponent requires `ln(base)`, which is undefined where `base <= 0`, so it is taken to be zero
+/// there.
+///
+/// The output node has the same shape as the inputs.
+pub fn pow<I1, I2>(base: I1, exponent: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let base = base.into();
+	let exponent = exponent.into();
+	merge_graphs(&[base.graph(), exponent.graph()]);
+	let output = base
+		.graph()
+		.new_node(base.shape())
+		.set_name_unique(&format!("pow({},{})", base, exponent));
+	let _op = Pow::new_default(base, exponent, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Pow = BinaryElementwise<PowFunc>;
+
+pub type PowBack = TernaryElementwise<PowBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct PowFunc {}
+
+impl BinaryFunc for PowFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input1.powf(input2)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Pow"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		PowBack::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input1),
+			PowBackFunc { wrt_exponent: false },
+		)
+		.build()?;
+		PowBack::new(
+			ctx.node(input1),
+			ctx.node(input2),
+			ctx.grad_of(output),
+			ctx.grad_of(input2),
+			PowBackFunc { wrt_exponent: true },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = base of pow
+/// input2 = exponent of pow
+/// input3 = grad of output of pow
+///
+/// If `wrt_exponent` is true the gradient of the exponent is produced, otherwise the gradient of the base.
+#[derive(Clone, Debug, Default)]
+pub struct PowBackFunc {
+	wrt_exponent: bool,
+}
+
+impl TernaryFunc for PowBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		if self.wrt_exponent {
+			if input1 > 0.0 {
+				input3 * input1.powf(input2) * input1.ln()
+			} else {
+				0.0
+			}
+		} else {
+			input3 * input2 * input1.powf(input2 - 1.0)
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		if self.wrt_exponent {
+			"PowExponentBackward"
+		} else {
+			"PowBaseBackward"
+		}
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_input3: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::pow;
+	use crate::tests::contract_case;
+	use alumina_core::{grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(binary "pow", pow);
+
+	#[test]
+	fn forward_test() {
+		let base = Node::new(&[13, 33]).set_name("base");
+		let exponent = Node::new(&[13, 33]).set_name("exponent");
+
+		let output = pow(&base, &exponent).unwrap();
+
+		base.set_value(arr0(1.25));
+		exponent.set_value(arr0(2.0));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.5625), ::std::f32::EPSILON));
+
+		base.set_value(arr0(4.0));
+		exponent.set_value(arr0(-0.5));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.5), ::std::f32::EPSILON));
+
+		base.set_value(arr0(-2.0));
+		exponent.set_value(arr0(3.0));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-8.0), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let base = Node::new(&[13, 33]).set_name("base").set_init(uniform(0.1, 2.0));
+		let exponent = Node::new(&[13, 33]).set_name("exponent").set_init(uniform(-2.0, 2.0));
+
+		let output = pow(&base, &exponent).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&base, &exponent])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_shared_input_test() {
+		let base = Node::new(&[13, 33]).set_name("base").set_init(uniform(0.1, 2.0));
+
+		let output = pow(&base, &base).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&base])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_non_positive_base_test() {
+		let base = Node::new(&[13, 33]).set_name("base");
+		let exponent = Node::new(&[13, 33]).set_name("exponent");
+
+		let output = pow(&base, &exponent).unwrap();
+		let grads = Grad::of(&output).wrt(&[&base, &exponent]).build().unwrap();
+
+		// ln(base) is undefined, so the exponent gradient is zero rather than NaN
+		base.set_value(arr0(-2.0));
+		exponent.set_value(arr0(3.0));
+		assert!(grads[&exponent]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.0), ::std::f32::EPSILON));
+		assert!(grads[&base]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(12.0), ::std::f32::EPSILON));
+	}
+}
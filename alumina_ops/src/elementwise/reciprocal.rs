@@ -1,7 +1,8 @@
-use crate::{
-	elementwise::div::Div,
-	elementwise::elementwise_single::{UnaryElementwise, UnaryFunc},
-	elementwise::{negative::negative, sqr::sqr},
+use crate::elementwise::{
+	div::Div,
+	elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc},
+	mul::mul,
+	scale::scale,
 };
 use alumina_core::{
 	base_ops::OpSpecification,
@@ -10,7 +11,9 @@ use alumina_core::{
 	graph::{Node, NodeID},
 };
 
-/// Returns the reciprocal of the input.
+/// Returns the reciprocal of the input plus epsilon, `1 / (input + epsilon)`.
+///
+/// The default epsilon is zero, to change it use `Reciprocal::epsilon(..)`.
 ///
 /// The output node has the same shape as the input.
 pub fn reciprocal<I>(input: I) -> Result<Node, OpBuildError>
@@ -28,13 +31,28 @@ where
 
 pub type Reciprocal = UnaryElementwise<ReciprocalFunc>;
 
+impl Reciprocal {
+	/// Added to the input before the reciprocal is taken, preventing division by zero for inputs of zero.
+	///
+	/// Default: 0.0
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon >= 0.0, "epsilon {} must not be negative", epsilon);
+		self.func_mut().epsilon = epsilon;
+		self
+	}
+}
+
+pub type ReciprocalBack = BinaryElementwise<ReciprocalBackFunc>;
+
 #[derive(Clone, Debug, Default)]
-pub struct ReciprocalFunc {}
+pub struct ReciprocalFunc {
+	epsilon: f32,
+}
 
 impl UnaryFunc for ReciprocalFunc {
 	#[inline]
 	fn calc(&self, input: f32) -> f32 {
-		1.0 / input
+		1.0 / (input + self.epsilon)
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -42,25 +60,82 @@ impl UnaryFunc for ReciprocalFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		let _op = Div::new_default(
+		ReciprocalBack::new(
+			ctx.node(input),
 			ctx.grad_of(output),
-			negative(sqr(ctx.node(input))?)?,
 			ctx.grad_of(input),
+			ReciprocalBackFunc { epsilon: self.epsilon },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of reciprocal
+/// input2 = grad of output of reciprocal
+#[derive(Clone, Debug, Default)]
+pub struct ReciprocalBackFunc {
+	epsilon: f32,
+}
+
+impl BinaryFunc for ReciprocalBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		let x = input1 + self.epsilon;
+		-input2 / (x * x)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ReciprocalBackward"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		// output = -input2 / d^2, where d = input1 + epsilon
+		ReciprocalBack::new(ctx.node(input1), ctx.grad_of(output), ctx.grad_of(input2), self.clone()).build()?;
+
+		// 2 * input2 * grad / d^3
+		let input1_node = ctx.node(input1);
+		let back = input1_node
+			.graph()
+			.new_node(input1_node.shape())
+			.set_name_unique(&format!("reciprocal_back({})", input1_node));
+		ReciprocalBack::new(
+			&input1_node,
+			mul(ctx.node(input2), ctx.grad_of(output))?,
+			&back,
+			self.clone(),
 		)
 		.build()?;
+		let _op = Div::new_default(scale(back, -2.0)?, input1_node, ctx.grad_of(input1))
+			.epsilon(self.epsilon)
+			.build()?;
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::reciprocal;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{reciprocal, Reciprocal, ReciprocalBack, ReciprocalBackFunc};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::uniform,
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "reciprocal", reciprocal);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -80,6 +155,19 @@ mod tests {
 			.all_relatively_close(&arr0(-1.25), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn epsilon_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(0.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Reciprocal::new_default(&input, &output).epsilon(0.5).build().unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(2.0), ::std::f32::EPSILON));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
@@ -87,4 +175,44 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
 	}
+
+	#[test]
+	fn grad_numeric_epsilon_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
+		let output = Node::new(&[37, 33]).set_name("output");
+
+		Reciprocal::new_default(&input, &output).epsilon(0.1).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
+		let output = reciprocal(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		GradNumericTest::new(&grad, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn back_grad_numeric_epsilon_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
+		let grad = Node::new(&[37, 33]).set_name("grad");
+		let output = Node::new(&[37, 33]).set_name("output");
+		merge_graphs(&[input.graph(), grad.graph(), output.graph()]);
+
+		ReciprocalBack::new(&input, &grad, &output, ReciprocalBackFunc { epsilon: 0.1 })
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input, &grad])
+			.tolerance(1e-3)
+			.run();
+	}
 }
@@ -59,8 +59,12 @@ pub struct ReluBackFunc {}
 impl BinaryFunc for ReluBackFunc {
 	#[inline]
 	fn calc(&self, input1: f32, input2: f32) -> f32 {
-		let sign = input1.signum();
-		input2 * (1.0 + sign) * 0.5 // x.signum().max(0.0); <- this should be better but doesnt compile to maxps,
+		// the subgradient at zero is taken to be zero, matching the convention of other frameworks
+		if input1 > 0.0 {
+			input2
+		} else {
+			0.0
+		}
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -81,12 +85,18 @@ impl BinaryFunc for ReluBackFunc {
 #[cfg(test)]
 mod tests {
 	use super::relu;
-	use alumina_core::{graph::Node, init::uniform};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		graph::Node,
+		init::{duplicate, uniform},
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "relu", relu);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -113,4 +123,14 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-3).run();
 	}
+
+	#[test]
+	fn grad_numeric_zero_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(duplicate(0.0));
+		let output = relu(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.expect_zero(&input, ::std::f32::EPSILON)
+			.run();
+	}
 }
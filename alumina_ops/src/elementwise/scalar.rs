@@ -0,0 +1,238 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the elementwise minimum of the input and a fixed number.
+///
+/// The gradient is passed through where the input is less than `scalar`.
+///
+/// The output node has the same shape as the input.
+pub fn minimum_scalar<I>(input: I, scalar: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("minimum_scalar({})", input));
+	let _op = MinimumScalar::new(input, output.clone(), MinimumScalarFunc { scalar }).build()?;
+	Ok(output)
+}
+
+/// Returns the elementwise maximum of the input and a fixed number.
+///
+/// The gradient is passed through where the input is greater than `scalar`, `maximum_scalar(x, 0.0)` is equivalent
+/// to `relu(x)`.
+///
+/// The output node has the same shape as the input.
+pub fn maximum_scalar<I>(input: I, scalar: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("maximum_scalar({})", input));
+	let _op = MaximumScalar::new(input, output.clone(), MaximumScalarFunc { scalar }).build()?;
+	Ok(output)
+}
+
+pub type MinimumScalar = UnaryElementwise<MinimumScalarFunc>;
+
+pub type MinimumScalarBack = BinaryElementwise<MinimumScalarBackFunc>;
+
+pub type MaximumScalar = UnaryElementwise<MaximumScalarFunc>;
+
+pub type MaximumScalarBack = BinaryElementwise<MaximumScalarBackFunc>;
+
+#[derive(Clone, Debug)]
+pub struct MinimumScalarFunc {
+	scalar: f32,
+}
+
+impl UnaryFunc for MinimumScalarFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.min(self.scalar)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MinimumScalar"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		MinimumScalarBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			MinimumScalarBackFunc { scalar: self.scalar },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of minimum_scalar
+/// input2 = grad of output of minimum_scalar
+#[derive(Clone, Debug)]
+pub struct MinimumScalarBackFunc {
+	scalar: f32,
+}
+
+impl BinaryFunc for MinimumScalarBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if input1 < self.scalar {
+			input2
+		} else {
+			0.0
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MinimumScalarBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct MaximumScalarFunc {
+	scalar: f32,
+}
+
+impl UnaryFunc for MaximumScalarFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.max(self.scalar)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MaximumScalar"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		MaximumScalarBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			MaximumScalarBackFunc { scalar: self.scalar },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of maximum_scalar
+/// input2 = grad of output of maximum_scalar
+#[derive(Clone, Debug)]
+pub struct MaximumScalarBackFunc {
+	scalar: f32,
+}
+
+impl BinaryFunc for MaximumScalarBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if input1 > self.scalar {
+			input2
+		} else {
+			0.0
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MaximumScalarBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{maximum_scalar, minimum_scalar};
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "minimum_scalar", |x| minimum_scalar(x, 1.25));
+	contract_case!(unary "maximum_scalar", |x| maximum_scalar(x, 1.25));
+
+	#[test]
+	fn minimum_scalar_forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = minimum_scalar(&input, 0.5).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.5), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.8), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn maximum_scalar_forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = maximum_scalar(&input, 0.5).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.25), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.5), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn minimum_scalar_grad_numeric_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+		let output = minimum_scalar(&input, 0.5).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-3).run();
+	}
+
+	#[test]
+	fn maximum_scalar_grad_numeric_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+		let output = maximum_scalar(&input, 0.5).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-3).run();
+	}
+}
@@ -0,0 +1,201 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Raises each element of the input to the power of a fixed exponent.
+///
+/// `let output = input.powf(exponent)`
+///
+/// The output node has the same shape as the input.
+pub fn scalar_pow<I>(input: I, exponent: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("scalar_pow({})", input));
+	let _op = ScalarPow::new_default(input, output.clone())
+		.exponent(exponent)
+		.build()?;
+	Ok(output)
+}
+
+pub type ScalarPow = UnaryElementwise<ScalarPowFunc>;
+
+impl ScalarPow {
+	/// The fixed power the input is raised to.
+	///
+	/// Default: 1.0
+	pub fn exponent(mut self, exponent: f32) -> Self {
+		self.func_mut().exponent = exponent;
+		self
+	}
+}
+
+pub type ScalarPowBack = BinaryElementwise<ScalarPowBackFunc>;
+
+#[derive(Clone, Debug)]
+pub struct ScalarPowFunc {
+	exponent: f32,
+}
+
+impl Default for ScalarPowFunc {
+	fn default() -> Self {
+		Self { exponent: 1.0 }
+	}
+}
+
+impl UnaryFunc for ScalarPowFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.powf(self.exponent)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ScalarPow"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		ScalarPowBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			ScalarPowBackFunc {
+				exponent: self.exponent,
+			},
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of scalar_pow
+/// input2 = grad of output of scalar_pow
+#[derive(Clone, Debug)]
+pub struct ScalarPowBackFunc {
+	exponent: f32,
+}
+
+impl Default for ScalarPowBackFunc {
+	fn default() -> Self {
+		Self { exponent: 1.0 }
+	}
+}
+
+impl BinaryFunc for ScalarPowBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		if self.exponent == 0.0 {
+			// the output is constant, avoid 0 * inf where the input is zero
+			0.0
+		} else {
+			input2 * self.exponent * input1.powf(self.exponent - 1.0)
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ScalarPowBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{scalar_pow, ScalarPow};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::{indexmap, indexset};
+	use ndarray::{arr0, arr1};
+
+	contract_case!(unary "scalar_pow", |x| scalar_pow(x, 1.5));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = scalar_pow(&input, 0.5).unwrap();
+
+		input.set_value(arr0(1.44));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.2), ::std::f32::EPSILON));
+
+		let output = scalar_pow(&input, -2.0).unwrap();
+
+		input.set_value(arr0(0.5));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(4.0), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn square_test() {
+		let input = Node::new(&[5])
+			.set_name("input")
+			.set_value(arr1(&[-2.0, -0.5, 0.0, 1.25, 3.0]));
+
+		let output = scalar_pow(&input, 2.0).unwrap();
+		assert_eq!(output.calc().unwrap(), arr1(&[4.0, 0.25, 0.0, 1.5625, 9.0]).into_dyn());
+
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		assert_eq!(grad.calc().unwrap(), arr1(&[-4.0, -1.0, 0.0, 2.5, 6.0]).into_dyn());
+	}
+
+	#[test]
+	fn exponent_round_trip_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(3.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+		let new_output = Node::new(&[13, 33]).set_name("new_output");
+
+		let op = ScalarPow::new_default(&input, &output).exponent(3.0).build().unwrap();
+
+		// the exponent must survive both the instance to specification round trip, and the cloning of the specification
+		let spec = op
+			.instance()
+			.as_specification(op.graph())
+			.downcast::<ScalarPow>()
+			.unwrap()
+			.clone_with_nodes_changed(&indexmap![output.clone() => new_output.clone()]);
+		spec.build().unwrap();
+
+		assert!(new_output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(27.0), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(0.1, 2.0));
+		let output = scalar_pow(&input, 2.5).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
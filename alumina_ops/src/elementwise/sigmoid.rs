@@ -0,0 +1,123 @@
+use crate::elementwise::{
+	elementwise_single::{UnaryElementwise, UnaryFunc},
+	logistic::LogisticBackFromOutput,
+};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Applies the sigmoid function to each element of the input.
+///
+/// `let output = 1 / (1 + exp(-input))`
+///
+/// Unlike `logistic`, negative inputs are calculated as `exp(input) / (1 + exp(input))` so that `exp` never overflows.
+///
+/// The output node has the same shape as the input.
+pub fn sigmoid<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("sigmoid({})", input));
+	let _op = Sigmoid::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Sigmoid = UnaryElementwise<SigmoidFunc>;
+
+/// The gradient of the sigmoid is calculated from its output, `output_grad * output * (1 - output)`, which is the same
+/// as for the logistic function.
+pub type SigmoidBack = LogisticBackFromOutput;
+
+#[derive(Clone, Debug, Default)]
+pub struct SigmoidFunc {}
+
+impl UnaryFunc for SigmoidFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		if input >= 0.0 {
+			1.0 / (1.0 + (-input).exp())
+		} else {
+			let exp = input.exp();
+			exp / (1.0 + exp)
+		}
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Sigmoid"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		SigmoidBack::new_default(ctx.node(output), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::sigmoid;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "sigmoid", sigmoid);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = sigmoid(&input).unwrap();
+
+		input.set_value(arr0(0.0));
+		assert_eq!(output.calc().unwrap(), arr0(0.5).broadcast(vec![13, 33]).unwrap());
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.777_299_9), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.310_025_5), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn forward_large_magnitude_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = sigmoid(&input).unwrap();
+
+		input.set_value(arr0(-20.0));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(2.061_153_6e-9), 1e-6));
+
+		// the naive form underflows to exactly zero here, as exp(100) overflows to infinity
+		input.set_value(arr0(-100.0));
+		assert!(output.calc().unwrap().iter().all(|&x| x > 0.0 && x < 1e-40));
+
+		input.set_value(arr0(100.0));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.0), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-5.0, 5.0));
+		let output = sigmoid(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
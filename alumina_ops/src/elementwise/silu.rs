@@ -0,0 +1,136 @@
+use crate::elementwise::elementwise_single::{TernaryElementwise, TernaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Applies the sigmoid linear unit (SiLU), also known as swish, to each element of the input.
+///
+/// `let output = input * logistic(input)`
+///
+/// The output node has the same shape as the input.
+pub fn silu<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("silu({})", input));
+	let _op = Silu::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Silu = UnaryElementwise<SiluFunc>;
+
+pub type SiluBack = TernaryElementwise<SiluBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct SiluFunc {}
+
+impl UnaryFunc for SiluFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input / (1.0 + (-input).exp())
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Silu"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		SiluBack::new_default(
+			ctx.node(input),
+			ctx.node(output),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of silu
+/// input2 = output of silu
+/// input3 = grad of output of silu
+#[derive(Clone, Debug, Default)]
+pub struct SiluBackFunc {}
+
+impl TernaryFunc for SiluBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32, input3: f32) -> f32 {
+		let sigmoid = 1.0 / (1.0 + (-input1).exp());
+		input3 * (input2 + sigmoid * (1.0 - input2))
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SiluBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_input3: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::silu;
+	use crate::elementwise::{logistic::logistic, mul::mul};
+	use crate::tests::contract_case;
+	use alumina_core::{grad::Grad, graph::Node};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "silu", silu);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = silu(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(0.971_624_9), 1e-6));
+
+		input.set_value(arr0(-0.8));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(-0.248_020_4), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = silu(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn grad_reference_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_random(-6.0, 6.0, 0);
+
+		// the reference gradient recalculates the logistic from the input
+		let output = silu(&input).unwrap();
+		let reference = mul(&input, logistic(&input).unwrap()).unwrap();
+
+		let grad = Grad::of(&output).wrt(&[&input]).build().unwrap()[&input]
+			.calc()
+			.unwrap();
+		let reference_grad = Grad::of(&reference).wrt(&[&input]).build().unwrap()[&input]
+			.calc()
+			.unwrap();
+
+		assert!(grad.all_relatively_close(&reference_grad, 1e-5));
+	}
+}
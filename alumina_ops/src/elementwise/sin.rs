@@ -74,12 +74,15 @@ impl BinaryFunc for SinBackFunc {
 #[cfg(test)]
 mod tests {
 	use super::sin;
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "sin", sin);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
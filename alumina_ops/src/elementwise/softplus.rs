@@ -1,10 +1,8 @@
-// y = ln(exp(x)+1)
-// y' = ln(exp(x)+1)
-
-use crate::{
-	elementwise::elementwise_single::{UnaryElementwise, UnaryFunc},
-	elementwise::exp::exp,
-	elementwise::{div::div, mul::Mul, offset::offset},
+use crate::elementwise::{
+	elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc},
+	mul::mul,
+	scale::scale,
+	sigmoid::{sigmoid, SigmoidBack, SigmoidFunc},
 };
 use alumina_core::{
 	base_ops::OpSpecification,
@@ -29,15 +27,56 @@ where
 	Ok(output)
 }
 
+/// Returns the softplus (y = ((beta * x).exp() + 1.0).ln() / beta) of the input element-wise.
+///
+/// Larger values of beta more closely approximate relu.
+///
+/// The output node has the same shape as the input.
+pub fn softplus_with_beta<I>(input: I, beta: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("softplus({})", input));
+	let _op = Softplus::new_default(input, output.clone()).beta(beta).build()?;
+	Ok(output)
+}
+
 pub type Softplus = UnaryElementwise<SoftplusFunc>;
 
-#[derive(Clone, Debug, Default)]
-pub struct SoftplusFunc {}
+impl Softplus {
+	/// Scales the input before, and the output after, the softplus is applied.
+	///
+	/// Default: 1.0
+	pub fn beta(mut self, beta: f32) -> Self {
+		assert!(beta > 0.0, "beta must be positive");
+		self.func_mut().beta = beta;
+		self
+	}
+}
+
+pub type SoftplusBack = BinaryElementwise<SoftplusBackFunc>;
+
+#[derive(Clone, Debug)]
+pub struct SoftplusFunc {
+	beta: f32,
+}
+
+impl Default for SoftplusFunc {
+	fn default() -> Self {
+		Self { beta: 1.0 }
+	}
+}
 
 impl UnaryFunc for SoftplusFunc {
 	#[inline]
 	fn calc(&self, input: f32) -> f32 {
-		(input.exp() + 1.0).ln()
+		// max(x, 0) + ln(1 + exp(-|x|)) is equal to ln(1 + exp(x)), but exp never overflows
+		let input = input * self.beta;
+		(input.max(0.0) + (-input.abs()).exp().ln_1p()) / self.beta
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -45,24 +84,78 @@ impl UnaryFunc for SoftplusFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		let exp = exp(ctx.node(input))?;
-		let exp_p1 = offset(exp.clone(), 1.0)?;
-		let do_di = div(exp, exp_p1)?;
+		SoftplusBack::new(
+			ctx.node(input),
+			ctx.grad_of(output),
+			ctx.grad_of(input),
+			SoftplusBackFunc { beta: self.beta },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of softplus
+/// input2 = grad of output of softplus
+#[derive(Clone, Debug)]
+pub struct SoftplusBackFunc {
+	beta: f32,
+}
+
+impl Default for SoftplusBackFunc {
+	fn default() -> Self {
+		Self { beta: 1.0 }
+	}
+}
+
+impl BinaryFunc for SoftplusBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * SigmoidFunc::default().calc(input1 * self.beta)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SoftplusBackward"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		// output = input2 * sigmoid(beta * input1)
+		SoftplusBack::new(ctx.node(input1), ctx.grad_of(output), ctx.grad_of(input2), self.clone()).build()?;
 
-		let _op = Mul::new_default(do_di, ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		// beta * input2 * grad * sigmoid'(beta * input1)
+		let _op = SigmoidBack::new_default(
+			sigmoid(scale(ctx.node(input1), self.beta)?)?,
+			scale(mul(ctx.node(input2), ctx.grad_of(output))?, self.beta)?,
+			ctx.grad_of(input1),
+		)
+		.build()?;
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::softplus;
-	use alumina_core::graph::Node;
+	use super::{softplus, softplus_with_beta, SoftplusBack, SoftplusBackFunc};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::uniform,
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "softplus", softplus);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -89,4 +182,88 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).run();
 	}
+
+	#[test]
+	fn forward_large_magnitude_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = softplus(&input).unwrap();
+
+		// the naive form overflows to infinity here
+		input.set_value(arr0(100.0));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(100.0), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-100.0));
+		assert!(output.calc().unwrap().iter().all(|&x| x > 0.0 && x < 1e-40));
+	}
+
+	#[test]
+	fn forward_beta_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = softplus_with_beta(&input, 2.0).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(1.289_444_9), 1e-6));
+
+		input.set_value(arr0(-0.8));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(0.091_950_37), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_wide_range_test() {
+		// large outputs limit the f32 precision of the numeric gradient, so use fewer elements and a larger step
+		let input = Node::new(&[5, 7]).set_name("input").set_init(uniform(-50.0, 50.0));
+		let output = softplus(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-2)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_beta_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-5.0, 5.0));
+		let output = softplus_with_beta(&input, 3.0).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+		let output = softplus(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		GradNumericTest::new(&grad, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn back_grad_numeric_beta_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-5.0, 5.0));
+		let grad = Node::new(&[13, 33]).set_name("grad");
+		let output = Node::new(&[13, 33]).set_name("output");
+		merge_graphs(&[input.graph(), grad.graph(), output.graph()]);
+
+		SoftplusBack::new(&input, &grad, &output, SoftplusBackFunc { beta: 3.0 })
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input, &grad])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
 }
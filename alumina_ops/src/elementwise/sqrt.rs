@@ -1,7 +1,8 @@
-use crate::{
-	elementwise::div::Div,
-	elementwise::elementwise_single::{UnaryElementwise, UnaryFunc},
-	elementwise::scale::scale,
+use crate::elementwise::{
+	div::Div,
+	elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc},
+	mul::mul,
+	scale::scale,
 };
 use alumina_core::{
 	base_ops::OpSpecification,
@@ -10,7 +11,9 @@ use alumina_core::{
 	graph::{Node, NodeID},
 };
 
-/// Returns the square (sqrt) of the input.
+/// Returns the square root (sqrt) of the input plus epsilon, `(input + epsilon).sqrt()`.
+///
+/// The default epsilon is zero, to change it use `Sqrt::epsilon(..)`.
 ///
 /// The output node has the same shape as the input.
 pub fn sqrt<I>(input: I) -> Result<Node, OpBuildError>
@@ -28,13 +31,28 @@ where
 
 pub type Sqrt = UnaryElementwise<SqrtFunc>;
 
+impl Sqrt {
+	/// Added to the input before the square root is taken, keeping the gradient finite for inputs of zero.
+	///
+	/// Default: 0.0
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon >= 0.0, "epsilon {} must not be negative", epsilon);
+		self.func_mut().epsilon = epsilon;
+		self
+	}
+}
+
+pub type SqrtBack = BinaryElementwise<SqrtBackFunc>;
+
 #[derive(Clone, Debug, Default)]
-pub struct SqrtFunc {}
+pub struct SqrtFunc {
+	epsilon: f32,
+}
 
 impl UnaryFunc for SqrtFunc {
 	#[inline]
 	fn calc(&self, input: f32) -> f32 {
-		input.sqrt()
+		(input + self.epsilon).sqrt()
 	}
 
 	fn type_name(&self) -> &'static str {
@@ -42,25 +60,81 @@ impl UnaryFunc for SqrtFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		let _op = Div::new_default(
+		SqrtBack::new(
+			ctx.node(input),
 			ctx.grad_of(output),
-			scale(sqrt(ctx.node(input))?, 2.0)?,
 			ctx.grad_of(input),
+			SqrtBackFunc { epsilon: self.epsilon },
+		)
+		.build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of sqrt
+/// input2 = grad of output of sqrt
+#[derive(Clone, Debug, Default)]
+pub struct SqrtBackFunc {
+	epsilon: f32,
+}
+
+impl BinaryFunc for SqrtBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 / (2.0 * (input1 + self.epsilon).sqrt())
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SqrtBackward"
+	}
+
+	fn grad(
+		&self,
+		ctx: &mut GradientContext,
+		input1: &NodeID,
+		input2: &NodeID,
+		output: &NodeID,
+	) -> Result<(), GradientError> {
+		// output = input2 / (2 * sqrt(d)), where d = input1 + epsilon
+		SqrtBack::new(ctx.node(input1), ctx.grad_of(output), ctx.grad_of(input2), self.clone()).build()?;
+
+		// -input2 * grad / (4 * d^1.5)
+		let input1_node = ctx.node(input1);
+		let back = input1_node
+			.graph()
+			.new_node(input1_node.shape())
+			.set_name_unique(&format!("sqrt_back({})", input1_node));
+		SqrtBack::new(
+			&input1_node,
+			mul(ctx.node(input2), ctx.grad_of(output))?,
+			&back,
+			self.clone(),
 		)
 		.build()?;
+		let _op = Div::new_default(scale(back, -0.5)?, input1_node, ctx.grad_of(input1))
+			.epsilon(self.epsilon)
+			.build()?;
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::sqrt;
-	use alumina_core::{graph::Node, init::uniform};
+	use super::{sqrt, Sqrt, SqrtBack, SqrtBackFunc};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		grad::Grad,
+		graph::{merge_graphs, Node},
+		init::uniform,
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "sqrt", sqrt);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -80,6 +154,31 @@ mod tests {
 			.all_relatively_close(&arr0(0.894_427_2), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn epsilon_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_value(arr0(0.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		Sqrt::new_default(&input, &output).epsilon(0.25).build().unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.5), ::std::f32::EPSILON));
+
+		// the gradient stays finite for inputs of zero
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		assert!(grad
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.0), ::std::f32::EPSILON));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
@@ -87,4 +186,51 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
 	}
+
+	#[test]
+	fn grad_numeric_epsilon_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.0, 4.0));
+		let output = Node::new(&[37, 33]).set_name("output");
+
+		Sqrt::new_default(&input, &output).epsilon(0.1).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_second_order_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.2, 3.0));
+		let output = sqrt(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		GradNumericTest::new(&grad, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn back_grad_numeric_epsilon_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(0.0, 4.0));
+		let grad = Node::new(&[37, 33]).set_name("grad");
+		let output = Node::new(&[37, 33]).set_name("output");
+		merge_graphs(&[input.graph(), grad.graph(), output.graph()]);
+
+		SqrtBack::new(&input, &grad, &output, SqrtBackFunc { epsilon: 0.1 })
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input, &grad])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
 }
@@ -0,0 +1,115 @@
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+/// Returns the square of the input.
+///
+/// Unlike `sqr`, the backward pass is a single fused op computing `2 * x * grad`, rather than a scale followed by a
+/// multiply.
+///
+/// The output node has the same shape as the input.
+pub fn square<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("square({})", input));
+	let _op = Square::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Square = UnaryElementwise<SquareFunc>;
+
+pub type SquareBack = BinaryElementwise<SquareBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct SquareFunc {}
+
+impl UnaryFunc for SquareFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input * input
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Square"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		SquareBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of square
+/// input2 = grad of output of square
+#[derive(Clone, Debug, Default)]
+pub struct SquareBackFunc {}
+
+impl BinaryFunc for SquareBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		2.0 * input1 * input2
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SquareBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::square;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::arr0;
+
+	contract_case!(unary "square", square);
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = square(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.5625), ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.64), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+		let output = square(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+}
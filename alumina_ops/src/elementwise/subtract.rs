@@ -60,12 +60,15 @@ impl BinaryFunc for SubtractFunc {
 #[cfg(test)]
 mod tests {
 	use super::subtract;
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(binary "subtract", subtract);
+
 	#[test]
 	fn forward_test() {
 		let input1 = Node::new(&[13, 33]).set_name("input1");
@@ -102,6 +105,32 @@ mod tests {
 			.all_relatively_close(&arr0(0.0), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn ordered_inputs_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1");
+		let input2 = Node::new(&[13, 33]).set_name("input2");
+
+		let output = subtract(&input1, &input2).unwrap();
+		assert_eq!(
+			output.parent_op().instance().ordered_inputs(),
+			vec![input1.id(), input2.id()]
+		);
+
+		let output = subtract(&input2, &input1).unwrap();
+		assert_eq!(
+			output.parent_op().instance().ordered_inputs(),
+			vec![input2.id(), input1.id()]
+		);
+
+		// the same node used for both arguments is repeated
+		let output = subtract(&input1, &input1).unwrap();
+		assert_eq!(output.parent_op().instance().inputs().len(), 1);
+		assert_eq!(
+			output.parent_op().instance().ordered_inputs(),
+			vec![input1.id(), input1.id()]
+		);
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input1 = Node::new(&[13, 33]).set_name("input1");
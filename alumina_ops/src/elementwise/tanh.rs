@@ -26,6 +26,8 @@ pub type Tanh = UnaryElementwise<TanhFunc>;
 
 pub type TanhBack = BinaryElementwise<TanhBackFunc>;
 
+pub type TanhBackFromOutput = BinaryElementwise<TanhBackFromOutputFunc>;
+
 #[derive(Clone, Debug, Default)]
 pub struct TanhFunc {}
 
@@ -40,7 +42,7 @@ impl UnaryFunc for TanhFunc {
 	}
 
 	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
-		TanhBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		TanhBackFromOutput::new_default(ctx.node(output), ctx.grad_of(output), ctx.grad_of(input)).build()?;
 		Ok(())
 	}
 }
@@ -72,15 +74,47 @@ impl BinaryFunc for TanhBackFunc {
 	}
 }
 
+/// input1 = output of tanh
+/// input2 = grad of output of tanh
+#[derive(Clone, Debug, Default)]
+pub struct TanhBackFromOutputFunc {}
+
+impl BinaryFunc for TanhBackFromOutputFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * (1.0 - input1 * input1)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"TanhBackwardFromOutput"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::tanh;
-	use alumina_core::graph::Node;
+	use super::{tanh, TanhBack, TanhBackFromOutput};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		graph::{merge_graphs, Node},
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
 	use indexmap::indexset;
 	use ndarray::arr0;
 
+	contract_case!(unary "tanh", tanh);
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -100,6 +134,22 @@ mod tests {
 			.all_relatively_close(&arr0(-0.664_036_75), ::std::f32::EPSILON));
 	}
 
+	#[test]
+	fn forward_zero_and_saturation_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = tanh(&input).unwrap();
+
+		input.set_value(arr0(0.0));
+		assert!(output.calc().unwrap().iter().all(|&x| x == 0.0));
+
+		input.set_value(arr0(20.0));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(1.0), 1e-6));
+
+		input.set_value(arr0(-20.0));
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(-1.0), 1e-6));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[13, 33]).set_name("input");
@@ -107,4 +157,25 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).run();
 	}
+
+	#[test]
+	fn back_from_output_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_random(-3.0, 3.0, 0);
+		let grad = Node::new(&[13, 33]).set_name("grad").set_random(-1.0, 1.0, 1);
+		let output = tanh(&input).unwrap();
+		let from_input = Node::new(&[13, 33]).set_name("from_input");
+		let from_output = Node::new(&[13, 33]).set_name("from_output");
+		merge_graphs(&[input.graph(), grad.graph(), from_input.graph(), from_output.graph()]);
+
+		// the back Op takes the input of tanh, and the FromOutput variant takes its output
+		let _op = TanhBack::new_default(&input, &grad, &from_input).build().unwrap();
+		let _op = TanhBackFromOutput::new_default(&output, &grad, &from_output)
+			.build()
+			.unwrap();
+
+		assert!(from_input
+			.calc()
+			.unwrap()
+			.all_relatively_close(&from_output.calc().unwrap(), 1e-5));
+	}
 }
@@ -0,0 +1,379 @@
+//! Trigonometric and hyperbolic elementwise ops.
+//!
+//! `sin` and `cos` are defined in their own modules and re-exported here so the full family is available in one
+//! place.
+
+use crate::elementwise::elementwise_single::{BinaryElementwise, BinaryFunc, UnaryElementwise, UnaryFunc};
+use alumina_core::{
+	base_ops::OpSpecification,
+	errors::{GradientError, OpBuildError},
+	grad::GradientContext,
+	graph::{Node, NodeID},
+};
+
+pub use crate::elementwise::cos::{cos, Cos, CosBack};
+pub use crate::elementwise::sin::{sin, Sin, SinBack};
+
+/// Returns the tangent (tan) of the input.
+///
+/// The output node has the same shape as the input.
+pub fn tan<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("tan({})", input));
+	let _op = Tan::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Tan = UnaryElementwise<TanFunc>;
+
+pub type TanBack = BinaryElementwise<TanBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct TanFunc {}
+
+impl UnaryFunc for TanFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.tan()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Tan"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		TanBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of tan
+/// input2 = grad of output of tan
+#[derive(Clone, Debug, Default)]
+pub struct TanBackFunc {}
+
+impl BinaryFunc for TanBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		let c = input1.cos();
+		input2 / (c * c)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"TanBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+/// Returns the hyperbolic sine (sinh) of the input.
+///
+/// The output node has the same shape as the input.
+pub fn sinh<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("sinh({})", input));
+	let _op = Sinh::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Sinh = UnaryElementwise<SinhFunc>;
+
+pub type SinhBack = BinaryElementwise<SinhBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct SinhFunc {}
+
+impl UnaryFunc for SinhFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.sinh()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Sinh"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		SinhBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of sinh
+/// input2 = grad of output of sinh
+#[derive(Clone, Debug, Default)]
+pub struct SinhBackFunc {}
+
+impl BinaryFunc for SinhBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * input1.cosh()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SinhBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+/// Returns the hyperbolic cosine (cosh) of the input.
+///
+/// The output node has the same shape as the input.
+pub fn cosh<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("cosh({})", input));
+	let _op = Cosh::new_default(input, output.clone()).build()?;
+	Ok(output)
+}
+
+pub type Cosh = UnaryElementwise<CoshFunc>;
+
+pub type CoshBack = BinaryElementwise<CoshBackFunc>;
+
+#[derive(Clone, Debug, Default)]
+pub struct CoshFunc {}
+
+impl UnaryFunc for CoshFunc {
+	#[inline]
+	fn calc(&self, input: f32) -> f32 {
+		input.cosh()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"Cosh"
+	}
+
+	fn grad(&self, ctx: &mut GradientContext, input: &NodeID, output: &NodeID) -> Result<(), GradientError> {
+		CoshBack::new_default(ctx.node(input), ctx.grad_of(output), ctx.grad_of(input)).build()?;
+		Ok(())
+	}
+}
+
+/// input1 = input of cosh
+/// input2 = grad of output of cosh
+#[derive(Clone, Debug, Default)]
+pub struct CoshBackFunc {}
+
+impl BinaryFunc for CoshBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		input2 * input1.sinh()
+	}
+
+	fn type_name(&self) -> &'static str {
+		"CoshBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{cos, cosh, sin, sinh, tan};
+	use crate::tests::contract_case;
+	use alumina_core::{grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::{arr0, arr1};
+	use std::f32::consts::{FRAC_PI_2, PI};
+
+	contract_case!(unary "tan", tan);
+	contract_case!(unary "sinh", sinh);
+	contract_case!(unary "cosh", cosh);
+
+	#[test]
+	fn sin_cos_reexport_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output_sin = sin(&input).unwrap();
+		let output_cos = cos(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output_sin
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.948_984_6), ::std::f32::EPSILON));
+		assert!(output_cos
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(0.315_322_37), ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn sin_cos_key_angles_test() {
+		let input = Node::new(&[3]).set_name("input").set_value(arr1(&[0.0, FRAC_PI_2, PI]));
+
+		let output_sin = sin(&input).unwrap();
+		let output_cos = cos(&input).unwrap();
+
+		// the expected values include zeros, so compare absolutely
+		let close = |output: &Node, expected: &[f32]| {
+			output
+				.calc()
+				.unwrap()
+				.iter()
+				.zip(expected)
+				.all(|(&o, &e)| (o - e).abs() <= 1e-6)
+		};
+		assert!(close(&output_sin, &[0.0, 1.0, 0.0]));
+		assert!(close(&output_cos, &[1.0, 0.0, -1.0]));
+
+		// each gradient is the complementary function of the input
+		let grad_sin = Grad::of(&output_sin)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		let grad_cos = Grad::of(&output_cos)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		assert!(close(&grad_sin, &[1.0, 0.0, -1.0]));
+		assert!(close(&grad_cos, &[0.0, -1.0, 0.0]));
+	}
+
+	#[test]
+	fn sin_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-PI, PI));
+		let output = sin(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn cos_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-PI, PI));
+		let output = cos(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn tan_forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = tan(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(3.009_569_7), 4.0 * ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-1.029_638_6), 4.0 * ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn tan_grad_numeric_test() {
+		// keep well away from the poles at +-pi/2
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-1.0, 1.0));
+		let output = tan(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn sinh_forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = sinh(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.601_919_1), 2.0 * ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(-0.888_105_96), 2.0 * ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn sinh_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-3.0, 3.0));
+		let output = sinh(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn cosh_forward_test() {
+		let input = Node::new(&[13, 33]).set_name("input");
+
+		let output = cosh(&input).unwrap();
+
+		input.set_value(arr0(1.25));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.888_423_9), 2.0 * ::std::f32::EPSILON));
+
+		input.set_value(arr0(-0.8));
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(1.337_434_9), 2.0 * ::std::f32::EPSILON));
+	}
+
+	#[test]
+	fn cosh_grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-3.0, 3.0));
+		let output = cosh(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+}
@@ -1 +1,99 @@
 pub mod stop_grad;
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		elementwise::{exp::exp, mul::mul, sin::sin, sqr::sqr},
+		reduce::reduce_sum::reduce_sum,
+	};
+	use alumina_core::{
+		grad::{grads_weighted, integrated_gradients, Grad},
+		graph::Node,
+	};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr0, arr1};
+
+	#[test]
+	fn intermediate_grad_test() {
+		let x = Node::new(&[3]).set_name("x").set_value(arr1(&[-0.5, 0.25, 1.0]));
+		let hidden = sqr(&x).unwrap();
+		let output = exp(&hidden).unwrap();
+
+		let grads = Grad::of(&output).wrt(&[&x, &hidden]).build().unwrap();
+
+		// d(exp(h))/dh = exp(h), and the chain rule continues through to x
+		let hidden_grad = grads[&hidden].calc().unwrap();
+		let expected = arr1(&[0.25f32.exp(), 0.0625f32.exp(), 1.0f32.exp()]);
+		assert!(hidden_grad.all_relatively_close(&expected, 1e-6));
+
+		let x_grad = grads[&x].calc().unwrap();
+		assert!(x_grad.all_relatively_close(&(&expected * &arr1(&[-1.0, 0.5, 2.0])), 1e-6));
+	}
+
+	#[test]
+	fn intermediate_grad_numeric_test() {
+		let x = Node::new(&[13, 33]).set_name("x");
+		let hidden = sqr(&x).unwrap();
+		let output = exp(&hidden).unwrap();
+
+		// only the intermediate is perturbed, x isn't required
+		GradNumericTest::new(&output, &indexset![&hidden])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn integrated_gradients_linear_test() {
+		let input = Node::new(&[4])
+			.set_name("input")
+			.set_value(arr1(&[1.0, -2.0, 0.5, 3.0]));
+		let weight = Node::new(&[4])
+			.set_name("weight")
+			.set_value(arr1(&[0.5, 1.5, -1.0, 2.0]));
+		let output = mul(&input, &weight).unwrap();
+
+		let baseline = arr1(&[0.0, 1.0, 0.5, -1.0]);
+		let attribution = integrated_gradients(&output, &input, baseline.clone(), 3).unwrap();
+
+		let expected = (arr1(&[1.0, -2.0, 0.5, 3.0]) - baseline) * arr1(&[0.5, 1.5, -1.0, 2.0]);
+		assert!(attribution.all_relatively_close(&expected, 1e-6));
+	}
+
+	#[test]
+	fn integrated_gradients_completeness_test() {
+		let input = Node::new(&[3]).set_name("input").set_value(arr1(&[-0.5, 0.25, 1.0]));
+		let output = reduce_sum(exp(sqr(&input).unwrap()).unwrap(), &[], false).unwrap();
+
+		let attribution = integrated_gradients(&output, &input, 0.0, 200).unwrap();
+
+		// attributions sum to the difference between the output at the input and at the baseline
+		let expected = output.calc().unwrap().sum() - 3.0;
+		assert!(arr0(attribution.sum()).all_relatively_close(&arr0(expected), 1e-2));
+	}
+
+	#[test]
+	fn grads_weighted_test() {
+		let x = Node::new(&[4]).set_name("x").set_value(arr1(&[-0.5, 0.25, 1.0, 2.0]));
+		let y = Node::new(&[4]).set_name("y").set_value(arr1(&[1.5, -1.0, 0.5, 0.75]));
+
+		let output1 = mul(sqr(&x).unwrap(), &y).unwrap();
+		let output2 = exp(mul(&x, sin(&y).unwrap()).unwrap()).unwrap();
+
+		let weighted = grads_weighted(&[(&output1, 0.25), (&output2, -2.0)], &[&x, &y]).unwrap();
+
+		let grads1 = Grad::of(&output1).wrt(&[&x, &y]).build().unwrap();
+		let grads2 = Grad::of(&output2).wrt(&[&x, &y]).build().unwrap();
+
+		for node in &[&x, &y] {
+			let expected = grads1[*node].calc().unwrap() * 0.25 + grads2[*node].calc().unwrap() * -2.0;
+			assert!(weighted[*node].calc().unwrap().all_relatively_close(&expected, 1e-6));
+		}
+
+		// repeated outputs have their weights summed
+		let repeated = grads_weighted(&[(&output1, 0.25), (&output1, 0.5)], &[&x]).unwrap();
+		let expected = grads1[&x].calc().unwrap() * 0.75;
+		assert!(repeated[&x].calc().unwrap().all_relatively_close(&expected, 1e-6));
+	}
+}
@@ -1,8 +1,10 @@
 //! Types (`OpBuilder`, `OpInstance`) for constructing and defining an `Op` within the graph.
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 pub mod boolean;
 pub mod elementwise;
 pub mod grad;
+pub mod loss;
 pub mod manip;
 pub mod math;
 pub mod nn;
@@ -12,6 +14,9 @@ pub mod reduce;
 pub mod regularisation;
 pub mod shape;
 
+#[cfg(test)]
+mod tests;
+
 use alumina_core::errors::OpBuildError;
 
 /// Unwraps the `Result` but prints the `Err` using `Display` rather than `Debug`
@@ -0,0 +1,515 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{arr0, Array0, ArrayViewD, Zip};
+use std::any::Any;
+
+/// Calculates the elementwise binary cross entropy of the sigmoid of the logits with the targets.
+///
+/// These operations are combined for numerical stability, the output is calculated as
+/// `max(x, 0) - x * z + ln(1 + exp(-abs(x)))` where `x` is the logit and `z` the target, so that large magnitude logits
+/// do not overflow.
+///
+/// A per-element weight for the positive term can be set on the `BceWithLogits` `OpBuilder` using `pos_weight()`.
+///
+/// The output node has the same shape as the logits and targets.
+pub fn bce_with_logits<I1, I2>(logits: I1, targets: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let logits = logits.into();
+	let targets = targets.into();
+
+	let graph = merge_graphs(&[logits.graph(), targets.graph()]);
+
+	let output = graph
+		.new_node(logits.shape())
+		.set_name_unique(&format!("bce_with_logits({},{})", logits, targets));
+
+	BceWithLogits::new(logits, targets, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Returns `ln(1 + exp(-x))` without overflow.
+#[inline]
+pub(crate) fn softplus_neg(x: f32) -> f32 {
+	(-x).max(0.0) + (-x.abs()).exp().ln_1p()
+}
+
+/// Returns `1 / (1 + exp(-x))` without overflow.
+#[inline]
+pub(crate) fn sigmoid(x: f32) -> f32 {
+	if x >= 0.0 {
+		1.0 / (1.0 + (-x).exp())
+	} else {
+		let exp = x.exp();
+		exp / (1.0 + exp)
+	}
+}
+
+/// `-(w * z * ln(sigmoid(x)) + (1 - z) * ln(1 - sigmoid(x)))`
+#[inline]
+fn loss(x: f32, z: f32, w: f32) -> f32 {
+	(1.0 - z) * x + (1.0 + (w - 1.0) * z) * softplus_neg(x)
+}
+
+/// `BceWithLogits` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct BceWithLogits {
+	logits: Node,
+	targets: Node,
+	pos_weight: Option<Node>,
+	output: Node,
+}
+
+impl BceWithLogits {
+	pub fn new<I1, I2, O>(logits: I1, targets: I2, output: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let logits = logits.into();
+		let targets = targets.into();
+		let output = output.into();
+		assert!(logits.shape().len() == targets.shape().len());
+		assert!(logits.shape().len() == output.shape().len());
+		BceWithLogits {
+			logits,
+			targets,
+			pos_weight: None,
+			output,
+		}
+	}
+
+	/// If Some, a node of the same shape as the logits whose elements weight the loss of the positive targets, e.g.
+	/// to compensate for an imbalance between positive and negative examples.
+	///
+	/// Default: None
+	pub fn pos_weight<I: Into<Node>>(mut self, pos_weight: Option<I>) -> Self {
+		self.pos_weight = pos_weight.map(Into::into);
+		self
+	}
+}
+
+impl OpSpecification for BceWithLogits {
+	type InstanceType = BceWithLogitsInstance;
+
+	fn type_name(&self) -> &'static str {
+		"BceWithLogits"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		let mut inputs = indexset![self.logits.clone(), self.targets.clone()];
+		if let Some(ref pos_weight) = self.pos_weight {
+			inputs.insert(pos_weight.clone());
+		}
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			targets: mapping.get(&self.targets).unwrap_or(&self.targets).clone(),
+			pos_weight: self
+				.pos_weight
+				.as_ref()
+				.map(|node| mapping.get(node).unwrap_or(node).clone()),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(BceWithLogitsInstance {
+			logits: self.logits.id(),
+			targets: self.targets.id(),
+			pos_weight: self.pos_weight.map(|node| node.id()),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// BceWithLogits OpInstance
+#[derive(Clone, Debug)]
+pub struct BceWithLogitsInstance {
+	logits: NodeID,
+	targets: NodeID,
+	pos_weight: Option<NodeID>,
+	output: NodeID,
+}
+
+impl OpInstance for BceWithLogitsInstance {
+	fn type_name(&self) -> &'static str {
+		"BceWithLogits"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(BceWithLogits {
+			logits: graph.node_from_id(self.logits),
+			targets: graph.node_from_id(self.targets),
+			pos_weight: self.pos_weight.map(|id| graph.node_from_id(id)),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		let mut inputs = indexset![self.logits, self.targets];
+		inputs.extend(self.pos_weight);
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		BceWithLogitsBack::new(
+			ctx.node(&self.logits),
+			ctx.grad_of(&self.logits),
+			ctx.node(&self.targets),
+			ctx.grad_of(&self.targets),
+			ctx.grad_of(&self.output),
+		)
+		.pos_weight(self.pos_weight.map(|id| (ctx.node(&id), ctx.grad_of(&id))))
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let mut inputs = vec![self.logits, self.targets];
+		inputs.extend(self.pos_weight);
+		ctx.require_equal_shapes(&inputs)?;
+		ctx.set_output_like(&self.output, &self.logits)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let logits = ctx.get_input(&self.logits);
+		let one = arr0(1.0);
+		let pos_weight = pos_weight_or_one(ctx, self.pos_weight, &one, logits.shape());
+
+		Zip::from(ctx.get_output(&self.output))
+			.and(&logits)
+			.and(&ctx.get_input(&self.targets))
+			.and(&pos_weight)
+			.par_for_each(|output, &x, &z, &w| *output += loss(x, z, w));
+
+		Ok(())
+	}
+}
+
+/// Returns the pos_weight input if present, otherwise a broadcast array of ones.
+fn pos_weight_or_one<'a>(
+	ctx: &'a ExecutionContext,
+	pos_weight: Option<NodeID>,
+	one: &'a Array0<f32>,
+	shape: &[usize],
+) -> ArrayViewD<'a, f32> {
+	match pos_weight {
+		Some(ref pos_weight) => ctx.get_input(pos_weight),
+		None => one.broadcast(shape).unwrap(),
+	}
+}
+
+/// `BceWithLogitsBack` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct BceWithLogitsBack {
+	logits: Node,
+	logits_grad: Node,
+	targets: Node,
+	targets_grad: Node,
+	pos_weight: Option<(Node, Node)>,
+	output_grad: Node,
+}
+
+impl BceWithLogitsBack {
+	pub fn new<I1, I2, I3, O1, O2>(logits: I1, logits_grad: O1, targets: I2, targets_grad: O2, output_grad: I3) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let logits = logits.into();
+		let logits_grad = logits_grad.into();
+		let targets = targets.into();
+		let targets_grad = targets_grad.into();
+		let output_grad = output_grad.into();
+		assert!(logits.shape().len() == targets.shape().len());
+		assert!(logits.shape().len() == logits_grad.shape().len());
+		assert!(logits.shape().len() == targets_grad.shape().len());
+		assert!(logits.shape().len() == output_grad.shape().len());
+		BceWithLogitsBack {
+			logits,
+			logits_grad,
+			targets,
+			targets_grad,
+			pos_weight: None,
+			output_grad,
+		}
+	}
+
+	/// If Some, the pos_weight node of the forward op and the node to accumulate its gradient into.
+	///
+	/// Default: None
+	pub fn pos_weight<I: Into<Node>, O: Into<Node>>(mut self, pos_weight: Option<(I, O)>) -> Self {
+		self.pos_weight = pos_weight.map(|(pos_weight, pos_weight_grad)| (pos_weight.into(), pos_weight_grad.into()));
+		self
+	}
+}
+
+impl OpSpecification for BceWithLogitsBack {
+	type InstanceType = BceWithLogitsBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"BceWithLogitsBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		let mut inputs = indexset![self.logits.clone(), self.targets.clone(), self.output_grad.clone()];
+		if let Some((ref pos_weight, _)) = self.pos_weight {
+			inputs.insert(pos_weight.clone());
+		}
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		let mut outputs = indexset![self.logits_grad.clone(), self.targets_grad.clone()];
+		if let Some((_, ref pos_weight_grad)) = self.pos_weight {
+			outputs.insert(pos_weight_grad.clone());
+		}
+		outputs
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			logits_grad: mapping.get(&self.logits_grad).unwrap_or(&self.logits_grad).clone(),
+			targets: mapping.get(&self.targets).unwrap_or(&self.targets).clone(),
+			targets_grad: mapping.get(&self.targets_grad).unwrap_or(&self.targets_grad).clone(),
+			pos_weight: self.pos_weight.as_ref().map(|(pos_weight, pos_weight_grad)| {
+				(
+					mapping.get(pos_weight).unwrap_or(pos_weight).clone(),
+					mapping.get(pos_weight_grad).unwrap_or(pos_weight_grad).clone(),
+				)
+			}),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(BceWithLogitsBackInstance {
+			logits: self.logits.id(),
+			logits_grad: self.logits_grad.id(),
+			targets: self.targets.id(),
+			targets_grad: self.targets_grad.id(),
+			pos_weight: self
+				.pos_weight
+				.map(|(pos_weight, pos_weight_grad)| (pos_weight.id(), pos_weight_grad.id())),
+			output_grad: self.output_grad.id(),
+		})
+	}
+}
+
+/// BceWithLogitsBack OpInstance
+#[derive(Clone, Debug)]
+pub struct BceWithLogitsBackInstance {
+	logits: NodeID,
+	logits_grad: NodeID,
+	targets: NodeID,
+	targets_grad: NodeID,
+	pos_weight: Option<(NodeID, NodeID)>,
+	output_grad: NodeID,
+}
+
+impl OpInstance for BceWithLogitsBackInstance {
+	fn type_name(&self) -> &'static str {
+		"BceWithLogitsBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(BceWithLogitsBack {
+			logits: graph.node_from_id(self.logits),
+			logits_grad: graph.node_from_id(self.logits_grad),
+			targets: graph.node_from_id(self.targets),
+			targets_grad: graph.node_from_id(self.targets_grad),
+			pos_weight: self.pos_weight.map(|(pos_weight, pos_weight_grad)| {
+				(graph.node_from_id(pos_weight), graph.node_from_id(pos_weight_grad))
+			}),
+			output_grad: graph.node_from_id(self.output_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		let mut inputs = indexset![self.logits, self.targets, self.output_grad];
+		inputs.extend(self.pos_weight.map(|(pos_weight, _)| pos_weight));
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		let mut outputs = indexset![self.logits_grad, self.targets_grad];
+		outputs.extend(self.pos_weight.map(|(_, pos_weight_grad)| pos_weight_grad));
+		outputs
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let mut inputs = vec![self.logits, self.targets, self.output_grad];
+		inputs.extend(self.pos_weight.map(|(pos_weight, _)| pos_weight));
+		ctx.require_equal_shapes(&inputs)?;
+
+		ctx.set_output_like(&self.logits_grad, &self.logits)?;
+		ctx.set_output_like(&self.targets_grad, &self.targets)?;
+		if let Some((pos_weight, pos_weight_grad)) = self.pos_weight {
+			ctx.set_output_like(&pos_weight_grad, &pos_weight)?;
+		}
+		Ok(())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let logits = ctx.get_input(&self.logits);
+		let targets = ctx.get_input(&self.targets);
+		let output_grad = ctx.get_input(&self.output_grad);
+		let one = arr0(1.0);
+		let pos_weight = pos_weight_or_one(
+			ctx,
+			self.pos_weight.map(|(pos_weight, _)| pos_weight),
+			&one,
+			logits.shape(),
+		);
+
+		// d/dx = (1 - z) - (1 + (w - 1) * z) * sigmoid(-x), which is sigmoid(x) - z when w = 1
+		if ctx.is_required_output(&self.logits_grad) {
+			Zip::from(ctx.get_output(&self.logits_grad))
+				.and(&logits)
+				.and(&targets)
+				.and(&pos_weight)
+				.and(&output_grad)
+				.par_for_each(|logits_grad, &x, &z, &w, &g| {
+					*logits_grad += g * ((1.0 - z) - (1.0 + (w - 1.0) * z) * sigmoid(-x))
+				});
+		}
+
+		// d/dz = -x + (w - 1) * ln(1 + exp(-x))
+		if ctx.is_required_output(&self.targets_grad) {
+			Zip::from(ctx.get_output(&self.targets_grad))
+				.and(&logits)
+				.and(&pos_weight)
+				.and(&output_grad)
+				.par_for_each(|targets_grad, &x, &w, &g| *targets_grad += g * ((w - 1.0) * softplus_neg(x) - x));
+		}
+
+		// d/dw = z * ln(1 + exp(-x))
+		if let Some((_, ref pos_weight_grad)) = self.pos_weight {
+			if ctx.is_required_output(pos_weight_grad) {
+				Zip::from(ctx.get_output(pos_weight_grad))
+					.and(&logits)
+					.and(&targets)
+					.and(&output_grad)
+					.par_for_each(|pos_weight_grad, &x, &z, &g| *pos_weight_grad += g * z * softplus_neg(x));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{bce_with_logits, BceWithLogits};
+	use crate::elementwise::logistic::logistic;
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::arr1;
+
+	// logistic keeps the targets in (0, 1)
+	contract_case!(binary "bce_with_logits", |x, t| bce_with_logits(x, logistic(t)?));
+
+	#[test]
+	fn forward_test() {
+		let logits = Node::new(&[6])
+			.set_value(arr1(&[100.0, -100.0, 50.0, -50.0, 0.0, 2.0]))
+			.set_name("logits");
+		let targets = Node::new(&[6])
+			.set_value(arr1(&[1.0, 0.0, 0.0, 1.0, 0.5, 1.0]))
+			.set_name("targets");
+
+		let output = bce_with_logits(&logits, &targets).unwrap().calc().unwrap();
+
+		assert!(output.iter().all(|x| x.is_finite()), "{}", output);
+		assert!(output.all_relatively_close(
+			&arr1(&[0.0, 0.0, 50.0, 50.0, 2.0f32.ln(), (1.0 + (-2.0f32).exp()).ln()]),
+			1e-5
+		));
+	}
+
+	#[test]
+	fn pos_weight_forward_test() {
+		let logits = Node::new(&[3]).set_value(arr1(&[0.0, 0.0, -3.0])).set_name("logits");
+		let targets = Node::new(&[3]).set_value(arr1(&[1.0, 0.0, 1.0])).set_name("targets");
+		let pos_weight = Node::new(&[3]).set_value(arr1(&[2.0, 2.0, 0.5])).set_name("pos_weight");
+		let output = Node::new(&[3]).set_name("output");
+
+		BceWithLogits::new(&logits, &targets, &output)
+			.pos_weight(Some(&pos_weight))
+			.build()
+			.unwrap();
+
+		// only the positive targets are weighted
+		assert!(output.calc().unwrap().all_relatively_close(
+			&arr1(&[2.0 * 2.0f32.ln(), 2.0f32.ln(), 0.5 * (1.0 + 3.0f32.exp()).ln()]),
+			1e-5
+		));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits").set_init(uniform(-4.0, 4.0));
+		let targets = Node::new(&[13, 33]).set_name("targets").set_init(uniform(0.0, 1.0));
+
+		let output = bce_with_logits(&logits, &targets).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits, &targets])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn pos_weight_grad_numeric_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits").set_init(uniform(-4.0, 4.0));
+		let targets = Node::new(&[13, 33]).set_name("targets").set_init(uniform(0.0, 1.0));
+		let pos_weight = Node::new(&[13, 33]).set_name("pos_weight").set_init(uniform(0.5, 3.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		BceWithLogits::new(&logits, &targets, &output)
+			.pos_weight(Some(&pos_weight))
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits, &targets, &pos_weight])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -0,0 +1,461 @@
+use crate::loss::bce::{sigmoid, softplus_neg};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::Zip;
+use std::any::Any;
+
+/// Calculates the elementwise focal loss of the sigmoid of the logits with the targets.
+///
+/// `let output = alpha_t * (1 - p_t)^gamma * bce_with_logits(logits, targets)`
+///
+/// where `p = sigmoid(logits)`, `p_t = targets * p + (1 - targets) * (1 - p)` is the probability given to the target,
+/// and `alpha_t = targets * alpha + (1 - targets) * (1 - alpha)`. Increasing `gamma` reduces the loss of examples that
+/// are already well classified, focusing training on hard examples. If `alpha` is negative no class weighting is
+/// applied, so `gamma = 0` and `alpha = -1` recovers `bce_with_logits`.
+///
+/// The targets are treated as constants and do not receive a gradient.
+///
+/// The output node has the same shape as the logits and targets.
+pub fn focal_loss<I1, I2>(logits: I1, targets: I2, gamma: f32, alpha: f32) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let logits = logits.into();
+	let targets = targets.into();
+
+	let graph = merge_graphs(&[logits.graph(), targets.graph()]);
+
+	let output = graph
+		.new_node(logits.shape())
+		.set_name_unique(&format!("focal_loss({},{})", logits, targets));
+
+	FocalLoss::new(logits, targets, output.clone())
+		.gamma(gamma)
+		.alpha(alpha)
+		.build()?;
+
+	Ok(output)
+}
+
+/// Returns `(alpha_t, 1 - p_t)`.
+#[inline]
+fn terms(x: f32, z: f32, alpha: f32) -> (f32, f32) {
+	let alpha_t = if alpha < 0.0 {
+		1.0
+	} else {
+		z * alpha + (1.0 - z) * (1.0 - alpha)
+	};
+	(alpha_t, z * sigmoid(-x) + (1.0 - z) * sigmoid(x))
+}
+
+#[inline]
+fn loss(x: f32, z: f32, gamma: f32, alpha: f32) -> f32 {
+	let (alpha_t, one_minus_p_t) = terms(x, z, alpha);
+	let cross_entropy = (1.0 - z) * x + softplus_neg(x);
+	alpha_t * one_minus_p_t.powf(gamma) * cross_entropy
+}
+
+/// `d/dx = alpha_t * ((1 - p_t)^gamma * (p - z) - gamma * (1 - p_t)^(gamma - 1) * (2z - 1) * p * (1 - p) * ce)`
+#[inline]
+fn loss_grad(x: f32, z: f32, gamma: f32, alpha: f32) -> f32 {
+	let (alpha_t, one_minus_p_t) = terms(x, z, alpha);
+	let cross_entropy = (1.0 - z) * x + softplus_neg(x);
+	let p = sigmoid(x);
+
+	let modulating = one_minus_p_t.powf(gamma);
+	let modulating_grad = if gamma == 0.0 || one_minus_p_t <= 0.0 {
+		0.0
+	} else {
+		-gamma * one_minus_p_t.powf(gamma - 1.0) * (2.0 * z - 1.0) * p * (1.0 - p)
+	};
+
+	alpha_t * (modulating * (p - z) + modulating_grad * cross_entropy)
+}
+
+/// `FocalLoss` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct FocalLoss {
+	logits: Node,
+	targets: Node,
+	output: Node,
+	gamma: f32,
+	alpha: f32,
+}
+
+impl FocalLoss {
+	pub fn new<I1, I2, O>(logits: I1, targets: I2, output: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let logits = logits.into();
+		let targets = targets.into();
+		let output = output.into();
+		assert!(logits.shape().len() == targets.shape().len());
+		assert!(logits.shape().len() == output.shape().len());
+		FocalLoss {
+			logits,
+			targets,
+			output,
+			gamma: 2.0,
+			alpha: 0.25,
+		}
+	}
+
+	/// The exponent of the modulating factor `(1 - p_t)`, must be non-negative.
+	///
+	/// Default: 2.0
+	pub fn gamma(mut self, gamma: f32) -> Self {
+		assert!(gamma >= 0.0, "FocalLoss gamma must be non-negative");
+		self.gamma = gamma;
+		self
+	}
+
+	/// The weight of positive targets, with negative targets weighted by `1 - alpha`. A negative value disables
+	/// weighting.
+	///
+	/// Default: 0.25
+	pub fn alpha(mut self, alpha: f32) -> Self {
+		assert!(alpha <= 1.0, "FocalLoss alpha must not be greater than 1.0");
+		self.alpha = alpha;
+		self
+	}
+}
+
+impl OpSpecification for FocalLoss {
+	type InstanceType = FocalLossInstance;
+
+	fn type_name(&self) -> &'static str {
+		"FocalLoss"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.logits.clone(), self.targets.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			targets: mapping.get(&self.targets).unwrap_or(&self.targets).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(FocalLossInstance {
+			logits: self.logits.id(),
+			targets: self.targets.id(),
+			output: self.output.id(),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		})
+	}
+}
+
+/// FocalLoss OpInstance
+#[derive(Clone, Debug)]
+pub struct FocalLossInstance {
+	logits: NodeID,
+	targets: NodeID,
+	output: NodeID,
+	gamma: f32,
+	alpha: f32,
+}
+
+impl OpInstance for FocalLossInstance {
+	fn type_name(&self) -> &'static str {
+		"FocalLoss"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(FocalLoss {
+			logits: graph.node_from_id(self.logits),
+			targets: graph.node_from_id(self.targets),
+			output: graph.node_from_id(self.output),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits, self.targets]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		FocalLossBack::new(
+			ctx.node(&self.logits),
+			ctx.grad_of(&self.logits),
+			ctx.node(&self.targets),
+			ctx.grad_of(&self.output),
+		)
+		.gamma(self.gamma)
+		.alpha(self.alpha)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.logits, self.targets])?;
+		ctx.set_output_like(&self.output, &self.logits)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let gamma = self.gamma;
+		let alpha = self.alpha;
+
+		Zip::from(ctx.get_output(&self.output))
+			.and(&ctx.get_input(&self.logits))
+			.and(&ctx.get_input(&self.targets))
+			.par_for_each(|output, &x, &z| *output += loss(x, z, gamma, alpha));
+
+		Ok(())
+	}
+}
+
+/// `FocalLossBack` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct FocalLossBack {
+	logits: Node,
+	logits_grad: Node,
+	targets: Node,
+	output_grad: Node,
+	gamma: f32,
+	alpha: f32,
+}
+
+impl FocalLossBack {
+	pub fn new<I1, I2, I3, O>(logits: I1, logits_grad: O, targets: I2, output_grad: I3) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O: Into<Node>,
+	{
+		let logits = logits.into();
+		let logits_grad = logits_grad.into();
+		let targets = targets.into();
+		let output_grad = output_grad.into();
+		assert!(logits.shape().len() == targets.shape().len());
+		assert!(logits.shape().len() == logits_grad.shape().len());
+		assert!(logits.shape().len() == output_grad.shape().len());
+		FocalLossBack {
+			logits,
+			logits_grad,
+			targets,
+			output_grad,
+			gamma: 2.0,
+			alpha: 0.25,
+		}
+	}
+
+	/// The exponent of the modulating factor `(1 - p_t)`, must be non-negative.
+	///
+	/// Default: 2.0
+	pub fn gamma(mut self, gamma: f32) -> Self {
+		assert!(gamma >= 0.0, "FocalLossBack gamma must be non-negative");
+		self.gamma = gamma;
+		self
+	}
+
+	/// The weight of positive targets, with negative targets weighted by `1 - alpha`. A negative value disables
+	/// weighting.
+	///
+	/// Default: 0.25
+	pub fn alpha(mut self, alpha: f32) -> Self {
+		assert!(alpha <= 1.0, "FocalLossBack alpha must not be greater than 1.0");
+		self.alpha = alpha;
+		self
+	}
+}
+
+impl OpSpecification for FocalLossBack {
+	type InstanceType = FocalLossBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"FocalLossBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.logits.clone(), self.targets.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.logits_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			logits_grad: mapping.get(&self.logits_grad).unwrap_or(&self.logits_grad).clone(),
+			targets: mapping.get(&self.targets).unwrap_or(&self.targets).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(FocalLossBackInstance {
+			logits: self.logits.id(),
+			logits_grad: self.logits_grad.id(),
+			targets: self.targets.id(),
+			output_grad: self.output_grad.id(),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		})
+	}
+}
+
+/// FocalLossBack OpInstance
+#[derive(Clone, Debug)]
+pub struct FocalLossBackInstance {
+	logits: NodeID,
+	logits_grad: NodeID,
+	targets: NodeID,
+	output_grad: NodeID,
+	gamma: f32,
+	alpha: f32,
+}
+
+impl OpInstance for FocalLossBackInstance {
+	fn type_name(&self) -> &'static str {
+		"FocalLossBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(FocalLossBack {
+			logits: graph.node_from_id(self.logits),
+			logits_grad: graph.node_from_id(self.logits_grad),
+			targets: graph.node_from_id(self.targets),
+			output_grad: graph.node_from_id(self.output_grad),
+			gamma: self.gamma,
+			alpha: self.alpha,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits, self.targets, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.logits, self.targets, self.output_grad])?;
+		ctx.set_output_like(&self.logits_grad, &self.logits)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let gamma = self.gamma;
+		let alpha = self.alpha;
+
+		Zip::from(ctx.get_output(&self.logits_grad))
+			.and(&ctx.get_input(&self.logits))
+			.and(&ctx.get_input(&self.targets))
+			.and(&ctx.get_input(&self.output_grad))
+			.par_for_each(|logits_grad, &x, &z, &g| *logits_grad += g * loss_grad(x, z, gamma, alpha));
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::focal_loss;
+	use crate::elementwise::logistic::logistic;
+	use crate::loss::bce::bce_with_logits;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr1, Array2};
+
+	// logistic keeps the targets in (0, 1)
+	contract_case!(binary "focal_loss", |x, t| focal_loss(x, logistic(t)?, 2.0, 0.25));
+
+	#[test]
+	fn forward_test() {
+		let logits = Node::new(&[6])
+			.set_value(arr1(&[30.0, -30.0, 2.0, -2.0, 0.0, 0.5]))
+			.set_name("logits");
+		let targets = Node::new(&[6])
+			.set_value(arr1(&[1.0, 0.0, 1.0, 1.0, 0.0, 0.3]))
+			.set_name("targets");
+
+		let bce = bce_with_logits(&logits, &targets).unwrap().calc().unwrap();
+
+		let unweighted = focal_loss(&logits, &targets, 0.0, -1.0).unwrap().calc().unwrap();
+		assert!(unweighted.all_relatively_close(&bce, 1e-6));
+
+		let alpha = focal_loss(&logits, &targets, 0.0, 0.25).unwrap().calc().unwrap();
+		let alpha_t = arr1(&[0.25, 0.75, 0.25, 0.25, 0.75, 0.3 * 0.25 + 0.7 * 0.75]);
+		assert!(alpha.all_relatively_close(&(&bce * &alpha_t), 1e-6));
+
+		// the well classified example at logit 2.0 is down-weighted much more than the misclassified one at -2.0
+		let focal = focal_loss(&logits, &targets, 2.0, -1.0).unwrap().calc().unwrap();
+		let p: f32 = 1.0 / (1.0 + (-2.0f32).exp());
+		assert!((focal[2] - (1.0 - p).powi(2) * bce[2]).abs() < 1e-6);
+		assert!((focal[3] - p.powi(2) * bce[3]).abs() < 1e-6);
+		assert!(focal[2] / bce[2] < 0.02);
+		assert!(focal[3] / bce[3] > 0.75);
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits").set_init(uniform(-4.0, 4.0));
+		let targets = Node::new(&[13, 33])
+			.set_name("targets")
+			.set_value(Array2::from_shape_fn((13, 33), |(i, j)| {
+				((i * 7 + j * 3) % 11) as f32 / 10.0
+			}));
+
+		let output = focal_loss(&logits, &targets, 2.0, 0.25).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_hard_targets_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits").set_init(uniform(-4.0, 4.0));
+		let targets = Node::new(&[13, 33])
+			.set_name("targets")
+			.set_value(Array2::from_shape_fn((13, 33), |(i, j)| ((i + j) % 2) as f32));
+
+		let output = focal_loss(&logits, &targets, 0.5, -1.0).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
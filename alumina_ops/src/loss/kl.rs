@@ -0,0 +1,450 @@
+use crate::loss::{reduce_loss, Reduction};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Calculates the Kullback-Leibler divergence of the distribution `q` from the distribution `p` along the selected
+/// axis, then applies the reduction.
+///
+/// `log_p` must be log-probabilities, e.g. the output of a log-softmax, and `q` must be probabilities.
+///
+/// `let output = reduce_sum(q * (ln(q) - log_p), &[axis])`
+///
+/// Elements where `q` is zero contribute zero to the output. If the reduction is `None` the output node has the shape
+/// of `log_p` and `q`, but with the axis removed, otherwise it is a scalar.
+pub fn kl_div<I1, I2>(log_p: I1, q: I2, axis: isize, reduction: Reduction) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let log_p = log_p.into();
+	let q = q.into();
+	let axis = wrap_dim(axis, log_p.shape().len());
+
+	let graph = merge_graphs(&[log_p.graph(), q.graph()]);
+
+	let output_shape = log_p
+		.shape()
+		.iter()
+		.enumerate()
+		.filter_map(|(i, x)| if i == axis { None } else { Some(x) })
+		.into();
+
+	let output = graph
+		.new_node(output_shape)
+		.set_name_unique(&format!("kl_div({},{})", log_p, q));
+
+	KlDiv::new(log_p, q, output.clone(), axis).build()?;
+
+	reduce_loss(output, reduction)
+}
+
+/// `KlDiv` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct KlDiv {
+	log_p: Node,
+	q: Node,
+	output: Node,
+	axis: usize,
+}
+
+impl KlDiv {
+	pub fn new<I1, I2, O>(log_p: I1, q: I2, output: O, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let log_p = log_p.into();
+		let q = q.into();
+		let output = output.into();
+		assert!(log_p.shape().len() == q.shape().len());
+		assert!(log_p.shape().len() == output.shape().len() + 1);
+		assert!(
+			axis < log_p.shape().len(),
+			"axis {} must be less than log_p.shape().len() {}",
+			axis,
+			log_p.shape().len()
+		);
+		KlDiv { log_p, q, output, axis }
+	}
+}
+
+impl OpSpecification for KlDiv {
+	type InstanceType = KlDivInstance;
+
+	fn type_name(&self) -> &'static str {
+		"KlDiv"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.log_p.clone(), self.q.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			log_p: mapping.get(&self.log_p).unwrap_or(&self.log_p).clone(),
+			q: mapping.get(&self.q).unwrap_or(&self.q).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(KlDivInstance {
+			log_p: self.log_p.id(),
+			q: self.q.id(),
+			output: self.output.id(),
+			axis: self.axis,
+		})
+	}
+}
+
+/// KlDiv OpInstance
+#[derive(Clone, Debug)]
+pub struct KlDivInstance {
+	log_p: NodeID,
+	q: NodeID,
+	output: NodeID,
+	axis: usize,
+}
+
+impl OpInstance for KlDivInstance {
+	fn type_name(&self) -> &'static str {
+		"KlDiv"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(KlDiv {
+			log_p: graph.node_from_id(self.log_p),
+			q: graph.node_from_id(self.q),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.log_p, self.q]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		KlDivBack::new(
+			ctx.node(&self.log_p),
+			ctx.grad_of(&self.log_p),
+			ctx.node(&self.q),
+			ctx.grad_of(&self.q),
+			ctx.grad_of(&self.output),
+			self.axis,
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.log_p, self.q])?;
+
+		let output_shape = ctx
+			.input_shape(&self.log_p)
+			.slice()
+			.iter()
+			.enumerate()
+			.filter_map(|(i, axis)| if i == self.axis { None } else { Some(axis) })
+			.into();
+
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		Zip::from(ctx.get_output(&self.output))
+			.and(ctx.get_input(&self.log_p).lanes(Axis(self.axis)))
+			.and(ctx.get_input(&self.q).lanes(Axis(self.axis)))
+			.par_for_each(|output, log_p, q| {
+				*output += log_p
+					.iter()
+					.zip(&q)
+					.filter(|&(_, &q)| q > 0.0)
+					.map(|(&log_p, &q)| q * (q.ln() - log_p))
+					.sum::<f32>();
+			});
+
+		Ok(())
+	}
+}
+
+/// `KlDivBack` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct KlDivBack {
+	log_p: Node,
+	log_p_grad: Node,
+	q: Node,
+	q_grad: Node,
+	output_grad: Node,
+	axis: usize,
+}
+
+impl KlDivBack {
+	pub fn new<I1, I2, I3, O1, O2>(log_p: I1, log_p_grad: O1, q: I2, q_grad: O2, output_grad: I3, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let log_p = log_p.into();
+		let log_p_grad = log_p_grad.into();
+		let q = q.into();
+		let q_grad = q_grad.into();
+		let output_grad = output_grad.into();
+		assert!(log_p.shape().len() == q.shape().len());
+		assert!(log_p.shape().len() == log_p_grad.shape().len());
+		assert!(log_p.shape().len() == q_grad.shape().len());
+		assert!(log_p.shape().len() == output_grad.shape().len() + 1);
+		assert!(
+			axis < log_p.shape().len(),
+			"axis {} must be less than log_p.shape().len() {}",
+			axis,
+			log_p.shape().len()
+		);
+		KlDivBack {
+			log_p,
+			log_p_grad,
+			q,
+			q_grad,
+			output_grad,
+			axis,
+		}
+	}
+}
+
+impl OpSpecification for KlDivBack {
+	type InstanceType = KlDivBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"KlDivBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.log_p.clone(), self.q.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.log_p_grad.clone(), self.q_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			log_p: mapping.get(&self.log_p).unwrap_or(&self.log_p).clone(),
+			log_p_grad: mapping.get(&self.log_p_grad).unwrap_or(&self.log_p_grad).clone(),
+			q: mapping.get(&self.q).unwrap_or(&self.q).clone(),
+			q_grad: mapping.get(&self.q_grad).unwrap_or(&self.q_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			axis: self.axis,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(KlDivBackInstance {
+			log_p: self.log_p.id(),
+			log_p_grad: self.log_p_grad.id(),
+			q: self.q.id(),
+			q_grad: self.q_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+		})
+	}
+}
+
+/// KlDivBack OpInstance
+#[derive(Clone, Debug)]
+pub struct KlDivBackInstance {
+	log_p: NodeID,
+	log_p_grad: NodeID,
+	q: NodeID,
+	q_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+}
+
+impl OpInstance for KlDivBackInstance {
+	fn type_name(&self) -> &'static str {
+		"KlDivBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(KlDivBack {
+			log_p: graph.node_from_id(self.log_p),
+			log_p_grad: graph.node_from_id(self.log_p_grad),
+			q: graph.node_from_id(self.q),
+			q_grad: graph.node_from_id(self.q_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.log_p, self.q, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.log_p_grad, self.q_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.log_p, self.q])?;
+
+		let log_p_shape = ctx.input_shape(&self.log_p).clone();
+		let output_grad_shape = ctx.input_shape(&self.output_grad);
+		if output_grad_shape.slice().len() + 1 != log_p_shape.slice().len()
+			|| log_p_shape
+				.slice()
+				.iter()
+				.enumerate()
+				.filter_map(|(i, &axis)| if i == self.axis { None } else { Some(axis) })
+				.zip(output_grad_shape.slice())
+				.any(|(input_axis, &out_grad_axis)| input_axis != out_grad_axis)
+		{
+			return Err(format!("KlDivBack requires the output grad to have the shape of the inputs with the selected axis removed: inputs:{:?} output_grad:{:?}, axis: {}", log_p_shape.slice(), output_grad_shape.slice(), self.axis).into());
+		}
+
+		ctx.set_output_like(&self.log_p_grad, &self.log_p)?;
+		ctx.set_output_like(&self.q_grad, &self.q)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let log_p = ctx.get_input(&self.log_p);
+		let q = ctx.get_input(&self.q);
+		let output_grad = ctx.get_input(&self.output_grad);
+
+		// d/dlog_p = -q
+		if ctx.is_required_output(&self.log_p_grad) {
+			Zip::from(ctx.get_output(&self.log_p_grad).lanes_mut(Axis(self.axis)))
+				.and(q.lanes(Axis(self.axis)))
+				.and(&output_grad)
+				.par_for_each(|mut log_p_grad, q, &output_grad| {
+					Zip::from(&mut log_p_grad)
+						.and(&q)
+						.for_each(|log_p_grad, &q| *log_p_grad -= q * output_grad);
+				});
+		}
+
+		// d/dq = ln(q) + 1 - log_p, taken as 0 where q is zero
+		if ctx.is_required_output(&self.q_grad) {
+			Zip::from(ctx.get_output(&self.q_grad).lanes_mut(Axis(self.axis)))
+				.and(log_p.lanes(Axis(self.axis)))
+				.and(q.lanes(Axis(self.axis)))
+				.and(&output_grad)
+				.par_for_each(|mut q_grad, log_p, q, &output_grad| {
+					Zip::from(&mut q_grad)
+						.and(&log_p)
+						.and(&q)
+						.for_each(|q_grad, &log_p, &q| {
+							if q > 0.0 {
+								*q_grad += (q.ln() + 1.0 - log_p) * output_grad;
+							}
+						});
+				});
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::kl_div;
+	use crate::loss::Reduction;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr0, arr1, arr2};
+
+	contract_case!(binary "kl_div", |x, y| kl_div(x, y, -1, Reduction::Mean));
+
+	#[test]
+	fn forward_test() {
+		let p: [[f32; 3]; 2] = [[0.5, 0.25, 0.25], [0.1, 0.6, 0.3]];
+		let q: [[f32; 3]; 2] = [[0.25, 0.25, 0.5], [0.0, 0.5, 0.5]];
+
+		let log_p = Node::new(&[2, 3]).set_value(arr2(&p).mapv(f32::ln)).set_name("log_p");
+		let q = Node::new(&[2, 3]).set_value(arr2(&q)).set_name("q");
+
+		// row 0: 0.25 * ln(0.5) + 0.25 * ln(1) + 0.5 * ln(2)
+		// row 1: 0 + 0.5 * ln(0.5 / 0.6) + 0.5 * ln(0.5 / 0.3)
+		let expected = arr1(&[
+			0.25 * 2.0f32.ln(),
+			0.5 * (5.0f32 / 6.0).ln() + 0.5 * (5.0f32 / 3.0).ln(),
+		]);
+
+		let none = kl_div(&log_p, &q, -1, Reduction::None).unwrap();
+		assert_eq!(none.shape(), (&[2]).into());
+		assert!(none.calc().unwrap().all_relatively_close(&expected, 1e-5));
+
+		let sum = kl_div(&log_p, &q, -1, Reduction::Sum).unwrap();
+		assert!(sum.calc().unwrap().all_relatively_close(&arr0(expected.sum()), 1e-5));
+
+		let mean = kl_div(&log_p, &q, -1, Reduction::Mean).unwrap();
+		assert!(mean
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr0(expected.sum() / 2.0), 1e-5));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let log_p = Node::new(&[4, 5]).set_name("log_p");
+		let q = Node::new(&[4, 5])
+			.set_value(arr2(&[
+				[0.1, 0.2, 0.3, 0.2, 0.2],
+				[0.0, 0.5, 0.25, 0.25, 0.0],
+				[0.6, 0.1, 0.1, 0.1, 0.1],
+				[0.2, 0.2, 0.2, 0.2, 0.2],
+			]))
+			.set_name("q");
+
+		let output = kl_div(&log_p, &q, -1, Reduction::None).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&log_p])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_q_test() {
+		let log_p = Node::new(&[4, 5]).set_name("log_p");
+		let q = Node::new(&[4, 5]).set_name("q").set_init(uniform(0.1, 1.0));
+
+		let output = kl_div(&log_p, &q, 0, Reduction::Sum).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&log_p, &q])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -0,0 +1,27 @@
+pub mod bce;
+pub mod focal;
+pub mod kl;
+
+use crate::reduce::reduce_sum::{reduce_mean, reduce_sum};
+use alumina_core::{errors::OpBuildError, graph::Node};
+
+/// How the elementwise losses produced by a loss function are combined to give its output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reduction {
+	/// Return the losses unreduced.
+	None,
+	/// Return the sum of the losses as a scalar.
+	Sum,
+	/// Return the mean of the losses as a scalar.
+	#[default]
+	Mean,
+}
+
+/// Applies the reduction to the output of a loss function.
+pub(crate) fn reduce_loss(loss: Node, reduction: Reduction) -> Result<Node, OpBuildError> {
+	match reduction {
+		Reduction::None => Ok(loss),
+		Reduction::Sum => reduce_sum(loss, &[], false),
+		Reduction::Mean => reduce_mean(loss, &[], false),
+	}
+}
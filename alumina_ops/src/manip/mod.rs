@@ -2,5 +2,7 @@ pub mod expand_dims;
 pub mod permute_axes;
 pub mod remove_dims;
 pub mod reshape;
+pub mod roll;
+pub mod take_along_axis;
 //pub mod slice;
 pub mod concat;
@@ -0,0 +1,224 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Slice};
+use std::any::Any;
+
+/// Circularly shifts the input by `shift` elements along `axis`, so that elements moved past the end wrap around to the
+/// start.
+///
+/// `output[(i + shift) % n] = input[i]`, where `n` is the size of `axis`. Negative shifts move elements towards the
+/// start.
+///
+/// The output node has the same shape as the input.
+pub fn roll<I>(input: I, shift: isize, axis: isize) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("roll({})", input));
+
+	let _op = Roll::new(&input, &output, shift, axis).build()?;
+
+	Ok(output)
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Roll {
+	input: Node,
+	output: Node,
+	shift: isize,
+	axis: usize,
+}
+
+impl Roll {
+	pub fn new<I, O>(input: I, output: O, shift: isize, axis: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same shape"
+		);
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		Roll {
+			input,
+			output,
+			shift,
+			axis,
+		}
+	}
+}
+
+impl OpSpecification for Roll {
+	type InstanceType = RollInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Roll"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			shift: self.shift,
+			axis: self.axis,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(RollInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			shift: self.shift,
+			axis: self.axis,
+		})
+	}
+}
+
+/// Roll OpInstance
+#[derive(Clone, Debug)]
+pub struct RollInstance {
+	input: NodeID,
+	output: NodeID,
+	shift: isize,
+	axis: usize,
+}
+
+impl OpInstance for RollInstance {
+	fn type_name(&self) -> &'static str {
+		"Roll"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Roll {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			shift: self.shift,
+			axis: self.axis,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// the inverse of a roll is the roll in the opposite direction
+		let _op = Roll::new(
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+			-self.shift,
+			self.axis,
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output = ctx.get_output(&self.output);
+
+		let len = input.shape()[self.axis];
+		if len == 0 {
+			return Ok(());
+		}
+		let shift = self.shift.rem_euclid(len as isize);
+		let len = len as isize;
+
+		// the end of the input wraps around to the start of the output
+		let axis = Axis(self.axis);
+		let mut output_end = output.slice_axis_mut(axis, Slice::from(shift..));
+		output_end += &input.slice_axis(axis, Slice::from(..len - shift));
+		let mut output_start = output.slice_axis_mut(axis, Slice::from(..shift));
+		output_start += &input.slice_axis(axis, Slice::from(len - shift..));
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::roll;
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+	use ndarray::{arr2, ArrayD};
+
+	contract_case!(unary "roll", |x| roll(x, -2, 1));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 5])
+			.set_name("input")
+			.set_value(arr2(&[[0.0, 1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0, 9.0]]));
+
+		let rolls = [
+			(2, 1, arr2(&[[3.0, 4.0, 0.0, 1.0, 2.0], [8.0, 9.0, 5.0, 6.0, 7.0]])),
+			(-1, -1, arr2(&[[1.0, 2.0, 3.0, 4.0, 0.0], [6.0, 7.0, 8.0, 9.0, 5.0]])),
+			(7, 1, arr2(&[[3.0, 4.0, 0.0, 1.0, 2.0], [8.0, 9.0, 5.0, 6.0, 7.0]])),
+			(5, 1, arr2(&[[0.0, 1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0, 9.0]])),
+			(1, 0, arr2(&[[5.0, 6.0, 7.0, 8.0, 9.0], [0.0, 1.0, 2.0, 3.0, 4.0]])),
+		];
+		for (shift, axis, expected) in &rolls {
+			let output = roll(&input, *shift, *axis).unwrap();
+			assert_eq!(output.calc().unwrap(), expected.clone().into_dyn());
+		}
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for &(shift, axis) in &[(3, 1), (-2, 1), (-9, 0)] {
+			let input = Node::new(&[7, 9, 5]).set_name("input");
+			let scale = Node::new(&[7, 9, 5])
+				.set_name("scale")
+				.set_value(ArrayD::from_shape_fn(vec![7, 9, 5], |ix| {
+					((ix[0] * 45 + ix[1] * 5 + ix[2]) as f32).sin()
+				}));
+
+			// the plain sum of the output is the same for any shift, so weight it to check the direction of the roll
+			let output = mul(roll(&input, shift, axis).unwrap(), &scale).unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input]).run();
+		}
+	}
+}
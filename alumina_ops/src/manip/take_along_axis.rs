@@ -0,0 +1,546 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayD, ArrayViewD, ArrayViewMutD, Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Selects elements of the input along `axis` at the positions given by `indices`, such as the output of `argmax` with
+/// the axis re-inserted.
+///
+/// `indices` must have the same number of axes as the input, and holds integer positions along `axis` stored as `f32`.
+/// On every other axis the input and indices are broadcast against each other, so either may have size 1.
+///
+/// The output node has the shape of the indices, except that broadcast axes take the size of the input.
+pub fn take_along_axis<I1, I2>(input: I1, indices: I2, axis: isize) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let input = input.into();
+	let indices = indices.into();
+	if input.shape().len() != indices.shape().len() {
+		return Err(format!(
+			"take_along_axis requires the input ({}) and indices ({}) to have the same number of axes, but they have shapes {} and {}",
+			input,
+			indices,
+			input.shape(),
+			indices.shape()
+		)
+		.into());
+	}
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let output_shape: NodeShape = input
+		.shape()
+		.iter()
+		.zip(indices.shape().iter())
+		.enumerate()
+		.map(|(i, (input_axis, indices_axis))| {
+			if i == axis {
+				Ok(indices_axis.clone())
+			} else if *indices_axis == NodeAxis::known(1) {
+				Ok(input_axis.clone())
+			} else {
+				indices_axis.broadcast_merge(input_axis)
+			}
+		})
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|_| {
+			format!(
+				"take_along_axis could not broadcast the input ({}) with shape {} against the indices ({}) with shape {}",
+				input,
+				input.shape(),
+				indices,
+				indices.shape()
+			)
+		})?
+		.into();
+
+	let output = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("take_along_axis({},{})", input, indices));
+
+	let _op = TakeAlongAxis::new(input, indices, output.clone(), axis).build()?;
+
+	Ok(output)
+}
+
+/// Returns the shape of the output of `TakeAlongAxis`, or an error if the input and indices can't be broadcast.
+fn calc_output_shape(input_shape: &[usize], indices_shape: &[usize], axis: usize) -> Result<Vec<usize>, String> {
+	if input_shape.len() != indices_shape.len() {
+		return Err(format!(
+			"TakeAlongAxis requires the input and indices to have the same number of axes, but they have shapes {:?} and {:?}",
+			input_shape, indices_shape
+		));
+	}
+	input_shape
+		.iter()
+		.zip(indices_shape)
+		.enumerate()
+		.map(|(i, (&input_axis, &indices_axis))| {
+			if i == axis || input_axis == 1 || input_axis == indices_axis {
+				Ok(indices_axis)
+			} else if indices_axis == 1 {
+				Ok(input_axis)
+			} else {
+				Err(format!(
+					"TakeAlongAxis could not broadcast the input with shape {:?} against the indices with shape {:?}",
+					input_shape, indices_shape
+				))
+			}
+		})
+		.collect()
+}
+
+/// Checks that every index is a whole number which is a valid position along an axis of length `len`.
+fn check_indices(indices: &ArrayViewD<f32>, len: usize) -> Result<(), ExecutionError> {
+	match indices
+		.iter()
+		.find(|&&index| index < 0.0 || index >= len as f32 || index.fract() != 0.0)
+	{
+		Some(index) => Err(format!(
+			"TakeAlongAxis index {} is not a position along an axis of length {}",
+			index, len
+		)
+		.into()),
+		None => Ok(()),
+	}
+}
+
+/// Takes elements from the input at the positions given by the indices along the axis.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct TakeAlongAxis {
+	input: Node,
+	indices: Node,
+	output: Node,
+	axis: usize,
+}
+
+impl TakeAlongAxis {
+	pub fn new<I1, I2, O>(input: I1, indices: I2, output: O, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let indices = indices.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == indices.shape().len() && input.shape().len() == output.shape().len(),
+			"input, indices and output must have the same number of axes"
+		);
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		TakeAlongAxis {
+			input,
+			indices,
+			output,
+			axis,
+		}
+	}
+}
+
+impl OpSpecification for TakeAlongAxis {
+	type InstanceType = TakeAlongAxisInstance;
+
+	fn type_name(&self) -> &'static str {
+		"TakeAlongAxis"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.indices.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			indices: mapping.get(&self.indices).unwrap_or(&self.indices).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(TakeAlongAxisInstance {
+			input: self.input.id(),
+			indices: self.indices.id(),
+			output: self.output.id(),
+			axis: self.axis,
+		})
+	}
+}
+
+/// TakeAlongAxis OpInstance
+#[derive(Clone, Debug)]
+pub struct TakeAlongAxisInstance {
+	input: NodeID,
+	indices: NodeID,
+	output: NodeID,
+	axis: usize,
+}
+
+impl OpInstance for TakeAlongAxisInstance {
+	fn type_name(&self) -> &'static str {
+		"TakeAlongAxis"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(TakeAlongAxis {
+			input: graph.node_from_id(self.input),
+			indices: graph.node_from_id(self.indices),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.indices]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// indices are not differentiable, so only the input receives a gradient
+		TakeAlongAxisBack::new(
+			ctx.node(&self.input),
+			ctx.node(&self.indices),
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+			self.axis,
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape = calc_output_shape(
+			ctx.input_shape(&self.input).slice(),
+			ctx.input_shape(&self.indices).slice(),
+			self.axis,
+		)?;
+		ctx.merge_output_shape(&self.output, &output_shape.into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let indices = ctx.get_input(&self.indices);
+		let mut output = ctx.get_output(&self.output);
+		let axis = Axis(self.axis);
+
+		check_indices(&indices, input.len_of(axis))?;
+
+		// broadcast every axis except the one being taken from
+		let mut input_shape = output.shape().to_vec();
+		input_shape[self.axis] = input.len_of(axis);
+		let input = input
+			.broadcast(input_shape)
+			.ok_or_else(|| "TakeAlongAxis could not broadcast the input".to_string())?;
+		let indices = indices
+			.broadcast(output.shape())
+			.ok_or_else(|| "TakeAlongAxis could not broadcast the indices".to_string())?;
+
+		Zip::from(output.lanes_mut(axis))
+			.and(indices.lanes(axis))
+			.and(input.lanes(axis))
+			.par_for_each(|output, indices, input| {
+				for (output, &index) in output.into_iter().zip(indices) {
+					*output += input[index as usize];
+				}
+			});
+
+		Ok(())
+	}
+}
+
+/// Scatters each element of the output grad back to the position of the input it was taken from, summing over repeated
+/// indices and broadcast axes.
+///
+/// Input/Output naming convention matches TakeAlongAxis Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The input is only used to determine the shape of the input grad.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct TakeAlongAxisBack {
+	input: Node,
+	indices: Node,
+	output_grad: Node,
+	input_grad: Node,
+	axis: usize,
+}
+
+impl TakeAlongAxisBack {
+	pub fn new<I1, I2, I3, O>(input: I1, indices: I2, output_grad: I3, input_grad: O, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let indices = indices.into();
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(
+			input.shape().len() == indices.shape().len()
+				&& input.shape().len() == output_grad.shape().len()
+				&& input.shape().len() == input_grad.shape().len(),
+			"input, indices, output_grad and input_grad must have the same number of axes"
+		);
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		TakeAlongAxisBack {
+			input,
+			indices,
+			output_grad,
+			input_grad,
+			axis,
+		}
+	}
+}
+
+impl OpSpecification for TakeAlongAxisBack {
+	type InstanceType = TakeAlongAxisBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"TakeAlongAxisBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.indices.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			indices: mapping.get(&self.indices).unwrap_or(&self.indices).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			axis: self.axis,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(TakeAlongAxisBackInstance {
+			input: self.input.id(),
+			indices: self.indices.id(),
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+			axis: self.axis,
+		})
+	}
+}
+
+/// TakeAlongAxisBack OpInstance
+#[derive(Clone, Debug)]
+pub struct TakeAlongAxisBackInstance {
+	input: NodeID,
+	indices: NodeID,
+	output_grad: NodeID,
+	input_grad: NodeID,
+	axis: usize,
+}
+
+impl OpInstance for TakeAlongAxisBackInstance {
+	fn type_name(&self) -> &'static str {
+		"TakeAlongAxisBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(TakeAlongAxisBack {
+			input: graph.node_from_id(self.input),
+			indices: graph.node_from_id(self.indices),
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+			axis: self.axis,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.indices, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// the adjoint of the scatter is taking the same positions again
+		TakeAlongAxis::new(
+			ctx.grad_of(&self.input_grad),
+			ctx.node(&self.indices),
+			ctx.grad_of(&self.output_grad),
+			self.axis,
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape: NodeShape = ctx.input_shape(&self.input).slice().into();
+		ctx.merge_output_shape(&self.input_grad, &input_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let indices = ctx.get_input(&self.indices);
+		let output_grad = ctx.get_input(&self.output_grad);
+		let mut input_grad = ctx.get_output(&self.input_grad);
+		let axis = Axis(self.axis);
+
+		check_indices(&indices, input_grad.len_of(axis))?;
+
+		let indices = indices
+			.broadcast(output_grad.shape())
+			.ok_or_else(|| "TakeAlongAxisBack could not broadcast the indices".to_string())?;
+
+		let scatter = |mut input_grad: ArrayViewMutD<f32>| {
+			Zip::from(input_grad.lanes_mut(axis))
+				.and(indices.lanes(axis))
+				.and(output_grad.lanes(axis))
+				.par_for_each(|mut input_grad, indices, output_grad| {
+					for (&index, &output_grad) in indices.into_iter().zip(output_grad) {
+						input_grad[index as usize] += output_grad;
+					}
+				});
+		};
+
+		let mut full_shape = output_grad.shape().to_vec();
+		full_shape[self.axis] = input_grad.len_of(axis);
+		if full_shape == input_grad.shape() {
+			scatter(input_grad);
+		} else {
+			// scatter at the broadcast shape, then sum over the axes the input was broadcast along
+			let mut full = ArrayD::zeros(full_shape);
+			scatter(full.view_mut());
+			for (i, &len) in input_grad.shape().iter().enumerate() {
+				if len == 1 && full.len_of(Axis(i)) != 1 {
+					full = full.sum_axis(Axis(i)).insert_axis(Axis(i));
+				}
+			}
+			input_grad += &full;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::take_along_axis;
+	use crate::tests::contract_case;
+	use crate::{elementwise::mul::mul, manip::expand_dims::expand_dims, math::argmax::argmax};
+	use alumina_core::{graph::Node, util::wrap_dim};
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+	use ndarray::{arr2, ArrayD};
+
+	contract_case!(unary "take_along_axis", |x| {
+		let indices = Node::new(&[4, 3])
+			.set_name("indices")
+			.set_value(ArrayD::from_shape_fn(vec![4, 3], |ix| ((ix[0] * 2 + ix[1] * 3) % 5) as f32));
+		take_along_axis(x, indices, 1)
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[5, 7]).set_name("input").set_value(arr2(&[
+			[18.0, 3.0, 25.0, 0.0, 6.0, 35.0, 9.2],
+			[28.0, 14.0, 33.0, 22.0, 20.0, 8.0, 41.0],
+			[13.0, 30.0, 21.0, 19.0, 7.0, 9.0, 18.0],
+			[16.0, 1.0, 26.0, 32.0, 2.0, 29.0, 17.0],
+			[17.0, 12.0, 5.0, 11.0, 10.0, 15.0, 3.0],
+		]));
+
+		// reduce_max along the last axis, keeping dims
+		let indices = expand_dims(argmax(&input, -1).unwrap(), &[1]).unwrap();
+		let output = take_along_axis(&input, &indices, -1).unwrap();
+
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[35.0], [41.0], [30.0], [32.0], [17.0]]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn forward_broadcast_test() {
+		let input = Node::new(&[1, 4])
+			.set_name("input")
+			.set_value(arr2(&[[0.0, 10.0, 20.0, 30.0]]));
+		let indices = Node::new(&[3, 2])
+			.set_name("indices")
+			.set_value(arr2(&[[3.0, 0.0], [1.0, 1.0], [2.0, 3.0]]));
+
+		let output = take_along_axis(&input, &indices, 1).unwrap();
+
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[30.0, 0.0], [10.0, 10.0], [20.0, 30.0]]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn forward_index_error_test() {
+		let input = Node::new(&[2, 3]).set_name("input").set_value(arr2(&[[0.0; 3]; 2]));
+		let indices = Node::new(&[2, 1]).set_name("indices").set_value(arr2(&[[1.0], [3.0]]));
+
+		let output = take_along_axis(&input, &indices, 1).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for &(input_shape, indices_shape, axis) in &[
+			([7, 9], [7, 4], 1),
+			([7, 9], [3, 9], 0),
+			([1, 9], [5, 4], 1),
+			([7, 9], [7, 1], -1),
+		] {
+			let axis_len = input_shape[wrap_dim(axis, 2)];
+			let input = Node::new(&input_shape).set_name("input");
+			// repeated indices check that the gradient is summed rather than overwritten
+			let indices = Node::new(&indices_shape)
+				.set_name("indices")
+				.set_value(ArrayD::from_shape_fn(indices_shape.to_vec(), |ix| {
+					((ix[0] * 5 + ix[1] * 3) % axis_len) as f32
+				}));
+			let scale = Node::new(&indices_shape)
+				.set_name("scale")
+				.set_value(ArrayD::from_shape_fn(indices_shape.to_vec(), |ix| {
+					((ix[0] * 9 + ix[1]) as f32).sin()
+				}));
+
+			let output = mul(take_along_axis(&input, &indices, axis).unwrap(), &scale).unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input]).run();
+		}
+	}
+}
@@ -0,0 +1,458 @@
+use crate::math::logdet::as_batch;
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Array2, Array3, ArrayView2, Dimension};
+use std::any::Any;
+
+/// Calculates the lower triangular Cholesky factor `L` of each symmetric positive definite matrix in the last two axes
+/// of the input, such that `L @ L^T = input`.
+///
+/// Only the symmetric part of the input, `(input + input^T) / 2`, is used. Execution returns an error if any matrix is
+/// not positive definite.
+///
+/// The output node has the same shape as the input.
+pub fn cholesky<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let len = input.shape().len();
+	if len < 2
+		|| input.shape().slice()[len - 2]
+			.merge(&input.shape().slice()[len - 1])
+			.is_err()
+	{
+		return Err(format!(
+			"cholesky requires the input ({}) to have at least 2 axes, the last two of which must be equal, but it has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("cholesky({})", input));
+
+	Cholesky::new(input, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Returns the Cholesky factor of the symmetric part of `matrix`, or `None` if it is not positive definite.
+fn decompose(matrix: ArrayView2<f64>) -> Option<Array2<f64>> {
+	let n = matrix.nrows();
+	let mut l = Array2::zeros((n, n));
+	for j in 0..n {
+		let mut diagonal = matrix[[j, j]];
+		for k in 0..j {
+			diagonal -= l[[j, k]] * l[[j, k]];
+		}
+		if diagonal.is_nan() || diagonal <= 0.0 {
+			return None;
+		}
+		let diagonal = diagonal.sqrt();
+		l[[j, j]] = diagonal;
+
+		for i in j + 1..n {
+			let mut sum = 0.5 * (matrix[[i, j]] + matrix[[j, i]]);
+			for k in 0..j {
+				sum -= l[[i, k]] * l[[j, k]];
+			}
+			l[[i, j]] = sum / diagonal;
+		}
+	}
+	Some(l)
+}
+
+/// Returns `X` such that `L^T @ X = rhs`, where `L` is lower triangular with a non-zero diagonal.
+fn solve_transposed(l: ArrayView2<f64>, rhs: ArrayView2<f64>) -> Array2<f64> {
+	let n = l.nrows();
+	let mut x = rhs.to_owned();
+	for mut column in x.columns_mut() {
+		for i in (0..n).rev() {
+			let mut sum = column[i];
+			for k in i + 1..n {
+				sum -= l[[k, i]] * column[k];
+			}
+			column[i] = sum / l[[i, i]];
+		}
+	}
+	x
+}
+
+/// `Cholesky` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Cholesky {
+	input: Node,
+	output: Node,
+}
+
+impl Cholesky {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same number of axes"
+		);
+		Cholesky { input, output }
+	}
+}
+
+impl OpSpecification for Cholesky {
+	type InstanceType = CholeskyInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Cholesky"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CholeskyInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// Cholesky OpInstance
+#[derive(Clone, Debug)]
+pub struct CholeskyInstance {
+	input: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for CholeskyInstance {
+	fn type_name(&self) -> &'static str {
+		"Cholesky"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Cholesky {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		CholeskyBack::new(
+			ctx.node(&self.output),
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).slice();
+		let len = input_shape.len();
+		if input_shape[len - 2] != input_shape[len - 1] {
+			return Err(format!(
+				"Cholesky requires the last two axes of the input to be equal, but it has shape {:?}",
+				input_shape
+			)
+			.into());
+		}
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = as_batch(&ctx.get_input(&self.input));
+		let mut output = ctx.get_output(&self.output);
+
+		let mut factors = Array3::<f32>::zeros(input.raw_dim());
+		for (matrix, mut factor) in input.outer_iter().zip(factors.outer_iter_mut()) {
+			let l = decompose(matrix).ok_or_else(|| {
+				"Cholesky could not decompose a matrix which is not symmetric positive definite".to_string()
+			})?;
+			factor.zip_mut_with(&l, |factor, &x| *factor = x as f32);
+		}
+		output += &factors
+			.into_shape(output.raw_dim())
+			.expect("Alumina Bug: Cholesky factors did not match the output shape");
+
+		Ok(())
+	}
+}
+
+/// Calculates the gradient of the Cholesky decomposition,
+/// `input_grad += sym(L^-T @ phi(L^T @ tril(output_grad)) @ L^-1)`, where `phi` takes the lower triangle with the
+/// diagonal halved and `sym(X) = (X + X^T) / 2`.
+///
+/// Input/Output naming convention matches Cholesky Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The gradient is calculated from the output of Cholesky using triangular solves, so no further decomposition is
+/// needed.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct CholeskyBack {
+	output: Node,
+	output_grad: Node,
+	input_grad: Node,
+}
+
+impl CholeskyBack {
+	pub fn new<I1, I2, O>(output: I1, output_grad: I2, input_grad: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let output = output.into();
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(output.shape().len() >= 2, "output must have at least 2 axes");
+		assert!(
+			output.shape().len() == output_grad.shape().len(),
+			"output_grad and output must have the same number of axes"
+		);
+		assert!(
+			output.shape().len() == input_grad.shape().len(),
+			"input_grad and output must have the same number of axes"
+		);
+		CholeskyBack {
+			output,
+			output_grad,
+			input_grad,
+		}
+	}
+}
+
+impl OpSpecification for CholeskyBack {
+	type InstanceType = CholeskyBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"CholeskyBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CholeskyBackInstance {
+			output: self.output.id(),
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+		})
+	}
+}
+
+/// CholeskyBack OpInstance
+#[derive(Clone, Debug)]
+pub struct CholeskyBackInstance {
+	output: NodeID,
+	output_grad: NodeID,
+	input_grad: NodeID,
+}
+
+impl OpInstance for CholeskyBackInstance {
+	fn type_name(&self) -> &'static str {
+		"CholeskyBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(CholeskyBack {
+			output: graph.node_from_id(self.output),
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.output)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let output = as_batch(&ctx.get_input(&self.output));
+		let output_grad = as_batch(&ctx.get_input(&self.output_grad));
+		let mut input_grad = ctx.get_output(&self.input_grad);
+
+		let mut grads = Array3::<f32>::zeros(output.raw_dim());
+		for ((l, output_grad), mut grad) in output
+			.outer_iter()
+			.zip(output_grad.outer_iter())
+			.zip(grads.outer_iter_mut())
+		{
+			// the upper triangle of the output is always zero, so its gradient doesn't flow back to the input
+			let mut phi = l.t().dot(&Array2::from_shape_fn(l.raw_dim(), |(i, j)| {
+				if i >= j {
+					output_grad[[i, j]]
+				} else {
+					0.0
+				}
+			}));
+			for ((i, j), x) in phi.indexed_iter_mut() {
+				if i < j {
+					*x = 0.0;
+				} else if i == j {
+					*x *= 0.5;
+				}
+			}
+
+			// (L^-T @ phi @ L^-1)^T = L^-T @ (L^-T @ phi)^T, the transpose is symmetrised away below
+			let x = solve_transposed(l, solve_transposed(l, phi.view()).t());
+			grad.zip_mut_with(&x, |grad, &x| *grad += (0.5 * x) as f32);
+			grad.zip_mut_with(&x.t(), |grad, &x| *grad += (0.5 * x) as f32);
+		}
+		input_grad += &grads
+			.into_shape(input_grad.raw_dim())
+			.expect("Alumina Bug: Cholesky gradients did not match the input shape");
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::cholesky;
+	use crate::elementwise::{identity::add, mul::mul};
+	use crate::tests::{contract_case, input, positive_definite};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr2, arr3, Array3, Ix3};
+
+	contract_case!("cholesky", || {
+		let m = input("m", &[3, 4, 4]);
+		(cholesky(positive_definite(&m)).unwrap(), vec![m])
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[4.0, 2.0], [2.0, 5.0]]));
+
+		let output = cholesky(&input).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr2(&[[2.0, 0.0], [1.0, 2.0]]), 1e-6));
+	}
+
+	#[test]
+	fn forward_reconstruction_test() {
+		let value = arr3(&[
+			[[4.0, 2.0, -1.0], [2.0, 5.0, 0.5], [-1.0, 0.5, 3.0]],
+			[[9.0, -3.0, 1.5], [-3.0, 2.0, 0.0], [1.5, 0.0, 7.0]],
+		]);
+		let input = Node::new(&[2, 3, 3]).set_name("input").set_value(value.clone());
+
+		let output = cholesky(&input)
+			.unwrap()
+			.calc()
+			.unwrap()
+			.into_dimensionality::<Ix3>()
+			.unwrap();
+
+		for (a, l) in value.outer_iter().zip(output.outer_iter()) {
+			assert!(l.dot(&l.t()).all_relatively_close(&a, 1e-5));
+			assert!(l.indexed_iter().all(|((i, j), &x)| i >= j || x == 0.0));
+		}
+	}
+
+	#[test]
+	fn not_positive_definite_error_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0], [2.0, 1.0]]));
+
+		let output = cholesky(&input).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input = Node::new(&[3, 2, 4]).set_name("input");
+
+		assert!(cholesky(&input).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let noise = Node::new(&[3, 4, 4]).set_name("noise").set_init(uniform(-1.0, 1.0));
+
+		// the symmetric part of noise plus a large diagonal is diagonally dominant, and so positive definite
+		let diagonal = Node::new(&[3, 4, 4])
+			.set_name("diagonal")
+			.set_value(Array3::from_shape_fn(
+				(3, 4, 4),
+				|(_, i, j)| if i == j { 5.0 } else { 0.0 },
+			));
+		let scale = Node::new(&[3, 4, 4])
+			.set_name("scale")
+			.set_value(Array3::from_shape_fn((3, 4, 4), |(i, j, k)| {
+				((i * 16 + j * 4 + k) as f32).sin()
+			}));
+
+		let output = mul(cholesky(add(&noise, &diagonal).unwrap()).unwrap(), &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&noise]).run();
+	}
+}
@@ -0,0 +1,390 @@
+use crate::reduce::reduce_sum::reduce_sum;
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension};
+use std::{any::Any, cmp};
+
+/// Extracts the main diagonal of the last two axes of the input.
+///
+/// `output[.., i] = input[.., i, i]`
+///
+/// The output node has the shape of the input with the last two axes, of size `m` and `n`, replaced by a single axis
+/// of size `min(m, n)`.
+pub fn diagonal<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	if input.shape().len() < 2 {
+		return Err(format!(
+			"diagonal requires the input ({}) to have at least 2 axes, but it has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+
+	let output = input
+		.graph()
+		.new_node(calc_output_shape(&input.shape()))
+		.set_name_unique(&format!("diagonal({})", input));
+
+	Diagonal::new(input, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Sums the main diagonal of the last two axes of the input.
+///
+/// The output node has the shape of the input with the last two axes removed.
+pub fn trace<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+
+	let output = reduce_sum(diagonal(input.clone())?, &[-1], false)?.set_name_unique(&format!("trace({})", input));
+
+	Ok(output)
+}
+
+/// Replaces the last two axes with one the length of the shorter.
+fn calc_output_shape(input_shape: &NodeShape) -> NodeShape {
+	let len = input_shape.len();
+	let (lower1, upper1) = input_shape.slice()[len - 2].as_interval();
+	let (lower2, upper2) = input_shape.slice()[len - 1].as_interval();
+	input_shape.slice()[..len - 2]
+		.iter()
+		.cloned()
+		.chain(Some(NodeAxis::interval(
+			cmp::min(lower1, lower2),
+			cmp::min(upper1, upper2),
+		)))
+		.into()
+}
+
+/// `Diagonal` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Diagonal {
+	input: Node,
+	output: Node,
+}
+
+impl Diagonal {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == output.shape().len() + 1,
+			"output must have one less axis than input"
+		);
+		Diagonal { input, output }
+	}
+}
+
+impl OpSpecification for Diagonal {
+	type InstanceType = DiagonalInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Diagonal"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DiagonalInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// Diagonal OpInstance
+#[derive(Clone, Debug)]
+pub struct DiagonalInstance {
+	input: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for DiagonalInstance {
+	fn type_name(&self) -> &'static str {
+		"Diagonal"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Diagonal {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		DiagonalBack::new(
+			ctx.node(&self.input),
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape = calc_output_shape(&ctx.input_shape(&self.input).slice().into());
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output = ctx.get_output(&self.output);
+		let row_axis = input.ndim() - 2;
+
+		for i in 0..output.shape()[row_axis] {
+			let input_row = input.index_axis(Axis(row_axis), i);
+			let mut output_elements = output.index_axis_mut(Axis(row_axis), i);
+			output_elements += &input_row.index_axis(Axis(row_axis), i);
+		}
+
+		Ok(())
+	}
+}
+
+/// Scatters the gradient of each diagonal element back onto the main diagonal of the last two axes.
+///
+/// Input/Output naming convention matches Diagonal Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The input is only used to determine the shape of the input grad.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct DiagonalBack {
+	input: Node,
+	output_grad: Node,
+	input_grad: Node,
+}
+
+impl DiagonalBack {
+	pub fn new<I1, I2, O>(input: I1, output_grad: I2, input_grad: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == input_grad.shape().len(),
+			"input_grad and input must have the same number of axes"
+		);
+		assert!(
+			input.shape().len() == output_grad.shape().len() + 1,
+			"output_grad must have one less axis than input"
+		);
+		DiagonalBack {
+			input,
+			output_grad,
+			input_grad,
+		}
+	}
+}
+
+impl OpSpecification for DiagonalBack {
+	type InstanceType = DiagonalBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"DiagonalBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DiagonalBackInstance {
+			input: self.input.id(),
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+		})
+	}
+}
+
+/// DiagonalBack OpInstance
+#[derive(Clone, Debug)]
+pub struct DiagonalBackInstance {
+	input: NodeID,
+	output_grad: NodeID,
+	input_grad: NodeID,
+}
+
+impl OpInstance for DiagonalBackInstance {
+	fn type_name(&self) -> &'static str {
+		"DiagonalBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(DiagonalBack {
+			input: graph.node_from_id(self.input),
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// the adjoint of the scatter is extracting the diagonal again
+		Diagonal::new(ctx.grad_of(&self.input_grad), ctx.grad_of(&self.output_grad)).build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape: NodeShape = ctx.input_shape(&self.input).slice().into();
+		ctx.merge_output_shape(&self.input_grad, &input_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let output_grad = ctx.get_input(&self.output_grad);
+		let mut input_grad = ctx.get_output(&self.input_grad);
+		let row_axis = input_grad.ndim() - 2;
+
+		for i in 0..output_grad.shape()[row_axis] {
+			let mut input_grad_row = input_grad.index_axis_mut(Axis(row_axis), i);
+			let mut input_grad_elements = input_grad_row.index_axis_mut(Axis(row_axis), i);
+			input_grad_elements += &output_grad.index_axis(Axis(row_axis), i);
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{diagonal, trace};
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+
+	use ndarray::{arr1, arr2, ArrayD, Dimension};
+
+	contract_case!(unary "diagonal", diagonal);
+	contract_case!(unary "trace", trace);
+
+	fn arange(shape: &[usize]) -> ArrayD<f32> {
+		ArrayD::from_shape_fn(shape.to_vec(), |ix| {
+			ix.slice().iter().fold(0, |sum, &i| sum * 10 + i) as f32
+		})
+	}
+
+	#[test]
+	fn forward_test() {
+		// element values are their indices as decimal digits, e.g. input[1, 2, 0] = 120
+		let input = Node::new(&[2, 3, 4]).set_name("input").set_value(arange(&[2, 3, 4]));
+
+		let output = diagonal(&input).unwrap();
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[0.0, 11.0, 22.0], [100.0, 111.0, 122.0]]).into_dyn()
+		);
+
+		let output = trace(&input).unwrap();
+		assert_eq!(output.calc().unwrap(), arr1(&[33.0, 333.0]).into_dyn());
+	}
+
+	#[test]
+	fn forward_tall_test() {
+		let input = Node::new(&[2, 4, 3]).set_name("input").set_value(arange(&[2, 4, 3]));
+
+		let output = diagonal(&input).unwrap();
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[0.0, 11.0, 22.0], [100.0, 111.0, 122.0]]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input = Node::new(&[5]).set_name("input");
+
+		assert!(diagonal(&input).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for shape in &[[3, 5, 4], [3, 4, 5]] {
+			let input = Node::new(shape).set_name("input");
+			let scale = Node::new(&[3, 4])
+				.set_name("scale")
+				.set_value(ArrayD::from_shape_fn(vec![3, 4], |ix| {
+					((ix[0] * 4 + ix[1]) as f32).sin()
+				}));
+
+			// weight the output so that the gradient must be scattered onto the right diagonal element
+			let output = mul(diagonal(&input).unwrap(), &scale).unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input]).run();
+		}
+	}
+
+	#[test]
+	fn grad_numeric_trace_test() {
+		let input = Node::new(&[3, 5, 5]).set_name("input");
+		let output = trace(&input).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+}
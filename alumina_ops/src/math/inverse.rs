@@ -0,0 +1,409 @@
+use crate::math::logdet::{as_batch, Lu};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{linalg::general_mat_mul, Array2, Array3, ArrayView3, Dimension};
+use std::any::Any;
+
+/// Calculates the inverse of each square matrix in the last two axes of the input.
+///
+/// The inverse is calculated by LU decomposition with partial pivoting. Execution returns an error if any matrix is
+/// singular.
+///
+/// The output node has the same shape as the input.
+pub fn inverse<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let len = input.shape().len();
+	if len < 2
+		|| input.shape().slice()[len - 2]
+			.merge(&input.shape().slice()[len - 1])
+			.is_err()
+	{
+		return Err(format!(
+			"inverse requires the input ({}) to have at least 2 axes, the last two of which must be equal, but it has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("inverse({})", input));
+
+	Inverse::new(input, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// `Inverse` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Inverse {
+	input: Node,
+	output: Node,
+}
+
+impl Inverse {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same number of axes"
+		);
+		Inverse { input, output }
+	}
+}
+
+impl OpSpecification for Inverse {
+	type InstanceType = InverseInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Inverse"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(InverseInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// Inverse OpInstance
+#[derive(Clone, Debug)]
+pub struct InverseInstance {
+	input: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for InverseInstance {
+	fn type_name(&self) -> &'static str {
+		"Inverse"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Inverse {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		InverseBack::new(
+			ctx.node(&self.output),
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).slice();
+		let len = input_shape.len();
+		if input_shape[len - 2] != input_shape[len - 1] {
+			return Err(format!(
+				"Inverse requires the last two axes of the input to be equal, but it has shape {:?}",
+				input_shape
+			)
+			.into());
+		}
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = as_batch(&ctx.get_input(&self.input));
+		let mut output = ctx.get_output(&self.output);
+
+		let mut inverses = Array3::<f32>::zeros(input.raw_dim());
+		for (matrix, mut inverse) in input.outer_iter().zip(inverses.outer_iter_mut()) {
+			let lu = Lu::new(matrix);
+			if lu.is_singular() {
+				return Err("Inverse could not invert a singular matrix".to_string().into());
+			}
+			inverse.zip_mut_with(&lu.inverse(), |inverse, &x| *inverse = x as f32);
+		}
+		output += &inverses
+			.into_shape(output.raw_dim())
+			.expect("Alumina Bug: inverses did not match the output shape");
+
+		Ok(())
+	}
+}
+
+/// Calculates the gradient of the matrix inverse, `input_grad += -output^T * output_grad * output^T`.
+///
+/// Input/Output naming convention matches Inverse Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The gradient is calculated from the output of Inverse, so no further matrices need to be inverted.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct InverseBack {
+	output: Node,
+	output_grad: Node,
+	input_grad: Node,
+}
+
+impl InverseBack {
+	pub fn new<I1, I2, O>(output: I1, output_grad: I2, input_grad: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let output = output.into();
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(output.shape().len() >= 2, "output must have at least 2 axes");
+		assert!(
+			output.shape().len() == output_grad.shape().len(),
+			"output_grad and output must have the same number of axes"
+		);
+		assert!(
+			output.shape().len() == input_grad.shape().len(),
+			"input_grad and output must have the same number of axes"
+		);
+		InverseBack {
+			output,
+			output_grad,
+			input_grad,
+		}
+	}
+}
+
+impl OpSpecification for InverseBack {
+	type InstanceType = InverseBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"InverseBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(InverseBackInstance {
+			output: self.output.id(),
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+		})
+	}
+}
+
+/// InverseBack OpInstance
+#[derive(Clone, Debug)]
+pub struct InverseBackInstance {
+	output: NodeID,
+	output_grad: NodeID,
+	input_grad: NodeID,
+}
+
+impl OpInstance for InverseBackInstance {
+	fn type_name(&self) -> &'static str {
+		"InverseBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(InverseBack {
+			output: graph.node_from_id(self.output),
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.output)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let output = ctx.get_input(&self.output);
+		let output_grad = ctx.get_input(&self.output_grad);
+		let mut input_grad = ctx.get_output(&self.input_grad);
+
+		let n = output.shape()[output.ndim() - 1];
+		let batch = output.len() / (n * n).max(1);
+		let output = output.as_standard_layout();
+		let output_grad = output_grad.as_standard_layout();
+		let output = ArrayView3::from_shape((batch, n, n), output.as_slice().unwrap()).unwrap();
+		let output_grad = ArrayView3::from_shape((batch, n, n), output_grad.as_slice().unwrap()).unwrap();
+
+		let mut grads = Array3::<f32>::zeros((batch, n, n));
+		let mut temp = Array2::<f32>::zeros((n, n));
+		for ((output, output_grad), mut grad) in output
+			.outer_iter()
+			.zip(output_grad.outer_iter())
+			.zip(grads.outer_iter_mut())
+		{
+			general_mat_mul(1.0, &output_grad, &output.t(), 0.0, &mut temp);
+			general_mat_mul(-1.0, &output.t(), &temp, 0.0, &mut grad);
+		}
+		input_grad += &grads
+			.into_shape(input_grad.raw_dim())
+			.expect("Alumina Bug: inverse gradients did not match the input shape");
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::inverse;
+	use crate::elementwise::identity::add;
+	use crate::tests::{contract_case, input, positive_definite};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr2, arr3, Array2, Array3, Ix3};
+
+	contract_case!("inverse", || {
+		let m = input("m", &[3, 4, 4]);
+		(inverse(positive_definite(&m)).unwrap(), vec![m])
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[4.0, 7.0], [2.0, 6.0]]));
+
+		let output = inverse(&input).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr2(&[[0.6, -0.7], [-0.2, 0.4]]), 1e-6));
+	}
+
+	#[test]
+	fn forward_identity_test() {
+		let value = arr3(&[
+			[[2.0, -1.0, 0.5], [1.0, 3.0, -2.0], [0.0, 1.0, 4.0]],
+			[[0.0, 1.0, 2.0], [-3.0, 0.5, 1.0], [1.0, 1.0, 0.0]], // requires a row swap
+		]);
+		let input = Node::new(&[2, 3, 3]).set_name("input").set_value(value.clone());
+
+		let output = inverse(&input)
+			.unwrap()
+			.calc()
+			.unwrap()
+			.into_dimensionality::<Ix3>()
+			.unwrap();
+
+		for (a, a_inv) in value.outer_iter().zip(output.outer_iter()) {
+			assert!(a.dot(&a_inv).all_relatively_close(&Array2::eye(3), 1e-5));
+		}
+	}
+
+	#[test]
+	fn singular_error_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0], [2.0, 4.0]]));
+
+		let output = inverse(&input).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn near_singular_error_test() {
+		// singular up to the rounding of 1/3
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 1.0 / 3.0], [3.0, 1.0]]));
+
+		let output = inverse(&input).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input = Node::new(&[3, 2, 4]).set_name("input");
+
+		assert!(inverse(&input).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let noise = Node::new(&[3, 4, 4]).set_name("noise").set_init(uniform(-1.0, 1.0));
+
+		// keep the matrices well conditioned by adding a large diagonal
+		let diagonal = Node::new(&[3, 4, 4])
+			.set_name("diagonal")
+			.set_value(Array3::from_shape_fn(
+				(3, 4, 4),
+				|(_, i, j)| if i == j { 5.0 } else { 0.0 },
+			));
+
+		let output = inverse(add(&noise, &diagonal).unwrap()).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&noise]).run();
+	}
+}
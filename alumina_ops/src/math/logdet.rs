@@ -0,0 +1,534 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::NodeShape,
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Array2, Array3, ArrayView2, ArrayViewD, Axis, Dimension};
+use std::{any::Any, cmp::Ordering};
+
+/// Calculates the natural logarithm of the determinant of each square matrix in the last two axes of the input.
+///
+/// The determinant is calculated by LU decomposition with partial pivoting. Matrices with a negative determinant have no
+/// real log-determinant and produce NaN, while singular matrices produce negative infinity.
+///
+/// The output node has the shape of the input with the last two axes removed.
+pub fn logdet<I>(input: I) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let len = input.shape().len();
+	if len < 2
+		|| input.shape().slice()[len - 2]
+			.merge(&input.shape().slice()[len - 1])
+			.is_err()
+	{
+		return Err(format!(
+			"logdet requires the input ({}) to have at least 2 axes, the last two of which must be equal, but it has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+
+	let output_shape: NodeShape = input.shape().slice()[..len - 2].iter().into();
+
+	let output = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("logdet({})", input));
+
+	LogDet::new(input, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Returns the input as a standard layout batch of matrices over the last two axes.
+pub(crate) fn as_batch(input: &ArrayViewD<f32>) -> Array3<f64> {
+	let rows = input.shape()[input.ndim() - 2];
+	let cols = input.shape()[input.ndim() - 1];
+	let batch = input.len() / (rows * cols).max(1);
+	Array3::from_shape_vec((batch, rows, cols), input.iter().map(|&x| f64::from(x)).collect())
+		.expect("Alumina Bug: batch of matrices could not be reshaped")
+}
+
+/// An LU decomposition with partial pivoting, `P * A = L * U`, stored compactly with `L` (excluding its unit
+/// diagonal) below the diagonal and `U` on and above it.
+pub(crate) struct Lu {
+	lu: Array2<f64>,
+	/// `permutation[i]` is the row of `A` which became row `i`
+	permutation: Vec<usize>,
+	/// The determinant of `P`, +1 or -1
+	sign: f64,
+}
+
+impl Lu {
+	pub(crate) fn new(matrix: ArrayView2<f64>) -> Self {
+		let n = matrix.nrows();
+		let mut lu = matrix.to_owned();
+		let mut permutation: Vec<usize> = (0..n).collect();
+		let mut sign = 1.0;
+
+		for k in 0..n {
+			let pivot = (k..n)
+				.max_by(|&i, &j| {
+					// NaN inputs compare equal so that they propagate to the output rather than panicking
+					lu[[i, k]]
+						.abs()
+						.partial_cmp(&lu[[j, k]].abs())
+						.unwrap_or(Ordering::Equal)
+				})
+				.unwrap();
+			if pivot != k {
+				for j in 0..n {
+					lu.swap([k, j], [pivot, j]);
+				}
+				permutation.swap(k, pivot);
+				sign = -sign;
+			}
+
+			let diagonal = lu[[k, k]];
+			if diagonal == 0.0 {
+				// singular, the remaining columns can still be eliminated but the determinant is zero
+				continue;
+			}
+			for i in k + 1..n {
+				let factor = lu[[i, k]] / diagonal;
+				lu[[i, k]] = factor;
+				for j in k + 1..n {
+					lu[[i, j]] -= factor * lu[[k, j]];
+				}
+			}
+		}
+
+		Lu { lu, permutation, sign }
+	}
+
+	/// Returns true if any pivot is negligible relative to the largest, `|u_ii| <= n * EPSILON * max|u_jj|`.
+	///
+	/// Inputs are `f32`, so pivots within `f32` rounding of zero are treated as zero even though the decomposition is
+	/// done in `f64`. A matrix of all zeros is singular.
+	pub(crate) fn is_singular(&self) -> bool {
+		let diag = self.lu.diag();
+		let max = diag.iter().fold(0.0, |max: f64, &x| max.max(x.abs()));
+		let tolerance = diag.len() as f64 * f64::from(f32::EPSILON) * max;
+		diag.iter().any(|&x| x.abs() <= tolerance)
+	}
+
+	/// Returns `ln(det(A))`, which is NaN for a negative determinant and negative infinity for a singular matrix.
+	fn logdet(&self) -> f64 {
+		let (sign, log_abs) = self.lu.diag().iter().fold((self.sign, 0.0), |(sign, log_abs), &x| {
+			(sign * x.signum(), log_abs + x.abs().ln())
+		});
+		if self.is_singular() {
+			f64::NEG_INFINITY
+		} else if sign < 0.0 {
+			f64::NAN
+		} else {
+			log_abs
+		}
+	}
+
+	/// Returns the inverse of `A`, which must not be singular.
+	pub(crate) fn inverse(&self) -> Array2<f64> {
+		self.solve(Array2::eye(self.lu.nrows()).view())
+	}
+
+	/// Returns `X` such that `A * X = B`, where `A` must not be singular.
+	pub(crate) fn solve(&self, rhs: ArrayView2<f64>) -> Array2<f64> {
+		let n = self.lu.nrows();
+		let mut solution = Array2::zeros(rhs.raw_dim());
+		for (rhs, mut x) in rhs.axis_iter(Axis(1)).zip(solution.axis_iter_mut(Axis(1))) {
+			// solve L * y = P * b
+			for i in 0..n {
+				let mut sum = rhs[self.permutation[i]];
+				for j in 0..i {
+					sum -= self.lu[[i, j]] * x[j];
+				}
+				x[i] = sum;
+			}
+			// solve U * x = y
+			for i in (0..n).rev() {
+				let mut sum = x[i];
+				for j in i + 1..n {
+					sum -= self.lu[[i, j]] * x[j];
+				}
+				x[i] = sum / self.lu[[i, i]];
+			}
+		}
+		solution
+	}
+}
+
+/// `LogDet` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct LogDet {
+	input: Node,
+	output: Node,
+}
+
+impl LogDet {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == output.shape().len() + 2,
+			"output must have two less axes than input"
+		);
+		LogDet { input, output }
+	}
+}
+
+impl OpSpecification for LogDet {
+	type InstanceType = LogDetInstance;
+
+	fn type_name(&self) -> &'static str {
+		"LogDet"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(LogDetInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// LogDet OpInstance
+#[derive(Clone, Debug)]
+pub struct LogDetInstance {
+	input: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for LogDetInstance {
+	fn type_name(&self) -> &'static str {
+		"LogDet"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(LogDet {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		LogDetBack::new(
+			ctx.node(&self.input),
+			ctx.grad_of(&self.output),
+			ctx.grad_of(&self.input),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).slice();
+		let len = input_shape.len();
+		if input_shape[len - 2] != input_shape[len - 1] {
+			return Err(format!(
+				"LogDet requires the last two axes of the input to be equal, but it has shape {:?}",
+				input_shape
+			)
+			.into());
+		}
+		let output_shape: NodeShape = input_shape[..len - 2].iter().into();
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = as_batch(&ctx.get_input(&self.input));
+		let mut output = ctx.get_output(&self.output);
+
+		let logdets: Vec<f32> = input
+			.outer_iter()
+			.map(|matrix| Lu::new(matrix).logdet() as f32)
+			.collect();
+		output += &ArrayViewD::from_shape(output.raw_dim(), &logdets)
+			.expect("Alumina Bug: logdet results did not match the output shape");
+
+		Ok(())
+	}
+}
+
+/// Calculates the gradient of the log-determinant, `input_grad += output_grad * inv(input)^T`.
+///
+/// Input/Output naming convention matches LogDet Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct LogDetBack {
+	input: Node,
+	output_grad: Node,
+	input_grad: Node,
+}
+
+impl LogDetBack {
+	pub fn new<I1, I2, O>(input: I1, output_grad: I2, input_grad: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(input.shape().len() >= 2, "input must have at least 2 axes");
+		assert!(
+			input.shape().len() == input_grad.shape().len(),
+			"input_grad and input must have the same number of axes"
+		);
+		assert!(
+			input.shape().len() == output_grad.shape().len() + 2,
+			"output_grad must have two less axes than input"
+		);
+		LogDetBack {
+			input,
+			output_grad,
+			input_grad,
+		}
+	}
+}
+
+impl OpSpecification for LogDetBack {
+	type InstanceType = LogDetBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"LogDetBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(LogDetBackInstance {
+			input: self.input.id(),
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+		})
+	}
+}
+
+/// LogDetBack OpInstance
+#[derive(Clone, Debug)]
+pub struct LogDetBackInstance {
+	input: NodeID,
+	output_grad: NodeID,
+	input_grad: NodeID,
+}
+
+impl OpInstance for LogDetBackInstance {
+	fn type_name(&self) -> &'static str {
+		"LogDetBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(LogDetBack {
+			input: graph.node_from_id(self.input),
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = as_batch(&ctx.get_input(&self.input));
+		let output_grad = ctx.get_input(&self.output_grad);
+		let mut input_grad = ctx.get_output(&self.input_grad);
+
+		let mut grads = Array3::<f32>::zeros(input.raw_dim());
+		for ((matrix, mut grad), &output_grad) in input.outer_iter().zip(grads.outer_iter_mut()).zip(output_grad.iter())
+		{
+			let lu = Lu::new(matrix);
+			if lu.is_singular() {
+				return Err(
+					"LogDetBack could not invert a singular matrix, the gradient of its log-determinant is undefined"
+						.to_string()
+						.into(),
+				);
+			}
+			let inverse = lu.inverse();
+			grad.zip_mut_with(&inverse.t(), |grad, &inverse| {
+				*grad = (f64::from(output_grad) * inverse) as f32
+			});
+		}
+		input_grad += &grads
+			.into_shape(input_grad.raw_dim())
+			.expect("Alumina Bug: logdet gradients did not match the input shape");
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::logdet;
+	use crate::elementwise::identity::add;
+	use crate::tests::{contract_case, input, positive_definite};
+	use alumina_core::{grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr0, arr1, arr2, arr3, Array3};
+
+	contract_case!("logdet", || {
+		let m = input("m", &[3, 4, 4]);
+		(logdet(positive_definite(&m)).unwrap(), vec![m])
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[4.0, 7.0], [2.0, 6.0]]));
+
+		let output = logdet(&input).unwrap();
+
+		assert!(output.calc().unwrap().all_relatively_close(&arr0(10.0f32.ln()), 1e-6));
+	}
+
+	#[test]
+	fn forward_batched_test() {
+		let input = Node::new(&[3, 2, 2]).set_name("input").set_value(arr3(&[
+			[[4.0, 7.0], [2.0, 6.0]],
+			[[2.0, 0.0], [0.0, 3.0]],
+			[[0.0, 1.0], [-5.0, 0.5]], // requires a row swap
+		]));
+
+		let output = logdet(&input).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[10.0f32.ln(), 6.0f32.ln(), 5.0f32.ln()]), 1e-6));
+	}
+
+	#[test]
+	fn forward_negative_and_singular_test() {
+		let input = Node::new(&[2, 2, 2])
+			.set_name("input")
+			.set_value(arr3(&[[[1.0, 2.0], [3.0, 4.0]], [[1.0, 2.0], [2.0, 4.0]]]));
+
+		let output = logdet(&input).unwrap().calc().unwrap();
+
+		assert!(output[0].is_nan());
+		assert_eq!(output[1], f32::NEG_INFINITY);
+	}
+
+	#[test]
+	fn forward_near_singular_test() {
+		// the second row is 3 times the first, up to the rounding of 1/3, so the last pivot is only rounding error
+		let input = Node::new(&[2, 2, 2])
+			.set_name("input")
+			.set_value(arr3(&[[[1.0, 1.0 / 3.0], [3.0, 1.0]], [[1e-20, 0.0], [0.0, 1e-20]]]));
+
+		let output = logdet(&input).unwrap().calc().unwrap();
+
+		assert_eq!(output[0], f32::NEG_INFINITY);
+		// singularity is relative to the scale of the matrix, so a small but well conditioned matrix isn't singular
+		assert!((output[1] - 2.0 * 1e-20f32.ln()).abs() < 1e-4);
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input = Node::new(&[3, 2, 4]).set_name("input");
+
+		assert!(logdet(&input).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let noise = Node::new(&[3, 4, 4]).set_name("noise").set_init(uniform(-1.0, 1.0));
+
+		// keep the matrices well conditioned with a positive determinant by adding a large diagonal
+		let diagonal = Node::new(&[3, 4, 4])
+			.set_name("diagonal")
+			.set_value(Array3::from_shape_fn(
+				(3, 4, 4),
+				|(_, i, j)| if i == j { 5.0 } else { 0.0 },
+			));
+
+		let output = logdet(add(&noise, &diagonal).unwrap()).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&noise]).run();
+	}
+
+	#[test]
+	fn grad_singular_error_test() {
+		let input = Node::new(&[2, 2])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0], [2.0, 4.0]]));
+
+		let output = logdet(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		assert!(grad.calc().is_err());
+	}
+}
@@ -1,3 +1,10 @@
 pub mod argmax;
 pub mod broadcast;
+pub mod cholesky;
+pub mod diag;
+pub mod inverse;
+pub mod logdet;
 pub mod muldiv;
+pub mod outer;
+pub mod pairwise_l2;
+pub mod solve;
@@ -7,7 +7,7 @@ use alumina_core::{
 	shape_prop::ShapePropContext,
 };
 use indexmap::{indexset, IndexMap, IndexSet};
-use ndarray::{Axis, Dimension, Zip};
+use ndarray::{ArrayView1, ArrayViewMut1, Axis, Zip};
 use std::any::Any;
 use unchecked_index as ui;
 
@@ -35,6 +35,7 @@ pub struct MulDiv {
 	input: Node,
 	output: Node,
 	epsilon: f32,
+	serial: bool,
 }
 
 impl MulDiv {
@@ -50,6 +51,7 @@ impl MulDiv {
 			input,
 			output,
 			epsilon: 0.1,
+			serial: false,
 		}
 	}
 
@@ -60,6 +62,15 @@ impl MulDiv {
 		self.epsilon = epsilon;
 		self
 	}
+
+	/// Execute on the calling thread rather than in parallel, for deterministic execution of this Op. Inputs smaller
+	/// than the execution's `par_threshold` are always executed on the calling thread.
+	///
+	/// Default: false
+	pub fn serial(mut self, serial: bool) -> Self {
+		self.serial = serial;
+		self
+	}
 }
 
 impl OpSpecification for MulDiv {
@@ -82,6 +93,7 @@ impl OpSpecification for MulDiv {
 			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		}
 	}
 
@@ -90,6 +102,7 @@ impl OpSpecification for MulDiv {
 			input: self.input.id(),
 			output: self.output.id(),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		})
 	}
 }
@@ -99,6 +112,7 @@ pub struct MulDivInstance {
 	input: NodeID,
 	output: NodeID,
 	epsilon: f32,
+	serial: bool,
 }
 
 impl OpInstance for MulDivInstance {
@@ -111,6 +125,7 @@ impl OpInstance for MulDivInstance {
 			input: graph.node_from_id(self.input),
 			output: graph.node_from_id(self.output),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		})
 	}
 
@@ -129,67 +144,186 @@ impl OpInstance for MulDivInstance {
 			ctx.grad_of(&self.output),
 		)
 		.epsilon(self.epsilon)
+		.serial(self.serial)
 		.build()?;
 		Ok(())
 	}
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
-		ctx.merge_output_shape(&self.output, &ctx.input_shape(&self.input).slice().into())
+		ctx.set_output_like(&self.output, &self.input)
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
-		let input = ctx.get_input_standard(&self.input);
+		// the input isn't converted to standard layout, as lanes which aren't contiguous (e.g. after a transpose) are
+		// handled without copying the whole input
+		let input = ctx.get_input(&self.input);
 		let mut output = ctx.get_output_standard(&self.output);
 		assert_eq!(input.shape(), output.shape());
 
 		let epsilon = self.epsilon;
 		let ndim = input.ndim();
-
-		Zip::from(input.lanes(Axis(ndim - 1)))
-			.and(output.lanes_mut(Axis(ndim - 1)))
-			.par_for_each(|input, mut output| {
-				let len = input.len();
-				debug_assert_eq!(input.len(), output.len());
-
-				let groups = len / 4;
-				let remainder = len - groups * 4;
-
-				unsafe {
-					let input = input.as_slice().unwrap();
-					let output = output.as_slice_mut().unwrap();
-
-					for i in 0..groups {
-						let a = ui::get_unchecked(input, i * 4);
-						let b = ui::get_unchecked(input, i * 4 + 1);
-						let c = ui::get_unchecked(input, i * 4 + 2);
-						let d = ui::get_unchecked(input, i * 4 + 3);
-
-						// complex multiplication
-						*ui::get_unchecked_mut(output, i * 4) += a * c - b * d;
-						*ui::get_unchecked_mut(output, i * 4 + 1) += a * d + b * c;
-
-						// complex division
-						let denom = epsilon * epsilon + c * c + d * d;
-						*ui::get_unchecked_mut(output, i * 4 + 2) += (a * c + b * d) / denom;
-						*ui::get_unchecked_mut(output, i * 4 + 3) += (b * c - a * d) / denom;
-					}
-
-					for i in 0..remainder {
-						*ui::get_unchecked_mut(output, groups * 4 + i) += *ui::get_unchecked(input, groups * 4 + i);
-					}
+		let parallel = ctx.is_parallel(output.len());
+
+		let zip = Zip::from(input.lanes(Axis(ndim - 1))).and(output.lanes_mut(Axis(ndim - 1)));
+		let f = |input: ArrayView1<f32>, mut output: ArrayViewMut1<f32>| {
+			let len = input.len();
+			debug_assert_eq!(input.len(), output.len());
+
+			let groups = len / 4;
+			let remainder = len - groups * 4;
+
+			let (input, output) = match (input.as_slice(), output.as_slice_mut()) {
+				(Some(input), Some(output)) => (input, output),
+				_ => {
+					muldiv_lane_strided(input, output, epsilon);
+					return;
+				},
+			};
+
+			#[cfg(feature = "simd")]
+			muldiv_groups_simd(&input[..groups * 4], &mut output[..groups * 4], epsilon);
+			#[cfg(not(feature = "simd"))]
+			muldiv_groups(&input[..groups * 4], &mut output[..groups * 4], epsilon);
+
+			unsafe {
+				for i in 0..remainder {
+					*ui::get_unchecked_mut(output, groups * 4 + i) += *ui::get_unchecked(input, groups * 4 + i);
 				}
-			});
+			}
+		};
+
+		if self.serial || !parallel {
+			zip.for_each(f);
+		} else {
+			zip.par_for_each(f);
+		}
 
 		Ok(())
 	}
 }
 
+/// Accumulates the muldiv of each group of 4 in `input` into `output`, one group at a time.
+///
+/// Both slices must have the same length, which must be a multiple of 4.
+fn muldiv_groups(input: &[f32], output: &mut [f32], epsilon: f32) {
+	debug_assert_eq!(input.len(), output.len());
+	debug_assert_eq!(input.len() % 4, 0);
+
+	unsafe {
+		for i in 0..input.len() / 4 {
+			let a = ui::get_unchecked(input, i * 4);
+			let b = ui::get_unchecked(input, i * 4 + 1);
+			let c = ui::get_unchecked(input, i * 4 + 2);
+			let d = ui::get_unchecked(input, i * 4 + 3);
+
+			// complex multiplication
+			*ui::get_unchecked_mut(output, i * 4) += a * c - b * d;
+			*ui::get_unchecked_mut(output, i * 4 + 1) += a * d + b * c;
+
+			// complex division
+			let denom = epsilon * epsilon + c * c + d * d;
+			*ui::get_unchecked_mut(output, i * 4 + 2) += (a * c + b * d) / denom;
+			*ui::get_unchecked_mut(output, i * 4 + 3) += (b * c - a * d) / denom;
+		}
+	}
+}
+
+/// Accumulates the muldiv of a lane which isn't contiguous into `output`, including the pass through of the remainder.
+///
+/// Slower than the unchecked path over slices, but avoids copying the input into standard layout.
+fn muldiv_lane_strided(input: ArrayView1<f32>, mut output: ArrayViewMut1<f32>, epsilon: f32) {
+	#[cfg(test)]
+	STRIDED_LANES.with(|count| count.set(count.get() + 1));
+
+	let groups = input.len() / 4;
+
+	for i in 0..groups {
+		let a = input[i * 4];
+		let b = input[i * 4 + 1];
+		let c = input[i * 4 + 2];
+		let d = input[i * 4 + 3];
+
+		// complex multiplication
+		output[i * 4] += a * c - b * d;
+		output[i * 4 + 1] += a * d + b * c;
+
+		// complex division
+		let denom = epsilon * epsilon + c * c + d * d;
+		output[i * 4 + 2] += (a * c + b * d) / denom;
+		output[i * 4 + 3] += (b * c - a * d) / denom;
+	}
+
+	for i in groups * 4..input.len() {
+		output[i] += input[i];
+	}
+}
+
+#[cfg(test)]
+thread_local! {
+	/// The number of lanes accumulated by `muldiv_lane_strided(..)` on this thread, so that tests can check it is used.
+	static STRIDED_LANES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// As `muldiv_groups(..)`, but processes 4 groups at a time so that the arithmetic and the division are done across
+/// lanes rather than within a group.
+///
+/// Each chunk of 4 groups is read with contiguous loads and de-interleaved in registers, by transposing the 4x4 block, so
+/// that each component is in its own `f32x4`. The results are transposed back before being accumulated into `output`.
+///
+/// Groups left over after the chunks of 4 use the scalar path.
+#[cfg(feature = "simd")]
+fn muldiv_groups_simd(input: &[f32], output: &mut [f32], epsilon: f32) {
+	use std::simd::f32x4;
+
+	debug_assert_eq!(input.len(), output.len());
+	debug_assert_eq!(input.len() % 4, 0);
+
+	let chunks = input.len() / 16;
+	let epsilon2 = f32x4::splat(epsilon * epsilon);
+
+	for (input, output) in input.chunks_exact(16).zip(output.chunks_exact_mut(16)) {
+		let [a, b, c, d] = transpose4([
+			f32x4::from_slice(&input[0..4]),
+			f32x4::from_slice(&input[4..8]),
+			f32x4::from_slice(&input[8..12]),
+			f32x4::from_slice(&input[12..16]),
+		]);
+
+		let denom = epsilon2 + c * c + d * d;
+		let results = transpose4([
+			a * c - b * d,
+			a * d + b * c,
+			(a * c + b * d) / denom,
+			(b * c - a * d) / denom,
+		]);
+
+		for (output, result) in output.chunks_exact_mut(4).zip(results.iter()) {
+			(f32x4::from_slice(output) + result).copy_to_slice(output);
+		}
+	}
+
+	muldiv_groups(&input[chunks * 16..], &mut output[chunks * 16..], epsilon);
+}
+
+/// Transposes a 4x4 block held as 4 rows.
+#[cfg(feature = "simd")]
+fn transpose4(rows: [std::simd::f32x4; 4]) -> [std::simd::f32x4; 4] {
+	let [r0, r1, r2, r3] = rows;
+	// [x0 x2 y0 y2], [z0 z2 w0 w2] and [x1 x3 y1 y3], [z1 z3 w1 w3]
+	let (t0, t1) = r0.interleave(r2);
+	let (t2, t3) = r1.interleave(r3);
+	let (x, y) = t0.interleave(t2);
+	let (z, w) = t1.interleave(t3);
+	[x, y, z, w]
+}
+
 #[derive(Clone, Debug)]
 pub struct MulDivBack {
 	input: Node,
 	input_grad: Node,
 	output_grad: Node,
 	epsilon: f32,
+	serial: bool,
 }
 
 impl MulDivBack {
@@ -208,6 +342,7 @@ impl MulDivBack {
 			input_grad,
 			output_grad,
 			epsilon: 0.1,
+			serial: false,
 		}
 	}
 
@@ -218,6 +353,15 @@ impl MulDivBack {
 		self.epsilon = epsilon;
 		self
 	}
+
+	/// Execute on the calling thread rather than in parallel, for deterministic execution of this Op. Inputs smaller
+	/// than the execution's `par_threshold` are always executed on the calling thread.
+	///
+	/// Default: false
+	pub fn serial(mut self, serial: bool) -> Self {
+		self.serial = serial;
+		self
+	}
 }
 
 impl OpSpecification for MulDivBack {
@@ -241,6 +385,7 @@ impl OpSpecification for MulDivBack {
 			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
 			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		}
 	}
 
@@ -250,6 +395,7 @@ impl OpSpecification for MulDivBack {
 			input_grad: self.input_grad.id(),
 			output_grad: self.output_grad.id(),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		})
 	}
 }
@@ -260,6 +406,7 @@ pub struct MulDivBackInstance {
 	input_grad: NodeID,
 	output_grad: NodeID,
 	epsilon: f32,
+	serial: bool,
 }
 
 impl OpInstance for MulDivBackInstance {
@@ -273,6 +420,7 @@ impl OpInstance for MulDivBackInstance {
 			input_grad: graph.node_from_id(self.input_grad),
 			output_grad: graph.node_from_id(self.output_grad),
 			epsilon: self.epsilon,
+			serial: self.serial,
 		})
 	}
 
@@ -289,19 +437,8 @@ impl OpInstance for MulDivBackInstance {
 	}
 
 	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
-		let input_shape = ctx.input_shape(&self.input).clone();
-		let output_grad_shape = ctx.input_shape(&self.output_grad).clone();
-
-		if output_grad_shape != input_shape {
-			return Err(format!(
-				"MulDivBack requires the output grad to have the shape of the input: input:{:?} output_grad:{:?}",
-				input_shape.slice(),
-				output_grad_shape.slice()
-			)
-			.into());
-		}
-
-		ctx.merge_output_shape(&self.input_grad, &input_shape.slice().into())
+		ctx.require_equal_shapes(&[self.input, self.output_grad])?;
+		ctx.set_output_like(&self.input_grad, &self.input)
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
@@ -313,77 +450,84 @@ impl OpInstance for MulDivBackInstance {
 
 		let epsilon = self.epsilon;
 		let ndim = input.ndim();
+		let parallel = ctx.is_parallel(input_grad.len());
 
-		Zip::from(input_grad.lanes_mut(Axis(ndim - 1)))
+		let zip = Zip::from(input_grad.lanes_mut(Axis(ndim - 1)))
 			.and(input.lanes(Axis(ndim - 1)))
-			.and(output_grad.lanes(Axis(ndim - 1)))
-			.par_for_each(|mut input_grad, input, output_grad| {
-				let len = input.len();
-				debug_assert_eq!(input.len(), output_grad.len());
-				debug_assert_eq!(input.len(), input_grad.len());
-
-				let groups = len / 4;
-				let remainder = len - groups * 4;
-
-				unsafe {
-					let input = input.as_slice().unwrap();
-					let input_grad = input_grad.as_slice_mut().unwrap();
-					let output_grad = output_grad.as_slice().unwrap();
-
-					for i in 0..groups {
-						let a = ui::get_unchecked(input, i * 4);
-						let b = ui::get_unchecked(input, i * 4 + 1);
-						let c = ui::get_unchecked(input, i * 4 + 2);
-						let d = ui::get_unchecked(input, i * 4 + 3);
-
-						let wg = ui::get_unchecked(input_grad, i * 4);
-						let xg = ui::get_unchecked(input_grad, i * 4 + 1);
-						let yg = ui::get_unchecked(input_grad, i * 4 + 2);
-						let zg = ui::get_unchecked(input_grad, i * 4 + 3);
-
-						let c2d2e = c * c + d * d + epsilon * epsilon;
-						let c2d2e_2 = c2d2e * c2d2e;
-
-						// gradients from multiplication
-						// let agm = c*wg + d*xg;
-						// let bgm = -d*wg +c*xg;
-						// let cgm = a*wg + b*xg;
-						// let dgm = -b*wg + a*xg;
-
-						//gradients from division
-						// a/c2d2e - 2.0*c*(a*c+b*d)/c2d2e_2 // dydc
-						// b/c2d2e - 2.0*d*(a*c+b*d)/c2d2e_2 // dydd
-						// b/c2d2e - 2.0*c*(b*c-a*d)/c2d2e_2 // dzdc
-						// -a/c2d2e- 2.0*d*(b*c-a*d)/c2d2e_2 // dzdd
-
-						// let agd = c*wg/c2d2e - d*xg/c2d2e;
-						// let bgd = d*wg/c2d2e + c*xg/c2d2e;
-						// let cgd = (a/c2d2e - 2.0*c*(a*c+b*d)/c2d2e_2)*yg + (b/c2d2e - 2.0*c*(b*c-a*d)/c2d2e_2)*zg;
-						// let dgd = (b/c2d2e - 2.0*d*(a*c+b*d)/c2d2e_2)*yg + (-a/c2d2e- 2.0*d*(b*c-a*d)/c2d2e_2)*zg;
-
-						// combined gradients
-						// hopefully this vectorises
-						let ag = wg * c + xg * d + yg * (c / c2d2e) + zg * -(d / c2d2e);
-						let bg = wg * -d + xg * c + yg * (d / c2d2e) + zg * (c / c2d2e);
-						let cg = wg * a
-							+ xg * b + yg * (a / c2d2e - (a * c + b * d) * (c * 2.0 / c2d2e_2))
-							+ zg * (b / c2d2e - (b * c - a * d) * (c * 2.0 / c2d2e_2));
-						let dg = wg * -b
-							+ xg * a + yg * (b / c2d2e - (a * c + b * d) * (d * 2.0 / c2d2e_2))
-							+ zg * (-a / c2d2e - (b * c - a * d) * (d * 2.0 / c2d2e_2));
-
-						*ui::get_unchecked_mut(input_grad, i * 4) += ag;
-						*ui::get_unchecked_mut(input_grad, i * 4 + 1) += bg;
-						*ui::get_unchecked_mut(input_grad, i * 4 + 2) += cg;
-						*ui::get_unchecked_mut(input_grad, i * 4 + 3) += dg;
-					}
-
-					for i in 0..remainder {
-						*ui::get_unchecked_mut(input_grad, groups * 4 + i) +=
-							*ui::get_unchecked(output_grad, groups * 4 + i);
-					}
+			.and(output_grad.lanes(Axis(ndim - 1)));
+		let f = |mut input_grad: ArrayViewMut1<f32>, input: ArrayView1<f32>, output_grad: ArrayView1<f32>| {
+			let len = input.len();
+			debug_assert_eq!(input.len(), output_grad.len());
+			debug_assert_eq!(input.len(), input_grad.len());
+
+			let groups = len / 4;
+			let remainder = len - groups * 4;
+
+			unsafe {
+				let input = input.as_slice().unwrap();
+				let input_grad = input_grad.as_slice_mut().unwrap();
+				let output_grad = output_grad.as_slice().unwrap();
+
+				for i in 0..groups {
+					let a = ui::get_unchecked(input, i * 4);
+					let b = ui::get_unchecked(input, i * 4 + 1);
+					let c = ui::get_unchecked(input, i * 4 + 2);
+					let d = ui::get_unchecked(input, i * 4 + 3);
+
+					let wg = ui::get_unchecked(input_grad, i * 4);
+					let xg = ui::get_unchecked(input_grad, i * 4 + 1);
+					let yg = ui::get_unchecked(input_grad, i * 4 + 2);
+					let zg = ui::get_unchecked(input_grad, i * 4 + 3);
+
+					let c2d2e = c * c + d * d + epsilon * epsilon;
+					let c2d2e_2 = c2d2e * c2d2e;
+
+					// gradients from multiplication
+					// let agm = c*wg + d*xg;
+					// let bgm = -d*wg +c*xg;
+					// let cgm = a*wg + b*xg;
+					// let dgm = -b*wg + a*xg;
+
+					//gradients from division
+					// a/c2d2e - 2.0*c*(a*c+b*d)/c2d2e_2 // dydc
+					// b/c2d2e - 2.0*d*(a*c+b*d)/c2d2e_2 // dydd
+					// b/c2d2e - 2.0*c*(b*c-a*d)/c2d2e_2 // dzdc
+					// -a/c2d2e- 2.0*d*(b*c-a*d)/c2d2e_2 // dzdd
+
+					// let agd = c*wg/c2d2e - d*xg/c2d2e;
+					// let bgd = d*wg/c2d2e + c*xg/c2d2e;
+					// let cgd = (a/c2d2e - 2.0*c*(a*c+b*d)/c2d2e_2)*yg + (b/c2d2e - 2.0*c*(b*c-a*d)/c2d2e_2)*zg;
+					// let dgd = (b/c2d2e - 2.0*d*(a*c+b*d)/c2d2e_2)*yg + (-a/c2d2e- 2.0*d*(b*c-a*d)/c2d2e_2)*zg;
+
+					// combined gradients
+					// hopefully this vectorises
+					let ag = wg * c + xg * d + yg * (c / c2d2e) + zg * -(d / c2d2e);
+					let bg = wg * -d + xg * c + yg * (d / c2d2e) + zg * (c / c2d2e);
+					let cg = wg * a
+						+ xg * b + yg * (a / c2d2e - (a * c + b * d) * (c * 2.0 / c2d2e_2))
+						+ zg * (b / c2d2e - (b * c - a * d) * (c * 2.0 / c2d2e_2));
+					let dg = wg * -b
+						+ xg * a + yg * (b / c2d2e - (a * c + b * d) * (d * 2.0 / c2d2e_2))
+						+ zg * (-a / c2d2e - (b * c - a * d) * (d * 2.0 / c2d2e_2));
+
+					*ui::get_unchecked_mut(input_grad, i * 4) += ag;
+					*ui::get_unchecked_mut(input_grad, i * 4 + 1) += bg;
+					*ui::get_unchecked_mut(input_grad, i * 4 + 2) += cg;
+					*ui::get_unchecked_mut(input_grad, i * 4 + 3) += dg;
 				}
-			});
+
+				for i in 0..remainder {
+					*ui::get_unchecked_mut(input_grad, groups * 4 + i) +=
+						*ui::get_unchecked(output_grad, groups * 4 + i);
+				}
+			}
+		};
+
+		if self.serial || !parallel {
+			zip.for_each(f);
+		} else {
+			zip.par_for_each(f);
+		}
 
 		Ok(())
 	}
@@ -391,12 +535,21 @@ impl OpInstance for MulDivBackInstance {
 
 #[cfg(test)]
 mod tests {
-	use super::{muldiv, MulDiv};
-	use alumina_core::{base_ops::OpSpecification, graph::Node};
+	use super::{muldiv, MulDiv, STRIDED_LANES};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		exec::{execute_op, ExecutionPlan},
+		grad::Grad,
+		graph::Node,
+		init::uniform,
+	};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 
-	use indexmap::indexset;
-	use ndarray::arr2;
+	use indexmap::{indexmap, indexset, IndexMap};
+	use ndarray::{arr1, arr2};
+
+	contract_case!(unary "muldiv", muldiv);
 
 	#[test]
 	fn forward_test() {
@@ -472,6 +625,74 @@ mod tests {
 		));
 	}
 
+	#[test]
+	fn execute_op_test() {
+		let input = Node::new(&[5, 18]).set_name("input").set_random(-1.0, 1.0, 0);
+		let output = muldiv(&input).unwrap();
+
+		// recompute the output from a fresh copy of the input, as when recomputing activations for checkpointing
+		let fresh_input = input.value().unwrap().to_owned().into_shared();
+		let recomputed = execute_op(&output.parent_op(), vec![(&input, fresh_input)]).unwrap();
+
+		assert_eq!(recomputed[&output], output.calc().unwrap());
+	}
+
+	#[test]
+	fn forward_long_lane_test() {
+		// 9 groups and a remainder of 3, so that lanes are split into chunks of 4 groups, a leftover group, and the
+		// remainder
+		let input = Node::new(&[3, 39]).set_name("input").set_random(-1.0, 1.0, 0);
+		let output = Node::new(&[3, 39]).set_name("output");
+
+		MulDiv::new(&input, &output).epsilon(0.1).build().unwrap();
+
+		let mut expected = input.value().unwrap().to_owned();
+		for mut lane in expected.rows_mut() {
+			for mut group in lane.exact_chunks_mut(4) {
+				let (a, b, c, d) = (group[0], group[1], group[2], group[3]);
+				let denom = 0.01 + c * c + d * d;
+				group.assign(&arr1(&[
+					a * c - b * d,
+					a * d + b * c,
+					(a * c + b * d) / denom,
+					(b * c - a * d) / denom,
+				]));
+			}
+		}
+
+		assert!(output.calc().unwrap().all_relatively_close(&expected, 1e-5));
+	}
+
+	#[test]
+	fn forward_transposed_test() {
+		let source = Node::new(&[4, 8]).set_name("source").set_random(-1.0, 1.0, 0);
+		let input = Node::new(&[8, 4]).set_name("input");
+		let output = Node::new(&[8, 4]).set_name("output");
+		MulDiv::new(&input, &output).serial(true).build().unwrap();
+
+		// a transposed view, so that each lane of the input has a stride of 8
+		let transposed = source.value().unwrap().reversed_axes();
+		assert_eq!(transposed.strides(), &[1, 8]);
+
+		let contiguous = Node::new(&[8, 4])
+			.set_name("contiguous")
+			.set_value(transposed.to_owned());
+		let expected = muldiv(&contiguous).unwrap().calc().unwrap();
+
+		// the counter is thread local, so keep every lane on this thread regardless of the global threshold
+		let strided_lanes = STRIDED_LANES.with(|count| count.get());
+		let result = ExecutionPlan::new(indexmap![input.clone() => transposed], &[&output])
+			.par_threshold(Some(usize::MAX))
+			.execute()
+			.unwrap()
+			.swap_remove(&output)
+			.unwrap();
+
+		// every lane is handled without copying the input into standard layout
+		assert_eq!(STRIDED_LANES.with(|count| count.get()) - strided_lanes, 8);
+		assert!(result.all_relatively_close(&expected, f32::EPSILON));
+	}
+
 	#[test]
 	fn grad_numeric_test() {
 		let input = Node::new(&[13, 43]).set_name("input");
@@ -490,4 +711,78 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-5).run();
 	}
+
+	#[test]
+	fn grad_numeric_small_eps_test() {
+		// With a small epsilon the division gradients are only well behaved away from c = d = 0, so keep all inputs
+		// (and therefore divisors) positive and of order one.
+		let input = Node::new(&[13, 43]).set_name("input").set_init(uniform(0.5, 1.5));
+		let output = Node::new(&[13, 43]).set_name("output");
+
+		MulDiv::new(&input, &output).epsilon(1e-4).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn serial_matches_parallel_test() {
+		let input = Node::new(&[13, 43]).set_name("input").set_random(-1.0, 1.0, 0);
+
+		let parallel = Node::new(&[13, 43]).set_name("parallel");
+		let serial = Node::new(&[13, 43]).set_name("serial");
+		MulDiv::new(&input, &parallel).build().unwrap();
+		MulDiv::new(&input, &serial).serial(true).build().unwrap();
+
+		let parallel_grad = Grad::of(&parallel)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		let serial_grad = Grad::of(&serial)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		// a threshold of 0 makes the ops that aren't forced to run serially take the parallel path
+		let results = ExecutionPlan::new(
+			IndexMap::<Node, _>::new(),
+			&[&parallel, &serial, &parallel_grad, &serial_grad],
+		)
+		.par_threshold(Some(0))
+		.execute()
+		.unwrap();
+
+		assert_eq!(results[&parallel], results[&serial]);
+		assert_eq!(results[&parallel_grad], results[&serial_grad]);
+	}
+
+	#[test]
+	fn par_threshold_test() {
+		let input = Node::new(&[13, 43]).set_name("input").set_random(-1.0, 1.0, 0);
+		let output = muldiv(&input).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		let outputs = indexset![output.clone(), grad.clone()];
+
+		let serial = ExecutionPlan::new(IndexMap::<Node, _>::new(), &outputs)
+			.par_threshold(Some(usize::MAX))
+			.execute()
+			.unwrap();
+
+		// the threshold only affects how the work is split up, so every threshold gives the same result
+		for &par_threshold in &[0, 13 * 43, 13 * 43 + 1] {
+			let result = ExecutionPlan::new(IndexMap::<Node, _>::new(), &outputs)
+				.par_threshold(Some(par_threshold))
+				.execute()
+				.unwrap();
+			assert_eq!(result, serial);
+		}
+	}
 }
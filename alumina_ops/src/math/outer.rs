@@ -0,0 +1,407 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape::NodeShape,
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayView1, ArrayViewD, ArrayViewMutD, Axis, Dimension, Ix1, Zip};
+use std::any::Any;
+
+/// Calculates the outer product of the vectors `a` and `b`.
+///
+/// `output[i, j] = a[i] * b[j]`
+///
+/// The output node has the shape `[m, n]`, where `a` has shape `[m]` and `b` has shape `[n]`.
+pub fn outer<I1, I2>(a: I1, b: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let a = a.into();
+	let b = b.into();
+	if a.shape().len() != 1 || b.shape().len() != 1 {
+		return Err(format!(
+			"outer requires both inputs to have 1 axis, but a ({}) has shape {} and b ({}) has shape {}",
+			a,
+			a.shape(),
+			b,
+			b.shape()
+		)
+		.into());
+	}
+
+	let graph = merge_graphs(&[a.graph(), b.graph()]);
+
+	let output_shape: NodeShape = a.shape().iter().chain(b.shape().iter()).into();
+
+	let output = graph
+		.new_node(output_shape)
+		.set_name_unique(&format!("outer({},{})", a, b));
+
+	Outer::new(a, b, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// `Outer` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Outer {
+	a: Node,
+	b: Node,
+	output: Node,
+}
+
+impl Outer {
+	pub fn new<I1, I2, O>(a: I1, b: I2, output: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let a = a.into();
+		let b = b.into();
+		let output = output.into();
+		assert!(a.shape().len() == 1, "a must have 1 axis");
+		assert!(b.shape().len() == 1, "b must have 1 axis");
+		assert!(output.shape().len() == 2, "output must have 2 axes");
+		Outer { a, b, output }
+	}
+}
+
+impl OpSpecification for Outer {
+	type InstanceType = OuterInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Outer"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(OuterInstance {
+			a: self.a.id(),
+			b: self.b.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// Outer OpInstance
+#[derive(Clone, Debug)]
+pub struct OuterInstance {
+	a: NodeID,
+	b: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for OuterInstance {
+	fn type_name(&self) -> &'static str {
+		"Outer"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Outer {
+			a: graph.node_from_id(self.a),
+			b: graph.node_from_id(self.b),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		OuterBack::new(
+			ctx.node(&self.a),
+			ctx.grad_of(&self.a),
+			ctx.node(&self.b),
+			ctx.grad_of(&self.b),
+			ctx.grad_of(&self.output),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape: NodeShape = ctx
+			.input_shape(&self.a)
+			.slice()
+			.iter()
+			.chain(ctx.input_shape(&self.b).slice())
+			.into();
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let a = ctx.get_input(&self.a);
+		let b = ctx.get_input(&self.b).into_dimensionality::<Ix1>().unwrap();
+
+		Zip::from(ctx.get_output(&self.output).lanes_mut(Axis(1)))
+			.and(&a)
+			.par_for_each(|output, &a| {
+				Zip::from(output).and(&b).for_each(|output, &b| *output += a * b);
+			});
+
+		Ok(())
+	}
+}
+
+/// `OuterBack` `OpBuilder`
+///
+/// Input/Output naming convention matches Outer Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct OuterBack {
+	a: Node,
+	a_grad: Node,
+	b: Node,
+	b_grad: Node,
+	output_grad: Node,
+}
+
+impl OuterBack {
+	pub fn new<I1, I2, I3, O1, O2>(a: I1, a_grad: O1, b: I2, b_grad: O2, output_grad: I3) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let a = a.into();
+		let a_grad = a_grad.into();
+		let b = b.into();
+		let b_grad = b_grad.into();
+		let output_grad = output_grad.into();
+		assert!(a.shape().len() == 1, "a must have 1 axis");
+		assert!(b.shape().len() == 1, "b must have 1 axis");
+		assert!(a_grad.shape().len() == 1, "a_grad must have 1 axis");
+		assert!(b_grad.shape().len() == 1, "b_grad must have 1 axis");
+		assert!(output_grad.shape().len() == 2, "output_grad must have 2 axes");
+		OuterBack {
+			a,
+			a_grad,
+			b,
+			b_grad,
+			output_grad,
+		}
+	}
+}
+
+impl OpSpecification for OuterBack {
+	type InstanceType = OuterBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"OuterBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.a_grad.clone(), self.b_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			a_grad: mapping.get(&self.a_grad).unwrap_or(&self.a_grad).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			b_grad: mapping.get(&self.b_grad).unwrap_or(&self.b_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(OuterBackInstance {
+			a: self.a.id(),
+			a_grad: self.a_grad.id(),
+			b: self.b.id(),
+			b_grad: self.b_grad.id(),
+			output_grad: self.output_grad.id(),
+		})
+	}
+}
+
+/// OuterBack OpInstance
+#[derive(Clone, Debug)]
+pub struct OuterBackInstance {
+	a: NodeID,
+	a_grad: NodeID,
+	b: NodeID,
+	b_grad: NodeID,
+	output_grad: NodeID,
+}
+
+impl OpInstance for OuterBackInstance {
+	fn type_name(&self) -> &'static str {
+		"OuterBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(OuterBack {
+			a: graph.node_from_id(self.a),
+			a_grad: graph.node_from_id(self.a_grad),
+			b: graph.node_from_id(self.b),
+			b_grad: graph.node_from_id(self.b_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a_grad, self.b_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let a_shape = ctx.input_shape(&self.a).clone();
+		let b_shape = ctx.input_shape(&self.b).clone();
+		let output_grad_shape = ctx.input_shape(&self.output_grad);
+		if output_grad_shape.slice() != [a_shape[0], b_shape[0]] {
+			return Err(format!(
+				"OuterBack requires the output grad to have the shape [a, b]: a:{:?} b:{:?} output_grad:{:?}",
+				a_shape.slice(),
+				b_shape.slice(),
+				output_grad_shape.slice()
+			)
+			.into());
+		}
+
+		ctx.set_output_like(&self.a_grad, &self.a)?;
+		ctx.set_output_like(&self.b_grad, &self.b)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let a = ctx.get_input(&self.a).into_dimensionality::<Ix1>().unwrap();
+		let b = ctx.get_input(&self.b).into_dimensionality::<Ix1>().unwrap();
+		let output_grad = ctx.get_input(&self.output_grad);
+
+		if self.a_grad == self.b_grad {
+			// outer(a, a), both gradients accumulate into the same array which can only be borrowed once
+			if ctx.is_required_output(&self.a_grad) {
+				let mut a_grad = ctx.get_output(&self.a_grad);
+				accumulate_a_grad(a_grad.view_mut(), &output_grad, &b);
+				accumulate_b_grad(a_grad, &output_grad, &a);
+			}
+		} else {
+			if ctx.is_required_output(&self.a_grad) {
+				accumulate_a_grad(ctx.get_output(&self.a_grad), &output_grad, &b);
+			}
+			if ctx.is_required_output(&self.b_grad) {
+				accumulate_b_grad(ctx.get_output(&self.b_grad), &output_grad, &a);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// `a_grad += output_grad @ b`
+fn accumulate_a_grad(a_grad: ArrayViewMutD<f32>, output_grad: &ArrayViewD<f32>, b: &ArrayView1<f32>) {
+	Zip::from(a_grad)
+		.and(output_grad.lanes(Axis(1)))
+		.par_for_each(|a_grad, output_grad| *a_grad += output_grad.dot(b));
+}
+
+/// `b_grad += a^T @ output_grad`
+fn accumulate_b_grad(b_grad: ArrayViewMutD<f32>, output_grad: &ArrayViewD<f32>, a: &ArrayView1<f32>) {
+	Zip::from(b_grad)
+		.and(output_grad.lanes(Axis(0)))
+		.par_for_each(|b_grad, output_grad| *b_grad += output_grad.dot(a));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::outer;
+	use crate::elementwise::mul::mul;
+	use crate::tests::{contract_case, input};
+	use alumina_core::graph::Node;
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+
+	use ndarray::{arr1, Array2};
+
+	contract_case!("outer", || {
+		let (a, b) = (input("a", &[4]), input("b", &[5]));
+		(outer(&a, &b).unwrap(), vec![a, b])
+	});
+
+	#[test]
+	fn forward_test() {
+		let a_values = arr1(&[1.0, -2.0, 0.5]);
+		let b_values = arr1(&[3.0, 0.25, -1.0, 2.0]);
+		let a = Node::new(&[3]).set_name("a").set_value(a_values.clone());
+		let b = Node::new(&[4]).set_name("b").set_value(b_values.clone());
+
+		let output = outer(&a, &b).unwrap();
+
+		let expected = Array2::from_shape_fn((3, 4), |(i, j)| a_values[i] * b_values[j]);
+		assert_eq!(output.calc().unwrap(), expected.into_dyn());
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let a = Node::new(&[3, 2]).set_name("a");
+		let b = Node::new(&[4]).set_name("b");
+
+		assert!(outer(&a, &b).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let a = Node::new(&[13]).set_name("a");
+		let b = Node::new(&[7]).set_name("b");
+		let scale = Node::new(&[13, 7])
+			.set_name("scale")
+			.set_value(Array2::from_shape_fn((13, 7), |(i, j)| ((i * 7 + j) as f32).sin()));
+
+		// the plain sum of the output would give every element of a the same gradient, so weight it
+		let output = mul(outer(&a, &b).unwrap(), &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a, &b]).run();
+	}
+
+	#[test]
+	fn grad_numeric_shared_input_test() {
+		let a = Node::new(&[13]).set_name("a");
+		let scale = Node::new(&[13, 13])
+			.set_name("scale")
+			.set_value(Array2::from_shape_fn((13, 13), |(i, j)| ((i * 13 + j) as f32).sin()));
+
+		let output = mul(outer(&a, &a).unwrap(), &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a]).run();
+	}
+}
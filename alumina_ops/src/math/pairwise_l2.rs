@@ -0,0 +1,397 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape::NodeShape,
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension, IxDyn, Zip};
+use std::any::Any;
+
+/// Returns the squared euclidean distance between every row of `a` and every row of `b`.
+///
+/// `a` must have shape [m, d] and `b` must have shape [n, d].
+///
+/// The output node has shape [m, n], where `output[i, j] = sum_k (a[i, k] - b[j, k])^2`.
+pub fn pairwise_l2<I1, I2>(a: I1, b: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let a = a.into();
+	let b = b.into();
+
+	let graph = merge_graphs(&[a.graph(), b.graph()]);
+
+	let output_shape: NodeShape = if a.shape().len() == 2 && b.shape().len() == 2 {
+		vec![a.shape().slice()[0].clone(), b.shape().slice()[0].clone()].into()
+	} else {
+		(&[-1, -1]).into()
+	};
+
+	let output = graph
+		.new_node(output_shape)
+		.set_name_unique(&format!("pairwise_l2({},{})", a, b));
+
+	PairwiseL2::new(a, b, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Checks that `a` and `b` are matrices with the same number of columns, returning their row counts.
+fn row_counts(a_shape: &IxDyn, b_shape: &IxDyn, type_name: &str) -> Result<(usize, usize), ShapePropError> {
+	if a_shape.ndim() != 2 || b_shape.ndim() != 2 || a_shape[1] != b_shape[1] {
+		return Err(format!(
+			"{} requires inputs of shape [m, d] and [n, d], found: {:?} {:?}",
+			type_name,
+			a_shape.slice(),
+			b_shape.slice()
+		)
+		.into());
+	}
+	Ok((a_shape[0], b_shape[0]))
+}
+
+/// `PairwiseL2` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct PairwiseL2 {
+	a: Node,
+	b: Node,
+	output: Node,
+}
+
+impl PairwiseL2 {
+	pub fn new<I1, I2, O>(a: I1, b: I2, output: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let a = a.into();
+		let b = b.into();
+		let output = output.into();
+		assert!(a.shape().len() == 2, "PairwiseL2 requires `a` to have two axes");
+		assert!(b.shape().len() == 2, "PairwiseL2 requires `b` to have two axes");
+		assert!(
+			output.shape().len() == 2,
+			"PairwiseL2 requires the output to have two axes"
+		);
+		PairwiseL2 { a, b, output }
+	}
+}
+
+impl OpSpecification for PairwiseL2 {
+	type InstanceType = PairwiseL2Instance;
+
+	fn type_name(&self) -> &'static str {
+		"PairwiseL2"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(PairwiseL2Instance {
+			a: self.a.id(),
+			b: self.b.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// PairwiseL2 OpInstance
+#[derive(Clone, Debug)]
+pub struct PairwiseL2Instance {
+	a: NodeID,
+	b: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for PairwiseL2Instance {
+	fn type_name(&self) -> &'static str {
+		"PairwiseL2"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(PairwiseL2 {
+			a: graph.node_from_id(self.a),
+			b: graph.node_from_id(self.b),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		PairwiseL2Back::new(
+			ctx.node(&self.a),
+			ctx.grad_of(&self.a),
+			ctx.node(&self.b),
+			ctx.grad_of(&self.b),
+			ctx.grad_of(&self.output),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let (m, n) = row_counts(ctx.input_shape(&self.a), ctx.input_shape(&self.b), self.type_name())?;
+		ctx.merge_output_shape(&self.output, &vec![m, n].into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let b = ctx.get_input(&self.b);
+
+		Zip::from(ctx.get_output(&self.output).axis_iter_mut(Axis(0)))
+			.and(ctx.get_input(&self.a).axis_iter(Axis(0)))
+			.par_for_each(|mut output, a| {
+				for (o, b) in output.iter_mut().zip(b.axis_iter(Axis(0))) {
+					*o += a.iter().zip(&b).map(|(&x, &y)| (x - y) * (x - y)).sum::<f32>();
+				}
+			});
+
+		Ok(())
+	}
+}
+
+/// `PairwiseL2Back` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct PairwiseL2Back {
+	a: Node,
+	a_grad: Node,
+	b: Node,
+	b_grad: Node,
+	output_grad: Node,
+}
+
+impl PairwiseL2Back {
+	pub fn new<I1, I2, I3, O1, O2>(a: I1, a_grad: O1, b: I2, b_grad: O2, output_grad: I3) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let a = a.into();
+		let a_grad = a_grad.into();
+		let b = b.into();
+		let b_grad = b_grad.into();
+		let output_grad = output_grad.into();
+		assert!(a.shape().len() == 2);
+		assert!(b.shape().len() == 2);
+		assert!(a_grad.shape().len() == 2);
+		assert!(b_grad.shape().len() == 2);
+		assert!(output_grad.shape().len() == 2);
+		PairwiseL2Back {
+			a,
+			a_grad,
+			b,
+			b_grad,
+			output_grad,
+		}
+	}
+}
+
+impl OpSpecification for PairwiseL2Back {
+	type InstanceType = PairwiseL2BackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"PairwiseL2Back"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.a_grad.clone(), self.b_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			a_grad: mapping.get(&self.a_grad).unwrap_or(&self.a_grad).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			b_grad: mapping.get(&self.b_grad).unwrap_or(&self.b_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(PairwiseL2BackInstance {
+			a: self.a.id(),
+			a_grad: self.a_grad.id(),
+			b: self.b.id(),
+			b_grad: self.b_grad.id(),
+			output_grad: self.output_grad.id(),
+		})
+	}
+}
+
+/// PairwiseL2Back OpInstance
+#[derive(Clone, Debug)]
+pub struct PairwiseL2BackInstance {
+	a: NodeID,
+	a_grad: NodeID,
+	b: NodeID,
+	b_grad: NodeID,
+	output_grad: NodeID,
+}
+
+impl OpInstance for PairwiseL2BackInstance {
+	fn type_name(&self) -> &'static str {
+		"PairwiseL2Back"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(PairwiseL2Back {
+			a: graph.node_from_id(self.a),
+			a_grad: graph.node_from_id(self.a_grad),
+			b: graph.node_from_id(self.b),
+			b_grad: graph.node_from_id(self.b_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a_grad, self.b_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let (m, n) = row_counts(ctx.input_shape(&self.a), ctx.input_shape(&self.b), self.type_name())?;
+
+		let output_grad_shape = ctx.input_shape(&self.output_grad);
+		if output_grad_shape.slice() != [m, n] {
+			return Err(format!(
+				"PairwiseL2Back requires the output grad to have shape [{}, {}], found: {:?}",
+				m,
+				n,
+				output_grad_shape.slice()
+			)
+			.into());
+		}
+
+		ctx.set_output_like(&self.a_grad, &self.a)?;
+		ctx.set_output_like(&self.b_grad, &self.b)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let a = ctx.get_input(&self.a);
+		let b = ctx.get_input(&self.b);
+		let output_grad = ctx.get_input(&self.output_grad);
+
+		// d/da[i, k] = sum_j 2 * output_grad[i, j] * (a[i, k] - b[j, k])
+		if ctx.is_required_output(&self.a_grad) {
+			Zip::from(ctx.get_output(&self.a_grad).axis_iter_mut(Axis(0)))
+				.and(a.axis_iter(Axis(0)))
+				.and(output_grad.axis_iter(Axis(0)))
+				.par_for_each(|mut a_grad, a, output_grad| {
+					for (&g, b) in output_grad.iter().zip(b.axis_iter(Axis(0))) {
+						Zip::from(&mut a_grad)
+							.and(&a)
+							.and(&b)
+							.for_each(|a_grad, &x, &y| *a_grad += 2.0 * g * (x - y));
+					}
+				});
+		}
+
+		// d/db[j, k] = sum_i 2 * output_grad[i, j] * (b[j, k] - a[i, k])
+		if ctx.is_required_output(&self.b_grad) {
+			Zip::from(ctx.get_output(&self.b_grad).axis_iter_mut(Axis(0)))
+				.and(b.axis_iter(Axis(0)))
+				.and(output_grad.axis_iter(Axis(1)))
+				.par_for_each(|mut b_grad, b, output_grad| {
+					for (&g, a) in output_grad.iter().zip(a.axis_iter(Axis(0))) {
+						Zip::from(&mut b_grad)
+							.and(&b)
+							.and(&a)
+							.for_each(|b_grad, &y, &x| *b_grad += 2.0 * g * (y - x));
+					}
+				});
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::pairwise_l2;
+	use crate::tests::{contract_case, input};
+	use alumina_core::graph::Node;
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr2, Array2};
+
+	contract_case!("pairwise_l2", || {
+		let (a, b) = (input("a", &[4, 5]), input("b", &[3, 5]));
+		(pairwise_l2(&a, &b).unwrap(), vec![a, b])
+	});
+
+	#[test]
+	fn forward_test() {
+		let a_value = arr2(&[[0.0, 1.0, 2.0], [-1.5, 0.5, 3.0]]);
+		let b_value = arr2(&[[1.0, 1.0, 1.0], [0.0, -2.0, 0.5], [-1.5, 0.5, 3.0]]);
+
+		let a = Node::new(&[2, 3]).set_name("a").set_value(a_value.clone());
+		let b = Node::new(&[3, 3]).set_name("b").set_value(b_value.clone());
+
+		let output = pairwise_l2(&a, &b).unwrap();
+		assert_eq!(output.shape(), (&[2, 3]).into());
+
+		let expected = Array2::from_shape_fn((2, 3), |(i, j)| {
+			(0..3).map(|k| (a_value[[i, k]] - b_value[[j, k]]).powi(2)).sum::<f32>()
+		});
+
+		let result = output.calc().unwrap();
+		assert!(result.all_relatively_close(&expected, 1e-6));
+		assert_eq!(result[[1, 2]], 0.0);
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let a = Node::new(&[7, 5]).set_name("a");
+		let b = Node::new(&[9, 5]).set_name("b");
+
+		let output = pairwise_l2(&a, &b).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a, &b])
+			.step_size(1e-3)
+			.tolerance(2e-3)
+			.run();
+	}
+}
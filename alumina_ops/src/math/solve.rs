@@ -0,0 +1,544 @@
+use crate::math::logdet::{as_batch, Lu};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape::NodeShape,
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Array3, ArrayD, ArrayViewMutD, Dimension, IxDyn};
+use std::any::Any;
+
+/// Calculates the solution `x` of `a @ x = b` for each square matrix in the last two axes of `a`.
+///
+/// The last two axes of `b` are the `n` rows and `k` right hand sides, and any preceding axes must match those of `a`.
+/// Solving is done by LU decomposition with partial pivoting, which is more stable than multiplying by
+/// `inverse(a)`. Execution returns an error if any matrix is singular.
+///
+/// The output node has the same shape as `b`.
+pub fn solve<I1, I2>(a: I1, b: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let a = a.into();
+	let b = b.into();
+	merge_graphs(&[a.graph(), b.graph()]);
+
+	let output_shape = calc_output_shape(&a.shape(), &b.shape()).map_err(|err| {
+		format!(
+			"solve could not be built for a ({}) with shape {} and b ({}) with shape {}: {}",
+			a,
+			a.shape(),
+			b,
+			b.shape(),
+			err
+		)
+	})?;
+
+	let output = a
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("solve({}, {})", a, b));
+
+	Solve::new(a, b, output.clone()).build()?;
+
+	Ok(output)
+}
+
+/// Checks that `a` is a batch of square matrices matching `b`, returning the shape of `b` with all but the last axis
+/// merged with `a`.
+fn calc_output_shape(a_shape: &NodeShape, b_shape: &NodeShape) -> Result<NodeShape, String> {
+	let len = a_shape.len();
+	if len < 2 {
+		return Err("a must have at least 2 axes".to_string());
+	}
+	if b_shape.len() != len {
+		return Err("a and b must have the same number of axes".to_string());
+	}
+	a_shape.slice()[len - 2]
+		.merge(&a_shape.slice()[len - 1])
+		.map_err(|_| "the last two axes of a must be equal".to_string())?;
+
+	a_shape.slice()[..len - 1]
+		.iter()
+		.zip(&b_shape.slice()[..len - 1])
+		.map(|(a_axis, b_axis)| {
+			a_axis
+				.merge(b_axis)
+				.map_err(|_| "all but the last axis of b must match a".to_string())
+		})
+		.chain(Some(Ok(b_shape.slice()[len - 1].clone())))
+		.collect::<Result<Vec<_>, _>>()
+		.map(Into::into)
+}
+
+/// Writes a batch of matrices into an array of the given shape.
+fn from_batch(batch: Array3<f64>, shape: &[usize]) -> ArrayD<f32> {
+	batch
+		.mapv(|x| x as f32)
+		.into_shape(IxDyn(shape))
+		.expect("Alumina Bug: batch of matrices could not be reshaped")
+}
+
+/// `Solve` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Solve {
+	a: Node,
+	b: Node,
+	output: Node,
+}
+
+impl Solve {
+	pub fn new<I1, I2, O>(a: I1, b: I2, output: O) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let a = a.into();
+		let b = b.into();
+		let output = output.into();
+		assert!(a.shape().len() >= 2, "a must have at least 2 axes");
+		assert!(
+			a.shape().len() == b.shape().len(),
+			"a and b must have the same number of axes"
+		);
+		assert!(
+			b.shape().len() == output.shape().len(),
+			"output and b must have the same number of axes"
+		);
+		Solve { a, b, output }
+	}
+}
+
+impl OpSpecification for Solve {
+	type InstanceType = SolveInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Solve"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(SolveInstance {
+			a: self.a.id(),
+			b: self.b.id(),
+			output: self.output.id(),
+		})
+	}
+}
+
+/// Solve OpInstance
+#[derive(Clone, Debug)]
+pub struct SolveInstance {
+	a: NodeID,
+	b: NodeID,
+	output: NodeID,
+}
+
+impl OpInstance for SolveInstance {
+	fn type_name(&self) -> &'static str {
+		"Solve"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Solve {
+			a: graph.node_from_id(self.a),
+			b: graph.node_from_id(self.b),
+			output: graph.node_from_id(self.output),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		SolveBack::new(
+			ctx.node(&self.a),
+			ctx.grad_of(&self.a),
+			ctx.grad_of(&self.b),
+			ctx.node(&self.output),
+			ctx.grad_of(&self.output),
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let a_shape: NodeShape = ctx.input_shape(&self.a).slice().into();
+		let b_shape: NodeShape = ctx.input_shape(&self.b).slice().into();
+		let output_shape = calc_output_shape(&a_shape, &b_shape).map_err(|err| {
+			format!(
+				"Solve could not be executed for a with shape {} and b with shape {}: {}",
+				a_shape, b_shape, err
+			)
+		})?;
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let a = as_batch(&ctx.get_input(&self.a));
+		let b = as_batch(&ctx.get_input(&self.b));
+		let mut output = ctx.get_output(&self.output);
+
+		let mut solutions = Array3::zeros(b.raw_dim());
+		for ((a, b), mut solution) in a.outer_iter().zip(b.outer_iter()).zip(solutions.outer_iter_mut()) {
+			let lu = Lu::new(a);
+			if lu.is_singular() {
+				return Err("Solve could not solve a system with a singular matrix"
+					.to_string()
+					.into());
+			}
+			solution.assign(&lu.solve(b));
+		}
+		output += &from_batch(solutions, output.shape());
+
+		Ok(())
+	}
+}
+
+/// Calculates the gradients of `solve` by the adjoint solve, `b_grad += solve(a^T, output_grad)` and
+/// `a_grad += -b_grad @ output^T`.
+///
+/// Input/Output naming convention matches Solve Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct SolveBack {
+	a: Node,
+	a_grad: Node,
+	b_grad: Node,
+	output: Node,
+	output_grad: Node,
+}
+
+impl SolveBack {
+	pub fn new<I1, I2, I3, O1, O2>(a: I1, a_grad: O1, b_grad: O2, output: I2, output_grad: I3) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let a = a.into();
+		let a_grad = a_grad.into();
+		let b_grad = b_grad.into();
+		let output = output.into();
+		let output_grad = output_grad.into();
+		let len = a.shape().len();
+		assert!(len >= 2, "a must have at least 2 axes");
+		assert!(
+			a_grad.shape().len() == len,
+			"a_grad and a must have the same number of axes"
+		);
+		assert!(
+			b_grad.shape().len() == len,
+			"b_grad and a must have the same number of axes"
+		);
+		assert!(
+			output.shape().len() == len,
+			"output and a must have the same number of axes"
+		);
+		assert!(
+			output_grad.shape().len() == len,
+			"output_grad and a must have the same number of axes"
+		);
+		SolveBack {
+			a,
+			a_grad,
+			b_grad,
+			output,
+			output_grad,
+		}
+	}
+}
+
+impl OpSpecification for SolveBack {
+	type InstanceType = SolveBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"SolveBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.output.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.a_grad.clone(), self.b_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			a_grad: mapping.get(&self.a_grad).unwrap_or(&self.a_grad).clone(),
+			b_grad: mapping.get(&self.b_grad).unwrap_or(&self.b_grad).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(SolveBackInstance {
+			a: self.a.id(),
+			a_grad: self.a_grad.id(),
+			b_grad: self.b_grad.id(),
+			output: self.output.id(),
+			output_grad: self.output_grad.id(),
+		})
+	}
+}
+
+/// SolveBack OpInstance
+#[derive(Clone, Debug)]
+pub struct SolveBackInstance {
+	a: NodeID,
+	a_grad: NodeID,
+	b_grad: NodeID,
+	output: NodeID,
+	output_grad: NodeID,
+}
+
+impl OpInstance for SolveBackInstance {
+	fn type_name(&self) -> &'static str {
+		"SolveBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(SolveBack {
+			a: graph.node_from_id(self.a),
+			a_grad: graph.node_from_id(self.a_grad),
+			b_grad: graph.node_from_id(self.b_grad),
+			output: graph.node_from_id(self.output),
+			output_grad: graph.node_from_id(self.output_grad),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.output, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a_grad, self.b_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.a_grad, &self.a)?;
+		ctx.set_output_like(&self.b_grad, &self.output)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let a_input = ctx.get_input(&self.a);
+		let output_grad_input = ctx.get_input(&self.output_grad);
+		let a = as_batch(&a_input);
+		let output = as_batch(&ctx.get_input(&self.output));
+		let output_grad = as_batch(&output_grad_input);
+
+		let mut a_grads = Array3::zeros(a.raw_dim());
+		let mut b_grads = Array3::zeros(output.raw_dim());
+		for ((((a, output), output_grad), mut a_grad), mut b_grad) in a
+			.outer_iter()
+			.zip(output.outer_iter())
+			.zip(output_grad.outer_iter())
+			.zip(a_grads.outer_iter_mut())
+			.zip(b_grads.outer_iter_mut())
+		{
+			let lu = Lu::new(a.t());
+			if lu.is_singular() {
+				return Err("SolveBack could not solve a system with a singular matrix"
+					.to_string()
+					.into());
+			}
+			b_grad.assign(&lu.solve(output_grad));
+			a_grad.assign(&-b_grad.dot(&output.t()));
+		}
+		let a_grads = from_batch(a_grads, a_input.shape());
+		let b_grads = from_batch(b_grads, output_grad_input.shape());
+
+		if self.a_grad == self.b_grad {
+			// solve(a, a), both gradients accumulate into the same array which can only be borrowed once
+			if ctx.is_required_output(&self.a_grad) {
+				let mut a_grad = ctx.get_output(&self.a_grad);
+				accumulate(a_grad.view_mut(), &a_grads);
+				accumulate(a_grad, &b_grads);
+			}
+		} else {
+			if ctx.is_required_output(&self.a_grad) {
+				accumulate(ctx.get_output(&self.a_grad), &a_grads);
+			}
+			if ctx.is_required_output(&self.b_grad) {
+				accumulate(ctx.get_output(&self.b_grad), &b_grads);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn accumulate(mut grad: ArrayViewMutD<f32>, value: &ArrayD<f32>) {
+	grad += value;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::solve;
+	use crate::elementwise::{identity::add, mul::mul};
+	use crate::tests::{contract_case, input, positive_definite};
+	use alumina_core::{exec::ExecutionPlan, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::{indexmap, indexset};
+
+	use ndarray::{arr2, arr3, Array3};
+
+	contract_case!("solve", || {
+		let (m, r) = (input("m", &[3, 4, 4]), input("r", &[3, 4, 2]));
+		(solve(positive_definite(&m), &r).unwrap(), vec![m, r])
+	});
+
+	/// Returns a noise node, and a batch of well conditioned matrices made by adding a large diagonal to the noise.
+	fn well_conditioned(batch: usize, n: usize) -> (Node, Node) {
+		let noise = Node::new(&[batch, n, n]).set_name("noise").set_init(uniform(-1.0, 1.0));
+		let diagonal = Node::new(&[batch, n, n])
+			.set_name("diagonal")
+			.set_value(Array3::from_shape_fn(
+				(batch, n, n),
+				|(_, i, j)| if i == j { 5.0 } else { 0.0 },
+			));
+
+		(noise.clone(), add(&noise, &diagonal).unwrap())
+	}
+
+	#[test]
+	fn forward_test() {
+		let a = Node::new(&[2, 2])
+			.set_name("a")
+			.set_value(arr2(&[[3.0, 1.0], [1.0, 2.0]]));
+		let b = Node::new(&[2, 1]).set_name("b").set_value(arr2(&[[9.0], [8.0]]));
+
+		let output = solve(&a, &b).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr2(&[[2.0], [3.0]]), 1e-6));
+	}
+
+	#[test]
+	fn forward_batched_test() {
+		let a = Node::new(&[2, 2, 2]).set_name("a").set_value(arr3(&[
+			[[3.0, 1.0], [1.0, 2.0]],
+			[[0.0, 2.0], [4.0, 1.0]], // requires a row swap
+		]));
+		let b = Node::new(&[2, 2, 2])
+			.set_name("b")
+			.set_value(arr3(&[[[9.0, 1.0], [8.0, -3.0]], [[4.0, -2.0], [6.0, 3.0]]]));
+
+		let output = solve(&a, &b).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr3(&[[[2.0, 1.0], [3.0, -2.0]], [[1.0, 1.0], [2.0, -1.0]]]), 1e-6));
+	}
+
+	#[test]
+	fn singular_error_test() {
+		let a = Node::new(&[2, 2])
+			.set_name("a")
+			.set_value(arr2(&[[1.0, 2.0], [2.0, 4.0]]));
+		let b = Node::new(&[2, 1]).set_name("b").set_value(arr2(&[[1.0], [1.0]]));
+
+		let output = solve(&a, &b).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn near_singular_error_test() {
+		// singular up to the rounding of 1/3
+		let a = Node::new(&[2, 2])
+			.set_name("a")
+			.set_value(arr2(&[[1.0, 1.0 / 3.0], [3.0, 1.0]]));
+		let b = Node::new(&[2, 1]).set_name("b").set_value(arr2(&[[1.0], [1.0]]));
+
+		let output = solve(&a, &b).unwrap();
+
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let a = Node::new(&[3, 4, 4]).set_name("a");
+
+		assert!(solve(&a, Node::new(&[3, 5, 2]).set_name("b")).is_err());
+		assert!(solve(&a, Node::new(&[2, 4, 2]).set_name("b")).is_err());
+		assert!(solve(&a, Node::new(&[4, 2]).set_name("b")).is_err());
+		assert!(solve(Node::new(&[3, 4, 3]).set_name("a"), Node::new(&[3, 4, 2]).set_name("b")).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let (noise, a) = well_conditioned(3, 4);
+		let b = Node::new(&[3, 4, 2]).set_name("b");
+		let scale = Node::new(&[3, 4, 2])
+			.set_name("scale")
+			.set_value(Array3::from_shape_fn((3, 4, 2), |(i, j, k)| {
+				((i * 8 + j * 2 + k) as f32).sin()
+			}));
+
+		let output = mul(solve(&a, &b).unwrap(), &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&noise, &b]).run();
+	}
+
+	#[test]
+	fn grad_shared_input_test() {
+		let (noise, a) = well_conditioned(3, 4);
+
+		// the output is always the identity, so both contributions to the gradient of a must cancel
+		let output = solve(&a, &a).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&noise])
+			.build()
+			.unwrap()
+			.swap_remove(&noise)
+			.unwrap();
+
+		let noise_value = noise.init_array().unwrap();
+		let grad_value = ExecutionPlan::new(indexmap![noise => noise_value.to_shared()], &[&grad])
+			.execute()
+			.unwrap()
+			.swap_remove(&grad)
+			.unwrap();
+		assert!(grad_value.iter().all(|x| x.abs() < 1e-5));
+	}
+}
@@ -0,0 +1,206 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::Axis;
+use std::any::Any;
+
+/// Returns a constant [seq_len, seq_len] additive mask for causal attention.
+///
+/// Elements on or below the diagonal are 0.0, and elements above the diagonal (i.e. where the key comes after the
+/// query) are negative infinity. Adding the mask to attention logits before a softmax over the last axis gives masked
+/// positions a weight of zero.
+///
+/// The mask has no inputs and is not differentiable. It is evaluated once when built and stored as the value of the
+/// output, rather than being recalculated on every execution.
+pub fn causal_mask(seq_len: usize) -> Result<Node, OpBuildError> {
+	let output = Node::new([seq_len, seq_len]).set_name_unique(&format!("causal_mask({})", seq_len));
+
+	let _op = CausalMask::new(output.clone(), seq_len).build_constant()?;
+
+	Ok(output)
+}
+
+/// `CausalMask` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct CausalMask {
+	output: Node,
+	seq_len: usize,
+}
+
+impl CausalMask {
+	pub fn new<O>(output: O, seq_len: usize) -> Self
+	where
+		O: Into<Node>,
+	{
+		let output = output.into();
+		assert!(
+			output.shape().len() == 2,
+			"CausalMask requires the output to have two axes, found shape {}",
+			output.shape()
+		);
+		CausalMask { output, seq_len }
+	}
+}
+
+impl OpSpecification for CausalMask {
+	type InstanceType = CausalMaskInstance;
+
+	fn type_name(&self) -> &'static str {
+		"CausalMask"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			seq_len: self.seq_len,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CausalMaskInstance {
+			output: self.output.id(),
+			seq_len: self.seq_len,
+		})
+	}
+}
+
+/// CausalMask OpInstance
+#[derive(Clone, Debug)]
+pub struct CausalMaskInstance {
+	output: NodeID,
+	seq_len: usize,
+}
+
+impl OpInstance for CausalMaskInstance {
+	fn type_name(&self) -> &'static str {
+		"CausalMask"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(CausalMask {
+			output: graph.node_from_id(self.output),
+			seq_len: self.seq_len,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.merge_output_shape(&self.output, &vec![self.seq_len, self.seq_len].into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		for (i, mut row) in ctx.get_output(&self.output).axis_iter_mut(Axis(0)).enumerate() {
+			for o in row.iter_mut().skip(i + 1) {
+				*o += f32::NEG_INFINITY;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{causal_mask, CausalMask};
+	use crate::tests::{contract_case, input};
+	use crate::{elementwise::identity::add, nn::softmax::softmax};
+	use alumina_core::{base_ops::OpSpecification, graph::Node, subgraph::execution_subgraph};
+	use alumina_test::relatively_close::RelClose;
+	use ndarray::{arr0, arr1, arr2, Axis, Ix2};
+
+	contract_case!("causal_mask", || {
+		// built as a regular Op rather than a constant, so that the mask is executed along with the rest of the plan
+		let x = input("x", &[5, 5]);
+		let mask = x.graph().new_node(x.shape()).set_name("mask");
+		CausalMask::new(&mask, 5).build().unwrap();
+		(softmax(add(&x, &mask).unwrap(), -1).unwrap(), vec![x])
+	});
+
+	#[test]
+	fn causal_mask_test() {
+		let value = causal_mask(4).unwrap().calc().unwrap();
+		assert_eq!(value.shape(), &[4, 4]);
+
+		for ((i, j), &v) in value.view().into_dimensionality::<Ix2>().unwrap().indexed_iter() {
+			if j <= i {
+				assert_eq!(v, 0.0, "{}", value);
+			} else {
+				assert_eq!(v, f32::NEG_INFINITY, "{}", value);
+			}
+		}
+	}
+
+	#[test]
+	fn masked_softmax_test() {
+		let logits = Node::new([3, 3])
+			.set_value(arr2(&[[0.5, 2.0, -1.0], [1.0, 3.0, 0.2], [-0.4, 0.6, 1.5]]))
+			.set_name("logits");
+
+		let weights = softmax(add(&logits, causal_mask(3).unwrap()).unwrap(), -1)
+			.unwrap()
+			.calc()
+			.unwrap();
+
+		assert_eq!(weights[[0, 1]], 0.0);
+		assert_eq!(weights[[0, 2]], 0.0);
+		assert_eq!(weights[[1, 2]], 0.0);
+		assert!(weights.sum_axis(Axis(1)).all_relatively_close(&arr0(1.0), 1e-6));
+		assert_eq!(weights[[0, 0]], 1.0);
+
+		let exp_sum = 1.0f32.exp() + 3.0f32.exp();
+		assert!(weights
+			.index_axis(Axis(0), 1)
+			.all_relatively_close(&arr1(&[1.0f32.exp() / exp_sum, 3.0f32.exp() / exp_sum, 0.0]), 1e-6));
+	}
+
+	#[test]
+	fn precomputed_test() {
+		let mask = causal_mask(5).unwrap();
+		assert!(mask.has_value());
+
+		let logits = Node::new([5, 5]).set_name("logits").set_value(arr0(0.5));
+		let output = add(&logits, &mask).unwrap();
+
+		// the mask op isn't part of the execution, however many times the output is calculated
+		let subgraph = execution_subgraph(&[] as &[Node], [&output], false).unwrap();
+		assert!(subgraph.ops.iter().all(|op| op.type_name() != "CausalMask"));
+
+		let first = output.calc().unwrap();
+		let second = output.calc().unwrap();
+		assert_eq!(first, second);
+		assert_eq!(first[[0, 0]], 0.5);
+		assert_eq!(first[[0, 4]], f32::NEG_INFINITY);
+
+		// without build_constant() the mask is calculated during execution
+		let unfolded = Node::new([5, 5]).set_name("unfolded");
+		CausalMask::new(&unfolded, 5).build().unwrap();
+		assert!(!unfolded.has_value());
+		assert_eq!(unfolded.calc().unwrap(), mask.value().unwrap());
+	}
+}
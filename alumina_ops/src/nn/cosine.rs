@@ -0,0 +1,489 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayView1, ArrayViewMut1, Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Calculates the cosine similarity of `a` and `b` along the selected axis.
+///
+/// `output = sum(a * b) / (max(norm(a), epsilon) * max(norm(b), epsilon))`, where the norms are taken along the axis.
+/// Flooring the norms at `epsilon` avoids division by zero for zero vectors, for which the output is then 0.
+///
+/// The output node has the shape of `a` and `b`, but with the axis removed.
+pub fn cosine_similarity<I1, I2>(a: I1, b: I2, axis: isize, epsilon: f32) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let a = a.into();
+	let b = b.into();
+	let axis = wrap_dim(axis, a.shape().len());
+
+	let graph = merge_graphs(&[a.graph(), b.graph()]);
+
+	let output_shape = a
+		.shape()
+		.iter()
+		.enumerate()
+		.filter_map(|(i, x)| if i == axis { None } else { Some(x) })
+		.into();
+
+	let output = graph
+		.new_node(output_shape)
+		.set_name_unique(&format!("cosine_similarity({},{})", a, b));
+
+	CosineSimilarity::new(a, b, output.clone(), axis)
+		.epsilon(epsilon)
+		.build()?;
+
+	Ok(output)
+}
+
+/// Returns `(dot, max(norm(x), epsilon), max(norm(y), epsilon))` for a pair of lanes.
+fn lane_terms(x: &ArrayView1<f32>, y: &ArrayView1<f32>, epsilon: f32) -> (f32, f32, f32) {
+	let (dot, x_sqr, y_sqr) = x.iter().zip(y).fold((0.0, 0.0, 0.0), |(dot, x_sqr, y_sqr), (&x, &y)| {
+		(dot + x * y, x_sqr + x * x, y_sqr + y * y)
+	});
+	(dot, x_sqr.sqrt().max(epsilon), y_sqr.sqrt().max(epsilon))
+}
+
+/// Accumulates the gradient of the cosine similarity with respect to `x` into `x_grad`.
+///
+/// `d/dx = output_grad * (y / (x_norm * y_norm) - similarity * x / x_norm^2)`, where the second term is absent if the
+/// norm of `x` was floored at epsilon.
+fn lane_grad(mut x_grad: ArrayViewMut1<f32>, x: ArrayView1<f32>, y: ArrayView1<f32>, output_grad: f32, epsilon: f32) {
+	let (dot, x_norm, y_norm) = lane_terms(&x, &y, epsilon);
+	let similarity = dot / (x_norm * y_norm);
+	let y_scale = output_grad / (x_norm * y_norm);
+	let x_scale = if x_norm > epsilon {
+		output_grad * similarity / (x_norm * x_norm)
+	} else {
+		0.0
+	};
+
+	Zip::from(&mut x_grad)
+		.and(&x)
+		.and(&y)
+		.for_each(|x_grad, &x, &y| *x_grad += y * y_scale - x * x_scale);
+}
+
+/// `CosineSimilarity` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct CosineSimilarity {
+	a: Node,
+	b: Node,
+	output: Node,
+	axis: usize,
+	epsilon: f32,
+}
+
+impl CosineSimilarity {
+	pub fn new<I1, I2, O>(a: I1, b: I2, output: O, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let a = a.into();
+		let b = b.into();
+		let output = output.into();
+		assert!(a.shape().len() == b.shape().len());
+		assert!(a.shape().len() == output.shape().len() + 1);
+		assert!(
+			axis < a.shape().len(),
+			"axis {} must be less than a.shape().len() {}",
+			axis,
+			a.shape().len()
+		);
+		CosineSimilarity {
+			a,
+			b,
+			output,
+			axis,
+			epsilon: 1e-8,
+		}
+	}
+
+	/// The minimum value of each norm, preventing division by zero.
+	///
+	/// Default: 1e-8
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon > 0.0, "CosineSimilarity epsilon must be greater than 0.0");
+		self.epsilon = epsilon;
+		self
+	}
+}
+
+impl OpSpecification for CosineSimilarity {
+	type InstanceType = CosineSimilarityInstance;
+
+	fn type_name(&self) -> &'static str {
+		"CosineSimilarity"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CosineSimilarityInstance {
+			a: self.a.id(),
+			b: self.b.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		})
+	}
+}
+
+/// CosineSimilarity OpInstance
+#[derive(Clone, Debug)]
+pub struct CosineSimilarityInstance {
+	a: NodeID,
+	b: NodeID,
+	output: NodeID,
+	axis: usize,
+	epsilon: f32,
+}
+
+impl OpInstance for CosineSimilarityInstance {
+	fn type_name(&self) -> &'static str {
+		"CosineSimilarity"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(CosineSimilarity {
+			a: graph.node_from_id(self.a),
+			b: graph.node_from_id(self.b),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		CosineSimilarityBack::new(
+			ctx.node(&self.a),
+			ctx.grad_of(&self.a),
+			ctx.node(&self.b),
+			ctx.grad_of(&self.b),
+			ctx.grad_of(&self.output),
+			self.axis,
+		)
+		.epsilon(self.epsilon)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.a, self.b])?;
+
+		let output_shape = ctx
+			.input_shape(&self.a)
+			.slice()
+			.iter()
+			.enumerate()
+			.filter_map(|(i, axis)| if i == self.axis { None } else { Some(axis) })
+			.into();
+
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let epsilon = self.epsilon;
+
+		Zip::from(ctx.get_output(&self.output))
+			.and(ctx.get_input(&self.a).lanes(Axis(self.axis)))
+			.and(ctx.get_input(&self.b).lanes(Axis(self.axis)))
+			.par_for_each(|output, a, b| {
+				let (dot, a_norm, b_norm) = lane_terms(&a, &b, epsilon);
+				*output += dot / (a_norm * b_norm);
+			});
+
+		Ok(())
+	}
+}
+
+/// `CosineSimilarityBack` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct CosineSimilarityBack {
+	a: Node,
+	a_grad: Node,
+	b: Node,
+	b_grad: Node,
+	output_grad: Node,
+	axis: usize,
+	epsilon: f32,
+}
+
+impl CosineSimilarityBack {
+	pub fn new<I1, I2, I3, O1, O2>(a: I1, a_grad: O1, b: I2, b_grad: O2, output_grad: I3, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let a = a.into();
+		let a_grad = a_grad.into();
+		let b = b.into();
+		let b_grad = b_grad.into();
+		let output_grad = output_grad.into();
+		assert!(a.shape().len() == b.shape().len());
+		assert!(a.shape().len() == a_grad.shape().len());
+		assert!(a.shape().len() == b_grad.shape().len());
+		assert!(a.shape().len() == output_grad.shape().len() + 1);
+		assert!(
+			axis < a.shape().len(),
+			"axis {} must be less than a.shape().len() {}",
+			axis,
+			a.shape().len()
+		);
+		CosineSimilarityBack {
+			a,
+			a_grad,
+			b,
+			b_grad,
+			output_grad,
+			axis,
+			epsilon: 1e-8,
+		}
+	}
+
+	/// The minimum value of each norm, preventing division by zero.
+	///
+	/// Default: 1e-8
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		assert!(epsilon > 0.0, "CosineSimilarityBack epsilon must be greater than 0.0");
+		self.epsilon = epsilon;
+		self
+	}
+}
+
+impl OpSpecification for CosineSimilarityBack {
+	type InstanceType = CosineSimilarityBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"CosineSimilarityBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.a.clone(), self.b.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.a_grad.clone(), self.b_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			a: mapping.get(&self.a).unwrap_or(&self.a).clone(),
+			a_grad: mapping.get(&self.a_grad).unwrap_or(&self.a_grad).clone(),
+			b: mapping.get(&self.b).unwrap_or(&self.b).clone(),
+			b_grad: mapping.get(&self.b_grad).unwrap_or(&self.b_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CosineSimilarityBackInstance {
+			a: self.a.id(),
+			a_grad: self.a_grad.id(),
+			b: self.b.id(),
+			b_grad: self.b_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		})
+	}
+}
+
+/// CosineSimilarityBack OpInstance
+#[derive(Clone, Debug)]
+pub struct CosineSimilarityBackInstance {
+	a: NodeID,
+	a_grad: NodeID,
+	b: NodeID,
+	b_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+	epsilon: f32,
+}
+
+impl OpInstance for CosineSimilarityBackInstance {
+	fn type_name(&self) -> &'static str {
+		"CosineSimilarityBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(CosineSimilarityBack {
+			a: graph.node_from_id(self.a),
+			a_grad: graph.node_from_id(self.a_grad),
+			b: graph.node_from_id(self.b),
+			b_grad: graph.node_from_id(self.b_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+			epsilon: self.epsilon,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a, self.b, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.a_grad, self.b_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.require_equal_shapes(&[self.a, self.b])?;
+
+		let a_shape = ctx.input_shape(&self.a).clone();
+		let output_grad_shape = ctx.input_shape(&self.output_grad);
+		if output_grad_shape.slice().len() + 1 != a_shape.slice().len()
+			|| a_shape
+				.slice()
+				.iter()
+				.enumerate()
+				.filter_map(|(i, &axis)| if i == self.axis { None } else { Some(axis) })
+				.zip(output_grad_shape.slice())
+				.any(|(input_axis, &out_grad_axis)| input_axis != out_grad_axis)
+		{
+			return Err(format!("CosineSimilarityBack requires the output grad to have the shape of the inputs with the selected axis removed: inputs:{:?} output_grad:{:?}, axis: {}", a_shape.slice(), output_grad_shape.slice(), self.axis).into());
+		}
+
+		ctx.set_output_like(&self.a_grad, &self.a)?;
+		ctx.set_output_like(&self.b_grad, &self.b)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let epsilon = self.epsilon;
+		let a = ctx.get_input(&self.a);
+		let b = ctx.get_input(&self.b);
+		let output_grad = ctx.get_input(&self.output_grad);
+
+		if ctx.is_required_output(&self.a_grad) {
+			Zip::from(ctx.get_output(&self.a_grad).lanes_mut(Axis(self.axis)))
+				.and(a.lanes(Axis(self.axis)))
+				.and(b.lanes(Axis(self.axis)))
+				.and(&output_grad)
+				.par_for_each(|a_grad, a, b, &output_grad| lane_grad(a_grad, a, b, output_grad, epsilon));
+		}
+
+		if ctx.is_required_output(&self.b_grad) {
+			Zip::from(ctx.get_output(&self.b_grad).lanes_mut(Axis(self.axis)))
+				.and(b.lanes(Axis(self.axis)))
+				.and(a.lanes(Axis(self.axis)))
+				.and(&output_grad)
+				.par_for_each(|b_grad, b, a, &output_grad| lane_grad(b_grad, b, a, output_grad, epsilon));
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::cosine_similarity;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	use ndarray::{arr1, arr2};
+
+	contract_case!(binary "cosine_similarity", |x, y| cosine_similarity(x, y, -1, 1e-8));
+
+	#[test]
+	fn forward_test() {
+		let a = Node::new(&[4, 3])
+			.set_value(arr2(&[
+				[1.0, 2.0, 3.0],
+				[1.0, 0.0, 0.0],
+				[3.0, 4.0, 0.0],
+				[0.0, 0.0, 0.0],
+			]))
+			.set_name("a");
+
+		let b = Node::new(&[4, 3])
+			.set_value(arr2(&[
+				[2.0, 4.0, 6.0],
+				[0.0, 5.0, 0.0],
+				[-3.0, -4.0, 0.0],
+				[1.0, 1.0, 1.0],
+			]))
+			.set_name("b");
+
+		let output = cosine_similarity(&a, &b, -1, 1e-8).unwrap();
+		assert_eq!(output.shape(), (&[4]).into());
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[1.0, 0.0, -1.0, 0.0]), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let a = Node::new(&[13, 9]).set_name("a");
+		let b = Node::new(&[13, 9]).set_name("b");
+
+		let output = cosine_similarity(&a, &b, -1, 1e-8).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a, &b])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_axis_test() {
+		let a = Node::new(&[7, 5, 3]).set_name("a");
+		let b = Node::new(&[7, 5, 3]).set_name("b");
+
+		let output = cosine_similarity(&a, &b, 1, 1e-8).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&a, &b])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -0,0 +1,581 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayD, IxDyn, Zip};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::any::Any;
+
+/// Randomly sets elements of the input to zero with probability `rate`, scaling the remaining elements by
+/// `1 / (1 - rate)` so that the expected value of each element is unchanged.
+///
+/// A new mask is drawn for each execution. To instead apply the same mask on every execution set a seed using
+/// `Dropout::seed(..)`, or to make the sequence of masks reproducible pass an `RngState` to
+/// `ExecutionPlan::rng_state(..)`.
+///
+/// The output node has the same shape as the input.
+pub fn dropout<I>(input: I, rate: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("dropout({})", input));
+
+	Dropout::new(input, output.clone(), rate).build()?;
+
+	Ok(output)
+}
+
+/// As `dropout(..)`, but also returns the applied mask, which is 1.0 for kept elements and 0.0 for dropped elements.
+///
+/// The mask is not differentiable.
+///
+/// Returns `(output, mask)`, both with the same shape as the input.
+pub fn dropout_with_mask<I>(input: I, rate: f32) -> Result<(Node, Node), OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("dropout({})", input));
+	let mask = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("dropout({})_mask", input));
+
+	Dropout::new(input, output.clone(), rate).mask(&mask).build()?;
+
+	Ok((output, mask))
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Dropout {
+	input: Node,
+	output: Node,
+	mask: Option<Node>,
+	rate: f32,
+	seed: u64,
+	fixed_seed: bool,
+}
+
+impl Dropout {
+	pub fn new<I, O>(input: I, output: O, rate: f32) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same shape"
+		);
+		assert!((0.0..1.0).contains(&rate), "rate {} must be in the range [0, 1)", rate);
+		Dropout {
+			input,
+			output,
+			mask: None,
+			rate,
+			seed: thread_rng().gen(),
+			fixed_seed: false,
+		}
+	}
+
+	/// An additional output to which the applied mask is written.
+	///
+	/// Default: None
+	pub fn mask<M>(mut self, mask: M) -> Self
+	where
+		M: Into<Node>,
+	{
+		let mask = mask.into();
+		assert!(
+			self.input.shape().len() == mask.shape().len(),
+			"mask and input must have the same shape"
+		);
+		self.mask = Some(mask);
+		self
+	}
+
+	/// Seed for the mask, which is then the same for every execution without an `RngState`.
+	///
+	/// Default: None, a new mask is drawn for each execution
+	pub fn seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self.fixed_seed = true;
+		self
+	}
+}
+
+impl OpSpecification for Dropout {
+	type InstanceType = DropoutInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Dropout"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		let mut outputs = indexset![self.output.clone()];
+		outputs.extend(self.mask.clone());
+		outputs
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			mask: self.mask.as_ref().map(|mask| mapping.get(mask).unwrap_or(mask).clone()),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DropoutInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			mask: self.mask.as_ref().map(Node::id),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		})
+	}
+}
+
+/// Dropout OpInstance
+#[derive(Clone, Debug)]
+pub struct DropoutInstance {
+	input: NodeID,
+	output: NodeID,
+	mask: Option<NodeID>,
+	rate: f32,
+	seed: u64,
+	fixed_seed: bool,
+}
+
+impl OpInstance for DropoutInstance {
+	fn type_name(&self) -> &'static str {
+		"Dropout"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Dropout {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			mask: self.mask.map(|mask| graph.node_from_id(mask)),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		let mut outputs = indexset![self.output];
+		outputs.extend(self.mask);
+		outputs
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// the mask doesn't depend on the input, so its gradient is ignored
+		DropoutBack::new(ctx.grad_of(&self.input), ctx.grad_of(&self.output), self.rate)
+			.seed(self.seed)
+			.fixed_seed(self.fixed_seed)
+			.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.output, &self.input)?;
+		if let Some(ref mask) = self.mask {
+			ctx.set_output_like(mask, &self.input)?;
+		}
+		Ok(())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mask = mask(input.raw_dim(), self.rate, seed(ctx, self.seed, self.fixed_seed));
+
+		if ctx.is_required_output(&self.output) {
+			let scale = 1.0 / (1.0 - self.rate);
+			Zip::from(ctx.get_output(&self.output))
+				.and(&input)
+				.and(&mask)
+				.par_for_each(|output, &input, &mask| *output += input * mask * scale);
+		}
+
+		if let Some(ref mask_node) = self.mask {
+			if ctx.is_required_output(mask_node) {
+				Zip::from(ctx.get_output(mask_node))
+					.and(&mask)
+					.par_for_each(|output, &mask| *output += mask);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Backward pass for Dropout Op, which applies the same mask and scaling to the output grad.
+///
+/// Input/Output naming convention matches Dropout Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The rate and seed must match the Dropout Op so that the same mask is regenerated.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct DropoutBack {
+	input_grad: Node,
+	output_grad: Node,
+	rate: f32,
+	seed: u64,
+	fixed_seed: bool,
+}
+
+impl DropoutBack {
+	pub fn new<I, O>(input_grad: O, output_grad: I, rate: f32) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input_grad = input_grad.into();
+		let output_grad = output_grad.into();
+		assert!(input_grad.shape().len() == output_grad.shape().len());
+		assert!((0.0..1.0).contains(&rate), "rate {} must be in the range [0, 1)", rate);
+		DropoutBack {
+			input_grad,
+			output_grad,
+			rate,
+			seed: 0,
+			fixed_seed: false,
+		}
+	}
+
+	/// Seed used by the Dropout Op.
+	///
+	/// Default: 0
+	pub fn seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self
+	}
+
+	/// Whether the seed was set on the Dropout Op, in which case the same mask is used for every execution without an
+	/// `RngState`.
+	///
+	/// Default: false
+	pub fn fixed_seed(mut self, fixed_seed: bool) -> Self {
+		self.fixed_seed = fixed_seed;
+		self
+	}
+}
+
+impl OpSpecification for DropoutBack {
+	type InstanceType = DropoutBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"DropoutBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DropoutBackInstance {
+			input_grad: self.input_grad.id(),
+			output_grad: self.output_grad.id(),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		})
+	}
+}
+
+/// DropoutBack OpInstance
+#[derive(Clone, Debug)]
+pub struct DropoutBackInstance {
+	input_grad: NodeID,
+	output_grad: NodeID,
+	rate: f32,
+	seed: u64,
+	fixed_seed: bool,
+}
+
+impl OpInstance for DropoutBackInstance {
+	fn type_name(&self) -> &'static str {
+		"DropoutBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(DropoutBack {
+			input_grad: graph.node_from_id(self.input_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			rate: self.rate,
+			seed: self.seed,
+			fixed_seed: self.fixed_seed,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.output_grad)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let output_grad = ctx.get_input(&self.output_grad);
+		let mask = mask(output_grad.raw_dim(), self.rate, seed(ctx, self.seed, self.fixed_seed));
+		let scale = 1.0 / (1.0 - self.rate);
+
+		Zip::from(ctx.get_output(&self.input_grad))
+			.and(&output_grad)
+			.and(&mask)
+			.par_for_each(|input_grad, &output_grad, &mask| *input_grad += output_grad * mask * scale);
+
+		Ok(())
+	}
+}
+
+/// Returns the seed to draw the mask from in this execution.
+fn seed(ctx: &ExecutionContext, seed: u64, fixed_seed: bool) -> u64 {
+	if fixed_seed {
+		ctx.seed(seed)
+	} else {
+		ctx.fresh_seed(seed)
+	}
+}
+
+/// Returns a mask of 1.0 for kept elements and 0.0 for dropped elements.
+///
+/// The mask is generated serially in standard order so that it depends only on the seed and shape.
+fn mask(shape: IxDyn, rate: f32, seed: u64) -> ArrayD<f32> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	ArrayD::from_shape_simple_fn(shape, || if rng.gen::<f32>() < rate { 0.0 } else { 1.0 })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{dropout, dropout_with_mask, Dropout};
+	use crate::tests::contract_case;
+	use alumina_core::{
+		base_ops::OpSpecification,
+		exec::{ExecutionPlan, RngState},
+		grad::Grad,
+		graph::Node,
+		init::uniform,
+	};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::{indexset, IndexMap};
+	use ndarray::{arr0, ArrayD, IxDyn, Zip};
+
+	contract_case!(unary "dropout", |x| {
+		// seeded so that every execution applies the same mask
+		let output = x.graph().new_node(x.shape());
+		Dropout::new(x, &output, 0.3).seed(0).build().map(|_| output)
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_value(arr0(2.0));
+
+		let output = dropout(&input, 0.25).unwrap().calc().unwrap();
+
+		// kept elements are scaled by 1/(1-rate)
+		assert!(output.iter().all(|&x| x == 0.0 || (x - 2.0 / 0.75).abs() < 1e-6));
+
+		let dropped = output.iter().filter(|&&x| x == 0.0).count() as f32 / output.len() as f32;
+		assert!((dropped - 0.25).abs() < 0.05, "dropped fraction {}", dropped);
+	}
+
+	#[test]
+	fn mask_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_random(-1.0, 1.0, 0);
+
+		let (output, mask) = dropout_with_mask(&input, 0.4).unwrap();
+
+		let results = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&output, &mask])
+			.execute()
+			.unwrap();
+		let output = &results[&output];
+		let mask = &results[&mask];
+		assert!(mask.iter().all(|&m| m == 0.0 || m == 1.0));
+
+		// the mask applied to the scaled input reproduces the output
+		let mut expected = ArrayD::zeros(IxDyn(&[37, 33]));
+		Zip::from(&mut expected)
+			.and(&input.value().unwrap())
+			.and(mask)
+			.for_each(|expected, &input, &mask| *expected = mask * input / 0.6);
+		assert!(output.all_relatively_close(&expected, 1e-6));
+	}
+
+	#[test]
+	fn seed_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_value(arr0(1.0));
+		let output1 = Node::new(&[37, 33]).set_name("output1");
+		let output2 = Node::new(&[37, 33]).set_name("output2");
+		let output3 = Node::new(&[37, 33]).set_name("output3");
+
+		Dropout::new(&input, &output1, 0.5).seed(1).build().unwrap();
+		Dropout::new(&input, &output2, 0.5).seed(1).build().unwrap();
+		Dropout::new(&input, &output3, 0.5).seed(2).build().unwrap();
+
+		// the mask is fixed by the seed, both across executions and Ops
+		let value1 = output1.calc().unwrap();
+		assert_eq!(value1, output1.calc().unwrap());
+		assert_eq!(value1, output2.calc().unwrap());
+		assert_ne!(value1, output3.calc().unwrap());
+	}
+
+	#[test]
+	fn grad_mask_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_value(arr0(1.0));
+		let output = Node::new(&[37, 33]).set_name("output");
+		let mask = Node::new(&[37, 33]).set_name("mask");
+
+		Dropout::new(&input, &output, 0.5).mask(&mask).build().unwrap();
+
+		// the gradient of the input is the mask applied to the scaled output grad
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		let results = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&mask, &grad])
+			.execute()
+			.unwrap();
+		let expected = results[&mask].mapv(|m| m / 0.5);
+		assert!(results[&grad].all_relatively_close(&expected, 1e-6));
+	}
+
+	#[test]
+	fn fresh_mask_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_value(arr0(1.0));
+		let (output, mask) = dropout_with_mask(&input, 0.5).unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		// without a seed each execution draws a new mask, which the backward Op shares
+		let results1 = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&mask, &grad])
+			.execute()
+			.unwrap();
+		let results2 = ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&mask, &grad])
+			.execute()
+			.unwrap();
+		assert_ne!(results1[&mask], results2[&mask]);
+		assert_eq!(results1[&grad], results1[&mask].mapv(|m| m / 0.5));
+		assert_eq!(results2[&grad], results2[&mask].mapv(|m| m / 0.5));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 33]).set_name("input").set_init(uniform(-1.0, 1.0));
+		let output = Node::new(&[13, 33]).set_name("output");
+
+		// the numeric gradient requires the same mask on every execution
+		Dropout::new(&input, &output, 0.3).seed(0).build().unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn rng_state_test() {
+		let input = Node::new(&[37, 33]).set_name("input").set_value(arr0(1.0));
+		let output = Node::new(&[37, 33]).set_name("output");
+		let mask = Node::new(&[37, 33]).set_name("mask");
+		Dropout::new(&input, &output, 0.5).mask(&mask).seed(3).build().unwrap();
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		let run = |rng_state: &mut RngState| {
+			ExecutionPlan::new(IndexMap::<Node, _>::new(), &[&mask, &grad])
+				.rng_state(Some(rng_state))
+				.execute()
+				.unwrap()
+		};
+
+		let mut rng_state = RngState::new();
+		let mut masks = vec![];
+		let mut checkpoint = None;
+		for i in 0..4 {
+			if i == 2 {
+				checkpoint = Some(rng_state.clone());
+			}
+			let results = run(&mut rng_state);
+
+			// the backward Op draws the same mask as the forward Op within an execution
+			assert_eq!(results[&grad], results[&mask].mapv(|m| m / 0.5));
+			masks.push(results[&mask].clone());
+		}
+
+		// without a state every execution of a seeded Op draws the first mask, with one each execution draws a new mask
+		assert_eq!(masks[0], mask.calc().unwrap());
+		assert_ne!(masks[0], masks[1]);
+		assert_ne!(masks[1], masks[2]);
+
+		// resuming from the checkpoint reproduces the rest of the sequence
+		let mut rng_state = checkpoint.unwrap();
+		assert_eq!(run(&mut rng_state)[&mask], masks[2]);
+		assert_eq!(run(&mut rng_state)[&mask], masks[3]);
+
+		// as does restoring the saved steps into a new state
+		let mut restored = RngState::new();
+		for (seed, step) in rng_state.steps() {
+			restored.set_step(seed, step - 2);
+		}
+		assert_eq!(run(&mut restored)[&mask], masks[2]);
+	}
+}
@@ -0,0 +1,647 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{merge_graphs, Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Array2, ArrayView2, Axis, Dimension, IxDyn, Zip};
+use std::any::Any;
+
+/// Normalises an `NCHW` (or `NC` followed by any number of spatial axes) input to zero mean and unit variance across
+/// groups of channels and their spatial extent, then applies a per-channel scale and shift.
+///
+/// The `C` channels are split into `num_groups` contiguous groups, and each group of each sample is normalised
+/// independently:
+///
+/// `let output = gamma * (input - mean) / sqrt(variance + epsilon) + beta`
+///
+/// With `num_groups == 1` this is layer normalisation over all non-batch axes, and with `num_groups == C` it is instance
+/// normalisation. `gamma` and `beta` must have shape `[C]`, and `C` must be divisible by `num_groups`.
+///
+/// The output node has the same shape as the input.
+pub fn group_norm<I1, I2, I3>(
+	input: I1,
+	num_groups: usize,
+	gamma: I2,
+	beta: I3,
+	epsilon: f32,
+) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+	I3: Into<Node>,
+{
+	let input = input.into();
+	let gamma = gamma.into();
+	let beta = beta.into();
+
+	if num_groups == 0 {
+		return Err("group_norm requires num_groups to be greater than 0".into());
+	}
+	if input.shape().len() < 2 {
+		return Err(format!(
+			"group_norm requires an input with at least 2 axes (NC followed by any spatial axes), but the input ({}) has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+	if let Some(channels) = input.shape().slice()[1].as_known() {
+		if !channels.is_multiple_of(num_groups) {
+			return Err(format!(
+				"group_norm requires the number of channels ({}) of the input ({}) to be divisible by num_groups ({})",
+				channels, input, num_groups
+			)
+			.into());
+		}
+	}
+
+	let graph = merge_graphs(&[input.graph(), gamma.graph(), beta.graph()]);
+
+	let output = graph
+		.new_node(input.shape())
+		.set_name_unique(&format!("group_norm({})", input));
+
+	GroupNorm::new(input, gamma, beta, output.clone(), num_groups)
+		.epsilon(epsilon)
+		.build()?;
+
+	Ok(output)
+}
+
+/// Returns the input shape viewed as `[N, G, C/G, S]` where `S` is the product of the spatial axes.
+fn group_shape(shape: &[usize], num_groups: usize) -> [usize; 4] {
+	[shape[0], num_groups, shape[1] / num_groups, shape[2..].iter().product()]
+}
+
+/// Returns the mean and `1/sqrt(variance + epsilon)` of a single group.
+fn group_stats(input: &ArrayView2<f32>, epsilon: f32) -> (f32, f32) {
+	let count = input.len() as f32;
+	let mean = input.sum() / count;
+	let variance = input.fold(0.0, |acc, &x| acc + (x - mean) * (x - mean)) / count;
+	(mean, 1.0 / (variance + epsilon).sqrt())
+}
+
+fn check_shapes(
+	ctx: &ShapePropContext,
+	input: &NodeID,
+	gamma: &NodeID,
+	beta: &NodeID,
+	num_groups: usize,
+) -> Result<(), ShapePropError> {
+	let input_shape = ctx.input_shape(input).slice();
+
+	if input_shape.len() < 2 {
+		return Err(format!(
+			"GroupNorm requires an input shape with at least 2 axes (NC followed by any spatial axes): {:?}",
+			input_shape
+		)
+		.into());
+	}
+
+	if !input_shape[1].is_multiple_of(num_groups) {
+		return Err(format!(
+			"GroupNorm requires the number of channels in input shape {:?} to be divisible by num_groups ({})",
+			input_shape, num_groups
+		)
+		.into());
+	}
+
+	for param in &[gamma, beta] {
+		let param_shape = ctx.input_shape(param).slice();
+		if param_shape != [input_shape[1]] {
+			return Err(format!(
+				"GroupNorm requires gamma and beta to have shape [C] matching input shape {:?}, but found {:?}",
+				input_shape, param_shape
+			)
+			.into());
+		}
+	}
+
+	Ok(())
+}
+
+/// `GroupNorm` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct GroupNorm {
+	input: Node,
+	gamma: Node,
+	beta: Node,
+	output: Node,
+	num_groups: usize,
+	epsilon: f32,
+}
+
+impl GroupNorm {
+	pub fn new<I1, I2, I3, O>(input: I1, gamma: I2, beta: I3, output: O, num_groups: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let gamma = gamma.into();
+		let beta = beta.into();
+		let output = output.into();
+		assert!(num_groups > 0, "GroupNorm num_groups must be greater than 0");
+		assert!(input.shape().len() == output.shape().len());
+		assert!(gamma.shape().len() == 1);
+		assert!(beta.shape().len() == 1);
+		GroupNorm {
+			input,
+			gamma,
+			beta,
+			output,
+			num_groups,
+			epsilon: 1e-5,
+		}
+	}
+
+	/// Added to the variance before taking the square root to avoid dividing by zero.
+	///
+	/// Default: 1e-5
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		self.epsilon = epsilon;
+		self
+	}
+}
+
+impl OpSpecification for GroupNorm {
+	type InstanceType = GroupNormInstance;
+
+	fn type_name(&self) -> &'static str {
+		"GroupNorm"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.gamma.clone(), self.beta.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			gamma: mapping.get(&self.gamma).unwrap_or(&self.gamma).clone(),
+			beta: mapping.get(&self.beta).unwrap_or(&self.beta).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(GroupNormInstance {
+			input: self.input.id(),
+			gamma: self.gamma.id(),
+			beta: self.beta.id(),
+			output: self.output.id(),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		})
+	}
+}
+
+/// GroupNorm OpInstance
+#[derive(Clone, Debug)]
+pub struct GroupNormInstance {
+	input: NodeID,
+	gamma: NodeID,
+	beta: NodeID,
+	output: NodeID,
+	num_groups: usize,
+	epsilon: f32,
+}
+
+impl OpInstance for GroupNormInstance {
+	fn type_name(&self) -> &'static str {
+		"GroupNorm"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(GroupNorm {
+			input: graph.node_from_id(self.input),
+			gamma: graph.node_from_id(self.gamma),
+			beta: graph.node_from_id(self.beta),
+			output: graph.node_from_id(self.output),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.gamma, self.beta]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		GroupNormBack::new(
+			ctx.node(&self.input),
+			ctx.grad_of(&self.input),
+			ctx.node(&self.gamma),
+			ctx.grad_of(&self.gamma),
+			ctx.grad_of(&self.beta),
+			ctx.grad_of(&self.output),
+			self.num_groups,
+		)
+		.epsilon(self.epsilon)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		check_shapes(ctx, &self.input, &self.gamma, &self.beta, self.num_groups)?;
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let epsilon = self.epsilon;
+		let shape = group_shape(ctx.shape(&self.input), self.num_groups);
+		let param_shape = [shape[1], shape[2]];
+
+		let input = ctx.get_input_standard(&self.input).into_shape(shape).unwrap();
+		let gamma = ctx.get_input_standard(&self.gamma).into_shape(param_shape).unwrap();
+		let beta = ctx.get_input_standard(&self.beta).into_shape(param_shape).unwrap();
+		let mut output = ctx.get_output(&self.output).into_shape(shape).expect(
+			"Alumina Bug: GroupNorm should be guaranteed that the reshape is valid by shape_prop and that the output is contiguous",
+		);
+
+		Zip::from(output.axis_iter_mut(Axis(0)))
+			.and(input.axis_iter(Axis(0)))
+			.par_for_each(|mut output, input| {
+				for (g, (mut output, input)) in output.outer_iter_mut().zip(input.outer_iter()).enumerate() {
+					let (mean, inv_std) = group_stats(&input, epsilon);
+					for (k, (output, input)) in output.outer_iter_mut().zip(input.outer_iter()).enumerate() {
+						let (gamma, beta) = (gamma[[g, k]], beta[[g, k]]);
+						Zip::from(output).and(input).for_each(|output, &input| {
+							*output += gamma * (input - mean) * inv_std + beta;
+						});
+					}
+				}
+			});
+
+		Ok(())
+	}
+}
+
+/// Optimised Backward pass for GroupNorm Op.
+///
+/// Input/Output naming convention matches GroupNorm Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct GroupNormBack {
+	input: Node,
+	input_grad: Node,
+	gamma: Node,
+	gamma_grad: Node,
+	beta_grad: Node,
+	output_grad: Node,
+	num_groups: usize,
+	epsilon: f32,
+}
+
+impl GroupNormBack {
+	pub fn new<I1, I2, I3, O1, O2, O3>(
+		input: I1,
+		input_grad: O1,
+		gamma: I2,
+		gamma_grad: O2,
+		beta_grad: O3,
+		output_grad: I3,
+		num_groups: usize,
+	) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+		O3: Into<Node>,
+	{
+		let input = input.into();
+		let input_grad = input_grad.into();
+		let gamma = gamma.into();
+		let gamma_grad = gamma_grad.into();
+		let beta_grad = beta_grad.into();
+		let output_grad = output_grad.into();
+		assert!(num_groups > 0, "GroupNormBack num_groups must be greater than 0");
+		assert!(input.shape().len() == input_grad.shape().len());
+		assert!(input.shape().len() == output_grad.shape().len());
+		assert!(gamma.shape().len() == 1);
+		assert!(gamma_grad.shape().len() == 1);
+		assert!(beta_grad.shape().len() == 1);
+		GroupNormBack {
+			input,
+			input_grad,
+			gamma,
+			gamma_grad,
+			beta_grad,
+			output_grad,
+			num_groups,
+			epsilon: 1e-5,
+		}
+	}
+
+	/// Added to the variance before taking the square root to avoid dividing by zero.
+	///
+	/// Default: 1e-5
+	pub fn epsilon(mut self, epsilon: f32) -> Self {
+		self.epsilon = epsilon;
+		self
+	}
+}
+
+impl OpSpecification for GroupNormBack {
+	type InstanceType = GroupNormBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"GroupNormBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.gamma.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone(), self.gamma_grad.clone(), self.beta_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			gamma: mapping.get(&self.gamma).unwrap_or(&self.gamma).clone(),
+			gamma_grad: mapping.get(&self.gamma_grad).unwrap_or(&self.gamma_grad).clone(),
+			beta_grad: mapping.get(&self.beta_grad).unwrap_or(&self.beta_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(GroupNormBackInstance {
+			input: self.input.id(),
+			input_grad: self.input_grad.id(),
+			gamma: self.gamma.id(),
+			gamma_grad: self.gamma_grad.id(),
+			beta_grad: self.beta_grad.id(),
+			output_grad: self.output_grad.id(),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		})
+	}
+}
+
+/// GroupNormBack OpInstance
+#[derive(Clone, Debug)]
+pub struct GroupNormBackInstance {
+	input: NodeID,
+	input_grad: NodeID,
+	gamma: NodeID,
+	gamma_grad: NodeID,
+	beta_grad: NodeID,
+	output_grad: NodeID,
+	num_groups: usize,
+	epsilon: f32,
+}
+
+impl OpInstance for GroupNormBackInstance {
+	fn type_name(&self) -> &'static str {
+		"GroupNormBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(GroupNormBack {
+			input: graph.node_from_id(self.input),
+			input_grad: graph.node_from_id(self.input_grad),
+			gamma: graph.node_from_id(self.gamma),
+			gamma_grad: graph.node_from_id(self.gamma_grad),
+			beta_grad: graph.node_from_id(self.beta_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			num_groups: self.num_groups,
+			epsilon: self.epsilon,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.gamma, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad, self.gamma_grad, self.beta_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		// gamma stands in for beta, which isn't an input, as they must have the same shape
+		check_shapes(ctx, &self.input, &self.gamma, &self.gamma, self.num_groups)?;
+		ctx.require_equal_shapes(&[self.input, self.output_grad])?;
+		ctx.set_output_like(&self.input_grad, &self.input)?;
+		ctx.set_output_like(&self.gamma_grad, &self.gamma)?;
+		ctx.set_output_like(&self.beta_grad, &self.gamma)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let epsilon = self.epsilon;
+		let shape = group_shape(ctx.shape(&self.input), self.num_groups);
+		let param_shape = [shape[1], shape[2]];
+		let count = (shape[2] * shape[3]) as f32;
+
+		let input = ctx.get_input_standard(&self.input).into_shape(shape).unwrap();
+		let gamma = ctx.get_input_standard(&self.gamma).into_shape(param_shape).unwrap();
+		let output_grad = ctx.get_input_standard(&self.output_grad).into_shape(shape).unwrap();
+
+		if ctx.is_required_output(&self.input_grad) {
+			let mut input_grad = ctx.get_output(&self.input_grad).into_shape(shape).expect(
+				"Alumina Bug: GroupNormBack should be guaranteed that the reshape is valid by shape_prop and that the output is contiguous",
+			);
+
+			// d(input) = inv_std * (d(normalised) - mean(d(normalised)) - normalised * mean(d(normalised) * normalised))
+			Zip::from(input_grad.axis_iter_mut(Axis(0)))
+				.and(input.axis_iter(Axis(0)))
+				.and(output_grad.axis_iter(Axis(0)))
+				.par_for_each(|mut input_grad, input, output_grad| {
+					for (g, ((mut input_grad, input), output_grad)) in input_grad
+						.outer_iter_mut()
+						.zip(input.outer_iter())
+						.zip(output_grad.outer_iter())
+						.enumerate()
+					{
+						let (mean, inv_std) = group_stats(&input, epsilon);
+
+						let mut sum = 0.0;
+						let mut sum_normalised = 0.0;
+						for (k, (input, output_grad)) in input.outer_iter().zip(output_grad.outer_iter()).enumerate() {
+							let gamma = gamma[[g, k]];
+							Zip::from(input).and(output_grad).for_each(|&input, &output_grad| {
+								let normalised_grad = output_grad * gamma;
+								sum += normalised_grad;
+								sum_normalised += normalised_grad * (input - mean) * inv_std;
+							});
+						}
+						let grad_mean = sum / count;
+						let grad_normalised_mean = sum_normalised / count;
+
+						for (k, ((input_grad, input), output_grad)) in input_grad
+							.outer_iter_mut()
+							.zip(input.outer_iter())
+							.zip(output_grad.outer_iter())
+							.enumerate()
+						{
+							let gamma = gamma[[g, k]];
+							Zip::from(input_grad).and(input).and(output_grad).for_each(
+								|input_grad, &input, &output_grad| {
+									let normalised = (input - mean) * inv_std;
+									*input_grad +=
+										inv_std * (output_grad * gamma - grad_mean - normalised * grad_normalised_mean);
+								},
+							);
+						}
+					}
+				});
+		}
+
+		let gamma_required = ctx.is_required_output(&self.gamma_grad);
+		let beta_required = ctx.is_required_output(&self.beta_grad);
+		if gamma_required || beta_required {
+			let mut gamma_grad = Array2::zeros(param_shape);
+			let mut beta_grad = Array2::zeros(param_shape);
+
+			for (input, output_grad) in input.outer_iter().zip(output_grad.outer_iter()) {
+				for (g, (input, output_grad)) in input.outer_iter().zip(output_grad.outer_iter()).enumerate() {
+					let (mean, inv_std) = group_stats(&input, epsilon);
+					for (k, (input, output_grad)) in input.outer_iter().zip(output_grad.outer_iter()).enumerate() {
+						Zip::from(input).and(output_grad).for_each(|&input, &output_grad| {
+							gamma_grad[[g, k]] += output_grad * (input - mean) * inv_std;
+							beta_grad[[g, k]] += output_grad;
+						});
+					}
+				}
+			}
+
+			if gamma_required {
+				let mut output = ctx.get_output(&self.gamma_grad);
+				output += &gamma_grad
+					.into_shape(IxDyn(&[param_shape[0] * param_shape[1]]))
+					.unwrap();
+			}
+			if beta_required {
+				let mut output = ctx.get_output(&self.beta_grad);
+				output += &beta_grad.into_shape(IxDyn(&[param_shape[0] * param_shape[1]])).unwrap();
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::group_norm;
+	use crate::tests::{contract_case, input};
+	use crate::{elementwise::mul::mul, nn::instancenorm::normalise};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr1, ArrayD};
+
+	contract_case!("group_norm", || {
+		let (x, gamma, beta) = (input("x", &[2, 4, 5, 3]), input("gamma", &[4]), input("beta", &[4]));
+		(group_norm(&x, 2, &gamma, &beta, 1e-5).unwrap(), vec![x, gamma, beta])
+	});
+
+	fn test_input(shape: &[usize]) -> ArrayD<f32> {
+		ArrayD::from_shape_fn(shape, |ix| {
+			let (n, c, h, w) = (ix[0] as f32, ix[1] as f32, ix[2] as f32, ix[3] as f32);
+			(n + 1.0) * (c - 1.5) + (h * 0.7 - w * 1.3 + c).sin() * (c + 1.0) * 3.0
+		})
+	}
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 4, 5, 3])
+			.set_name("input")
+			.set_value(test_input(&[2, 4, 5, 3]));
+		let gamma = Node::new(&[4]).set_name("gamma").set_value(arr1(&[1.0, 1.0, 1.0, 1.0]));
+		let beta = Node::new(&[4]).set_name("beta").set_value(arr1(&[0.0, 0.0, 0.0, 0.0]));
+
+		// a single group is layer normalisation over C, H, W
+		let output = group_norm(&input, 1, &gamma, &beta, 1e-5).unwrap();
+		let expected = normalise(&input, &[1, 2, 3], 1e-5).unwrap();
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&expected.calc().unwrap(), 1e-4));
+
+		// one group per channel is instance normalisation
+		let output = group_norm(&input, 4, &gamma, &beta, 1e-5).unwrap();
+		let expected = normalise(&input, &[2, 3], 1e-5).unwrap();
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&expected.calc().unwrap(), 1e-4));
+
+		// gamma and beta are applied per channel, not per group
+		gamma.set_value(arr1(&[2.0, 0.5, 1.0, -1.0]));
+		beta.set_value(arr1(&[1.0, -3.0, 0.25, 0.0]));
+		let output = group_norm(&input, 2, &gamma, &beta, 1e-5).unwrap();
+		let grouped = input.calc().unwrap().into_shape(vec![2, 2, 2, 5, 3]).unwrap();
+		let expected = normalise(&Node::from(grouped), &[2, 3, 4], 1e-5)
+			.unwrap()
+			.calc()
+			.unwrap()
+			.into_shape(vec![2, 4, 5, 3])
+			.unwrap();
+		let expected = ArrayD::from_shape_fn(vec![2, 4, 5, 3], |ix| {
+			let c = ix[1];
+			expected[&ix] * [2.0, 0.5, 1.0, -1.0][c] + [1.0, -3.0, 0.25, 0.0][c]
+		});
+		assert!(output.calc().unwrap().all_relatively_close(&expected, 1e-4));
+	}
+
+	#[test]
+	fn divisibility_test() {
+		let input = Node::new(&[2, 6, 5, 3]).set_name("input");
+		let gamma = Node::new(&[6]).set_name("gamma");
+		let beta = Node::new(&[6]).set_name("beta");
+
+		assert!(group_norm(&input, 4, &gamma, &beta, 1e-5).is_err());
+		assert!(group_norm(&input, 0, &gamma, &beta, 1e-5).is_err());
+		assert!(group_norm(&input, 3, &gamma, &beta, 1e-5).is_ok());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[3, 6, 5, 4]).set_name("input");
+		let gamma = Node::new(&[6]).set_name("gamma").set_init(uniform(0.5, 1.5));
+		let beta = Node::new(&[6]).set_name("beta");
+
+		// the plain sum of the output doesn't depend on the input, so weight it
+		let weights = Node::new(&[3, 6, 5, 4])
+			.set_name("weights")
+			.set_value(ArrayD::from_shape_fn(vec![3, 6, 5, 4], |ix| {
+				((ix[0] * 7 + ix[1] * 5 + ix[2] * 3 + ix[3]) as f32).sin()
+			}));
+
+		let output = mul(group_norm(&input, 3, &gamma, &beta, 1e-3).unwrap(), &weights).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input, &gamma, &beta])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -0,0 +1,509 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayD, ArrayViewD, Axis, Dimension, Zip};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::any::Any;
+
+/// Draws a differentiable sample from the categorical distribution given by the logits, grouped along the innermost
+/// axis.
+///
+/// Gumbel noise is added to the logits, which are then divided by the temperature `tau` and passed through a softmax.
+/// Lower temperatures give samples closer to one-hot.
+///
+/// If `hard` is true the output is the one-hot argmax of the soft sample, and the gradient is that of the soft
+/// sample (a straight-through estimator).
+///
+/// The noise is determined by a seed chosen when the Op is built, and is the same for every execution unless an
+/// `RngState` is passed to `ExecutionPlan::rng_state(..)`, in which case new noise is drawn for each execution. To set
+/// the seed use `GumbelSoftmax::seed(..)`.
+pub fn gumbel_softmax<I>(logits: I, tau: f32, hard: bool) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let logits = logits.into();
+	let axis = logits.shape().len().saturating_sub(1);
+
+	let output = logits
+		.graph()
+		.new_node(logits.shape())
+		.set_name_unique(&format!("gumbel_softmax({})", logits));
+
+	GumbelSoftmax::new(logits, output.clone(), axis)
+		.tau(tau)
+		.hard(hard)
+		.build()?;
+
+	Ok(output)
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct GumbelSoftmax {
+	logits: Node,
+	output: Node,
+	axis: usize,
+	tau: f32,
+	hard: bool,
+	seed: u64,
+}
+
+impl GumbelSoftmax {
+	pub fn new<I, O>(logits: I, output: O, axis: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let logits = logits.into();
+		let output = output.into();
+		assert!(
+			logits.shape().len() == output.shape().len(),
+			"output and logits must have the same shape"
+		);
+		assert!(
+			axis < logits.shape().len(),
+			"axis {} must be less than logits.shape().len() {}",
+			axis,
+			logits.shape().len()
+		);
+		GumbelSoftmax {
+			logits,
+			output,
+			axis,
+			tau: 1.0,
+			hard: false,
+			seed: thread_rng().gen(),
+		}
+	}
+
+	/// Temperature which the noisy logits are divided by before the softmax.
+	///
+	/// Default: 1.0
+	pub fn tau(mut self, tau: f32) -> Self {
+		assert!(tau > 0.0, "tau {} must be greater than zero", tau);
+		self.tau = tau;
+		self
+	}
+
+	/// Whether to output the one-hot argmax of each sample rather than the soft sample.
+	///
+	/// Default: false
+	pub fn hard(mut self, hard: bool) -> Self {
+		self.hard = hard;
+		self
+	}
+
+	/// Seed for the Gumbel noise.
+	///
+	/// Default: random
+	pub fn seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self
+	}
+}
+
+impl OpSpecification for GumbelSoftmax {
+	type InstanceType = GumbelSoftmaxInstance;
+
+	fn type_name(&self) -> &'static str {
+		"GumbelSoftmax"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.logits.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			tau: self.tau,
+			hard: self.hard,
+			seed: self.seed,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(GumbelSoftmaxInstance {
+			logits: self.logits.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			tau: self.tau,
+			hard: self.hard,
+			seed: self.seed,
+		})
+	}
+}
+
+/// GumbelSoftmax OpInstance
+#[derive(Clone, Debug)]
+pub struct GumbelSoftmaxInstance {
+	logits: NodeID,
+	output: NodeID,
+	axis: usize,
+	tau: f32,
+	hard: bool,
+	seed: u64,
+}
+
+impl OpInstance for GumbelSoftmaxInstance {
+	fn type_name(&self) -> &'static str {
+		"GumbelSoftmax"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(GumbelSoftmax {
+			logits: graph.node_from_id(self.logits),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			tau: self.tau,
+			hard: self.hard,
+			seed: self.seed,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// straight-through, the hard output uses the gradient of the soft sample
+		GumbelSoftmaxBack::new(
+			ctx.node(&self.logits),
+			ctx.grad_of(&self.logits),
+			ctx.grad_of(&self.output),
+			self.axis,
+		)
+		.tau(self.tau)
+		.seed(self.seed)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.merge_output_shape(&self.output, &ctx.input_shape(&self.logits).slice().into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let seed = ctx.seed(self.seed);
+		let samples = soft_samples(ctx.get_input(&self.logits).view(), self.axis, self.tau, seed);
+
+		if self.hard {
+			Zip::from(samples.lanes(Axis(self.axis)))
+				.and(ctx.get_output(&self.output).lanes_mut(Axis(self.axis)))
+				.par_for_each(|samples, mut outputs| {
+					let (argmax, _) =
+						samples
+							.iter()
+							.enumerate()
+							.fold(
+								(0, f32::NEG_INFINITY),
+								|(i_max, max), (i, &v)| {
+									if v > max {
+										(i, v)
+									} else {
+										(i_max, max)
+									}
+								},
+							);
+					outputs[argmax] += 1.0;
+				});
+		} else {
+			Zip::from(&samples)
+				.and(ctx.get_output(&self.output))
+				.par_for_each(|&sample, output| *output += sample);
+		}
+
+		Ok(())
+	}
+}
+
+/// Backward pass for GumbelSoftmax Op, the Jacobian of the soft sample.
+///
+/// Input/Output naming convention matches GumbelSoftmax Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The tau and seed must match the GumbelSoftmax Op so that the same noise is regenerated.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct GumbelSoftmaxBack {
+	logits: Node,
+	logits_grad: Node,
+	output_grad: Node,
+	axis: usize,
+	tau: f32,
+	seed: u64,
+}
+
+impl GumbelSoftmaxBack {
+	pub fn new<I1, I2, O>(logits: I1, logits_grad: O, output_grad: I2, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let logits = logits.into();
+		let logits_grad = logits_grad.into();
+		let output_grad = output_grad.into();
+		assert!(logits.shape().len() == logits_grad.shape().len());
+		assert!(logits.shape().len() == output_grad.shape().len());
+		assert!(
+			axis < logits.shape().len(),
+			"axis {} must be less than logits.shape().len() {}",
+			axis,
+			logits.shape().len()
+		);
+		GumbelSoftmaxBack {
+			logits,
+			logits_grad,
+			output_grad,
+			axis,
+			tau: 1.0,
+			seed: 0,
+		}
+	}
+
+	/// Temperature used by the GumbelSoftmax Op.
+	///
+	/// Default: 1.0
+	pub fn tau(mut self, tau: f32) -> Self {
+		assert!(tau > 0.0, "tau {} must be greater than zero", tau);
+		self.tau = tau;
+		self
+	}
+
+	/// Seed used by the GumbelSoftmax Op.
+	///
+	/// Default: 0
+	pub fn seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self
+	}
+}
+
+impl OpSpecification for GumbelSoftmaxBack {
+	type InstanceType = GumbelSoftmaxBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"GumbelSoftmaxBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.logits.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.logits_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			logits_grad: mapping.get(&self.logits_grad).unwrap_or(&self.logits_grad).clone(),
+			axis: self.axis,
+			tau: self.tau,
+			seed: self.seed,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(GumbelSoftmaxBackInstance {
+			logits: self.logits.id(),
+			logits_grad: self.logits_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+			tau: self.tau,
+			seed: self.seed,
+		})
+	}
+}
+
+/// GumbelSoftmaxBack OpInstance
+#[derive(Clone, Debug)]
+pub struct GumbelSoftmaxBackInstance {
+	logits: NodeID,
+	logits_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+	tau: f32,
+	seed: u64,
+}
+
+impl OpInstance for GumbelSoftmaxBackInstance {
+	fn type_name(&self) -> &'static str {
+		"GumbelSoftmaxBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(GumbelSoftmaxBack {
+			logits: graph.node_from_id(self.logits),
+			logits_grad: graph.node_from_id(self.logits_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+			tau: self.tau,
+			seed: self.seed,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.logits_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let logits_shape = ctx.input_shape(&self.logits).clone();
+		let output_grad_shape = ctx.input_shape(&self.output_grad).clone();
+
+		if output_grad_shape != logits_shape {
+			return Err(format!(
+				"GumbelSoftmaxBack requires the output grad to have the shape of the logits: logits:{:?} output_grad:{:?}",
+				logits_shape.slice(),
+				output_grad_shape.slice()
+			)
+			.into());
+		}
+
+		ctx.merge_output_shape(&self.logits_grad, &logits_shape.slice().into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let tau = self.tau;
+		let seed = ctx.seed(self.seed);
+		let samples = soft_samples(ctx.get_input(&self.logits).view(), self.axis, self.tau, seed);
+
+		Zip::from(ctx.get_output(&self.logits_grad).lanes_mut(Axis(self.axis)))
+			.and(samples.lanes(Axis(self.axis)))
+			.and(ctx.get_input(&self.output_grad).lanes(Axis(self.axis)))
+			.par_for_each(|logits_grad, samples, output_grad| {
+				let dot = samples.iter().zip(&output_grad).fold(0.0, |sum, (&y, &g)| sum + y * g);
+
+				Zip::from(logits_grad)
+					.and(&samples)
+					.and(&output_grad)
+					.for_each(|logits_grad, &y, &g| *logits_grad += y * (g - dot) / tau);
+			});
+
+		Ok(())
+	}
+}
+
+/// Returns `softmax((logits + gumbel_noise) / tau)` along the axis.
+///
+/// The noise is generated serially in standard order so that it depends only on the seed and shape.
+fn soft_samples(logits: ArrayViewD<f32>, axis: usize, tau: f32, seed: u64) -> ArrayD<f32> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let mut samples = ArrayD::from_shape_simple_fn(logits.raw_dim(), || {
+		let u: f32 = rng.gen_range(f32::MIN_POSITIVE..1.0);
+		-(-u.ln()).ln()
+	});
+
+	Zip::from(&mut samples)
+		.and(&logits)
+		.for_each(|sample, &logit| *sample = (*sample + logit) / tau);
+
+	Zip::from(samples.lanes_mut(Axis(axis))).par_for_each(|mut lane| {
+		let max = lane.iter().fold(f32::NEG_INFINITY, |max, &v| v.max(max));
+		lane.mapv_inplace(|v| (v - max).exp());
+		let exp_sum = lane.sum();
+		lane.mapv_inplace(|v| v / exp_sum);
+	});
+
+	samples
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{gumbel_softmax, GumbelSoftmax};
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr0, arr1, Array2, Axis};
+
+	contract_case!(unary "gumbel_softmax", |x| gumbel_softmax(x, 0.5, false));
+
+	#[test]
+	fn forward_test() {
+		let logits = [0.5f32, 1.0, 2.0, -1.0];
+		let rows = 20_000;
+		let input = Node::new(&[-1, 4])
+			.set_value(Array2::from_shape_fn((rows, 4), |(_, j)| logits[j]))
+			.set_name("logits");
+
+		// the Gumbel-max trick samples in proportion to softmax(logits), regardless of temperature
+		let exp_sum: f32 = logits.iter().map(|v| v.exp()).sum();
+		let expected = arr1(&logits).mapv(|v| v.exp() / exp_sum);
+
+		let hard = gumbel_softmax(&input, 0.5, true).unwrap().calc().unwrap();
+		assert!(hard.iter().all(|&v| v == 0.0 || v == 1.0));
+		assert!(hard.sum_axis(Axis(1)).all_relatively_close(&arr0(1.0), f32::EPSILON));
+
+		let frequencies = hard.mean_axis(Axis(0)).unwrap();
+		for (frequency, expected) in frequencies.iter().zip(&expected) {
+			assert!(
+				(frequency - expected).abs() < 0.02,
+				"frequencies {} expected {}",
+				frequencies,
+				expected
+			);
+		}
+
+		let soft = gumbel_softmax(&input, 0.5, false).unwrap().calc().unwrap();
+		assert!(soft.iter().all(|&v| (0.0..=1.0).contains(&v)));
+		assert!(soft.sum_axis(Axis(1)).all_relatively_close(&arr0(1.0), 1e-5));
+	}
+
+	#[test]
+	fn seed_test() {
+		let logits = Node::new(&[7, 5]).set_name("logits").set_value(arr0(0.3));
+
+		let output = |seed| {
+			let output = logits.graph().new_node(logits.shape());
+			GumbelSoftmax::new(&logits, &output, 1)
+				.tau(0.7)
+				.seed(seed)
+				.build()
+				.unwrap();
+			output.calc().unwrap()
+		};
+
+		assert_eq!(output(3), output(3));
+		assert_ne!(output(3), output(4));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits");
+		let rand = Node::new(&[13, 33]).set_name("rand"); // multiply output by random amounts to prevent gradient cancellation
+
+		let output = mul(&gumbel_softmax(&logits, 2.0, false).unwrap(), &rand).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits, &rand])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -0,0 +1,141 @@
+use crate::{
+	elementwise::{identity::add, mul::mul, offset::offset, reciprocal::reciprocal, sqrt::sqrt, subtract::subtract},
+	manip::expand_dims::expand_dims,
+	math::broadcast::broadcast_fn,
+	reduce::moments::moments,
+};
+use alumina_core::{errors::OpBuildError, graph::Node};
+
+/// Normalises each sample-channel of an `NCHW` (or `NC` followed by any number of spatial axes) input to zero mean and
+/// unit variance across its spatial extent, then applies a per-channel scale and shift.
+///
+/// `let output = gamma * (input - mean) / sqrt(variance + epsilon) + beta`
+///
+/// where `mean` and `variance` are calculated with `moments(..)` over the spatial axes, independently for each sample
+/// and channel. `gamma` and `beta` must have shape `[C]`.
+///
+/// The output node has the same shape as the input.
+pub fn instance_norm<I1, I2, I3>(input: I1, gamma: I2, beta: I3, epsilon: f32) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+	I3: Into<Node>,
+{
+	let input = input.into();
+	let gamma = gamma.into();
+	let beta = beta.into();
+
+	let len = input.shape().len();
+	if len < 3 {
+		return Err(format!(
+			"instance_norm requires an input with at least 3 axes (NC followed by spatial axes), but the input ({}) has shape {}",
+			input,
+			input.shape()
+		)
+		.into());
+	}
+	for param in &[&gamma, &beta] {
+		if param.shape().len() != 1 {
+			return Err(format!(
+				"instance_norm requires gamma and beta to have shape [C], but ({}) has shape {}",
+				param,
+				param.shape()
+			)
+			.into());
+		}
+	}
+
+	let spatial_axes: Vec<isize> = (2..len as isize).collect();
+	let normalised = normalise(&input, &spatial_axes, epsilon)?;
+
+	// place the channel axis at position 1 so gamma and beta broadcast across samples and spatial axes
+	let param_axes: Vec<usize> = (0..len).filter(|&axis| axis != 1).collect();
+	let scaled = broadcast_fn(mul, normalised, expand_dims(gamma, &param_axes)?)?;
+	let output = broadcast_fn(add, scaled, expand_dims(beta, &param_axes)?)?
+		.set_name_unique(&format!("instance_norm({})", input));
+
+	Ok(output)
+}
+
+/// Returns `(input - mean) / sqrt(variance + epsilon)`, with the moments calculated across the given axes.
+pub(crate) fn normalise(input: &Node, axes: &[isize], epsilon: f32) -> Result<Node, OpBuildError> {
+	let (mean, variance) = moments(input, axes, true)?;
+	let inv_std = reciprocal(sqrt(offset(variance, epsilon)?)?)?;
+	let centred = broadcast_fn(subtract, input, mean)?;
+	broadcast_fn(mul, centred, inv_std)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::instance_norm;
+	use crate::tests::{contract_case, input};
+	use crate::{elementwise::mul::mul, reduce::moments::moments};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr1, ArrayD};
+
+	contract_case!("instance_norm", || {
+		let (x, gamma, beta) = (input("x", &[2, 3, 4, 5]), input("gamma", &[3]), input("beta", &[3]));
+		(instance_norm(&x, &gamma, &beta, 1e-5).unwrap(), vec![x, gamma, beta])
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 3, 5, 4])
+			.set_name("input")
+			.set_value(ArrayD::from_shape_fn(vec![2, 3, 5, 4], |ix| {
+				let (n, c, h, w) = (ix[0] as f32, ix[1] as f32, ix[2] as f32, ix[3] as f32);
+				(n + 1.0) * (c - 1.5) + (h * 0.7 - w * 1.3).sin() * (c + 1.0) * 3.0
+			}));
+		let gamma = Node::new(&[3]).set_name("gamma").set_value(arr1(&[1.0, 1.0, 1.0]));
+		let beta = Node::new(&[3]).set_name("beta").set_value(arr1(&[0.0, 0.0, 0.0]));
+
+		let output = instance_norm(&input, &gamma, &beta, 1e-5).unwrap();
+		assert_eq!(output.shape(), input.shape());
+
+		let (mean, variance) = moments(&output, &[2, 3], false).unwrap();
+		assert!(mean
+			.calc()
+			.unwrap()
+			.all_relatively_close(&ArrayD::zeros(vec![2, 3]), 1e-5));
+		assert!(variance
+			.calc()
+			.unwrap()
+			.all_relatively_close(&ArrayD::from_elem(vec![2, 3], 1.0), 1e-3));
+
+		// gamma and beta set the per channel scale and shift
+		gamma.set_value(arr1(&[2.0, 0.5, 1.0]));
+		beta.set_value(arr1(&[1.0, -3.0, 0.25]));
+		let (mean, variance) = moments(&output, &[0, 2, 3], false).unwrap();
+		assert!(mean
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[1.0, -3.0, 0.25]), 1e-5));
+		assert!(variance
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[4.0, 0.25, 1.0]), 1e-3));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[3, 4, 5, 6]).set_name("input");
+		let gamma = Node::new(&[4]).set_name("gamma").set_init(uniform(0.5, 1.5));
+		let beta = Node::new(&[4]).set_name("beta");
+
+		// the plain sum of the output doesn't depend on the input, so weight it
+		let weights = Node::new(&[3, 4, 5, 6])
+			.set_name("weights")
+			.set_value(ArrayD::from_shape_fn(vec![3, 4, 5, 6], |ix| {
+				((ix[0] * 7 + ix[1] * 5 + ix[2] * 3 + ix[3]) as f32).sin()
+			}));
+
+		let output = mul(instance_norm(&input, &gamma, &beta, 1e-3).unwrap(), &weights).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input, &gamma, &beta])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
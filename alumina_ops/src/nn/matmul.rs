@@ -338,6 +338,10 @@ impl OpInstance for MatMulInstance {
 		indexset![self.matrix_a, self.matrix_b]
 	}
 
+	fn ordered_inputs(&self) -> Vec<NodeID> {
+		vec![self.matrix_a, self.matrix_b]
+	}
+
 	fn outputs(&self) -> IndexSet<NodeID> {
 		indexset![self.matrix_c]
 	}
@@ -727,12 +731,18 @@ fn get_outer(inner: usize, outer: Option<usize>, shape: &[usize], trans: bool) -
 
 #[cfg(test)]
 mod tests {
-	use super::MatMul;
+	use super::{matmul, MatMul};
 	use crate::elementwise::identity::Identity;
+	use crate::tests::{contract_case, input};
 	use alumina_core::{base_ops::OpSpecification, graph::Node};
 	use alumina_test::grad_numeric_test::GradNumericTest;
 	use indexmap::indexset;
 
+	contract_case!("matmul", || {
+		let (x, z) = (input("x", &[4, 5]), input("z", &[5, 3]));
+		(matmul(&x, &z).unwrap(), vec![x, z])
+	});
+
 	#[test]
 	fn grad_numeric_test() {
 		let input1 = Node::new(&[7, 5]).set_name("input1");
@@ -1,5 +1,13 @@
+pub mod causal_mask;
 pub mod conv;
+pub mod cosine;
+pub mod dropout;
+pub mod groupnorm;
+pub mod gumbel_softmax;
+pub mod instancenorm;
 pub mod matmul;
 pub mod softmax;
 pub mod softmax_cross_entropy;
+pub mod spectralnorm;
 pub mod spline;
+pub mod weightnorm;
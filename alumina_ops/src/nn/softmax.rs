@@ -8,7 +8,7 @@ use alumina_core::{
 	util::wrap_dim,
 };
 use indexmap::{indexset, IndexMap, IndexSet};
-use ndarray::{Axis, Dimension, Zip};
+use ndarray::{ArrayView1, Axis, Dimension, Zip};
 use std::any::Any;
 
 /// Calculates the combined Softmax norm of the input nodes.
@@ -28,12 +28,34 @@ where
 	Ok(output)
 }
 
+/// Calculates the combined Softmax norm of the input nodes, after dividing the logits by the temperature `tau`.
+///
+/// Higher temperatures give flatter distributions, and lower temperatures give sharper distributions. A temperature
+/// of 1.0 is equivalent to `softmax(..)`.
+///
+/// Axis determines the grouping direction.
+pub fn softmax_with_temperature<I>(logits: I, axis: isize, tau: f32) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let logits = logits.into();
+	let axis = wrap_dim(axis, logits.shape().len());
+
+	let output = logits.graph().new_node(logits.shape());
+
+	Softmax::new(logits, output.clone(), axis).tau(tau).build()?;
+
+	Ok(output)
+}
+
 #[must_use = "Op builder not used, call .build()"]
 #[derive(Clone, Debug)]
 pub struct Softmax {
 	logits: Node,
 	output: Node,
 	axis: usize,
+	tau: f32,
+	online: bool,
 }
 
 impl Softmax {
@@ -54,7 +76,32 @@ impl Softmax {
 			axis,
 			logits.shape().len()
 		);
-		Softmax { logits, output, axis }
+		Softmax {
+			logits,
+			output,
+			axis,
+			tau: 1.0,
+			online: false,
+		}
+	}
+
+	/// Temperature which the logits are divided by before the exponential.
+	///
+	/// Default: 1.0
+	pub fn tau(mut self, tau: f32) -> Self {
+		assert!(tau > 0.0, "tau {} must be greater than zero", tau);
+		self.tau = tau;
+		self
+	}
+
+	/// If true, the max and the sum of exponentials of each group are found together in a single pass over the
+	/// logits, rescaling the running sum whenever the running max increases. This saves a pass over each group, which
+	/// helps when groups are too long to stay in cache. Results match the default path to within rounding.
+	///
+	/// Default: false
+	pub fn online(mut self, online: bool) -> Self {
+		self.online = online;
+		self
 	}
 }
 
@@ -78,6 +125,8 @@ impl OpSpecification for Softmax {
 			logits: mapping.get(&self.logits).unwrap_or(&self.logits).clone(),
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
 			axis: self.axis,
+			tau: self.tau,
+			online: self.online,
 		}
 	}
 
@@ -86,6 +135,8 @@ impl OpSpecification for Softmax {
 			logits: self.logits.id(),
 			output: self.output.id(),
 			axis: self.axis,
+			tau: self.tau,
+			online: self.online,
 		})
 	}
 }
@@ -96,6 +147,8 @@ pub struct SoftmaxInstance {
 	logits: NodeID,
 	output: NodeID,
 	axis: usize,
+	tau: f32,
+	online: bool,
 }
 
 impl OpInstance for SoftmaxInstance {
@@ -108,6 +161,8 @@ impl OpInstance for SoftmaxInstance {
 			logits: graph.node_from_id(self.logits),
 			output: graph.node_from_id(self.output),
 			axis: self.axis,
+			tau: self.tau,
+			online: self.online,
 		})
 	}
 
@@ -126,6 +181,7 @@ impl OpInstance for SoftmaxInstance {
 			ctx.grad_of(&self.output),
 			self.axis,
 		)
+		.tau(self.tau)
 		.build()?;
 		Ok(())
 	}
@@ -135,14 +191,20 @@ impl OpInstance for SoftmaxInstance {
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let tau = self.tau;
+		let online = self.online;
 		Zip::from(ctx.get_input(&self.logits).lanes(Axis(self.axis)))
 			.and(ctx.get_output(&self.output).lanes_mut(Axis(self.axis)))
 			.par_for_each(|logits, outputs| {
-				let max = logits.iter().fold(::std::f32::NEG_INFINITY, |max, &v| v.max(max));
-				let exp_sum = logits.iter().fold(0.0, |sum, &v| sum + (v - max).exp());
+				let (max, exp_sum) = if online {
+					online_max_exp_sum(logits, tau)
+				} else {
+					let max = logits.iter().fold(::std::f32::NEG_INFINITY, |max, &v| v.max(max));
+					(max, logits.iter().fold(0.0, |sum, &v| sum + ((v - max) / tau).exp()))
+				};
 
 				Zip::from(logits).and(outputs).for_each(|logit, output| {
-					*output += (logit - max).exp() / exp_sum;
+					*output += ((logit - max) / tau).exp() / exp_sum;
 				});
 			});
 
@@ -150,6 +212,21 @@ impl OpInstance for SoftmaxInstance {
 	}
 }
 
+/// Returns the max of the logits, and the sum of `((logit - max) / tau).exp()`, in a single pass.
+///
+/// `-inf` logits contribute nothing to the sum, so that masked groups give the same result as the two pass approach.
+fn online_max_exp_sum(logits: ArrayView1<f32>, tau: f32) -> (f32, f32) {
+	logits.iter().fold((::std::f32::NEG_INFINITY, 0.0), |(max, sum), &v| {
+		if v > max {
+			(v, sum * ((max - v) / tau).exp() + 1.0)
+		} else if v == ::std::f32::NEG_INFINITY {
+			(max, sum)
+		} else {
+			(max, sum + ((v - max) / tau).exp())
+		}
+	})
+}
+
 /// Optimised Backward pass for Softmax Op.
 ///
 /// Input/Output naming convention matches Softmax Input/Outputs, i.e. output_grad is an input to this Op.
@@ -162,6 +239,7 @@ pub struct SoftmaxBack {
 	logits_grad: Node,
 	output_grad: Node,
 	axis: usize,
+	tau: f32,
 }
 
 impl SoftmaxBack {
@@ -187,8 +265,18 @@ impl SoftmaxBack {
 			logits_grad,
 			output_grad,
 			axis,
+			tau: 1.0,
 		}
 	}
+
+	/// Temperature which the logits were divided by in the Softmax Op.
+	///
+	/// Default: 1.0
+	pub fn tau(mut self, tau: f32) -> Self {
+		assert!(tau > 0.0, "tau {} must be greater than zero", tau);
+		self.tau = tau;
+		self
+	}
 }
 
 impl OpSpecification for SoftmaxBack {
@@ -212,6 +300,7 @@ impl OpSpecification for SoftmaxBack {
 			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
 			logits_grad: mapping.get(&self.logits_grad).unwrap_or(&self.logits_grad).clone(),
 			axis: self.axis,
+			tau: self.tau,
 		}
 	}
 
@@ -221,6 +310,7 @@ impl OpSpecification for SoftmaxBack {
 			logits_grad: self.logits_grad.id(),
 			output_grad: self.output_grad.id(),
 			axis: self.axis,
+			tau: self.tau,
 		})
 	}
 }
@@ -232,6 +322,7 @@ pub struct SoftmaxBackInstance {
 	logits_grad: NodeID,
 	output_grad: NodeID,
 	axis: usize,
+	tau: f32,
 }
 
 impl OpInstance for SoftmaxBackInstance {
@@ -245,6 +336,7 @@ impl OpInstance for SoftmaxBackInstance {
 			logits_grad: graph.node_from_id(self.logits_grad),
 			output_grad: graph.node_from_id(self.output_grad),
 			axis: self.axis,
+			tau: self.tau,
 		})
 	}
 
@@ -280,6 +372,7 @@ impl OpInstance for SoftmaxBackInstance {
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let tau = self.tau;
 		Zip::from(ctx.get_output(&self.logits_grad).lanes_mut(Axis(self.axis)))
 			.and(ctx.get_input(&self.logits).lanes(Axis(self.axis)))
 			.and(ctx.get_input(&self.output_grad).lanes(Axis(self.axis)))
@@ -287,22 +380,23 @@ impl OpInstance for SoftmaxBackInstance {
 				let len = logits.len();
 
 				let max = logits.iter().fold(::std::f32::NEG_INFINITY, |max, &v| v.max(max));
-				let exp_sum = logits.iter().fold(0., |sum, &v| sum + (v - max).exp());
+				let exp_sum = logits.iter().fold(0., |sum, &v| sum + ((v - max) / tau).exp());
 				// let exp_sum_ln = exp_sum.ln();
 
 				for (i, grad) in output_grad.iter().enumerate() {
 					if grad.abs() > 0.0 {
 						// hopefully output gradients are sparse, eg from cross entropy loss
 
-						let a = logits[i] - max;
+						let a = (logits[i] - max) / tau;
 						// let x = (a - exp_sum_ln).exp();
 						let x = a.exp() / exp_sum;
-						let g_x = grad * x;
+						// the scaling of the logits by 1/tau also scales their gradient
+						let g_x = grad * x / tau;
 
 						let mut other_sum = 0.0;
 
 						for j in 0..i {
-							let b = logits[j] - max;
+							let b = (logits[j] - max) / tau;
 							// logits_grad[j] -= g_x * (b - exp_sum_ln).exp();
 							logits_grad[j] -= g_x * b.exp() / exp_sum;
 							other_sum += b.exp() / exp_sum;
@@ -314,7 +408,7 @@ impl OpInstance for SoftmaxBackInstance {
 						// {(v-max).exp()} else {0.0})*(mult/exp_sum);
 
 						for j in i + 1..len {
-							let b = logits[j] - max;
+							let b = (logits[j] - max) / tau;
 							// logits_grad[j] -= g_x * (b- exp_sum_ln).exp();
 							logits_grad[j] -= g_x * b.exp() / exp_sum;
 							other_sum += b.exp() / exp_sum;
@@ -331,12 +425,15 @@ impl OpInstance for SoftmaxBackInstance {
 
 #[cfg(test)]
 mod tests {
-	use super::softmax;
+	use super::{softmax, softmax_with_temperature, Softmax};
 	use crate::elementwise::mul::mul;
-	use alumina_core::graph::Node;
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 	use indexmap::indexset;
-	use ndarray::arr2;
+	use ndarray::{arr0, arr2, Axis};
+
+	contract_case!(unary "softmax", |x| softmax(x, -1));
 
 	#[test]
 	fn forward_test() {
@@ -363,6 +460,43 @@ mod tests {
 		));
 	}
 
+	#[test]
+	fn online_forward_test() {
+		// long enough that the running max increases many times along each group
+		let logits = Node::new(&[3, 100_000]).set_name("logits").set_random(-20.0, 20.0, 0);
+		let two_pass = Node::new(&[3, 100_000]).set_name("two_pass");
+		let online = Node::new(&[3, 100_000]).set_name("online");
+
+		Softmax::new(&logits, &two_pass, 1).tau(0.5).build().unwrap();
+		Softmax::new(&logits, &online, 1).tau(0.5).online(true).build().unwrap();
+
+		let two_pass = two_pass.calc().unwrap();
+		let online = online.calc().unwrap();
+		assert!(online.sum_axis(Axis(1)).all_relatively_close(&arr0(1.0), 1e-4));
+		assert!(online.all_relatively_close(&two_pass, 1e-4));
+	}
+
+	#[test]
+	fn online_masked_forward_test() {
+		let logits = Node::new(&[3, 4])
+			.set_value(arr2(&[
+				[::std::f32::NEG_INFINITY, 0.4, ::std::f32::NEG_INFINITY, 0.8],
+				[1.2, ::std::f32::NEG_INFINITY, 1.6, 1.8],
+				[2.2, 2.4, 2.6, ::std::f32::NEG_INFINITY],
+			]))
+			.set_name("logits");
+		let two_pass = Node::new(&[3, 4]).set_name("two_pass");
+		let online = Node::new(&[3, 4]).set_name("online");
+
+		Softmax::new(&logits, &two_pass, 1).build().unwrap();
+		Softmax::new(&logits, &online, 1).online(true).build().unwrap();
+
+		assert!(online
+			.calc()
+			.unwrap()
+			.all_relatively_close(&two_pass.calc().unwrap(), 1e-6));
+	}
+
 	#[test]
 	fn grad_numeric_rand_test() {
 		let logits = Node::new(&[13, 33]).set_name("logits");
@@ -388,4 +522,64 @@ mod tests {
 			.tolerance(4e-3)
 			.run();
 	}
+
+	#[test]
+	fn temperature_forward_test() {
+		let logits = Node::new(&[2, 4])
+			.set_value(arr2(&[[0.2, 0.4, 0.6, 0.8], [-1.0, 3.0, 0.5, 2.0]]))
+			.set_name("logits");
+		let scaled_logits = Node::new(&[2, 4])
+			.set_value(arr2(&[[0.08, 0.16, 0.24, 0.32], [-0.4, 1.2, 0.2, 0.8]]))
+			.set_name("scaled_logits");
+
+		let spread = |tau: f32| {
+			let output = softmax_with_temperature(&logits, -1, tau).unwrap().calc().unwrap();
+			assert!(output.sum_axis(Axis(1)).all_relatively_close(&arr0(1.0), 1e-5));
+			output.fold_axis(Axis(1), 0.0f32, |&m, &v| m.max(v)) - output.fold_axis(Axis(1), 1.0f32, |&m, &v| m.min(v))
+		};
+
+		// higher temperatures flatten the distribution
+		let (cold, normal, hot) = (spread(0.5), spread(1.0), spread(4.0));
+		for i in 0..2 {
+			assert!(cold[i] > normal[i], "{} {}", cold, normal);
+			assert!(normal[i] > hot[i], "{} {}", normal, hot);
+		}
+
+		assert!(softmax_with_temperature(&logits, -1, 2.5)
+			.unwrap()
+			.calc()
+			.unwrap()
+			.all_relatively_close(&softmax(&scaled_logits, -1).unwrap().calc().unwrap(), 1e-5));
+		assert!(softmax_with_temperature(&logits, -1, 1.0)
+			.unwrap()
+			.calc()
+			.unwrap()
+			.all_relatively_close(&softmax(&logits, -1).unwrap().calc().unwrap(), 1e-6));
+	}
+
+	#[test]
+	fn temperature_grad_numeric_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits");
+		let rand = Node::new(&[13, 33]).set_name("rand"); // multiply output by random amounts to prevent gradient cancellation
+
+		let output = mul(&softmax_with_temperature(&logits, -1, 2.5).unwrap(), &rand).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits, &rand])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn temperature_grad_numeric_sharp_test() {
+		let logits = Node::new(&[13, 33]).set_name("logits");
+		let rand = Node::new(&[13, 33]).set_name("rand");
+
+		let output = mul(&softmax_with_temperature(&logits, 0, 0.4).unwrap(), &rand).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&logits, &rand])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
 }
@@ -18,6 +18,8 @@ use std::any::Any;
 ///
 /// `let output = reduce_sum(mul(labels, negative(ln(softmax(logits, axis)))), &[axis])`
 ///
+/// Label smoothing can be enabled by building `SoftmaxCrossEntropy` directly with `.label_smoothing(..)`.
+///
 /// The output node has the shape of the logits and labels, but with the axis removed.
 pub fn softmax_cross_entropy<I1, I2>(logits: I1, labels: I2, axis: isize) -> Result<Node, OpBuildError>
 where
@@ -53,6 +55,7 @@ pub struct SoftmaxCrossEntropy {
 	labels: Node,
 	output: Node,
 	axis: usize,
+	label_smoothing: f32,
 }
 
 impl SoftmaxCrossEntropy {
@@ -79,8 +82,26 @@ impl SoftmaxCrossEntropy {
 			labels,
 			output,
 			axis,
+			label_smoothing: 0.0,
 		}
 	}
+
+	/// Mixes the labels with a uniform distribution across the axis before calculating the cross entropy:
+	///
+	/// `labels * (1 - label_smoothing) + label_smoothing / logits.shape()[axis]`
+	///
+	/// Must be in the range [0, 1].
+	///
+	/// Default: 0.0
+	pub fn label_smoothing(mut self, label_smoothing: f32) -> Self {
+		assert!(
+			(0.0..=1.0).contains(&label_smoothing),
+			"label_smoothing {} must be in the range [0, 1]",
+			label_smoothing
+		);
+		self.label_smoothing = label_smoothing;
+		self
+	}
 }
 
 impl OpSpecification for SoftmaxCrossEntropy {
@@ -104,6 +125,7 @@ impl OpSpecification for SoftmaxCrossEntropy {
 			labels: mapping.get(&self.labels).unwrap_or(&self.labels).clone(),
 			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		}
 	}
 
@@ -113,6 +135,7 @@ impl OpSpecification for SoftmaxCrossEntropy {
 			labels: self.labels.id(),
 			output: self.output.id(),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		})
 	}
 }
@@ -124,6 +147,7 @@ pub struct SoftmaxCrossEntropyInstance {
 	labels: NodeID,
 	output: NodeID,
 	axis: usize,
+	label_smoothing: f32,
 }
 
 impl OpInstance for SoftmaxCrossEntropyInstance {
@@ -137,6 +161,7 @@ impl OpInstance for SoftmaxCrossEntropyInstance {
 			labels: graph.node_from_id(self.labels),
 			output: graph.node_from_id(self.output),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		})
 	}
 
@@ -157,6 +182,7 @@ impl OpInstance for SoftmaxCrossEntropyInstance {
 			ctx.grad_of(&self.output),
 			self.axis,
 		)
+		.label_smoothing(self.label_smoothing)
 		.build()?;
 		Ok(())
 	}
@@ -194,14 +220,18 @@ impl OpInstance for SoftmaxCrossEntropyInstance {
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let label_smoothing = self.label_smoothing;
+
 		Zip::from(ctx.get_output(&self.output))
 			.and(ctx.get_input(&self.logits).lanes(Axis(self.axis)))
 			.and(ctx.get_input(&self.labels).lanes(Axis(self.axis)))
 			.par_for_each(|output, logits, labels| {
 				let max = logits.iter().fold(::std::f32::NEG_INFINITY, |max, &v| v.max(max));
 				let exp_sum = logits.iter().fold(0., |sum, &v| sum + (v - max).exp());
+				let uniform = label_smoothing / logits.len() as f32;
 
 				Zip::from(logits).and(labels).for_each(|logit, label| {
+					let label = label * (1.0 - label_smoothing) + uniform;
 					*output += label * (exp_sum.ln() - (logit - max));
 				});
 			});
@@ -224,6 +254,7 @@ pub struct SoftmaxCrossEntropyBack {
 	logits_grad: Node,
 	labels_grad: Node,
 	axis: usize,
+	label_smoothing: f32,
 }
 
 impl SoftmaxCrossEntropyBack {
@@ -264,8 +295,22 @@ impl SoftmaxCrossEntropyBack {
 			logits_grad,
 			labels_grad,
 			axis,
+			label_smoothing: 0.0,
 		}
 	}
+
+	/// Mixes the labels with a uniform distribution across the axis, see `SoftmaxCrossEntropy::label_smoothing`.
+	///
+	/// Default: 0.0
+	pub fn label_smoothing(mut self, label_smoothing: f32) -> Self {
+		assert!(
+			(0.0..=1.0).contains(&label_smoothing),
+			"label_smoothing {} must be in the range [0, 1]",
+			label_smoothing
+		);
+		self.label_smoothing = label_smoothing;
+		self
+	}
 }
 
 impl OpSpecification for SoftmaxCrossEntropyBack {
@@ -291,6 +336,7 @@ impl OpSpecification for SoftmaxCrossEntropyBack {
 			logits_grad: mapping.get(&self.logits_grad).unwrap_or(&self.logits_grad).clone(),
 			labels_grad: mapping.get(&self.labels_grad).unwrap_or(&self.labels_grad).clone(),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		}
 	}
 
@@ -302,6 +348,7 @@ impl OpSpecification for SoftmaxCrossEntropyBack {
 			labels_grad: self.labels_grad.id(),
 			output_grad: self.output_grad.id(),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		})
 	}
 }
@@ -315,6 +362,7 @@ pub struct SoftmaxCrossEntropyBackInstance {
 	labels_grad: NodeID,
 	output_grad: NodeID,
 	axis: usize,
+	label_smoothing: f32,
 }
 
 impl OpInstance for SoftmaxCrossEntropyBackInstance {
@@ -330,6 +378,7 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 			labels_grad: graph.node_from_id(self.labels_grad),
 			output_grad: graph.node_from_id(self.output_grad),
 			axis: self.axis,
+			label_smoothing: self.label_smoothing,
 		})
 	}
 
@@ -385,6 +434,8 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 	}
 
 	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let label_smoothing = self.label_smoothing;
+
 		if !ctx.is_required_output(&self.labels_grad) {
 			Zip::from(ctx.get_output(&self.logits_grad).lanes_mut(Axis(self.axis)))
 				.and(ctx.get_input(&self.logits).lanes(Axis(self.axis)))
@@ -396,7 +447,10 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 					let max = logits.iter().fold(::std::f32::NEG_INFINITY, |max, &v| v.max(max));
 					let exp_sum = logits.iter().fold(0., |sum, &v| sum + (v - max).exp());
 
+					let uniform = label_smoothing / len as f32;
+
 					for (i, &label) in labels.iter().enumerate() {
+						let label = label * (1.0 - label_smoothing) + uniform;
 						if label != 0.0 {
 							let strength = label * output_grad;
 
@@ -425,7 +479,7 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 					let exp_sum = logits.iter().fold(0., |sum, &v| sum + (v - max).exp());
 
 					for (i, &logit) in logits.iter().enumerate() {
-						label_grad[i] += *output_grad * (exp_sum.ln() - (logit - max));
+						label_grad[i] += *output_grad * (1.0 - label_smoothing) * (exp_sum.ln() - (logit - max));
 					}
 				});
 		} else {
@@ -443,10 +497,13 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 					let exp_sum = logits.iter().fold(0., |sum, &v| sum + (v - max).exp());
 
 					for (i, &logit) in logits.iter().enumerate() {
-						label_grad[i] += *output_grad * (exp_sum.ln() - (logit - max));
+						label_grad[i] += *output_grad * (1.0 - label_smoothing) * (exp_sum.ln() - (logit - max));
 					}
 
+					let uniform = label_smoothing / len as f32;
+
 					for (i, &label) in labels.iter().enumerate() {
+						let label = label * (1.0 - label_smoothing) + uniform;
 						if label != 0.0 {
 							let strength = label * output_grad;
 
@@ -474,12 +531,15 @@ impl OpInstance for SoftmaxCrossEntropyBackInstance {
 
 #[cfg(test)]
 mod tests {
-	use super::softmax_cross_entropy;
-	use alumina_core::graph::Node;
+	use super::{softmax_cross_entropy, SoftmaxCrossEntropy};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node};
 	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 	use indexmap::indexset;
 
 	use ndarray::{arr1, arr2};
+
+	contract_case!(binary "softmax_cross_entropy", |x, y| softmax_cross_entropy(x, y, -1));
 	#[test]
 	fn forward_test() {
 		let logits = Node::new(&[4, 4])
@@ -526,4 +586,73 @@ mod tests {
 			.tolerance(4e-3)
 			.run();
 	}
+
+	#[test]
+	fn label_smoothing_forward_test() {
+		let logits = Node::new(&[3, 4])
+			.set_value(arr2(&[
+				[0.2, 0.4, 0.6, 0.8],
+				[1.2, -1.4, 1.6, 1.8],
+				[2.2, 2.4, 0.6, -2.8],
+			]))
+			.set_name("logits");
+
+		let labels = Node::new(&[3, 4])
+			.set_value(arr2(&[
+				[0.0, 1.0, 0.0, 0.0],
+				[0.0, 0.0, 1.0, 0.0],
+				[1.0, 0.0, 0.0, 0.0],
+			]))
+			.set_name("labels");
+
+		let smoothed_labels = Node::new(&[3, 4])
+			.set_value(arr2(&[
+				[0.05, 0.85, 0.05, 0.05],
+				[0.05, 0.05, 0.85, 0.05],
+				[0.85, 0.05, 0.05, 0.05],
+			]))
+			.set_name("smoothed_labels");
+
+		let plain = softmax_cross_entropy(&logits, &labels, -1).unwrap();
+
+		let unsmoothed = logits.graph().new_node(plain.shape()).set_name("unsmoothed");
+		SoftmaxCrossEntropy::new(&logits, &labels, &unsmoothed, 1)
+			.label_smoothing(0.0)
+			.build()
+			.unwrap();
+
+		let smoothed = logits.graph().new_node(plain.shape()).set_name("smoothed");
+		SoftmaxCrossEntropy::new(&logits, &labels, &smoothed, 1)
+			.label_smoothing(0.2)
+			.build()
+			.unwrap();
+
+		let expected = softmax_cross_entropy(&logits, &smoothed_labels, -1).unwrap();
+
+		assert!(unsmoothed
+			.calc()
+			.unwrap()
+			.all_relatively_close(&plain.calc().unwrap(), 1e-6));
+		assert!(smoothed
+			.calc()
+			.unwrap()
+			.all_relatively_close(&expected.calc().unwrap(), 1e-5));
+	}
+
+	#[test]
+	fn label_smoothing_grad_numeric_test() {
+		let input1 = Node::new(&[13, 33]).set_name("input1");
+		let input2 = Node::new(&[13, 33]).set_name("input2");
+		let output = Node::new(&[13]).set_name("output");
+
+		SoftmaxCrossEntropy::new(&input1, &input2, &output, 1)
+			.label_smoothing(0.1)
+			.build()
+			.unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input1, &input2])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
 }
@@ -0,0 +1,284 @@
+use crate::{
+	elementwise::{div::div, mul::mul},
+	math::broadcast::broadcast_fn,
+	reduce::reduce_sum::reduce_sum,
+};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Array1, Dimension, Ix2, Zip};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::any::Any;
+
+/// Divides a `[K, N]` weight matrix by an estimate of its largest singular value.
+///
+/// `let output = weight / (u^T * weight * v)`
+///
+/// where `u` and `v` are estimates of the leading left and right singular vectors, calculated by `iterations` steps of
+/// power iteration with `PowerIteration`. The singular vectors are treated as constants, so gradients flow to the weight
+/// only through the two uses of the weight above.
+///
+/// The starting vector of the power iteration is determined by a seed chosen when the Op is built, and is the same for
+/// every execution.
+///
+/// The output node has the same shape as the weight.
+pub fn spectral_norm<I>(weight: I, iterations: usize) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let weight = weight.into();
+
+	if weight.shape().len() != 2 {
+		return Err(format!(
+			"spectral_norm requires a weight with 2 axes, but the weight ({}) has shape {}",
+			weight,
+			weight.shape()
+		)
+		.into());
+	}
+
+	let singular_vectors = weight
+		.graph()
+		.new_node(weight.shape())
+		.set_name_unique(&format!("spectral_norm({})_singular_vectors", weight));
+
+	PowerIteration::new(&weight, &singular_vectors, iterations).build()?;
+
+	let sigma = reduce_sum(mul(&weight, singular_vectors)?, &[], false)?
+		.set_name_unique(&format!("spectral_norm({})_sigma", weight));
+	let output = broadcast_fn(div, &weight, sigma)?.set_name_unique(&format!("spectral_norm({})", weight));
+
+	Ok(output)
+}
+
+/// Estimates the leading singular vectors of a `[K, N]` matrix by power iteration, outputting their outer product
+/// `u * v^T` with shape `[K, N]`.
+///
+/// The output is treated as a constant, and no gradient is propagated to the input.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct PowerIteration {
+	input: Node,
+	output: Node,
+	iterations: usize,
+	seed: u64,
+}
+
+impl PowerIteration {
+	pub fn new<I, O>(input: I, output: O, iterations: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(input.shape().len() == 2, "input must have 2 axes");
+		assert!(output.shape().len() == 2, "output must have 2 axes");
+		assert!(iterations > 0, "iterations must be greater than zero");
+		PowerIteration {
+			input,
+			output,
+			iterations,
+			seed: thread_rng().gen(),
+		}
+	}
+
+	/// Seed for the starting vector of the power iteration.
+	///
+	/// Default: random
+	pub fn seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self
+	}
+}
+
+impl OpSpecification for PowerIteration {
+	type InstanceType = PowerIterationInstance;
+
+	fn type_name(&self) -> &'static str {
+		"PowerIteration"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			iterations: self.iterations,
+			seed: self.seed,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(PowerIterationInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			iterations: self.iterations,
+			seed: self.seed,
+		})
+	}
+}
+
+/// PowerIteration OpInstance
+#[derive(Clone, Debug)]
+pub struct PowerIterationInstance {
+	input: NodeID,
+	output: NodeID,
+	iterations: usize,
+	seed: u64,
+}
+
+impl OpInstance for PowerIterationInstance {
+	fn type_name(&self) -> &'static str {
+		"PowerIteration"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(PowerIteration {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			iterations: self.iterations,
+			seed: self.seed,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).slice().to_vec();
+		if input_shape.len() != 2 {
+			return Err(format!(
+				"PowerIteration requires an input with 2 axes, but the input has shape {:?}",
+				input_shape
+			)
+			.into());
+		}
+		ctx.merge_output_shape(&self.output, &input_shape.into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx
+			.get_input(&self.input)
+			.into_dimensionality::<Ix2>()
+			.expect("Alumina Bug: PowerIteration input must have 2 axes");
+
+		let mut rng = StdRng::seed_from_u64(self.seed);
+		let mut u = Array1::from_shape_simple_fn(input.shape()[0], || rng.gen_range(-1.0f32..1.0));
+		normalise(&mut u);
+		let mut v = Array1::zeros(input.shape()[1]);
+
+		for _ in 0..self.iterations {
+			v = input.t().dot(&u);
+			normalise(&mut v);
+			u = input.dot(&v);
+			normalise(&mut u);
+		}
+
+		let output = ctx
+			.get_output(&self.output)
+			.into_dimensionality::<Ix2>()
+			.expect("Alumina Bug: PowerIteration output must have 2 axes");
+
+		Zip::indexed(output).par_for_each(|(k, n), output| *output += u[k] * v[n]);
+
+		Ok(())
+	}
+}
+
+/// Scales the vector to unit length, leaving an all zero vector unchanged.
+fn normalise(x: &mut Array1<f32>) {
+	let norm = x.iter().fold(0.0, |sum, &x| sum + x * x).sqrt();
+	if norm > 0.0 {
+		x.mapv_inplace(|x| x / norm);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::spectral_norm;
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+	use ndarray::{Array1, ArrayD, ArrayView2, Ix2};
+
+	contract_case!(unary "spectral_norm", |x| spectral_norm(x, 3));
+
+	/// Returns the largest singular value by running power iteration to convergence.
+	fn largest_singular_value(matrix: ArrayView2<f32>) -> f32 {
+		let mut v = Array1::from_elem(matrix.shape()[1], 1.0);
+		for _ in 0..1000 {
+			let next = matrix.t().dot(&matrix.dot(&v));
+			let norm = next.dot(&next).sqrt();
+			v = next / norm;
+		}
+		matrix.dot(&v).dot(&matrix.dot(&v)).sqrt()
+	}
+
+	#[test]
+	fn forward_test() {
+		let weight = Node::new(&[6, 4])
+			.set_name("weight")
+			.set_value(ArrayD::from_shape_fn(vec![6, 4], |ix| {
+				((ix[0] * 5 + ix[1] * 3) as f32).sin() * 2.0 + (ix[0] as f32 + 1.0) * (ix[1] as f32 - 0.5)
+			}));
+		let weight_sigma = largest_singular_value(weight.value().unwrap().view().into_dimensionality::<Ix2>().unwrap());
+		assert!(weight_sigma > 5.0);
+
+		let output = spectral_norm(&weight, 5).unwrap();
+		assert_eq!(output.shape(), weight.shape());
+
+		let output = output.calc().unwrap().into_dimensionality::<Ix2>().unwrap();
+		let output_sigma = largest_singular_value(output.view());
+		assert!((output_sigma - 1.0).abs() < 1e-3, "{}", output_sigma);
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let weight = Node::new(&[3, 3, 4]).set_name("weight");
+		assert!(spectral_norm(&weight, 1).is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let weight = Node::new(&[5, 7]).set_name("weight").set_init(uniform(-2.0, 2.0));
+		let scale = Node::new(&[5, 7])
+			.set_name("scale")
+			.set_value(ArrayD::from_shape_fn(vec![5, 7], |ix| {
+				((ix[0] * 7 + ix[1]) as f32).sin()
+			}));
+
+		// the plain sum of the output is insensitive to the scale of the weight, so weight it
+		// with a converged estimate the constant singular vectors don't change the gradient
+		let output = mul(spectral_norm(&weight, 100).unwrap(), &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&weight])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
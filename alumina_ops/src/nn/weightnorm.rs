@@ -0,0 +1,136 @@
+use crate::{
+	elementwise::{div::div, mul::mul, sqr::sqr, sqrt::sqrt},
+	math::broadcast::broadcast_fn,
+	reduce::reduce_sum::reduce_sum,
+};
+use alumina_core::{errors::OpBuildError, graph::Node};
+
+/// Reparameterises a weight as a magnitude `g` and a direction `v`.
+///
+/// `let output = g * v / ||v||`
+///
+/// `g` must have the same number of axes as `v`. The norm of `v` is taken over the axes where `g` has size 1, so each
+/// element of `g` sets the magnitude of the corresponding slice of the output. For the `[K, N]` weights of `linear(..)`
+/// a `g` of shape `[1, N]` normalises each output unit, while a `g` of shape `[1, 1]` normalises the whole weight.
+///
+/// Both `g` and `v` receive gradients through the norm. The output is NaN where a slice of `v` is all zeros.
+///
+/// The output node has the same shape as `v`.
+pub fn weight_norm<I1, I2>(v: I1, g: I2) -> Result<Node, OpBuildError>
+where
+	I1: Into<Node>,
+	I2: Into<Node>,
+{
+	let v = v.into();
+	let g = g.into();
+
+	if g.shape().len() != v.shape().len() {
+		return Err(format!(
+			"weight_norm requires g ({}) shape {} to have the same number of axes as v ({}) shape {}",
+			g,
+			g.shape(),
+			v,
+			v.shape()
+		)
+		.into());
+	}
+
+	let axes: Vec<isize> = g
+		.shape()
+		.slice()
+		.iter()
+		.enumerate()
+		.filter_map(|(i, axis)| {
+			if axis.as_known() == Some(1) {
+				Some(i as isize)
+			} else {
+				None
+			}
+		})
+		.collect();
+	if axes.is_empty() {
+		return Err(format!(
+			"weight_norm requires g ({}) shape {} to have size 1 on at least one axis, over which the norm of v is taken",
+			g,
+			g.shape()
+		)
+		.into());
+	}
+
+	let norm = sqrt(reduce_sum(sqr(&v)?, &axes, true)?)?.set_name_unique(&format!("weight_norm({})_norm", v));
+	let scale = div(g, norm)?;
+	let output = broadcast_fn(mul, &v, scale)?.set_name_unique(&format!("weight_norm({})", v));
+
+	Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::weight_norm;
+	use crate::tests::{contract_case, input};
+	use crate::{elementwise::sqr::sqr, reduce::reduce_sum::reduce_sum};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr0, arr2};
+
+	contract_case!("weight_norm", || {
+		let (x, g) = (input("x", &[4, 5]), input("g", &[1, 5]));
+		(weight_norm(&x, &g).unwrap(), vec![x, g])
+	});
+
+	#[test]
+	fn forward_test() {
+		let v = Node::new(&[3, 4]).set_name("v").set_value(arr2(&[
+			[1.0, -2.0, 0.5, 3.0],
+			[0.0, 4.0, -1.0, 2.0],
+			[2.5, 1.5, -0.5, -1.0],
+		]));
+
+		// a norm per output unit
+		let g = Node::new(&[1, 4])
+			.set_name("g")
+			.set_value(arr2(&[[2.0, 0.5, 1.0, 3.0]]));
+		let output = weight_norm(&v, &g).unwrap();
+		assert_eq!(output.shape(), v.shape());
+
+		let norms = reduce_sum(sqr(&output).unwrap(), &[0], true).unwrap().calc().unwrap();
+		assert!(norms.all_relatively_close(&arr2(&[[4.0, 0.25, 1.0, 9.0]]), 1e-5));
+
+		// the direction of v is preserved
+		let ratio = output.calc().unwrap()[[1, 3]] / output.calc().unwrap()[[0, 3]];
+		assert!((ratio - 2.0 / 3.0).abs() < 1e-5);
+
+		// a single norm
+		let g = Node::new(&[1, 1]).set_name("g").set_value(arr2(&[[5.0]]));
+		let output = weight_norm(&v, &g).unwrap();
+		let norm = reduce_sum(sqr(&output).unwrap(), &[], false).unwrap().calc().unwrap();
+		assert!(norm.all_relatively_close(&arr0(25.0), 1e-5));
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let v = Node::new(&[5, 7]).set_name("v").set_init(uniform(-2.0, 2.0));
+		let g = Node::new(&[1, 7]).set_name("g").set_init(uniform(0.5, 2.0));
+
+		let output = weight_norm(&v, &g).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&v, &g])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_conv_test() {
+		let v = Node::new(&[3, 3, 4, 6]).set_name("v").set_init(uniform(-2.0, 2.0));
+		let g = Node::new(&[1, 1, 1, 6]).set_name("g").set_init(uniform(0.5, 2.0));
+
+		let output = weight_norm(&v, &g).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&v, &g])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -182,17 +182,30 @@ where
 	build_or_pretty_panic(identity::add_n(inputs), "Identity")
 }
 
-/// Returns the leaky rectified linear unit activation (leaky relu) of the input.
+/// Returns the leaky rectified linear unit activation (leaky relu) of the input, with a negative slope of 0.01.
 ///
 /// The output node has the same shape as the input.
 ///
 /// # Panics
 /// Panics if building the underlying Op panics.
-pub fn leaky_relu<I>(input: I, alpha: f32) -> Node
+pub fn leaky_relu<I>(input: I) -> Node
 where
 	I: Into<Node>,
 {
-	build_or_pretty_panic(leaky_relu::leaky_relu(input, alpha), "LeakyRelu")
+	build_or_pretty_panic(leaky_relu::leaky_relu(input), "LeakyRelu")
+}
+
+/// Returns the leaky rectified linear unit activation (leaky relu) of the input, with the given negative slope.
+///
+/// The output node has the same shape as the input.
+///
+/// # Panics
+/// Panics if building the underlying Op panics.
+pub fn leaky_relu_with_slope<I>(input: I, slope: f32) -> Node
+where
+	I: Into<Node>,
+{
+	build_or_pretty_panic(leaky_relu::leaky_relu_with_slope(input, slope), "LeakyRelu")
 }
 
 /// Returns the natural logarithm (ln) of the input.
@@ -0,0 +1,466 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Calculates the cumulative product of the input along `axis`.
+///
+/// `output[i] = input[0] * input[1] * ... * input[i]`
+///
+/// To reverse the direction of the product, or to exclude each element from its own product, use
+/// `Cumprod::reverse(..)` and `Cumprod::exclusive(..)`.
+///
+/// The output node has the same shape as the input.
+pub fn cumprod<I>(input: I, axis: isize) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("cumprod({})", input));
+
+	Cumprod::new(input, output.clone(), axis).build()?;
+
+	Ok(output)
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Cumprod {
+	input: Node,
+	output: Node,
+	axis: usize,
+	reverse: bool,
+	exclusive: bool,
+}
+
+impl Cumprod {
+	pub fn new<I, O>(input: I, output: O, axis: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same shape"
+		);
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		Cumprod {
+			input,
+			output,
+			axis,
+			reverse: false,
+			exclusive: false,
+		}
+	}
+
+	/// If `true` the product runs from the last element of the axis to the first,
+	/// `output[i] = input[i] * input[i + 1] * ... * input[n - 1]`.
+	///
+	/// Default: false
+	pub fn reverse(mut self, reverse: bool) -> Self {
+		self.reverse = reverse;
+		self
+	}
+
+	/// If `true` each element is excluded from its own product, so the first output along the axis is one,
+	/// `output[i] = input[0] * ... * input[i - 1]`.
+	///
+	/// Default: false
+	pub fn exclusive(mut self, exclusive: bool) -> Self {
+		self.exclusive = exclusive;
+		self
+	}
+}
+
+impl OpSpecification for Cumprod {
+	type InstanceType = CumprodInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Cumprod"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CumprodInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		})
+	}
+}
+
+/// Cumprod OpInstance
+#[derive(Clone, Debug)]
+pub struct CumprodInstance {
+	input: NodeID,
+	output: NodeID,
+	axis: usize,
+	reverse: bool,
+	exclusive: bool,
+}
+
+impl OpInstance for CumprodInstance {
+	fn type_name(&self) -> &'static str {
+		"Cumprod"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Cumprod {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		CumprodBack::new(
+			ctx.node(&self.input),
+			ctx.grad_of(&self.input),
+			ctx.grad_of(&self.output),
+			self.axis,
+		)
+		.reverse(self.reverse)
+		.exclusive(self.exclusive)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let (reverse, exclusive) = (self.reverse, self.exclusive);
+		Zip::from(ctx.get_output(&self.output).lanes_mut(Axis(self.axis)))
+			.and(ctx.get_input(&self.input).lanes(Axis(self.axis)))
+			.par_for_each(|mut output, input| {
+				let len = input.len();
+				let mut prod = 1.0;
+				for j in 0..len {
+					let i = if reverse { len - 1 - j } else { j };
+					if exclusive {
+						output[i] += prod;
+						prod *= input[i];
+					} else {
+						prod *= input[i];
+						output[i] += prod;
+					}
+				}
+			});
+		Ok(())
+	}
+}
+
+/// Calculates the gradient of `Cumprod` with respect to its input.
+///
+/// Input/Output naming convention matches Cumprod Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The gradient is calculated without dividing by the input, so it remains exact where the input contains zeros.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct CumprodBack {
+	input: Node,
+	input_grad: Node,
+	output_grad: Node,
+	axis: usize,
+	reverse: bool,
+	exclusive: bool,
+}
+
+impl CumprodBack {
+	pub fn new<I1, I2, O>(input: I1, input_grad: O, output_grad: I2, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let input_grad = input_grad.into();
+		let output_grad = output_grad.into();
+		assert!(input.shape().len() == input_grad.shape().len());
+		assert!(input.shape().len() == output_grad.shape().len());
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		CumprodBack {
+			input,
+			input_grad,
+			output_grad,
+			axis,
+			reverse: false,
+			exclusive: false,
+		}
+	}
+
+	/// Whether the Cumprod Op ran from the last element of the axis to the first.
+	///
+	/// Default: false
+	pub fn reverse(mut self, reverse: bool) -> Self {
+		self.reverse = reverse;
+		self
+	}
+
+	/// Whether the Cumprod Op excluded each element from its own product.
+	///
+	/// Default: false
+	pub fn exclusive(mut self, exclusive: bool) -> Self {
+		self.exclusive = exclusive;
+		self
+	}
+}
+
+impl OpSpecification for CumprodBack {
+	type InstanceType = CumprodBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"CumprodBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(CumprodBackInstance {
+			input: self.input.id(),
+			input_grad: self.input_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		})
+	}
+}
+
+/// CumprodBack OpInstance
+#[derive(Clone, Debug)]
+pub struct CumprodBackInstance {
+	input: NodeID,
+	input_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+	reverse: bool,
+	exclusive: bool,
+}
+
+impl OpInstance for CumprodBackInstance {
+	fn type_name(&self) -> &'static str {
+		"CumprodBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(CumprodBack {
+			input: graph.node_from_id(self.input),
+			input_grad: graph.node_from_id(self.input_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+			reverse: self.reverse,
+			exclusive: self.exclusive,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).clone();
+		let output_grad_shape = ctx.input_shape(&self.output_grad).clone();
+
+		if output_grad_shape != input_shape {
+			return Err(format!(
+				"CumprodBack requires the output grad to have the shape of the input: input:{:?} output_grad:{:?}",
+				input_shape.slice(),
+				output_grad_shape.slice()
+			)
+			.into());
+		}
+
+		ctx.merge_output_shape(&self.input_grad, &input_shape.slice().into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let (reverse, exclusive) = (self.reverse, self.exclusive);
+		Zip::from(ctx.get_output(&self.input_grad).lanes_mut(Axis(self.axis)))
+			.and(ctx.get_input(&self.input).lanes(Axis(self.axis)))
+			.and(ctx.get_input(&self.output_grad).lanes(Axis(self.axis)))
+			.par_for_each(|mut input_grad, input, output_grad| {
+				// Work in the order of the product, so that element k only affects outputs at k and after.
+				let len = input.len();
+				let ix = |j: usize| if reverse { len - 1 - j } else { j };
+
+				// The gradient of input k is the product of the inputs before k, multiplied by the sum over later
+				// outputs of their grad times the product of the inputs between k and that output. Accumulating the
+				// sum from the end avoids dividing the output by input k, which would fail where input k is zero.
+				let mut prefix = vec![1.0; len];
+				for j in 1..len {
+					prefix[j] = prefix[j - 1] * input[ix(j - 1)];
+				}
+
+				let mut sum = 0.0;
+				for j in (0..len).rev() {
+					if exclusive {
+						input_grad[ix(j)] += prefix[j] * sum;
+						sum = output_grad[ix(j)] + input[ix(j)] * sum;
+					} else {
+						sum = output_grad[ix(j)] + if j + 1 < len { input[ix(j + 1)] * sum } else { 0.0 };
+						input_grad[ix(j)] += prefix[j] * sum;
+					}
+				}
+			});
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{cumprod, Cumprod};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+	use ndarray::{arr1, arr2};
+
+	contract_case!(unary "cumprod", |x| cumprod(x, 1));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 4])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0, 3.0, 4.0], [0.5, -2.0, 0.0, 3.0]]));
+
+		let output = cumprod(&input, 1).unwrap();
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[1.0, 2.0, 6.0, 24.0], [0.5, -1.0, 0.0, 0.0]]).into_dyn()
+		);
+
+		let flags = [
+			(true, false, arr2(&[[24.0, 24.0, 12.0, 4.0], [0.0, 0.0, 0.0, 3.0]])),
+			(false, true, arr2(&[[1.0, 1.0, 2.0, 6.0], [1.0, 0.5, -1.0, 0.0]])),
+			(true, true, arr2(&[[24.0, 12.0, 4.0, 1.0], [0.0, 0.0, 3.0, 1.0]])),
+		];
+		for (reverse, exclusive, expected) in &flags {
+			let output = Node::new(&[2, 4]).set_name("output");
+			Cumprod::new(&input, &output, 1)
+				.reverse(*reverse)
+				.exclusive(*exclusive)
+				.build()
+				.unwrap();
+			assert_eq!(output.calc().unwrap(), expected.clone().into_dyn());
+		}
+
+		let output = cumprod(&input, 0).unwrap();
+		assert_eq!(
+			output.calc().unwrap(),
+			arr2(&[[1.0, 2.0, 3.0, 4.0], [0.5, -4.0, 0.0, 12.0]]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for &(reverse, exclusive) in &[(false, false), (true, false), (false, true), (true, true)] {
+			let input = Node::new(&[5, 7, 3]).set_name("input").set_init(uniform(0.5, 1.5));
+			let output = Node::new(&[5, 7, 3]).set_name("output");
+			Cumprod::new(&input, &output, 1)
+				.reverse(reverse)
+				.exclusive(exclusive)
+				.build()
+				.unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input])
+				.step_size(1e-3)
+				.tolerance(4e-3)
+				.run();
+		}
+	}
+
+	#[test]
+	fn grad_zero_test() {
+		// with a single zero, only the zero has a non-zero gradient after it, which dividing by the input would lose
+		let input = Node::new(&[4]).set_name("input").set_value(arr1(&[2.0, 0.0, 3.0, 4.0]));
+		let output = cumprod(&input, 0).unwrap();
+
+		let grad = Grad::of(&output).wrt(&[&input]).build().unwrap()[&input]
+			.calc()
+			.unwrap();
+
+		// d/dx1 of (2, 2 x1, 6 x1, 24 x1) summed is 2 + 6 + 24 = 32
+		assert!(grad.all_relatively_close(&arr1(&[1.0, 32.0, 0.0, 0.0]), 1e-6));
+	}
+}
@@ -0,0 +1,479 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Calculates the log of the sum of the exponentials of the input along `axis`.
+///
+/// `output = ln(sum(exp(input)))`
+///
+/// The maximum of each lane is subtracted before exponentiating, and added back afterwards, so that large inputs
+/// don't overflow.
+///
+/// The output node has the shape of the input, but with the axis removed, or with size 1 if `keep_dims` is `true`.
+pub fn logsumexp<I>(input: I, axis: isize, keep_dims: bool) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let output_shape: NodeShape = calc_output_shape(&input.shape(), axis, keep_dims);
+
+	let output = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("logsumexp({})", input));
+
+	let _op = LogSumExp::new(input, output.clone())
+		.axis(axis)
+		.keep_dims(keep_dims)
+		.build()?;
+
+	Ok(output)
+}
+
+/// `LogSumExp` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct LogSumExp {
+	input: Node,
+	output: Node,
+	axis: usize,
+	keep_dims: bool,
+}
+
+impl LogSumExp {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			!input.shape().is_empty(),
+			"input must have at least one axis to reduce over"
+		);
+		let axis = input.shape().len() - 1;
+		LogSumExp {
+			input,
+			output,
+			axis,
+			keep_dims: false,
+		}
+	}
+
+	/// The axis to reduce over.
+	///
+	/// Default: the last axis
+	///
+	/// # Panics
+	/// Panics if the axis is not less than `input.shape().len()`.
+	pub fn axis(mut self, axis: usize) -> Self {
+		assert!(
+			axis < self.input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			self.input.shape().len()
+		);
+		self.axis = axis;
+		self
+	}
+
+	/// If `true` the reduced axis still appears in the output with size 1, otherwise it is removed.
+	///
+	/// Default: `false`
+	pub fn keep_dims(mut self, keep_dims: bool) -> Self {
+		self.keep_dims = keep_dims;
+		self
+	}
+}
+
+impl OpSpecification for LogSumExp {
+	type InstanceType = LogSumExpInstance;
+
+	fn type_name(&self) -> &'static str {
+		"LogSumExp"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(LogSumExpInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		})
+	}
+}
+
+/// LogSumExp OpInstance
+#[derive(Clone, Debug)]
+pub struct LogSumExpInstance {
+	input: NodeID,
+	output: NodeID,
+	axis: usize,
+	keep_dims: bool,
+}
+
+impl OpInstance for LogSumExpInstance {
+	fn type_name(&self) -> &'static str {
+		"LogSumExp"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(LogSumExp {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		LogSumExpBack::new(
+			ctx.node(&self.input),
+			ctx.node(&self.output),
+			ctx.grad_of(&self.input),
+			ctx.grad_of(&self.output),
+			self.axis,
+		)
+		.keep_dims(self.keep_dims)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape: NodeShape =
+			calc_output_shape(&ctx.input_shape(&self.input).slice().into(), self.axis, self.keep_dims);
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output = ctx.get_output(&self.output);
+		if self.keep_dims {
+			output = output.index_axis_move(Axis(self.axis), 0);
+		}
+
+		Zip::from(input.lanes(Axis(self.axis)))
+			.and(&mut output)
+			.par_for_each(|input, output| {
+				let max = input.fold(f32::NEG_INFINITY, |max, &x| max.max(x));
+				if max.is_finite() {
+					*output += max + input.fold(0.0, |sum, &x| sum + (x - max).exp()).ln();
+				} else {
+					// empty lanes and lanes of -inf sum to zero, and any +inf dominates the sum
+					*output += max;
+				}
+			});
+
+		Ok(())
+	}
+}
+
+/// Calculates the gradient of `LogSumExp` with respect to its input, which is the softmax of the input along the axis
+/// multiplied by the output grad.
+///
+/// Input/Output naming convention matches LogSumExp Input/Outputs, i.e. output_grad is an input to this Op.
+///
+/// The softmax is recovered from the output of LogSumExp as `exp(input - output)`.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct LogSumExpBack {
+	input: Node,
+	output: Node,
+	input_grad: Node,
+	output_grad: Node,
+	axis: usize,
+	keep_dims: bool,
+}
+
+impl LogSumExpBack {
+	pub fn new<I1, I2, I3, O>(input: I1, output: I2, input_grad: O, output_grad: I3, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		I3: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		let input_grad = input_grad.into();
+		let output_grad = output_grad.into();
+		assert!(input.shape().len() == input_grad.shape().len());
+		assert!(output.shape().len() == output_grad.shape().len());
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		LogSumExpBack {
+			input,
+			output,
+			input_grad,
+			output_grad,
+			axis,
+			keep_dims: false,
+		}
+	}
+
+	/// Whether the LogSumExp Op kept the reduced axis in its output.
+	///
+	/// Default: `false`
+	pub fn keep_dims(mut self, keep_dims: bool) -> Self {
+		self.keep_dims = keep_dims;
+		self
+	}
+}
+
+impl OpSpecification for LogSumExpBack {
+	type InstanceType = LogSumExpBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"LogSumExpBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.output.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(LogSumExpBackInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			input_grad: self.input_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		})
+	}
+}
+
+/// LogSumExpBack OpInstance
+#[derive(Clone, Debug)]
+pub struct LogSumExpBackInstance {
+	input: NodeID,
+	output: NodeID,
+	input_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+	keep_dims: bool,
+}
+
+impl OpInstance for LogSumExpBackInstance {
+	fn type_name(&self) -> &'static str {
+		"LogSumExpBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(LogSumExpBack {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			input_grad: graph.node_from_id(self.input_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.output, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output = ctx.get_input(&self.output);
+		let mut output_grad = ctx.get_input(&self.output_grad);
+		if self.keep_dims {
+			output = output.index_axis_move(Axis(self.axis), 0);
+			output_grad = output_grad.index_axis_move(Axis(self.axis), 0);
+		}
+
+		Zip::from(ctx.get_output(&self.input_grad).lanes_mut(Axis(self.axis)))
+			.and(input.lanes(Axis(self.axis)))
+			.and(&output)
+			.and(&output_grad)
+			.par_for_each(|mut input_grad, input, &output, &output_grad| {
+				Zip::from(&mut input_grad).and(&input).for_each(|input_grad, &input| {
+					*input_grad += (input - output).exp() * output_grad;
+				});
+			});
+
+		Ok(())
+	}
+}
+
+fn calc_output_shape(input_shape: &NodeShape, axis: usize, keep_dims: bool) -> NodeShape {
+	input_shape
+		.iter()
+		.enumerate()
+		.filter_map(|(i, node_axis)| {
+			if i != axis {
+				Some(node_axis.clone())
+			} else if keep_dims {
+				Some(NodeAxis::known(1))
+			} else {
+				None
+			}
+		})
+		.into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{logsumexp, LogSumExp};
+	use crate::tests::contract_case;
+	use crate::{
+		elementwise::{exp::exp, ln::ln},
+		reduce::reduce_sum::reduce_sum,
+	};
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+
+	use indexmap::indexset;
+	use ndarray::{arr1, arr2};
+
+	contract_case!(unary "logsumexp", |x| logsumexp(x, 0, false));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[5, 7, 9]).set_name("input").set_init(uniform(-2.0, 2.0));
+		input.init_value();
+
+		for axis in 0..3 {
+			let output = logsumexp(&input, axis, false).unwrap();
+			let direct = ln(reduce_sum(exp(&input).unwrap(), &[axis], false).unwrap()).unwrap();
+
+			assert!(output
+				.calc()
+				.unwrap()
+				.all_relatively_close(&direct.calc().unwrap(), 1e-5));
+		}
+	}
+
+	#[test]
+	fn forward_large_test() {
+		let input = Node::new(&[2, 3])
+			.set_name("input")
+			.set_value(arr2(&[[1000.0, 1000.0, 1000.0], [-1000.0, 0.0, f32::NEG_INFINITY]]));
+
+		// a direct ln(sum(exp(..))) would overflow to inf for the first row
+		let output = logsumexp(&input, -1, false).unwrap();
+
+		assert!(output
+			.calc()
+			.unwrap()
+			.all_relatively_close(&arr1(&[1000.0 + 3.0f32.ln(), 0.0]), 1e-6));
+	}
+
+	#[test]
+	fn axis_test() {
+		let input = Node::new(&[4, 6]).set_name("input").set_init(uniform(-2.0, 2.0));
+		input.init_value();
+		let output = Node::new(&[1, 6]).set_name("output");
+
+		LogSumExp::new(&input, &output).axis(0).keep_dims(true).build().unwrap();
+
+		let expected = logsumexp(&input, 0, true).unwrap();
+		assert_eq!(output.calc().unwrap(), expected.calc().unwrap());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+
+		let output = logsumexp(&input, 1, false).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn grad_numeric_keep_dims_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+
+		let output = logsumexp(&input, -1, true).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(2e-4).run();
+	}
+
+	#[test]
+	fn grad_direct_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input").set_init(uniform(-2.0, 2.0));
+		input.init_value();
+
+		// the softmax gradient must agree with the gradient of the direct composition
+		let output = logsumexp(&input, 1, false).unwrap();
+		let direct = ln(reduce_sum(exp(&input).unwrap(), &[1], false).unwrap()).unwrap();
+
+		let grad = Grad::of(&output).wrt(&[&input]).build().unwrap();
+		let direct_grad = Grad::of(&direct).wrt(&[&input]).build().unwrap();
+
+		assert!(grad[&input]
+			.calc()
+			.unwrap()
+			.all_relatively_close(&direct_grad[&input].calc().unwrap(), 1e-5));
+	}
+}
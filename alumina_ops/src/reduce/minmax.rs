@@ -0,0 +1,594 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayView1, Axis, Dimension, Zip};
+use std::any::Any;
+use std::fmt;
+
+/// Calculates the maximum of the input along `axis`.
+///
+/// The whole output gradient is passed to the position of the maximum in each lane, and none to the other elements.
+/// Where several elements share the maximum, the first is selected.
+///
+/// The output node has the shape of the input, but with the axis removed, or with size 1 if `keep_dims` is `true`.
+pub fn reduce_max<I>(input: I, axis: isize, keep_dims: bool) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	reduce_extremum::<ReduceMaxFunc, _>(input, axis, keep_dims, "reduce_max")
+}
+
+/// Calculates the minimum of the input along `axis`.
+///
+/// The whole output gradient is passed to the position of the minimum in each lane, and none to the other elements.
+/// Where several elements share the minimum, the first is selected.
+///
+/// The output node has the shape of the input, but with the axis removed, or with size 1 if `keep_dims` is `true`.
+pub fn reduce_min<I>(input: I, axis: isize, keep_dims: bool) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	reduce_extremum::<ReduceMinFunc, _>(input, axis, keep_dims, "reduce_min")
+}
+
+fn reduce_extremum<F, I>(input: I, axis: isize, keep_dims: bool, name: &str) -> Result<Node, OpBuildError>
+where
+	F: ExtremumFunc + Default,
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let output_shape: NodeShape = calc_output_shape(&input.shape(), axis, keep_dims);
+
+	let output = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("{}({})", name, input));
+
+	let _op = ReduceExtremum::<F>::new(input, output.clone())
+		.axis(axis)
+		.keep_dims(keep_dims)
+		.build()?;
+
+	Ok(output)
+}
+
+/// Defines which element of a lane is selected by `ReduceExtremum`.
+pub trait ExtremumFunc: Send + Sync + Clone + fmt::Debug + 'static {
+	/// Returns true if `x` should replace the currently selected element `current`.
+	///
+	/// This must be a strict comparison, so that the first of several equal elements remains selected.
+	fn replaces(&self, x: f32, current: f32) -> bool;
+
+	/// The output for a lane with no elements.
+	fn identity(&self) -> f32;
+
+	fn type_name(&self) -> &'static str;
+
+	fn back_type_name(&self) -> &'static str;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReduceMaxFunc {}
+
+impl ExtremumFunc for ReduceMaxFunc {
+	#[inline]
+	fn replaces(&self, x: f32, current: f32) -> bool {
+		x > current
+	}
+
+	fn identity(&self) -> f32 {
+		f32::NEG_INFINITY
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ReduceMax"
+	}
+
+	fn back_type_name(&self) -> &'static str {
+		"ReduceMaxBack"
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReduceMinFunc {}
+
+impl ExtremumFunc for ReduceMinFunc {
+	#[inline]
+	fn replaces(&self, x: f32, current: f32) -> bool {
+		x < current
+	}
+
+	fn identity(&self) -> f32 {
+		f32::INFINITY
+	}
+
+	fn type_name(&self) -> &'static str {
+		"ReduceMin"
+	}
+
+	fn back_type_name(&self) -> &'static str {
+		"ReduceMinBack"
+	}
+}
+
+/// Returns the index of the element selected by `f` in the lane, or None if the lane is empty.
+fn select<F: ExtremumFunc>(f: &F, lane: &ArrayView1<f32>) -> Option<usize> {
+	let mut iter = lane.iter().enumerate();
+	let (mut selected_i, mut selected) = iter.next()?;
+	for (i, x) in iter {
+		if f.replaces(*x, *selected) {
+			selected = x;
+			selected_i = i;
+		}
+	}
+	Some(selected_i)
+}
+
+pub type ReduceMax = ReduceExtremum<ReduceMaxFunc>;
+
+pub type ReduceMin = ReduceExtremum<ReduceMinFunc>;
+
+pub type ReduceMaxBack = ReduceExtremumBack<ReduceMaxFunc>;
+
+pub type ReduceMinBack = ReduceExtremumBack<ReduceMinFunc>;
+
+/// `ReduceExtremum` `OpBuilder`, see `ReduceMax` and `ReduceMin`.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct ReduceExtremum<F: ExtremumFunc> {
+	input: Node,
+	output: Node,
+	axis: usize,
+	keep_dims: bool,
+	f: F,
+}
+
+impl<F: ExtremumFunc> ReduceExtremum<F> {
+	pub fn new<I, O>(input: I, output: O) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+		F: Default,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			!input.shape().is_empty(),
+			"input must have at least one axis to reduce over"
+		);
+		let axis = input.shape().len() - 1;
+		ReduceExtremum {
+			input,
+			output,
+			axis,
+			keep_dims: false,
+			f: F::default(),
+		}
+	}
+
+	/// The axis to reduce over.
+	///
+	/// Default: the last axis
+	///
+	/// # Panics
+	/// Panics if the axis is not less than `input.shape().len()`.
+	pub fn axis(mut self, axis: usize) -> Self {
+		assert!(
+			axis < self.input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			self.input.shape().len()
+		);
+		self.axis = axis;
+		self
+	}
+
+	/// If `true` the reduced axis still appears in the output with size 1, otherwise it is removed.
+	///
+	/// Default: `false`
+	pub fn keep_dims(mut self, keep_dims: bool) -> Self {
+		self.keep_dims = keep_dims;
+		self
+	}
+}
+
+impl<F: ExtremumFunc> OpSpecification for ReduceExtremum<F> {
+	type InstanceType = ReduceExtremumInstance<F>;
+
+	fn type_name(&self) -> &'static str {
+		self.f.type_name()
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f.clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(ReduceExtremumInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f,
+		})
+	}
+}
+
+/// ReduceExtremum OpInstance
+#[derive(Clone, Debug)]
+pub struct ReduceExtremumInstance<F: ExtremumFunc> {
+	input: NodeID,
+	output: NodeID,
+	axis: usize,
+	keep_dims: bool,
+	f: F,
+}
+
+impl<F: ExtremumFunc> OpInstance for ReduceExtremumInstance<F> {
+	fn type_name(&self) -> &'static str {
+		self.f.type_name()
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(ReduceExtremum {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f.clone(),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		ReduceExtremumBack {
+			input: ctx.node(&self.input),
+			input_grad: ctx.grad_of(&self.input),
+			output_grad: ctx.grad_of(&self.output),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f.clone(),
+		}
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape: NodeShape =
+			calc_output_shape(&ctx.input_shape(&self.input).slice().into(), self.axis, self.keep_dims);
+		ctx.merge_output_shape(&self.output, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output = ctx.get_output(&self.output);
+		if self.keep_dims {
+			output = output.index_axis_move(Axis(self.axis), 0);
+		}
+
+		Zip::from(input.lanes(Axis(self.axis)))
+			.and(&mut output)
+			.par_for_each(|input, output| {
+				*output += select(&self.f, &input).map_or_else(|| self.f.identity(), |i| input[i]);
+			});
+
+		Ok(())
+	}
+}
+
+/// Passes the output grad of `ReduceExtremum` to the position of the selected element in each lane of the input grad.
+///
+/// Input/Output naming convention matches ReduceExtremum Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct ReduceExtremumBack<F: ExtremumFunc> {
+	input: Node,
+	input_grad: Node,
+	output_grad: Node,
+	axis: usize,
+	keep_dims: bool,
+	f: F,
+}
+
+impl<F: ExtremumFunc> ReduceExtremumBack<F> {
+	pub fn new<I1, I2, O>(input: I1, input_grad: O, output_grad: I2, axis: usize) -> Self
+	where
+		I1: Into<Node>,
+		I2: Into<Node>,
+		O: Into<Node>,
+		F: Default,
+	{
+		let input = input.into();
+		let input_grad = input_grad.into();
+		let output_grad = output_grad.into();
+		assert!(input.shape().len() == input_grad.shape().len());
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		ReduceExtremumBack {
+			input,
+			input_grad,
+			output_grad,
+			axis,
+			keep_dims: false,
+			f: F::default(),
+		}
+	}
+
+	/// Whether the ReduceExtremum Op kept the reduced axis in its output.
+	///
+	/// Default: `false`
+	pub fn keep_dims(mut self, keep_dims: bool) -> Self {
+		self.keep_dims = keep_dims;
+		self
+	}
+}
+
+impl<F: ExtremumFunc> OpSpecification for ReduceExtremumBack<F> {
+	type InstanceType = ReduceExtremumBackInstance<F>;
+
+	fn type_name(&self) -> &'static str {
+		self.f.back_type_name()
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone(), self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f.clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(ReduceExtremumBackInstance {
+			input: self.input.id(),
+			input_grad: self.input_grad.id(),
+			output_grad: self.output_grad.id(),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f,
+		})
+	}
+}
+
+/// ReduceExtremumBack OpInstance
+#[derive(Clone, Debug)]
+pub struct ReduceExtremumBackInstance<F: ExtremumFunc> {
+	input: NodeID,
+	input_grad: NodeID,
+	output_grad: NodeID,
+	axis: usize,
+	keep_dims: bool,
+	f: F,
+}
+
+impl<F: ExtremumFunc> OpInstance for ReduceExtremumBackInstance<F> {
+	fn type_name(&self) -> &'static str {
+		self.f.back_type_name()
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(ReduceExtremumBack {
+			input: graph.node_from_id(self.input),
+			input_grad: graph.node_from_id(self.input_grad),
+			output_grad: graph.node_from_id(self.output_grad),
+			axis: self.axis,
+			keep_dims: self.keep_dims,
+			f: self.f.clone(),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input, self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.input_grad, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let mut output_grad = ctx.get_input(&self.output_grad);
+		if self.keep_dims {
+			output_grad = output_grad.index_axis_move(Axis(self.axis), 0);
+		}
+
+		Zip::from(ctx.get_output(&self.input_grad).lanes_mut(Axis(self.axis)))
+			.and(input.lanes(Axis(self.axis)))
+			.and(&output_grad)
+			.par_for_each(|mut input_grad, input, &output_grad| {
+				if let Some(i) = select(&self.f, &input) {
+					input_grad[i] += output_grad;
+				}
+			});
+
+		Ok(())
+	}
+}
+
+fn calc_output_shape(input_shape: &NodeShape, axis: usize, keep_dims: bool) -> NodeShape {
+	input_shape
+		.iter()
+		.enumerate()
+		.filter_map(|(i, node_axis)| {
+			if i != axis {
+				Some(node_axis.clone())
+			} else if keep_dims {
+				Some(NodeAxis::known(1))
+			} else {
+				None
+			}
+		})
+		.into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{reduce_max, reduce_min, ReduceMax, ReduceMin};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node, init::uniform};
+	use alumina_test::grad_numeric_test::GradNumericTest;
+
+	use indexmap::indexset;
+	use ndarray::{arr1, arr2, ArcArray, IxDyn};
+
+	contract_case!(unary "reduce_max", |x| reduce_max(x, 1, false));
+	contract_case!(unary "reduce_min", |x| reduce_min(x, 0, true));
+
+	fn input() -> Node {
+		// each row has a repeated extremum, so the tie breaking is exercised
+		Node::new(&[3, 8]).set_name("input").set_value(arr2(&[
+			[0.5, 3.0, -1.0, 3.0, 2.0, -1.0, 0.0, 1.0],
+			[-2.0, -2.0, 4.0, 1.5, 4.0, 0.0, -0.5, 2.0],
+			[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+		]))
+	}
+
+	/// Checks that exactly one element per row of the gradient is non-zero, at the expected index, with the full output
+	/// grad of one.
+	fn assert_routed(grad: ArcArray<f32, IxDyn>, expected: &[usize]) {
+		for (row, &i) in grad.outer_iter().zip(expected) {
+			assert_eq!(row.iter().filter(|&&g| g != 0.0).count(), 1);
+			assert_eq!(row[i], 1.0);
+		}
+	}
+
+	#[test]
+	fn forward_test() {
+		let input = input();
+
+		assert_eq!(
+			reduce_max(&input, 1, false).unwrap().calc().unwrap(),
+			arr1(&[3.0, 4.0, 1.0]).into_dyn()
+		);
+		assert_eq!(
+			reduce_min(&input, -1, true).unwrap().calc().unwrap(),
+			arr2(&[[-1.0], [-2.0], [1.0]]).into_dyn()
+		);
+		assert_eq!(
+			reduce_max(&input, 0, false).unwrap().calc().unwrap(),
+			arr1(&[1.0, 3.0, 4.0, 3.0, 4.0, 1.0, 1.0, 2.0]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn grad_max_routing_test() {
+		let input = input();
+		let output = reduce_max(&input, 1, false).unwrap();
+
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		// the first of tied maxima receives the whole gradient
+		assert_routed(grad.calc().unwrap(), &[1, 2, 0]);
+	}
+
+	#[test]
+	fn grad_min_routing_test() {
+		let input = input();
+		let output = Node::new(&[3, 1]).set_name("output");
+		ReduceMin::new(&input, &output).axis(1).keep_dims(true).build().unwrap();
+
+		let grad = Grad::of(&output)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+
+		assert_routed(grad.calc().unwrap(), &[2, 0, 0]);
+	}
+
+	#[test]
+	fn axis_test() {
+		let input = input();
+		let output = Node::new(&[8]).set_name("output");
+		ReduceMax::new(&input, &output).axis(0).build().unwrap();
+
+		assert_eq!(
+			output.calc().unwrap(),
+			reduce_max(&input, 0, false).unwrap().calc().unwrap()
+		);
+	}
+
+	#[test]
+	fn grad_numeric_max_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input").set_init(uniform(-1.0, 1.0));
+
+		let output = reduce_max(&input, 1, false).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+
+	#[test]
+	fn grad_numeric_min_test() {
+		let input = Node::new(&[13, 33, 7]).set_name("input").set_init(uniform(-1.0, 1.0));
+
+		let output = reduce_min(&input, -1, true).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input])
+			.step_size(1e-3)
+			.tolerance(4e-3)
+			.run();
+	}
+}
@@ -1,2 +1,6 @@
+pub mod cumprod;
+pub mod logsumexp;
+pub mod minmax;
+pub mod moments;
 pub mod reduce_prod;
 pub mod reduce_sum;
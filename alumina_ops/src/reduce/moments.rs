@@ -0,0 +1,524 @@
+use crate::reduce::reduce_sum::{calc_output_shape, regularise_axes};
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{ArrayD, ArrayViewD, Dimension, Zip};
+use smallvec::SmallVec;
+use std::any::Any;
+
+/// Calculates the mean and (population) variance of the input across the selected axes in a single `Op`.
+///
+/// This is equivalent to, but cheaper than:
+/// `let mean = reduce_mean(input, axes, keep_dims)`
+/// `let variance = reduce_mean(sqr(input - mean), axes, keep_dims)`
+///
+/// If axes is empty, all axes are reduced.
+///
+/// Returns `(mean, variance)`, both with the shape of the input with the reduced axes removed, or set to 1 if
+/// `keep_dims` is `true`.
+pub fn moments<I>(input: I, axes: &[isize], keep_dims: bool) -> Result<(Node, Node), OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let usize_axes = regularise_axes(axes, input.shape().len());
+
+	let output_shape: NodeShape = calc_output_shape(&input.shape(), &usize_axes, keep_dims);
+
+	let mean = input
+		.graph()
+		.new_node(output_shape.clone())
+		.set_name_unique(&format!("moments({})_mean", input));
+	let variance = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("moments({})_variance", input));
+
+	let _op = Moments::new(input, mean.clone(), variance.clone())
+		.axes(axes)
+		.keep_dims(keep_dims)
+		.build()?;
+
+	Ok((mean, variance))
+}
+
+/// Returns the shape the outputs would have if `keep_dims` were `true`, allowing them to be broadcast against the
+/// input.
+fn keep_dims_shape(input_shape: &[usize], axes: &[usize]) -> SmallVec<[usize; 8]> {
+	calc_output_shape(&input_shape.into(), axes, true)
+		.into_iter()
+		.map(NodeAxis::lower)
+		.collect()
+}
+
+/// Returns the mean and variance in the `keep_dims` shape, along with the number of elements reduced into each.
+fn calc_moments(input: &ArrayViewD<f32>, keep_shape: &[usize]) -> (ArrayD<f32>, ArrayD<f32>, f32) {
+	let mut mean = ArrayD::zeros(keep_shape);
+	let mut variance = ArrayD::zeros(keep_shape);
+	let count = (input.len() / mean.len().max(1)) as f32;
+
+	Zip::from(input)
+		.and_broadcast(mean.cell_view())
+		.for_each(|&input, mean| {
+			mean.set(mean.get() + input);
+		});
+	mean /= count;
+
+	Zip::from(input)
+		.and_broadcast(&mean)
+		.and_broadcast(variance.cell_view())
+		.for_each(|&input, &mean, variance| {
+			let diff = input - mean;
+			variance.set(variance.get() + diff * diff);
+		});
+	variance /= count;
+
+	(mean, variance, count)
+}
+
+/// `Moments` `OpBuilder`
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Moments {
+	input: Node,
+	mean: Node,
+	variance: Node,
+	axes: Vec<usize>,
+	keep_dims: bool,
+}
+
+impl Moments {
+	pub fn new<I, O1, O2>(input: I, mean: O1, variance: O2) -> Self
+	where
+		I: Into<Node>,
+		O1: Into<Node>,
+		O2: Into<Node>,
+	{
+		let input = input.into();
+		let mean = mean.into();
+		let variance = variance.into();
+		assert!(mean.shape().len() == variance.shape().len());
+		let axes = (0..input.shape().len()).collect();
+		Moments {
+			input,
+			mean,
+			variance,
+			axes,
+			keep_dims: false,
+		}
+	}
+
+	/// Supply which axes are to be reduced across.
+	///
+	/// If axes is empty, all axes are reduced.
+	/// Each element of `axes` can be in the range [-input.len(), input.len()).
+	///
+	/// Default: empty
+	///
+	/// # Panics
+	/// Panics if axes are outside of the valid range.
+	pub fn axes(mut self, axes: &[isize]) -> Self {
+		self.axes = regularise_axes(axes, self.input.shape().len());
+		self
+	}
+
+	/// If `true` the reduced axes still appear in the outputs with size 1, otherwise they are removed.
+	///
+	/// Default: `false`
+	pub fn keep_dims(mut self, keep_dims: bool) -> Self {
+		self.keep_dims = keep_dims;
+		self
+	}
+}
+
+impl OpSpecification for Moments {
+	type InstanceType = MomentsInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Moments"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.mean.clone(), self.variance.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			mean: mapping.get(&self.mean).unwrap_or(&self.mean).clone(),
+			variance: mapping.get(&self.variance).unwrap_or(&self.variance).clone(),
+			axes: self.axes.clone(),
+			keep_dims: self.keep_dims,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(MomentsInstance {
+			input: self.input.id(),
+			mean: self.mean.id(),
+			variance: self.variance.id(),
+			axes: self.axes,
+			keep_dims: self.keep_dims,
+		})
+	}
+}
+
+/// Moments OpInstance
+#[derive(Clone, Debug)]
+pub struct MomentsInstance {
+	input: NodeID,
+	mean: NodeID,
+	variance: NodeID,
+	axes: Vec<usize>,
+	keep_dims: bool,
+}
+
+impl OpInstance for MomentsInstance {
+	fn type_name(&self) -> &'static str {
+		"Moments"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Moments {
+			input: graph.node_from_id(self.input),
+			mean: graph.node_from_id(self.mean),
+			variance: graph.node_from_id(self.variance),
+			axes: self.axes.clone(),
+			keep_dims: self.keep_dims,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.mean, self.variance]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// only one of the outputs may lie on the path to the ys
+		let mean_grad = if ctx.contains(&self.mean) {
+			Some(ctx.grad_of(&self.mean))
+		} else {
+			None
+		};
+		let variance_grad = if ctx.contains(&self.variance) {
+			Some(ctx.grad_of(&self.variance))
+		} else {
+			None
+		};
+
+		MomentsBack::new(ctx.node(&self.input), ctx.grad_of(&self.input), self.axes.clone())
+			.mean_grad(mean_grad)
+			.variance_grad(variance_grad)
+			.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let output_shape: NodeShape =
+			calc_output_shape(&ctx.input_shape(&self.input).slice().into(), &self.axes, self.keep_dims);
+		ctx.merge_output_shape(&self.mean, &output_shape)?;
+		ctx.merge_output_shape(&self.variance, &output_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let keep_shape = keep_dims_shape(input.shape(), &self.axes);
+
+		let (mean, variance, _count) = calc_moments(&input, &keep_shape);
+
+		for (output, value) in [(&self.mean, mean), (&self.variance, variance)].iter() {
+			if ctx.is_required_output(output) {
+				let mut output = ctx.get_output(output).into_shape(keep_shape.as_slice()).expect("Alumina Bug: Moments should be guaranteed that the reshape is valid by shape_prop and that the output is contiguous");
+				output += value;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Optimised Backward pass for Moments Op.
+///
+/// The gradients of the mean and variance are optional, as either output may not contribute to the loss.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct MomentsBack {
+	input: Node,
+	input_grad: Node,
+	mean_grad: Option<Node>,
+	variance_grad: Option<Node>,
+	axes: Vec<usize>,
+}
+
+impl MomentsBack {
+	/// `axes` must already be regularised, i.e. sorted, deduplicated, and in the range [0, input.len()).
+	pub fn new<I, O>(input: I, input_grad: O, axes: Vec<usize>) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let input_grad = input_grad.into();
+		assert!(input.shape().len() == input_grad.shape().len());
+		assert!(axes.iter().all(|&axis| axis < input.shape().len()));
+		MomentsBack {
+			input,
+			input_grad,
+			mean_grad: None,
+			variance_grad: None,
+			axes,
+		}
+	}
+
+	/// The gradient of the mean output.
+	///
+	/// Default: None
+	pub fn mean_grad<I: Into<Node>>(mut self, mean_grad: Option<I>) -> Self {
+		self.mean_grad = mean_grad.map(Into::into);
+		self
+	}
+
+	/// The gradient of the variance output.
+	///
+	/// Default: None
+	pub fn variance_grad<I: Into<Node>>(mut self, variance_grad: Option<I>) -> Self {
+		self.variance_grad = variance_grad.map(Into::into);
+		self
+	}
+}
+
+impl OpSpecification for MomentsBack {
+	type InstanceType = MomentsBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"MomentsBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		let mut inputs = indexset![self.input.clone()];
+		inputs.extend(self.mean_grad.clone());
+		inputs.extend(self.variance_grad.clone());
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			mean_grad: self
+				.mean_grad
+				.as_ref()
+				.map(|node| mapping.get(node).unwrap_or(node).clone()),
+			variance_grad: self
+				.variance_grad
+				.as_ref()
+				.map(|node| mapping.get(node).unwrap_or(node).clone()),
+			axes: self.axes.clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(MomentsBackInstance {
+			input: self.input.id(),
+			input_grad: self.input_grad.id(),
+			mean_grad: self.mean_grad.as_ref().map(Node::id),
+			variance_grad: self.variance_grad.as_ref().map(Node::id),
+			axes: self.axes,
+		})
+	}
+}
+
+/// MomentsBack OpInstance
+#[derive(Clone, Debug)]
+pub struct MomentsBackInstance {
+	input: NodeID,
+	input_grad: NodeID,
+	mean_grad: Option<NodeID>,
+	variance_grad: Option<NodeID>,
+	axes: Vec<usize>,
+}
+
+impl OpInstance for MomentsBackInstance {
+	fn type_name(&self) -> &'static str {
+		"MomentsBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(MomentsBack {
+			input: graph.node_from_id(self.input),
+			input_grad: graph.node_from_id(self.input_grad),
+			mean_grad: self.mean_grad.map(|id| graph.node_from_id(id)),
+			variance_grad: self.variance_grad.map(|id| graph.node_from_id(id)),
+			axes: self.axes.clone(),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		let mut inputs = indexset![self.input];
+		inputs.extend(self.mean_grad);
+		inputs.extend(self.variance_grad);
+		inputs
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, _ctx: &mut GradientContext) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let input_shape = ctx.input_shape(&self.input).clone();
+		let keep_shape = keep_dims_shape(input_shape.slice(), &self.axes);
+		let keep_size: usize = keep_shape.iter().product();
+
+		for grad in self.mean_grad.iter().chain(&self.variance_grad) {
+			let grad_shape = ctx.input_shape(grad);
+			if grad_shape.size() != keep_size {
+				return Err(format!(
+					"MomentsBack requires the output grads to have the shape of the input {:?} reduced across axes {:?}, but found {:?}",
+					input_shape.slice(),
+					self.axes,
+					grad_shape.slice()
+				)
+				.into());
+			}
+		}
+
+		ctx.set_output_like(&self.input_grad, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let input = ctx.get_input(&self.input);
+		let keep_shape = keep_dims_shape(input.shape(), &self.axes);
+
+		let (mean, _variance, count) = calc_moments(&input, &keep_shape);
+
+		// d(mean)/dx = 1/n, d(variance)/dx = 2(x - mean)/n
+		let grad_or_zero = |grad: Option<NodeID>| -> ArrayD<f32> {
+			match grad {
+				Some(grad) => ctx
+					.get_input(&grad)
+					.into_shape(keep_shape.as_slice())
+					.expect("Alumina Bug: MomentsBack should be guaranteed that the reshape is valid by shape_prop")
+					.to_owned(),
+				None => ArrayD::zeros(keep_shape.as_slice()),
+			}
+		};
+		let mean_grad = grad_or_zero(self.mean_grad) / count;
+		let variance_grad = grad_or_zero(self.variance_grad) * (2.0 / count);
+
+		Zip::from(ctx.get_output(&self.input_grad))
+			.and(&input)
+			.and_broadcast(&mean)
+			.and_broadcast(&mean_grad)
+			.and_broadcast(&variance_grad)
+			.for_each(|input_grad, &input, &mean, &mean_grad, &variance_grad| {
+				*input_grad += mean_grad + variance_grad * (input - mean);
+			});
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::moments;
+	use crate::tests::contract_case;
+	use crate::{
+		elementwise::{identity::add, sqr::sqr, subtract::subtract},
+		math::broadcast::broadcast_fn,
+		reduce::reduce_sum::reduce_mean,
+	};
+	use alumina_core::{graph::Node, init::uniform};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
+	use indexmap::indexset;
+
+	contract_case!(unary "moments", |x| {
+		let (mean, variance) = moments(x, &[1], false)?;
+		crate::elementwise::identity::add(mean, variance)
+	});
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[5, 7, 9]).set_name("input").set_init(uniform(-2.0, 3.0));
+		input.set_value(input.init_array().unwrap());
+
+		for &(axes, keep_dims) in &[
+			(&[1isize][..], false),
+			(&[0, -1][..], true),
+			(&[][..], false),
+			(&[2][..], true),
+		] {
+			let (mean, variance) = moments(&input, axes, keep_dims).unwrap();
+
+			let expected_mean = reduce_mean(&input, axes, keep_dims).unwrap();
+			let kept_mean = reduce_mean(&input, axes, true).unwrap();
+			let expected_variance = reduce_mean(
+				sqr(broadcast_fn(subtract, &input, &kept_mean).unwrap()).unwrap(),
+				axes,
+				keep_dims,
+			)
+			.unwrap();
+
+			assert_eq!(mean.shape(), expected_mean.shape());
+			assert_eq!(variance.shape(), expected_variance.shape());
+			assert!(mean
+				.calc()
+				.unwrap()
+				.all_relatively_close(&expected_mean.calc().unwrap(), 1e-5));
+			assert!(variance
+				.calc()
+				.unwrap()
+				.all_relatively_close(&expected_variance.calc().unwrap(), 1e-5));
+		}
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input");
+
+		let (mean, variance) = moments(&input, &[0, 2], false).unwrap();
+		let output = add(&mean, &variance).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).tolerance(1e-3).run();
+	}
+
+	#[test]
+	fn grad_numeric_keep_dims_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input");
+
+		let (mean, variance) = moments(&input, &[1], true).unwrap();
+		let output = add(&mean, &variance).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn grad_numeric_variance_only_test() {
+		let input = Node::new(&[13, 7, 33]).set_name("input");
+
+		let (_mean, variance) = moments(&input, &[-1], false).unwrap();
+
+		GradNumericTest::new(&variance, &indexset![&input]).run();
+	}
+}
@@ -199,13 +199,18 @@ impl OpInstance for ReduceProdInstance {
 				.collect::<SmallVec<[usize; 8]>>();
 			let mut output = output.into_shape(output_shape.as_slice()).expect("Alumina Bug: ReduceProd should be guaranteed that the reshape is valid by shape_prop and that the output is contiguous");
 
-			let chunks: Vec<usize> = output_shape.iter().zip(input.shape()).map(|(o, i)| i / o).collect();
-
-			Zip::from(&mut output)
-				.and(input.exact_chunks(chunks))
-				.par_for_each(|output, input| {
-					*output += input.iter().fold(1.0, |prod, v| prod * v);
-				});
+			if input.is_empty() {
+				// the product of no elements is one, e.g. the number of elements in the shape of a scalar
+				output.par_map_inplace(|output| *output += 1.0);
+			} else {
+				let chunks: Vec<usize> = output_shape.iter().zip(input.shape()).map(|(o, i)| i / o).collect();
+
+				Zip::from(&mut output)
+					.and(input.exact_chunks(chunks))
+					.par_for_each(|output, input| {
+						*output += input.iter().fold(1.0, |prod, v| prod * v);
+					});
+			}
 		}
 
 		Ok(())
@@ -258,11 +263,14 @@ fn calc_output_shape(input_shape: &NodeShape, axes: &[usize], keep_dims: bool) -
 #[cfg(test)]
 mod tests {
 	use super::reduce_prod;
+	use crate::tests::contract_case;
 	use alumina_core::graph::Node;
 	use alumina_test::grad_numeric_test::GradNumericTest;
 	use indexmap::indexset;
 	use ndarray::{arr2, arr3};
 
+	contract_case!(unary "reduce_prod", |x| reduce_prod(x, &[1], false));
+
 	#[test]
 	fn forward_test() {
 		let input = Node::new(&[2, 3, 5])
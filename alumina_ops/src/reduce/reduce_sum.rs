@@ -228,7 +228,7 @@ impl OpInstance for ReduceSumInstance {
 }
 
 /// convert from wrapping isize axis numbering, to sorted, direct, deduplicated usize numbering
-fn regularise_axes(axes: &[isize], input_len: usize) -> Vec<usize> {
+pub(crate) fn regularise_axes(axes: &[isize], input_len: usize) -> Vec<usize> {
 	if axes.is_empty() {
 		return (0..input_len).collect();
 	}
@@ -249,7 +249,7 @@ fn regularise_axes(axes: &[isize], input_len: usize) -> Vec<usize> {
 	axes
 }
 
-fn calc_output_shape(input_shape: &NodeShape, axes: &[usize], keep_dims: bool) -> NodeShape {
+pub(crate) fn calc_output_shape(input_shape: &NodeShape, axes: &[usize], keep_dims: bool) -> NodeShape {
 	let output_len = input_shape.len() - if keep_dims { 0 } else { axes.len() };
 	let mut output_shape: SmallVec<[NodeAxis; 8]> = (0..output_len).map(|_| NodeAxis::known(1)).collect();
 	let mut axes_i = 0;
@@ -272,11 +272,16 @@ fn calc_output_shape(input_shape: &NodeShape, axes: &[usize], keep_dims: bool) -
 
 #[cfg(test)]
 mod tests {
-	use super::{reduce_mean, reduce_sum};
-	use alumina_core::graph::Node;
-	use alumina_test::grad_numeric_test::GradNumericTest;
+	use super::{reduce_mean, reduce_sum, ReduceSum};
+	use crate::elementwise::{sqr::sqr, subtract::subtract};
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, grad::Grad, graph::Node};
+	use alumina_test::{grad_numeric_test::GradNumericTest, relatively_close::RelClose};
 	use indexmap::indexset;
-	use ndarray::{arr2, arr3};
+	use ndarray::{arr0, arr2, arr3, ArrayD, Axis, IxDyn};
+
+	contract_case!(unary "reduce_sum", |x| reduce_sum(x, &[1], false));
+	contract_case!(unary "reduce_mean", |x| reduce_mean(x, &[0], true));
 
 	#[test]
 	fn forward_sum_test() {
@@ -311,6 +316,35 @@ mod tests {
 		assert_eq!(expected2, output2.calc().unwrap());
 	}
 
+	#[test]
+	fn keep_dims_test() {
+		let value = ArrayD::from_shape_fn(IxDyn(&[4, 5, 6]), |ix| (ix[0] * 30 + ix[1] * 6 + ix[2]) as f32);
+		let input = Node::new(&[4, 5, 6]).set_value(value.clone()).set_name("input");
+		let output_keep = Node::new(&[4, 1, 6]).set_name("output_keep");
+
+		let removed = reduce_sum(&input, &[1], false).unwrap();
+		ReduceSum::new(&input, &output_keep)
+			.axes(&[1])
+			.keep_dims(true)
+			.build()
+			.unwrap();
+
+		let expected = value.sum_axis(Axis(1));
+		assert_eq!(removed.calc().unwrap().shape(), &[4, 6]);
+		assert_eq!(removed.calc().unwrap(), expected);
+		assert_eq!(output_keep.calc().unwrap().shape(), &[4, 1, 6]);
+		assert_eq!(output_keep.calc().unwrap(), expected.insert_axis(Axis(1)));
+
+		// the output gradient is broadcast back along the reduced axis
+		let grad = Grad::of(&output_keep)
+			.wrt(&[&input])
+			.build()
+			.unwrap()
+			.swap_remove(&input)
+			.unwrap();
+		assert_eq!(grad.calc().unwrap(), ArrayD::from_elem(IxDyn(&[4, 5, 6]), 1.0));
+	}
+
 	#[test]
 	fn forward_mean_test() {
 		let input = Node::new(&[2, 3, 5])
@@ -373,4 +407,54 @@ mod tests {
 
 		GradNumericTest::new(&output, &indexset![&input]).run();
 	}
+
+	#[test]
+	fn grad_numeric_mean_last_axis_test() {
+		let input = Node::new(&[7, 11]).set_name("input");
+
+		let output = reduce_mean(&input, &[-1], false).unwrap().set_name("output");
+		let output_keep = reduce_mean(&input, &[-1], true).unwrap().set_name("output_keep");
+		assert_eq!(output.shape().slice(), &[7.into()]);
+		assert_eq!(output_keep.shape().slice(), &[7.into(), 1.into()]);
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+		GradNumericTest::new(&output_keep, &indexset![&input]).run();
+	}
+
+	#[test]
+	fn scalar_loss_test() {
+		let input = Node::new(&[2, 3])
+			.set_value(arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]))
+			.set_name("input");
+		let target = Node::new(&[2, 3])
+			.set_value(arr2(&[[1.0, 1.0, 1.0], [2.0, 2.0, 2.0]]))
+			.set_name("target");
+
+		let loss = reduce_mean(sqr(subtract(&input, &target).unwrap()).unwrap(), &[], false)
+			.unwrap()
+			.set_name("loss");
+		assert_eq!(loss.shape().len(), 0);
+
+		let loss_value = loss.calc().unwrap();
+		assert_eq!(loss_value.ndim(), 0);
+		assert!(loss_value.all_relatively_close(&arr0((0.0 + 1.0 + 4.0 + 4.0 + 9.0 + 16.0) / 6.0), 1e-6));
+
+		let sum_value = reduce_sum(&input, &[], false).unwrap().calc().unwrap();
+		assert_eq!(sum_value, arr0(21.0).into_dyn());
+
+		let grads = Grad::of(&loss).wrt(&[&input]).build().unwrap();
+		let input_grad = grads[&input].calc().unwrap();
+		assert_eq!(input_grad.shape(), &[2, 3]);
+		assert!(input_grad.all_relatively_close(&arr2(&[[0.0, 1.0, 2.0], [2.0, 3.0, 4.0]]).mapv(|x| x / 3.0), 1e-6));
+	}
+
+	#[test]
+	fn grad_numeric_scalar_mean_test() {
+		let input = Node::new(&[5, 3, 7]).set_name("input");
+
+		let output = reduce_mean(&input, &[], false).unwrap().set_name("output");
+		assert_eq!(output.shape().len(), 0);
+
+		GradNumericTest::new(&output, &indexset![&input]).run();
+	}
 }
@@ -0,0 +1,418 @@
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape::{NodeAxis, NodeShape},
+	shape_prop::ShapePropContext,
+	util::wrap_dim,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::{Axis, Dimension, Zip};
+use std::any::Any;
+
+/// Calculates the `n`-th order discrete difference of the input along `axis`.
+///
+/// For `n = 1`, `output[i] = input[i + 1] - input[i]`, and higher orders repeat the difference `n` times.
+///
+/// The output node has the shape of the input, except that `axis` is shorter by `n`.
+pub fn diff<I>(input: I, axis: isize, n: usize) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axis = wrap_dim(axis, input.shape().len());
+
+	let input_axis = input.shape().slice()[axis].clone();
+	if input_axis.upper() < n {
+		return Err(format!(
+			"diff of order {} requires at least {} elements along axis {}, but the input ({}) has shape {}",
+			n,
+			n,
+			axis,
+			input,
+			input.shape()
+		)
+		.into());
+	}
+
+	let mut output_shape = input.shape();
+	output_shape.slice_mut()[axis] = shrink_axis(&input_axis, n);
+
+	let output = input
+		.graph()
+		.new_node(output_shape)
+		.set_name_unique(&format!("diff({})", input));
+
+	Diff::new(input, output.clone(), axis, n).build()?;
+
+	Ok(output)
+}
+
+/// Shortens an axis by `n`, leaving an unbounded upper limit unbounded.
+fn shrink_axis(axis: &NodeAxis, n: usize) -> NodeAxis {
+	let (lower, upper) = axis.as_interval();
+	let upper = if upper == usize::MAX { upper } else { upper - n };
+	NodeAxis::interval(lower.saturating_sub(n), upper)
+}
+
+/// Returns the weights of `input[i + k]` in `output[i]` for an `n`-th order difference, `(-1)^(n - k) * C(n, k)`.
+fn coefficients(n: usize) -> Vec<f32> {
+	let mut coefficients = vec![0.0; n + 1];
+	let mut binomial = 1.0;
+	for (k, coefficient) in coefficients.iter_mut().enumerate() {
+		*coefficient = (-1.0f32).powi((n - k) as i32) * binomial;
+		binomial = binomial * (n - k) as f32 / (k + 1) as f32;
+	}
+	coefficients
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Diff {
+	input: Node,
+	output: Node,
+	axis: usize,
+	n: usize,
+}
+
+impl Diff {
+	pub fn new<I, O>(input: I, output: O, axis: usize, n: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same number of axes"
+		);
+		assert!(
+			axis < input.shape().len(),
+			"axis {} must be less than input.shape().len() {}",
+			axis,
+			input.shape().len()
+		);
+		Diff { input, output, axis, n }
+	}
+}
+
+impl OpSpecification for Diff {
+	type InstanceType = DiffInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Diff"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axis: self.axis,
+			n: self.n,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DiffInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			axis: self.axis,
+			n: self.n,
+		})
+	}
+}
+
+/// Diff OpInstance
+#[derive(Clone, Debug)]
+pub struct DiffInstance {
+	input: NodeID,
+	output: NodeID,
+	axis: usize,
+	n: usize,
+}
+
+impl OpInstance for DiffInstance {
+	fn type_name(&self) -> &'static str {
+		"Diff"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Diff {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			axis: self.axis,
+			n: self.n,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		DiffBack::new(ctx.grad_of(&self.output), ctx.grad_of(&self.input), self.axis, self.n).build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let mut output_shape = ctx.input_shape(&self.input).slice().to_vec();
+		if output_shape[self.axis] < self.n {
+			return Err(format!(
+				"Diff of order {} requires at least {} elements along axis {}, but the input has shape {:?}",
+				self.n, self.n, self.axis, output_shape
+			)
+			.into());
+		}
+		output_shape[self.axis] -= self.n;
+		ctx.merge_output_shape(&self.output, &output_shape.into())
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let coefficients = coefficients(self.n);
+		Zip::from(ctx.get_output(&self.output).lanes_mut(Axis(self.axis)))
+			.and(ctx.get_input(&self.input).lanes(Axis(self.axis)))
+			.par_for_each(|mut output, input| {
+				for (i, output) in output.iter_mut().enumerate() {
+					*output += coefficients
+						.iter()
+						.enumerate()
+						.fold(0.0, |sum, (k, c)| sum + c * input[i + k]);
+				}
+			});
+		Ok(())
+	}
+}
+
+/// Applies the adjoint of `Diff`, scattering each element of the output grad back over the `n + 1` inputs it was
+/// calculated from.
+///
+/// Input/Output naming convention matches Diff Input/Outputs, i.e. output_grad is an input to this Op.
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct DiffBack {
+	output_grad: Node,
+	input_grad: Node,
+	axis: usize,
+	n: usize,
+}
+
+impl DiffBack {
+	pub fn new<I, O>(output_grad: I, input_grad: O, axis: usize, n: usize) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let output_grad = output_grad.into();
+		let input_grad = input_grad.into();
+		assert!(
+			output_grad.shape().len() == input_grad.shape().len(),
+			"output_grad and input_grad must have the same number of axes"
+		);
+		assert!(
+			axis < output_grad.shape().len(),
+			"axis {} must be less than output_grad.shape().len() {}",
+			axis,
+			output_grad.shape().len()
+		);
+		DiffBack {
+			output_grad,
+			input_grad,
+			axis,
+			n,
+		}
+	}
+}
+
+impl OpSpecification for DiffBack {
+	type InstanceType = DiffBackInstance;
+
+	fn type_name(&self) -> &'static str {
+		"DiffBack"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.output_grad.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.input_grad.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			output_grad: mapping.get(&self.output_grad).unwrap_or(&self.output_grad).clone(),
+			input_grad: mapping.get(&self.input_grad).unwrap_or(&self.input_grad).clone(),
+			axis: self.axis,
+			n: self.n,
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(DiffBackInstance {
+			output_grad: self.output_grad.id(),
+			input_grad: self.input_grad.id(),
+			axis: self.axis,
+			n: self.n,
+		})
+	}
+}
+
+/// DiffBack OpInstance
+#[derive(Clone, Debug)]
+pub struct DiffBackInstance {
+	output_grad: NodeID,
+	input_grad: NodeID,
+	axis: usize,
+	n: usize,
+}
+
+impl OpInstance for DiffBackInstance {
+	fn type_name(&self) -> &'static str {
+		"DiffBack"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(DiffBack {
+			output_grad: graph.node_from_id(self.output_grad),
+			input_grad: graph.node_from_id(self.input_grad),
+			axis: self.axis,
+			n: self.n,
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output_grad]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input_grad]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// the adjoint of the adjoint is the difference itself
+		Diff::new(
+			ctx.grad_of(&self.input_grad),
+			ctx.grad_of(&self.output_grad),
+			self.axis,
+			self.n,
+		)
+		.build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		let mut input_grad_shape = ctx.input_shape(&self.output_grad).slice().to_vec();
+		input_grad_shape[self.axis] += self.n;
+		let input_grad_shape: NodeShape = input_grad_shape.into();
+		ctx.merge_output_shape(&self.input_grad, &input_grad_shape)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let coefficients = coefficients(self.n);
+		Zip::from(ctx.get_output(&self.input_grad).lanes_mut(Axis(self.axis)))
+			.and(ctx.get_input(&self.output_grad).lanes(Axis(self.axis)))
+			.par_for_each(|mut input_grad, output_grad| {
+				for (i, output_grad) in output_grad.iter().enumerate() {
+					for (k, c) in coefficients.iter().enumerate() {
+						input_grad[i + k] += c * output_grad;
+					}
+				}
+			});
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{diff, DiffBack};
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::{base_ops::OpSpecification, graph::Node};
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+	use ndarray::{arr2, ArrayD};
+
+	contract_case!(unary "diff", |x| diff(x, 1, 2));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 5])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0, 4.0, 7.0, 0.0], [3.0, 1.0, 4.0, 1.0, 5.0]]));
+
+		let first = diff(&input, 1, 1).unwrap();
+		assert_eq!(
+			first.calc().unwrap(),
+			arr2(&[[1.0, 2.0, 3.0, -7.0], [-2.0, 3.0, -3.0, 4.0]]).into_dyn()
+		);
+
+		let second = diff(&input, -1, 2).unwrap();
+		assert_eq!(
+			second.calc().unwrap(),
+			arr2(&[[1.0, 1.0, -10.0], [5.0, -6.0, 7.0]]).into_dyn()
+		);
+
+		let first_axis0 = diff(&input, 0, 1).unwrap();
+		assert_eq!(
+			first_axis0.calc().unwrap(),
+			arr2(&[[2.0, -1.0, 0.0, -6.0, 5.0]]).into_dyn()
+		);
+	}
+
+	#[test]
+	fn shape_error_test() {
+		let input = Node::new(&[2, 5]).set_name("input");
+		assert!(diff(&input, 1, 6).is_err());
+
+		let input = Node::new(&[2, -1])
+			.set_name("input")
+			.set_value(arr2(&[[1.0, 2.0], [3.0, 4.0]]));
+		let output = diff(&input, 1, 3).unwrap();
+		assert!(output.calc().is_err());
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for &(axis, n) in &[(1, 1), (1, 2), (0, 3)] {
+			let input = Node::new(&[7, 9, 5]).set_name("input");
+			let output = diff(&input, axis, n).unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input]).run();
+		}
+	}
+
+	#[test]
+	fn grad_numeric_back_test() {
+		let output_grad = Node::new(&[7, 6, 5]).set_name("output_grad");
+		let input_grad = Node::new(&[7, 9, 5]).set_name("input_grad");
+
+		let scale = Node::new(&[7, 9, 5])
+			.set_name("scale")
+			.set_value(ArrayD::from_shape_fn(vec![7, 9, 5], |ix| {
+				((ix[0] * 45 + ix[1] * 5 + ix[2]) as f32).sin()
+			}));
+
+		DiffBack::new(&output_grad, &input_grad, 1, 3).build().unwrap();
+
+		// the weights of each difference sum to zero, so the plain sum of the input grad is insensitive to the output
+		// grad
+		let output = mul(&input_grad, &scale).unwrap();
+
+		GradNumericTest::new(&output, &indexset![&output_grad]).run();
+	}
+}
@@ -0,0 +1,203 @@
+use crate::reduce::reduce_sum::regularise_axes;
+use alumina_core::{
+	base_ops::{OpInstance, OpSpecification},
+	errors::{ExecutionError, GradientError, OpBuildError, ShapePropError},
+	exec::ExecutionContext,
+	grad::GradientContext,
+	graph::{Graph, Node, NodeID},
+	shape_prop::ShapePropContext,
+};
+use indexmap::{indexset, IndexMap, IndexSet};
+use ndarray::Axis;
+use std::any::Any;
+
+/// Reverses the order of the elements of the input along each of the given axes.
+///
+/// If `axes` is empty all axes are reversed, and repeated axes are only reversed once.
+///
+/// The output node has the same shape as the input.
+pub fn flip<I>(input: I, axes: &[isize]) -> Result<Node, OpBuildError>
+where
+	I: Into<Node>,
+{
+	let input = input.into();
+	let axes = regularise_axes(axes, input.shape().len());
+
+	let output = input
+		.graph()
+		.new_node(input.shape())
+		.set_name_unique(&format!("flip({})", input));
+
+	let _op = Flip::new(&input, &output, &axes).build()?;
+
+	Ok(output)
+}
+
+#[must_use = "Op builder not used, call .build()"]
+#[derive(Clone, Debug)]
+pub struct Flip {
+	input: Node,
+	output: Node,
+	axes: Vec<usize>,
+}
+
+impl Flip {
+	pub fn new<I, O>(input: I, output: O, axes: &[usize]) -> Self
+	where
+		I: Into<Node>,
+		O: Into<Node>,
+	{
+		let input = input.into();
+		let output = output.into();
+		assert!(
+			input.shape().len() == output.shape().len(),
+			"output and input must have the same shape"
+		);
+		for &axis in axes {
+			assert!(
+				axis < input.shape().len(),
+				"axis {} must be less than input.shape().len() {}",
+				axis,
+				input.shape().len()
+			);
+		}
+		Flip {
+			input,
+			output,
+			axes: axes.to_vec(),
+		}
+	}
+}
+
+impl OpSpecification for Flip {
+	type InstanceType = FlipInstance;
+
+	fn type_name(&self) -> &'static str {
+		"Flip"
+	}
+
+	fn inputs(&self) -> IndexSet<Node> {
+		indexset![self.input.clone()]
+	}
+
+	fn outputs(&self) -> IndexSet<Node> {
+		indexset![self.output.clone()]
+	}
+
+	fn clone_with_nodes_changed(&self, mapping: &IndexMap<Node, Node>) -> Self {
+		Self {
+			input: mapping.get(&self.input).unwrap_or(&self.input).clone(),
+			output: mapping.get(&self.output).unwrap_or(&self.output).clone(),
+			axes: self.axes.clone(),
+		}
+	}
+
+	fn build_instance(self) -> Result<Self::InstanceType, OpBuildError> {
+		Ok(FlipInstance {
+			input: self.input.id(),
+			output: self.output.id(),
+			axes: self.axes,
+		})
+	}
+}
+
+/// Flip OpInstance
+#[derive(Clone, Debug)]
+pub struct FlipInstance {
+	input: NodeID,
+	output: NodeID,
+	axes: Vec<usize>,
+}
+
+impl OpInstance for FlipInstance {
+	fn type_name(&self) -> &'static str {
+		"Flip"
+	}
+
+	fn as_specification(&self, graph: &Graph) -> Box<dyn Any> {
+		Box::new(Flip {
+			input: graph.node_from_id(self.input),
+			output: graph.node_from_id(self.output),
+			axes: self.axes.clone(),
+		})
+	}
+
+	fn inputs(&self) -> IndexSet<NodeID> {
+		indexset![self.input]
+	}
+
+	fn outputs(&self) -> IndexSet<NodeID> {
+		indexset![self.output]
+	}
+
+	fn gradient(&self, ctx: &mut GradientContext) -> Result<(), GradientError> {
+		// a flip is its own inverse
+		let _op = Flip::new(ctx.grad_of(&self.output), ctx.grad_of(&self.input), &self.axes).build()?;
+		Ok(())
+	}
+
+	fn propagate_shapes(&self, ctx: &mut ShapePropContext) -> Result<(), ShapePropError> {
+		ctx.set_output_like(&self.output, &self.input)
+	}
+
+	fn execute(&self, ctx: &ExecutionContext) -> Result<(), ExecutionError> {
+		let mut input = ctx.get_input(&self.input);
+		for &axis in &self.axes {
+			input.invert_axis(Axis(axis));
+		}
+
+		let mut output = ctx.get_output(&self.output);
+		output += &input;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::flip;
+	use crate::elementwise::mul::mul;
+	use crate::tests::contract_case;
+	use alumina_core::graph::Node;
+	use alumina_test::grad_numeric_test::GradNumericTest;
+	use indexmap::indexset;
+	use ndarray::{arr2, ArrayD};
+
+	contract_case!(unary "flip", |x| flip(x, &[0, -1]));
+
+	#[test]
+	fn forward_test() {
+		let input = Node::new(&[2, 3])
+			.set_name("input")
+			.set_value(arr2(&[[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]));
+
+		let flips: [(&[isize], _); 5] = [
+			(&[1], arr2(&[[2.0, 1.0, 0.0], [5.0, 4.0, 3.0]])),
+			(&[0], arr2(&[[3.0, 4.0, 5.0], [0.0, 1.0, 2.0]])),
+			(&[-1, 0], arr2(&[[5.0, 4.0, 3.0], [2.0, 1.0, 0.0]])),
+			(&[], arr2(&[[5.0, 4.0, 3.0], [2.0, 1.0, 0.0]])),
+			(&[1, -1], arr2(&[[2.0, 1.0, 0.0], [5.0, 4.0, 3.0]])),
+		];
+		for (axes, expected) in &flips {
+			let output = flip(&input, axes).unwrap();
+			assert_eq!(output.calc().unwrap(), expected.clone().into_dyn());
+		}
+	}
+
+	#[test]
+	fn grad_numeric_test() {
+		for axes in &[&[1][..], &[0, 2], &[]] {
+			let input = Node::new(&[7, 9, 5]).set_name("input");
+			let scale = Node::new(&[7, 9, 5])
+				.set_name("scale")
+				.set_value(ArrayD::from_shape_fn(vec![7, 9, 5], |ix| {
+					((ix[0] * 45 + ix[1] * 5 + ix[2]) as f32).sin()
+				}));
+
+			// the plain sum of the output is the same for any flip, so weight it to check which elements were reversed
+			let output = mul(flip(&input, axes).unwrap(), &scale).unwrap();
+
+			GradNumericTest::new(&output, &indexset![&input]).run();
+		}
+	}
+}
@@ -1,3 +1,5 @@
+pub mod diff;
+pub mod flip;
 pub mod linterp;
 pub mod pixel_shuffle;
 pub mod shape_of;
@@ -0,0 +1,280 @@
+//! Tests of contracts which every Op must uphold.
+
+use crate::{elementwise::identity::add, reduce::reduce_sum::reduce_sum};
+use alumina_core::{
+	errors::OpBuildError,
+	exec::{BufferPool, ExecutionPlan},
+	grad::Grad,
+	graph::Node,
+};
+use alumina_test::relatively_close::RelClose;
+use indexmap::{IndexMap, IndexSet};
+use ndarray::{ArrayD, Dimension};
+
+/// A case for the contract tests, registered using `contract_case!(..)` by the test module of the Op it covers.
+pub(crate) struct ContractCase {
+	pub name: &'static str,
+
+	/// Builds the case on fresh inputs, returning the output and the inputs to take gradients w.r.t.
+	pub build: fn() -> (Node, Vec<Node>),
+}
+
+inventory::collect!(ContractCase);
+
+/// Registers a case for the contract tests. Invoke this in the test module of each new Op, and add the name to
+/// `EXPECTED_CASES`.
+///
+/// `contract_case!(unary "name", f)` applies `f` to a fresh `[4, 5]` input, and `contract_case!(binary "name", f)` to
+/// two. Otherwise the second argument is a closure which builds the inputs and returns `(output, inputs)`.
+macro_rules! contract_case {
+	(unary $name:expr, $f:expr) => {
+		$crate::tests::contract_case!($name, || $crate::tests::unary_case($f));
+	};
+	(binary $name:expr, $f:expr) => {
+		$crate::tests::contract_case!($name, || $crate::tests::binary_case($f));
+	};
+	($name:expr, $build:expr) => {
+		const _: () = {
+			fn build() -> (alumina_core::graph::Node, Vec<alumina_core::graph::Node>) {
+				($build)()
+			}
+
+			inventory::submit! {
+				$crate::tests::ContractCase { name: $name, build }
+			}
+		};
+	};
+}
+
+pub(crate) use contract_case;
+
+/// An input with a fixed value, positive and away from zero so that it is in the domain of every Op.
+pub(crate) fn input(name: &str, shape: &[usize]) -> Node {
+	Node::new(shape)
+		.set_name(name)
+		.set_value(ArrayD::from_shape_fn(shape.to_vec(), |ix| {
+			1.25 + (ix.slice().iter().fold(0, |sum, &i| sum * 7 + i + 1) as f32).sin() * 0.75
+		}))
+}
+
+pub(crate) fn unary_case<F>(f: F) -> (Node, Vec<Node>)
+where
+	F: FnOnce(Node) -> Result<Node, OpBuildError>,
+{
+	let x = input("x", &[4, 5]);
+	(f(x.clone()).unwrap(), vec![x])
+}
+
+pub(crate) fn binary_case<F>(f: F) -> (Node, Vec<Node>)
+where
+	F: FnOnce(Node, Node) -> Result<Node, OpBuildError>,
+{
+	let (x, y) = (input("x", &[4, 5]), input("y", &[4, 5]));
+	(f(x.clone(), y.clone()).unwrap(), vec![x, y])
+}
+
+/// Adds 5 to the diagonal of each matrix in the batch `m`, making an `input(..)` diagonally dominant, and so positive
+/// definite and well conditioned.
+pub(crate) fn positive_definite(m: &Node) -> Node {
+	let shape = m
+		.value()
+		.expect("positive_definite requires an input with a value")
+		.shape()
+		.to_vec();
+	let n = shape.len();
+	let shift = Node::new(&shape)
+		.set_name("shift")
+		.set_value(ArrayD::from_shape_fn(shape, |ix| {
+			if ix[n - 2] == ix[n - 1] {
+				5.0
+			} else {
+				0.0
+			}
+		}));
+	add(m, shift).unwrap()
+}
+
+/// Builds every registered case, returning the case name, the output, and the inputs to take gradients w.r.t.
+fn cases() -> impl Iterator<Item = (&'static str, Node, Vec<Node>)> {
+	inventory::iter::<ContractCase>.into_iter().map(|case| {
+		let (output, inputs) = (case.build)();
+		(case.name, output, inputs)
+	})
+}
+
+/// The name of every case which should be registered, so that an Op whose registration is missing fails
+/// `registered_cases_test` rather than silently going untested.
+const EXPECTED_CASES: &[&str] = &[
+	"abs",
+	"add",
+	"atan2",
+	"bce_with_logits",
+	"blend",
+	"causal_mask",
+	"cholesky",
+	"clamp",
+	"cos",
+	"cosh",
+	"cosine_similarity",
+	"cumprod",
+	"diagonal",
+	"diff",
+	"div",
+	"dropout",
+	"erf",
+	"erfc",
+	"exp",
+	"expm1",
+	"flip",
+	"focal_loss",
+	"gelu",
+	"group_norm",
+	"gumbel_softmax",
+	"instance_norm",
+	"inverse",
+	"kl_div",
+	"leaky_relu",
+	"ln",
+	"log",
+	"log1p",
+	"logdet",
+	"logistic",
+	"logsumexp",
+	"matmul",
+	"max",
+	"maximum_scalar",
+	"min",
+	"minimum_scalar",
+	"mish",
+	"moments",
+	"mul",
+	"muldiv",
+	"neg",
+	"one_minus",
+	"outer",
+	"pairwise_l2",
+	"pow",
+	"reciprocal",
+	"reduce_max",
+	"reduce_mean",
+	"reduce_min",
+	"reduce_prod",
+	"reduce_sum",
+	"relu",
+	"roll",
+	"scalar_pow",
+	"sigmoid",
+	"silu",
+	"sin",
+	"sinh",
+	"softmax",
+	"softmax_cross_entropy",
+	"softplus",
+	"solve",
+	"spectral_norm",
+	"sqr",
+	"sqrt",
+	"square",
+	"subtract",
+	"take_along_axis",
+	"tan",
+	"tanh",
+	"trace",
+	"weight_norm",
+];
+
+#[test]
+fn registered_cases_test() {
+	let names: Vec<&str> = inventory::iter::<ContractCase>
+		.into_iter()
+		.map(|case| case.name)
+		.collect();
+	let unique: IndexSet<&str> = names.iter().cloned().collect();
+	assert_eq!(unique.len(), names.len(), "case names must be unique: {:?}", names);
+
+	let expected: IndexSet<&str> = EXPECTED_CASES.iter().cloned().collect();
+	let missing: Vec<&&str> = expected.difference(&unique).collect();
+	let unexpected: Vec<&&str> = unique.difference(&expected).collect();
+	assert!(
+		missing.is_empty() && unexpected.is_empty(),
+		"cases are missing: {:?}, cases are not in EXPECTED_CASES: {:?}",
+		missing,
+		unexpected
+	);
+}
+
+/// Ops accumulate (`+=`) into their outputs, so output and gradient buffers must be zeroed before each execution.
+///
+/// Executing the same plan a second time must not double the results, and no Op may overwrite a buffer which another
+/// Op has already written to.
+#[test]
+fn zeroed_output_contract_test() {
+	for (name, output, inputs) in cases() {
+		// sum the output so that every gradient has a fully known shape
+		let loss = reduce_sum(&output, &[], false).unwrap();
+		let grads = Grad::of(&loss).wrt(&inputs).build().unwrap();
+		let nodes: IndexSet<Node> = Some(output).into_iter().chain(grads.values().cloned()).collect();
+
+		let mut plan = ExecutionPlan::new(IndexMap::<Node, _>::new(), &nodes);
+		let first = plan.execute().unwrap();
+		let second = plan.execute().unwrap();
+
+		let checked = ExecutionPlan::new(IndexMap::<Node, _>::new(), &nodes)
+			.check_accumulation(true)
+			.execute()
+			.unwrap_or_else(|err| panic!("{}: {}", name, err));
+
+		for node in &nodes {
+			assert!(
+				first[node].all_relatively_close(&second[node], 1e-6),
+				"{}: second execution of {} differs",
+				name,
+				node
+			);
+			assert!(
+				first[node].all_relatively_close(&checked[node], 1e-6),
+				"{}: checked execution of {} differs",
+				name,
+				node
+			);
+		}
+	}
+}
+
+/// Executing with a `BufferPool` reuses arrays from earlier executions, which must not change the results.
+#[test]
+fn buffer_pool_contract_test() {
+	for (name, output, inputs) in cases() {
+		let loss = reduce_sum(&output, &[], false).unwrap();
+		let grads = Grad::of(&loss).wrt(&inputs).build().unwrap();
+		let nodes: IndexSet<Node> = Some(output).into_iter().chain(grads.values().cloned()).collect();
+
+		let fresh = ExecutionPlan::new(IndexMap::<Node, _>::new(), &nodes)
+			.execute()
+			.unwrap();
+
+		let mut pool = BufferPool::new();
+		for i in 0..2 {
+			let pooled = ExecutionPlan::new(IndexMap::<Node, _>::new(), &nodes)
+				.buffer_pool(Some(&mut pool))
+				.execute()
+				.unwrap_or_else(|err| panic!("{}: {}", name, err));
+
+			for node in &nodes {
+				assert!(
+					fresh[node].all_relatively_close(&pooled[node], 1e-6),
+					"{}: pooled execution {} of {} differs",
+					name,
+					i,
+					node
+				);
+			}
+
+			// hand the outputs back so that they are reused, dirty, by the next execution
+			for (_node, value) in pooled {
+				pool.recycle(value);
+			}
+		}
+		assert!(pool.reuses() > 0, "{}: no arrays were reused", name);
+	}
+}
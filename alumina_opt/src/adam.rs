@@ -1,4 +1,8 @@
-use crate::{calc_change_sqr, GradientStepper};
+use crate::{
+	calc_change_sqr,
+	checkpoint::{CheckpointError, StepperState},
+	GradientStepper,
+};
 use alumina_core::{errors::ExecError, graph::Node};
 use indexmap::{indexmap, IndexMap};
 use ndarray::{ArcArray, ArrayD, IxDyn, Zip};
@@ -19,6 +23,8 @@ pub struct Adam {
 	beta2: f32,
 	epsilon: f32,
 	bias_correct: bool,
+	weight_decay: f32,
+	decoupled: bool,
 	states: IndexMap<Node, NodeState>,
 }
 
@@ -33,6 +39,8 @@ impl Adam {
 			beta2,
 			epsilon: 1e-7,
 			bias_correct: true,
+			weight_decay: 0.0,
+			decoupled: false,
 			states: indexmap![],
 		}
 	}
@@ -100,6 +108,28 @@ impl Adam {
 		self.bias_correct = bias_correct;
 		self
 	}
+
+	/// Weight decay coefficient, λ
+	///
+	/// If `decoupled` is false this is equivalent to L2 regularisation, with `λ θ` added to the gradient before the
+	/// moment estimates are updated.
+	///
+	/// Default: 0.0
+	pub fn weight_decay(&mut self, weight_decay: f32) -> &mut Self {
+		self.weight_decay = weight_decay;
+		self
+	}
+
+	/// Should weight decay be decoupled from the gradient (AdamW).
+	///
+	/// If true the decay is applied directly to the parameter, θ = θ - α λ θ, rather than being folded into the
+	/// gradient where it would be rescaled by the curvature estimate.
+	///
+	/// Default: false
+	pub fn decoupled(&mut self, decoupled: bool) -> &mut Self {
+		self.decoupled = decoupled;
+		self
+	}
 }
 
 impl GradientStepper for Adam {
@@ -107,6 +137,37 @@ impl GradientStepper for Adam {
 		self.step_count
 	}
 
+	fn state(&self) -> Result<StepperState, CheckpointError> {
+		Ok(StepperState {
+			step_count: self.step_count,
+			buffers: self
+				.states
+				.iter()
+				.map(|(param, state)| (param.clone(), vec![state.momentums.clone(), state.curvatures.clone()]))
+				.collect(),
+		})
+	}
+
+	fn set_state(&mut self, state: StepperState) -> Result<(), CheckpointError> {
+		let mut states = indexmap![];
+		for (param, buffers) in state.buffers {
+			let mut buffers = buffers.into_iter();
+			match (buffers.next(), buffers.next(), buffers.next()) {
+				(Some(momentums), Some(curvatures), None) => {
+					states.insert(param, NodeState { momentums, curvatures });
+				},
+				_ => {
+					return Err(CheckpointError::InvalidState {
+						desc: format!("Adam requires 2 buffers for each parameter, {} does not have 2", param),
+					})
+				},
+			}
+		}
+		self.states = states;
+		self.step_count = state.step_count;
+		Ok(())
+	}
+
 	fn step(
 		&mut self,
 		mut parameters_and_grad_values: IndexMap<Node, ArcArray<f32, IxDyn>>,
@@ -140,6 +201,11 @@ impl GradientStepper for Adam {
 		let epsilon = self.epsilon;
 		//let calc_change = options.calc_change;
 		let bias_correct = self.bias_correct;
+		let (l2_decay, decoupled_decay) = if self.decoupled {
+			(0.0, self.rate * self.weight_decay)
+		} else {
+			(self.weight_decay, 0.0)
+		};
 		let momentum_correction = 1.0 / (1.0 - self.beta1.powi(self.step_count as i32 + 1));
 		let curv_correction = 1.0 / (1.0 - self.beta2.powi(self.step_count as i32 + 1));
 
@@ -174,22 +240,26 @@ impl GradientStepper for Adam {
 							.and(&mut state.momentums)
 							.and(&mut state.curvatures)
 							.and(grad_arr.view_mut())
-							.for_each(|param, momentum, curv, grad| {
-								*momentum = *momentum * beta1 + (1.0 - beta1) * *grad;
-								*curv = *curv * beta2 + (1.0 - beta1) * *grad * *grad;
+							.for_each(|&param, momentum, curv, grad| {
+								let g = *grad + l2_decay * param;
+								*momentum = *momentum * beta1 + (1.0 - beta1) * g;
+								*curv = *curv * beta2 + (1.0 - beta1) * g * g;
 								*grad = param
-									- rate * (*momentum) * momentum_correction
-										/ ((*curv * curv_correction).sqrt() + epsilon);
+									- decoupled_decay * param - rate * (*momentum) * momentum_correction
+									/ ((*curv * curv_correction).sqrt() + epsilon);
 							});
 					} else {
 						Zip::from(&param_arr)
 							.and(&mut state.momentums)
 							.and(&mut state.curvatures)
 							.and(grad_arr.view_mut())
-							.for_each(|param, momentum, curv, grad| {
-								*momentum = *momentum * beta1 + (1.0 - beta1) * *grad;
-								*curv = *curv * beta2 + (1.0 - beta1) * *grad * *grad;
-								*grad = param - rate * (*momentum) / ((*curv * curv_correction).sqrt() + epsilon);
+							.for_each(|&param, momentum, curv, grad| {
+								let g = *grad + l2_decay * param;
+								*momentum = *momentum * beta1 + (1.0 - beta1) * g;
+								*curv = *curv * beta2 + (1.0 - beta1) * g * g;
+								*grad = param
+									- decoupled_decay * param - rate * (*momentum)
+									/ ((*curv * curv_correction).sqrt() + epsilon);
 							});
 					}
 
@@ -213,3 +283,39 @@ impl GradientStepper for Adam {
 		//})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::Adam;
+	use crate::GradientStepper;
+	use alumina_core::graph::Node;
+	use indexmap::indexmap;
+	use ndarray::{arr1, ArcArray, IxDyn};
+
+	fn step_once(adam: &mut Adam) -> ArcArray<f32, IxDyn> {
+		let param = Node::new(&[3]).set_name("param").set_value(arr1(&[1.0, -2.0, 4.0]));
+		let grad = arr1(&[0.5, 0.5, -0.5]).into_shared().into_dyn();
+		adam.step(indexmap![param.clone() => grad], false).unwrap();
+		param.value().unwrap()
+	}
+
+	#[test]
+	fn decoupled_weight_decay_test() {
+		let plain = step_once(&mut Adam::new(1e-2, 0.9, 0.995));
+		let l2 = step_once(Adam::new(1e-2, 0.9, 0.995).weight_decay(0.1));
+		let decoupled = step_once(Adam::new(1e-2, 0.9, 0.995).weight_decay(0.1).decoupled(true));
+
+		// On the first step the L2 term is normalised away by the curvature estimate as it doesnt change the sign of
+		// any gradient element, whereas decoupled decay shrinks each parameter by α λ θ.
+		for (((&p, &l), &d), &init) in plain
+			.iter()
+			.zip(l2.iter())
+			.zip(decoupled.iter())
+			.zip(&[1.0f32, -2.0, 4.0])
+		{
+			assert!((l - p).abs() < 1e-6);
+			assert!((d - (p - 1e-2 * 0.1 * init)).abs() < 1e-6);
+			assert!((d - l).abs() > 1e-4);
+		}
+	}
+}
@@ -0,0 +1,561 @@
+//! Saving and restoring training state so that optimisation can be resumed.
+//!
+//! A checkpoint contains the value of every node tagged `Parameter` in a graph, along with the state of a
+//! `GradientStepper` (e.g. the moment estimates of `Adam` and its step count). Parameters are identified by name, so
+//! parameter names must be unique within the graph, and a checkpoint can be loaded into a freshly constructed graph
+//! with the same parameter names and shapes.
+//!
+//! There is currently no learning rate scheduler with state of its own, schedules applied via callbacks should derive
+//! the rate from `GradientStepper::step_count()`, which is restored.
+//!
+//! The `RngState` of the training loop can also be stored, so that Ops such as dropout continue their sequence of random
+//! values rather than repeating it. Streams are identified by the seed each Op was built with, so for the sequence to
+//! continue in a freshly constructed graph those Ops must be built with an explicit seed.
+//!
+//! The file format is little endian:
+//! * magic `b"ALUMCKPT"`, followed by a `u32` format version
+//! * `u64` step count
+//! * `u32` parameter count, then for each parameter: name, array
+//! * `u32` buffer set count, then for each set: parameter name, `u32` array count, arrays
+//! * `u32` random number stream count, then for each stream: `u64` seed, `u64` step
+//!
+//! Names are a `u32` byte length followed by UTF-8 bytes. Arrays are a `u32` rank, a `u64` per axis, and then the
+//! `f32` elements in logical (row major) order.
+
+use crate::GradientStepper;
+use alumina_core::{
+	exec::RngState,
+	graph::{Graph, Node, NodeTag},
+};
+use indexmap::{indexmap, IndexMap};
+use ndarray::{ArrayD, ArrayViewD, Dimension, IxDyn};
+use std::{
+	error::Error,
+	fmt,
+	fs::File,
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"ALUMCKPT";
+const VERSION: u32 = 1;
+
+/// Error returned when saving or loading a checkpoint.
+#[derive(Debug)]
+pub enum CheckpointError {
+	/// The underlying reader or writer returned an error.
+	Io(io::Error),
+
+	/// The file is not a checkpoint, or is truncated or corrupt.
+	Format { desc: String },
+
+	/// More than one parameter in the graph has this name.
+	DuplicateName { name: String },
+
+	/// A parameter in the graph has no value to save.
+	NoValue { name: String },
+
+	/// The checkpoint contains a parameter which is not in the graph.
+	UnknownParameter { name: String },
+
+	/// A parameter in the graph is not in the checkpoint.
+	MissingParameter { name: String },
+
+	/// A parameter in the checkpoint does not have the shape of the parameter in the graph.
+	ShapeMismatch {
+		name: String,
+		expected: Vec<usize>,
+		found: Vec<usize>,
+	},
+
+	/// The stored state can not be restored into this `GradientStepper`.
+	InvalidState { desc: String },
+
+	/// The `GradientStepper` does not support saving and restoring its state.
+	Unsupported,
+}
+
+impl fmt::Display for CheckpointError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CheckpointError::Io(err) => write!(f, "CheckpointError::Io Reading or writing checkpoint failed: {}", err),
+			CheckpointError::Format { desc } => write!(f, "CheckpointError::Format Invalid checkpoint: {}", desc),
+			CheckpointError::DuplicateName { name } => write!(
+				f,
+				"CheckpointError::DuplicateName More than one parameter is named '{}'",
+				name
+			),
+			CheckpointError::NoValue { name } => {
+				write!(f, "CheckpointError::NoValue Parameter '{}' has no value", name)
+			},
+			CheckpointError::UnknownParameter { name } => write!(
+				f,
+				"CheckpointError::UnknownParameter Checkpoint contains '{}' which is not a parameter of the graph",
+				name
+			),
+			CheckpointError::MissingParameter { name } => write!(
+				f,
+				"CheckpointError::MissingParameter Parameter '{}' is not in the checkpoint",
+				name
+			),
+			CheckpointError::ShapeMismatch { name, expected, found } => write!(
+				f,
+				"CheckpointError::ShapeMismatch Parameter '{}' has shape {:?} but the checkpoint value has shape {:?}",
+				name, expected, found
+			),
+			CheckpointError::InvalidState { desc } => {
+				write!(
+					f,
+					"CheckpointError::InvalidState Stepper state can not be restored: {}",
+					desc
+				)
+			},
+			CheckpointError::Unsupported => write!(
+				f,
+				"CheckpointError::Unsupported The stepper does not support saving and restoring its state"
+			),
+		}
+	}
+}
+
+impl Error for CheckpointError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			CheckpointError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for CheckpointError {
+	fn from(err: io::Error) -> Self {
+		if err.kind() == io::ErrorKind::UnexpectedEof {
+			CheckpointError::Format {
+				desc: "unexpected end of file".to_string(),
+			}
+		} else {
+			CheckpointError::Io(err)
+		}
+	}
+}
+
+/// The internal state of a `GradientStepper` required to resume optimisation.
+#[derive(Clone, Debug, Default)]
+pub struct StepperState {
+	pub step_count: usize,
+
+	/// Per parameter buffers, e.g. `Adam` stores the momentum and curvature estimates, in that order.
+	pub buffers: IndexMap<Node, Vec<ArrayD<f32>>>,
+}
+
+/// Writes the value of each parameter in the graph, the state of the stepper, and the random number state if Some, to
+/// the file at `path`.
+pub fn save_checkpoint<P, S>(
+	path: P,
+	graph: &Graph,
+	stepper: &S,
+	rng_state: Option<&RngState>,
+) -> Result<(), CheckpointError>
+where
+	P: AsRef<Path>,
+	S: GradientStepper,
+{
+	let params = named_parameters(graph)?;
+	let state = stepper.state()?;
+	let mut writer = BufWriter::new(File::create(path)?);
+	write_checkpoint(&mut writer, &params, &state, rng_state)?;
+	writer.flush()?;
+	Ok(())
+}
+
+/// Reads a checkpoint written by `save_checkpoint(..)`, setting the value of each parameter in the graph and restoring
+/// the state of the stepper, and the random number state if Some.
+///
+/// Every parameter of the graph must be in the checkpoint and vice versa. Nothing is modified if an error is returned.
+pub fn load_checkpoint<P, S>(
+	path: P,
+	graph: &Graph,
+	stepper: &mut S,
+	rng_state: Option<&mut RngState>,
+) -> Result<(), CheckpointError>
+where
+	P: AsRef<Path>,
+	S: GradientStepper,
+{
+	let params = named_parameters(graph)?;
+	let (values, state, stored_rng_state) = read_checkpoint(&mut BufReader::new(File::open(path)?), &params)?;
+	stepper.set_state(state)?;
+	for (param, value) in values {
+		param.set_value(value);
+	}
+	if let Some(rng_state) = rng_state {
+		*rng_state = stored_rng_state;
+	}
+	Ok(())
+}
+
+fn named_parameters(graph: &Graph) -> Result<IndexMap<String, Node>, CheckpointError> {
+	let mut params = indexmap![];
+	for param in graph.nodes_tagged(NodeTag::Parameter) {
+		let name = param.name();
+		if params.insert(name.clone(), param).is_some() {
+			return Err(CheckpointError::DuplicateName { name });
+		}
+	}
+	Ok(params)
+}
+
+fn write_checkpoint<W: Write>(
+	writer: &mut W,
+	params: &IndexMap<String, Node>,
+	state: &StepperState,
+	rng_state: Option<&RngState>,
+) -> Result<(), CheckpointError> {
+	writer.write_all(MAGIC)?;
+	writer.write_all(&VERSION.to_le_bytes())?;
+	writer.write_all(&(state.step_count as u64).to_le_bytes())?;
+
+	writer.write_all(&(params.len() as u32).to_le_bytes())?;
+	for (name, param) in params {
+		let value = param
+			.value()
+			.ok_or_else(|| CheckpointError::NoValue { name: name.clone() })?;
+		write_name(writer, name)?;
+		write_array(writer, value.view())?;
+	}
+
+	writer.write_all(&(state.buffers.len() as u32).to_le_bytes())?;
+	for (param, arrs) in &state.buffers {
+		write_name(writer, &param.name())?;
+		writer.write_all(&(arrs.len() as u32).to_le_bytes())?;
+		for arr in arrs {
+			write_array(writer, arr.view())?;
+		}
+	}
+
+	let steps: Vec<(u64, u64)> = rng_state.into_iter().flat_map(RngState::steps).collect();
+	writer.write_all(&(steps.len() as u32).to_le_bytes())?;
+	for (seed, step) in steps {
+		writer.write_all(&seed.to_le_bytes())?;
+		writer.write_all(&step.to_le_bytes())?;
+	}
+	Ok(())
+}
+
+#[allow(clippy::type_complexity)]
+fn read_checkpoint<R: Read>(
+	reader: &mut R,
+	params: &IndexMap<String, Node>,
+) -> Result<(Vec<(Node, ArrayD<f32>)>, StepperState, RngState), CheckpointError> {
+	let mut magic = [0u8; 8];
+	reader.read_exact(&mut magic)?;
+	if &magic != MAGIC {
+		return Err(CheckpointError::Format {
+			desc: "not a checkpoint file".to_string(),
+		});
+	}
+	let version = read_u32(reader)?;
+	if version != VERSION {
+		return Err(CheckpointError::Format {
+			desc: format!("unsupported version {}", version),
+		});
+	}
+	let step_count = read_u64(reader)? as usize;
+
+	let lookup = |name: &str, arrs: &[ArrayD<f32>]| -> Result<Node, CheckpointError> {
+		let param = params
+			.get(name)
+			.ok_or_else(|| CheckpointError::UnknownParameter { name: name.to_string() })?;
+		let expected = param.shape().to_data_shape().map(|s| s.slice().to_vec()).ok();
+		for arr in arrs {
+			if expected.as_deref() != Some(arr.shape()) {
+				return Err(CheckpointError::ShapeMismatch {
+					name: name.to_string(),
+					expected: expected.unwrap_or_default(),
+					found: arr.shape().to_vec(),
+				});
+			}
+		}
+		Ok(param.clone())
+	};
+
+	let param_count = read_u32(reader)?;
+	let mut values: Vec<(Node, ArrayD<f32>)> = vec![];
+	for _ in 0..param_count {
+		let name = read_name(reader)?;
+		let arr = read_array(reader)?;
+		let param = lookup(&name, std::slice::from_ref(&arr))?;
+		values.push((param, arr));
+	}
+	for (name, param) in params {
+		if !values.iter().any(|(p, _)| p == param) {
+			return Err(CheckpointError::MissingParameter { name: name.clone() });
+		}
+	}
+
+	let buffer_count = read_u32(reader)?;
+	let mut buffers = indexmap![];
+	for _ in 0..buffer_count {
+		let name = read_name(reader)?;
+		let arr_count = read_u32(reader)?;
+		let arrs = (0..arr_count)
+			.map(|_| read_array(reader))
+			.collect::<Result<Vec<_>, _>>()?;
+		buffers.insert(lookup(&name, &arrs)?, arrs);
+	}
+
+	let mut rng_state = RngState::new();
+	let stream_count = read_u32(reader)?;
+	for _ in 0..stream_count {
+		let seed = read_u64(reader)?;
+		let step = read_u64(reader)?;
+		rng_state.set_step(seed, step);
+	}
+
+	Ok((values, StepperState { step_count, buffers }, rng_state))
+}
+
+fn write_name<W: Write>(writer: &mut W, name: &str) -> io::Result<()> {
+	writer.write_all(&(name.len() as u32).to_le_bytes())?;
+	writer.write_all(name.as_bytes())
+}
+
+fn write_array<W: Write>(writer: &mut W, arr: ArrayViewD<f32>) -> io::Result<()> {
+	writer.write_all(&(arr.ndim() as u32).to_le_bytes())?;
+	for &dim in arr.shape() {
+		writer.write_all(&(dim as u64).to_le_bytes())?;
+	}
+	for x in arr.iter() {
+		writer.write_all(&x.to_le_bytes())?;
+	}
+	Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+	let mut buf = [0u8; 4];
+	reader.read_exact(&mut buf)?;
+	Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+	let mut buf = [0u8; 8];
+	reader.read_exact(&mut buf)?;
+	Ok(u64::from_le_bytes(buf))
+}
+
+/// Reads exactly `len` bytes.
+///
+/// The buffer grows as bytes are read, rather than being allocated up front, so that a corrupt length can't cause an
+/// allocation much larger than the remaining input.
+fn read_bytes<R: Read>(reader: &mut R, len: u64) -> Result<Vec<u8>, CheckpointError> {
+	let mut buf = vec![];
+	reader.take(len).read_to_end(&mut buf)?;
+	if (buf.len() as u64) < len {
+		return Err(CheckpointError::Format {
+			desc: "unexpected end of file".to_string(),
+		});
+	}
+	Ok(buf)
+}
+
+fn read_name<R: Read>(reader: &mut R) -> Result<String, CheckpointError> {
+	let len = read_u32(reader)?;
+	let buf = read_bytes(reader, u64::from(len))?;
+	String::from_utf8(buf).map_err(|_| CheckpointError::Format {
+		desc: "parameter name is not valid UTF-8".to_string(),
+	})
+}
+
+fn read_array<R: Read>(reader: &mut R) -> Result<ArrayD<f32>, CheckpointError> {
+	let ndim = read_u32(reader)? as usize;
+	let shape = (0..ndim)
+		.map(|_| read_u64(reader).map(|dim| dim as usize))
+		.collect::<io::Result<Vec<_>>>()?;
+	let byte_len = shape
+		.iter()
+		.try_fold(4u64, |len, &dim| len.checked_mul(dim as u64))
+		.ok_or_else(|| CheckpointError::Format {
+			desc: format!("array shape {:?} is too large", shape),
+		})?;
+	let data = read_bytes(reader, byte_len)?
+		.chunks_exact(4)
+		.map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+		.collect();
+	Ok(ArrayD::from_shape_vec(IxDyn(&shape), data).expect("Alumina Bug: checkpoint array length did not match shape"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		load_checkpoint, named_parameters, read_checkpoint, save_checkpoint, write_checkpoint, CheckpointError,
+		StepperState, MAGIC, VERSION,
+	};
+	use crate::{adam::Adam, sgd::Sgd, GradientStepper};
+	use alumina_core::{
+		errors::ExecError,
+		exec::RngState,
+		graph::{Node, NodeTag},
+	};
+	use indexmap::{indexmap, IndexMap};
+	use ndarray::{arr1, arr2, ArcArray, IxDyn};
+
+	fn params() -> (Node, Node) {
+		let w = Node::new(&[2, 2])
+			.set_name("w")
+			.add_tag(NodeTag::Parameter)
+			.set_value(arr2(&[[0.5, -1.0], [2.0, 0.25]]));
+		let b = Node::new(&[2])
+			.set_name("b")
+			.add_tag(NodeTag::Parameter)
+			.set_value(arr1(&[0.1, -0.3]));
+		w.graph().merge(b.graph());
+		(w, b)
+	}
+
+	// gradients depend on the current parameter values so that the trajectory depends on all prior steps
+	fn step<S: GradientStepper>(stepper: &mut S, w: &Node, b: &Node) {
+		let grad = |p: &Node| -> ArcArray<f32, IxDyn> { p.value().unwrap().mapv(|x| x * x - 0.5).into_shared() };
+		stepper
+			.step(indexmap![w.clone() => grad(w), b.clone() => grad(b)], false)
+			.unwrap();
+	}
+
+	fn resume_matches_uninterrupted<S: GradientStepper, F: Fn() -> S>(new_stepper: F, name: &str) {
+		let path = std::env::temp_dir().join(format!("alumina_checkpoint_{}_{}.ckpt", name, std::process::id()));
+
+		let (w, b) = params();
+		let mut stepper = new_stepper();
+		for _ in 0..3 {
+			step(&mut stepper, &w, &b);
+		}
+		save_checkpoint(&path, w.graph(), &stepper, None).unwrap();
+		step(&mut stepper, &w, &b);
+
+		let (w2, b2) = params();
+		let mut stepper2 = new_stepper();
+		load_checkpoint(&path, w2.graph(), &mut stepper2, None).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(stepper2.step_count(), 3);
+		step(&mut stepper2, &w2, &b2);
+
+		assert_eq!(stepper2.step_count(), stepper.step_count());
+		assert_eq!(w2.value().unwrap(), w.value().unwrap());
+		assert_eq!(b2.value().unwrap(), b.value().unwrap());
+	}
+
+	#[test]
+	fn adam_resume_test() {
+		resume_matches_uninterrupted(|| Adam::new(1e-2, 0.9, 0.995), "adam");
+	}
+
+	#[test]
+	fn sgd_resume_test() {
+		resume_matches_uninterrupted(|| Sgd::new(1e-2, Some(0.9)), "sgd");
+	}
+
+	#[test]
+	fn rng_state_test() {
+		let (w, _b) = params();
+		let params = named_parameters(w.graph()).unwrap();
+		let state = StepperState::default();
+
+		let mut rng_state = RngState::new();
+		rng_state.set_step(3, 5);
+		rng_state.set_step(u64::MAX, 1);
+		let mut bytes = vec![];
+		write_checkpoint(&mut bytes, &params, &state, Some(&rng_state)).unwrap();
+		let (_, _, restored) = read_checkpoint(&mut &bytes[..], &params).unwrap();
+		assert_eq!(restored.steps().collect::<Vec<_>>(), vec![(3, 5), (u64::MAX, 1)]);
+	}
+
+	#[test]
+	fn shape_mismatch_test() {
+		let path = std::env::temp_dir().join(format!("alumina_checkpoint_shape_{}.ckpt", std::process::id()));
+		let (w, _b) = params();
+		save_checkpoint(&path, w.graph(), &Sgd::new(1e-2, None), None).unwrap();
+
+		let w2 = Node::new(&[3])
+			.set_name("w")
+			.add_tag(NodeTag::Parameter)
+			.set_value(arr1(&[0.0, 0.0, 0.0]));
+		let result = load_checkpoint(&path, w2.graph(), &mut Sgd::new(1e-2, None), None);
+		std::fs::remove_file(&path).unwrap();
+		// w is stored first, so its shape is checked before b is found to be unknown
+		match result {
+			Err(CheckpointError::ShapeMismatch {
+				ref name,
+				ref expected,
+				ref found,
+			}) if name == "w" && expected == &[3] && found == &[2, 2] => {},
+			x => panic!("{:?}", x),
+		}
+		assert_eq!(w2.value().unwrap(), arr1(&[0.0, 0.0, 0.0]).into_dyn().into_shared());
+	}
+
+	#[test]
+	fn corrupt_length_test() {
+		let (w, _b) = params();
+		let params = named_parameters(w.graph()).unwrap();
+
+		let header = |bytes: &mut Vec<u8>| {
+			bytes.extend_from_slice(MAGIC);
+			bytes.extend_from_slice(&VERSION.to_le_bytes());
+			bytes.extend_from_slice(&0u64.to_le_bytes());
+			bytes.extend_from_slice(&1u32.to_le_bytes());
+		};
+
+		// a name length far beyond the end of the input
+		let mut bytes = vec![];
+		header(&mut bytes);
+		bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+		bytes.extend_from_slice(b"w");
+		match read_checkpoint(&mut &bytes[..], &params) {
+			Err(CheckpointError::Format { .. }) => {},
+			x => panic!("{:?}", x.map(|_| ())),
+		}
+
+		// array shapes which are larger than the input, and which overflow
+		for dims in &[[1u64 << 30, 1 << 30], [u64::MAX, 2]] {
+			let mut bytes = vec![];
+			header(&mut bytes);
+			bytes.extend_from_slice(&1u32.to_le_bytes());
+			bytes.extend_from_slice(b"w");
+			bytes.extend_from_slice(&2u32.to_le_bytes());
+			for dim in dims {
+				bytes.extend_from_slice(&dim.to_le_bytes());
+			}
+			bytes.extend_from_slice(&1.0f32.to_le_bytes());
+			match read_checkpoint(&mut &bytes[..], &params) {
+				Err(CheckpointError::Format { .. }) => {},
+				x => panic!("{:?}", x.map(|_| ())),
+			}
+		}
+	}
+
+	#[test]
+	fn unsupported_test() {
+		struct Stateless;
+
+		impl GradientStepper for Stateless {
+			fn step(
+				&mut self,
+				_parameters_and_grad_values: IndexMap<Node, ArcArray<f32, IxDyn>>,
+				_calc_change: bool,
+			) -> Result<f32, ExecError> {
+				Ok(0.0)
+			}
+
+			fn step_count(&self) -> usize {
+				0
+			}
+		}
+
+		let path = std::env::temp_dir().join(format!("alumina_checkpoint_unsupported_{}.ckpt", std::process::id()));
+		let (w, _b) = params();
+		match save_checkpoint(&path, w.graph(), &Stateless, None) {
+			Err(CheckpointError::Unsupported) => {},
+			x => panic!("{:?}", x),
+		}
+		assert!(!path.exists());
+	}
+}
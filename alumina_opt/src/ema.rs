@@ -0,0 +1,130 @@
+use alumina_core::graph::{Node, NodeID};
+use indexmap::{indexmap, IndexMap};
+use ndarray::{ArcArray, IxDyn, Zip};
+
+/// Maintains an exponential moving average (shadow copy) of parameter values.
+///
+/// After each optimisation step `update(..)` should be called with the parameters, each shadow value is then updated
+/// as:
+/// ema = decay * ema + (1 - decay) * θ
+///
+/// The first call to `update(..)` for a given parameter initialises the shadow value to the current parameter value.
+///
+/// The averaged values are often better for evaluation than the raw parameters. `swap(..)` exchanges the shadow values
+/// with the parameter values so the graph can be evaluated with the averaged values, a second call swaps them back.
+#[derive(Clone, Debug)]
+pub struct Ema {
+	decay: f32,
+	shadows: IndexMap<NodeID, ArcArray<f32, IxDyn>>,
+	swapped: bool,
+}
+
+impl Ema {
+	pub fn new(decay: f32) -> Self {
+		Ema {
+			decay,
+			shadows: indexmap![],
+			swapped: false,
+		}
+	}
+
+	/// Decay coefficient, the proportion of the shadow value retained at each update.
+	///
+	/// Larger values result in a smoother average which lags further behind the parameters.
+	pub fn decay(&mut self, decay: f32) -> &mut Self {
+		self.decay = decay;
+		self
+	}
+
+	/// Returns the shadow value for the parameter, if one exists.
+	pub fn shadow(&self, id: NodeID) -> Option<&ArcArray<f32, IxDyn>> {
+		self.shadows.get(&id)
+	}
+
+	/// Returns true if the shadow values are currently swapped into the graph.
+	pub fn is_swapped(&self) -> bool {
+		self.swapped
+	}
+
+	/// Update the shadow value of each parameter towards the current parameter value.
+	///
+	/// # Panics
+	/// Panics if called while the shadow values are swapped into the graph, or if any parameter does not have a value.
+	pub fn update<I: Into<Node>, T: IntoIterator<Item = I>>(&mut self, params: T) {
+		assert!(
+			!self.swapped,
+			"Ema::update(..) cannot be called while shadow values are swapped into the graph"
+		);
+		let decay = self.decay;
+		for param in params {
+			let param: Node = param.into();
+			let value = param
+				.value()
+				.unwrap_or_else(|| panic!("Parameter {} does not have a value", param));
+			match self.shadows.get_mut(&param.id()) {
+				Some(shadow) => {
+					Zip::from(shadow).and(&value).par_for_each(|shadow, &value| {
+						*shadow = decay * *shadow + (1.0 - decay) * value;
+					});
+				},
+				None => {
+					self.shadows.insert(param.id(), value);
+				},
+			}
+		}
+	}
+
+	/// Exchange the shadow values and the parameter values.
+	///
+	/// Calling this once places the averaged values in the graph for evaluation, calling it again restores the original
+	/// parameter values. Parameters without a shadow value are left unchanged.
+	pub fn swap<I: Into<Node>, T: IntoIterator<Item = I>>(&mut self, params: T) {
+		for param in params {
+			let param: Node = param.into();
+			if let Some(shadow) = self.shadows.get_mut(&param.id()) {
+				let value = param
+					.take_value()
+					.unwrap_or_else(|| panic!("Parameter {} does not have a value", param));
+				param.set_value(std::mem::replace(shadow, value));
+			}
+		}
+		self.swapped = !self.swapped;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Ema;
+	use alumina_core::graph::Node;
+	use ndarray::arr1;
+
+	#[test]
+	fn ema_tracks_param() {
+		let param = Node::new(&[2]).set_name("param").set_value(arr1(&[0.0, 0.0]));
+		let mut ema = Ema::new(0.9);
+
+		ema.update(&[&param]);
+		assert_eq!(ema.shadow(param.id()).unwrap(), &arr1(&[0.0, 0.0]).into_dyn());
+
+		param.set_value(arr1(&[1.0, -1.0]));
+		ema.update(&[&param]);
+		ema.update(&[&param]);
+		let expected = 1.0 - 0.9 * 0.9;
+		for (&s, &e) in ema.shadow(param.id()).unwrap().iter().zip(&[expected, -expected]) {
+			assert!((s - e).abs() < 1e-6);
+		}
+
+		ema.swap(&[&param]);
+		assert!(ema.is_swapped());
+		assert!((param.value().unwrap()[0] - expected).abs() < 1e-6);
+
+		ema.swap(&[&param]);
+		assert!(!ema.is_swapped());
+		assert_eq!(param.value().unwrap(), arr1(&[1.0, -1.0]).into_dyn());
+		assert!(ema
+			.shadow(param.id())
+			.unwrap()
+			.iter()
+			.all(|&s| (s.abs() - expected).abs() < 1e-6));
+	}
+}
@@ -1,6 +1,7 @@
+use crate::checkpoint::{CheckpointError, StepperState};
 use alumina_core::{
 	errors::ExecError,
-	exec::{ExecutionPlan, OpPerf},
+	exec::{ExecutionPlan, OpPerf, RngState},
 	grad::Grad,
 	graph::{Node, NodeTag, Op},
 	subgraph::{execution_subgraph, SubGraph},
@@ -8,11 +9,13 @@ use alumina_core::{
 use alumina_data::DataStream;
 use indexmap::{IndexMap, IndexSet};
 use ndarray::{ArcArray, IxDyn};
-use ndarray::{ArrayViewD, Zip};
-use std::{borrow::Borrow, iter::once};
+use ndarray::{ArrayViewD, Axis, Zip};
+use std::{borrow::Borrow, iter::once, path::Path};
 use unchecked_index as ui;
 
 pub mod adam;
+pub mod checkpoint;
+pub mod ema;
 pub mod sgd;
 
 /// Calculates the L2 norm of the difference between two arrays
@@ -41,6 +44,50 @@ pub fn calc_change_sqr(arr1: ArrayViewD<f32>, arr2: ArrayViewD<f32>) -> f32 {
 	change_sqr
 }
 
+/// Clamps each element of each gradient array into the range `[-max_abs, max_abs]`.
+///
+/// Unlike norm clipping, each element is clipped independently, which can change the direction of the gradient.
+/// This operates on the gradient values prior to being passed to `GradientStepper::step(..)`.
+pub fn clip_grad_value(grads: &mut IndexMap<Node, ArcArray<f32, IxDyn>>, max_abs: f32) {
+	assert!(
+		max_abs >= 0.0,
+		"max_abs must be greater than or equal to zero, found: {}",
+		max_abs
+	);
+	for grad in grads.values_mut() {
+		grad.par_mapv_inplace(|x| x.max(-max_abs).min(max_abs));
+	}
+}
+
+/// Gradient centralisation, subtracts the mean of each parameter gradient from that gradient.
+///
+/// The mean is taken over all axes except the last (output) axis, consistent with the layout of the weights used by
+/// `linear` and `conv`. Gradients of rank 0 or 1 (e.g. biases) are left unchanged, as are gradients of nodes not
+/// included in `params`.
+/// This operates on the gradient values prior to being passed to `GradientStepper::step(..)`.
+pub fn center_gradients<I, T>(grads: &mut IndexMap<Node, ArcArray<f32, IxDyn>>, params: T)
+where
+	I: Into<Node>,
+	T: IntoIterator<Item = I>,
+{
+	for param in params {
+		let param = param.into();
+		if let Some(grad) = grads.get_mut(&param) {
+			if grad.ndim() < 2 || grad.is_empty() {
+				continue;
+			}
+
+			let mut mean = grad.sum_axis(Axis(0));
+			while mean.ndim() > 1 {
+				mean = mean.sum_axis(Axis(0));
+			}
+			mean /= (grad.len() / mean.len()) as f32;
+
+			*grad -= &mean;
+		}
+	}
+}
+
 pub struct StepData<'a> {
 	pub loss: f32,
 
@@ -84,6 +131,20 @@ pub trait GradientStepper {
 
 	fn step_count(&self) -> usize;
 
+	/// Returns the internal state required to resume optimisation, e.g. for `checkpoint::save_checkpoint(..)`.
+	///
+	/// The default implementation returns `CheckpointError::Unsupported`.
+	fn state(&self) -> Result<StepperState, CheckpointError> {
+		Err(CheckpointError::Unsupported)
+	}
+
+	/// Replaces the internal state with one previously returned by `state()`.
+	///
+	/// The default implementation returns `CheckpointError::Unsupported`.
+	fn set_state(&mut self, _state: StepperState) -> Result<(), CheckpointError> {
+		Err(CheckpointError::Unsupported)
+	}
+
 	/// Return best estimate for each node
 	fn best_estimate(&self, params: &mut dyn Iterator<Item = &Node>) -> IndexMap<Node, ArcArray<f32, IxDyn>> {
 		params
@@ -135,6 +196,7 @@ where
 	callbacks: Vec<Box<dyn 'a + FnMut(&mut S, &StepData) -> Signal>>,
 	perf_records: Option<&'a mut IndexMap<Op, OpPerf>>,
 	grad_step: S,
+	rng_state: RngState,
 	calc_change: bool,
 	calc_loss: bool,
 }
@@ -201,6 +263,7 @@ where
 			callbacks: vec![],
 			perf_records: None,
 			grad_step,
+			rng_state: RngState::new(),
 			calc_change: true,
 			calc_loss: true,
 		}
@@ -267,6 +330,7 @@ where
 			callbacks: vec![],
 			perf_records: None,
 			grad_step,
+			rng_state: RngState::new(),
 			calc_change: true,
 			calc_loss: true,
 		}
@@ -315,6 +379,31 @@ where
 		self.inner
 	}
 
+	/// The random number state passed to each execution, so that Ops such as dropout draw new values each step.
+	pub fn rng_state(&self) -> &RngState {
+		&self.rng_state
+	}
+
+	pub fn rng_state_mut(&mut self) -> &mut RngState {
+		&mut self.rng_state
+	}
+
+	/// Writes the parameters of the loss graph, the state of the stepper, and the random number state to the file at
+	/// `path`. See `checkpoint::save_checkpoint(..)`.
+	pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+		checkpoint::save_checkpoint(path, self.inner.loss.graph(), &self.grad_step, Some(&self.rng_state))
+	}
+
+	/// Restores the state written by `save_checkpoint(..)`. See `checkpoint::load_checkpoint(..)`.
+	pub fn load_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CheckpointError> {
+		checkpoint::load_checkpoint(
+			path,
+			self.inner.loss.graph(),
+			&mut self.grad_step,
+			Some(&mut self.rng_state),
+		)
+	}
+
 	pub fn boxed_callback(&mut self, func: Box<dyn 'a + FnMut(&mut S, &StepData) -> Signal>) {
 		self.callbacks.push(func);
 	}
@@ -325,6 +414,7 @@ where
 	fn step_with_callbacks_impl<'b, I, T1>(
 		inner: &'b OptInner,
 		grad_stepper: &mut S,
+		rng_state: &mut RngState,
 		callbacks: &mut [Box<dyn 'a + FnMut(&mut S, &StepData) -> Signal>],
 		perf_records: Option<&'b mut IndexMap<Op, OpPerf>>,
 		inputs: T1,
@@ -341,6 +431,7 @@ where
 			let mut results = ExecutionPlan::new(inputs, inner.parameters_and_grads.values().chain(once(&inner.loss)))
 				.perf_records(perf_records)
 				.subgraph(Some(&inner.subgraph))
+				.rng_state(Some(rng_state))
 				.execute()?;
 			let loss = results.swap_remove(&inner.loss).unwrap().sum();
 			(results, loss)
@@ -348,6 +439,7 @@ where
 			let results = ExecutionPlan::new(inputs, inner.parameters_and_grads.values())
 				.perf_records(perf_records)
 				.subgraph(Some(&inner.subgraph))
+				.rng_state(Some(rng_state))
 				.execute()?;
 			(results, 0.0)
 		};
@@ -414,6 +506,7 @@ where
 			let step_result = Self::step_with_callbacks_impl(
 				&self.inner,
 				&mut self.grad_step,
+				&mut self.rng_state,
 				&mut self.callbacks,
 				perf_records.as_deref_mut(),
 				//self.perf_records.as_mut().map(|i| *i).unwrap_or(&mut x),
@@ -501,3 +594,108 @@ pub fn nth_step<'a, S: GradientStepper, I: Into<Signal>, F: 'a + FnMut(&mut S, &
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{center_gradients, clip_grad_value, max_steps, sgd::Sgd, GradientOptimiser, GradientStepper, StepData};
+	use alumina_core::{
+		base_ops::OpSpecification,
+		graph::{Node, NodeTag},
+	};
+	use alumina_data::DataStream;
+	use alumina_ops::{nn::dropout::Dropout, reduce::reduce_sum::reduce_sum};
+	use indexmap::indexmap;
+	use ndarray::{arr1, arr2, arr3, ArcArray, Axis, IxDyn};
+	use std::{cell::RefCell, rc::Rc};
+
+	struct NoInputs;
+
+	impl DataStream for NoInputs {
+		fn next(&mut self) -> Vec<ArcArray<f32, IxDyn>> {
+			vec![]
+		}
+	}
+
+	fn dropout_loss() -> Node {
+		let w = Node::new(&[16])
+			.set_name("w")
+			.add_tag(NodeTag::Parameter)
+			.set_value(arr1(&[1.0; 16]));
+		let hidden = w.graph().new_node(w.shape()).set_name("hidden");
+		Dropout::new(&w, &hidden, 0.5).seed(7).build().unwrap();
+		reduce_sum(&hidden, &[], false).unwrap()
+	}
+
+	// records the loss of each step until the step count reaches `steps`
+	fn optimise_to(opt: &mut GradientOptimiser<Sgd>, steps: usize) -> Vec<f32> {
+		let losses = Rc::new(RefCell::new(vec![]));
+		let recorded = losses.clone();
+		opt.callback(move |_s: &mut Sgd, data: &StepData| recorded.borrow_mut().push(data.loss));
+		opt.callback(max_steps(steps));
+		opt.optimise(&mut NoInputs).unwrap();
+		opt.callbacks.clear();
+		let losses = losses.borrow().clone();
+		losses
+	}
+
+	#[test]
+	fn rng_state_test() {
+		let path = std::env::temp_dir().join(format!("alumina_opt_rng_state_{}.ckpt", std::process::id()));
+
+		let mut opt = GradientOptimiser::new(dropout_loss(), Vec::<Node>::new(), Sgd::new(0.1, None));
+		let first = optimise_to(&mut opt, 3);
+		opt.save_checkpoint(&path).unwrap();
+		let rest = optimise_to(&mut opt, 6);
+
+		// a new mask is drawn each step, so the loss is not the same each step
+		assert!(first.iter().chain(&rest).any(|&loss| loss != first[0]));
+
+		// resuming a freshly constructed graph continues the sequence of masks
+		let mut opt2 = GradientOptimiser::new(dropout_loss(), Vec::<Node>::new(), Sgd::new(0.1, None));
+		opt2.load_checkpoint(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(opt2.grad_step.step_count(), 3);
+		assert_eq!(optimise_to(&mut opt2, 6), rest);
+	}
+
+	#[test]
+	fn clip_grad_value_test() {
+		let a = Node::new(&[4]).set_name("a");
+		let b = Node::new(&[2, 2]).set_name("b");
+
+		let mut grads = indexmap![
+			a.clone() => arr1(&[-3.0, -0.5, 0.25, 2.0]).into_shared().into_dyn(),
+			b.clone() => arr2(&[[10.0, -10.0], [1.0, -1.0]]).into_shared().into_dyn(),
+		];
+
+		clip_grad_value(&mut grads, 1.0);
+
+		assert_eq!(grads[&a], arr1(&[-1.0, -0.5, 0.25, 1.0]).into_dyn());
+		assert_eq!(grads[&b], arr2(&[[1.0, -1.0], [1.0, -1.0]]).into_dyn());
+	}
+
+	#[test]
+	fn center_gradients_test() {
+		let weights = Node::new(&[2, 3, 2]).set_name("weights");
+		let bias = Node::new(&[2]).set_name("bias");
+		let other = Node::new(&[2, 2]).set_name("other");
+
+		let weights_grad = arr3(&[[[1.0, -4.0], [2.0, 0.5], [3.0, 7.0]], [[-1.0, 2.0], [0.0, 0.0], [9.0, 1.5]]]);
+		let mut grads = indexmap![
+			weights.clone() => weights_grad.into_shared().into_dyn(),
+			bias.clone() => arr1(&[3.0, 5.0]).into_shared().into_dyn(),
+			other.clone() => arr2(&[[1.0, 2.0], [3.0, 4.0]]).into_shared().into_dyn(),
+		];
+
+		center_gradients(&mut grads, &[&weights, &bias]);
+
+		let mean = grads[&weights].sum_axis(Axis(0)).sum_axis(Axis(0)).mapv(|x| x / 6.0);
+		assert!(mean.iter().all(|x| x.abs() < 1e-6));
+		// 14 / 6 subtracted from the first output, 7 / 6 from the second
+		assert!((grads[&weights][[0, 0, 0]] - (1.0 - 14.0 / 6.0)).abs() < 1e-6);
+		assert!((grads[&weights][[1, 2, 1]] - (1.5 - 7.0 / 6.0)).abs() < 1e-6);
+
+		assert_eq!(grads[&bias], arr1(&[3.0, 5.0]).into_dyn());
+		assert_eq!(grads[&other], arr2(&[[1.0, 2.0], [3.0, 4.0]]).into_dyn());
+	}
+}
@@ -1,4 +1,8 @@
-use crate::{calc_change_sqr, GradientStepper};
+use crate::{
+	calc_change_sqr,
+	checkpoint::{CheckpointError, StepperState},
+	GradientStepper,
+};
 use alumina_core::{errors::ExecError, graph::Node};
 use indexmap::{indexmap, IndexMap};
 use ndarray::{ArcArray, ArrayD, IxDyn, Zip};
@@ -53,6 +57,37 @@ impl GradientStepper for Sgd {
 		self.step_count
 	}
 
+	fn state(&self) -> Result<StepperState, CheckpointError> {
+		Ok(StepperState {
+			step_count: self.step_count,
+			buffers: self
+				.momentums
+				.iter()
+				.map(|(param, momentum_arr)| (param.clone(), vec![momentum_arr.clone()]))
+				.collect(),
+		})
+	}
+
+	fn set_state(&mut self, state: StepperState) -> Result<(), CheckpointError> {
+		let mut momentums = indexmap![];
+		for (param, buffers) in state.buffers {
+			let mut buffers = buffers.into_iter();
+			match (buffers.next(), buffers.next()) {
+				(Some(momentum_arr), None) => {
+					momentums.insert(param, momentum_arr);
+				},
+				_ => {
+					return Err(CheckpointError::InvalidState {
+						desc: format!("Sgd requires 1 buffer for each parameter, {} does not have 1", param),
+					})
+				},
+			}
+		}
+		self.momentums = momentums;
+		self.step_count = state.step_count;
+		Ok(())
+	}
+
 	fn step(
 		&mut self,
 		mut parameters_and_grad_values: IndexMap<Node, ArcArray<f32, IxDyn>>,
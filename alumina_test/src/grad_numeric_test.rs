@@ -24,6 +24,7 @@ pub struct GradNumericTest {
 	tolerance: f32,
 	// rel_tolerance: bool,
 	expect_zero: IndexMap<Node, f32>,
+	check_accumulation: bool,
 }
 
 impl GradNumericTest {
@@ -48,6 +49,7 @@ impl GradNumericTest {
 			tolerance: 1e-4,
 			// rel_tolerance: true,
 			expect_zero: indexmap![],
+			check_accumulation: false,
 		}
 	}
 
@@ -130,6 +132,17 @@ impl GradNumericTest {
 		self
 	}
 
+	/// Determines whether the gradient calculation is checked for Ops which overwrite, rather than accumulate into,
+	/// a gradient shared with other Ops. See `ExecutionPlan::check_accumulation()`.
+	///
+	/// Checked Ops are executed twice, so this is slow and off by default.
+	///
+	/// Default: false
+	pub fn check_accumulation(mut self, check_accumulation: bool) -> Self {
+		self.check_accumulation = check_accumulation;
+		self
+	}
+
 	pub fn run(self) {
 		grad_numeric_test_iters(&self)
 	}
@@ -240,6 +253,7 @@ pub fn grad_numeric_test(config: &GradNumericTest) -> (f32, IndexSet<Node>) {
 				indexset![i.clone()],
 				&grads,
 				config.step_size,
+				config.check_accumulation,
 			);
 			let error = expected_diff - diff;
 			let rel_error = (error.abs() / diff.abs().max(expected_diff.abs())) as f32;
@@ -269,8 +283,14 @@ pub fn grad_numeric_test(config: &GradNumericTest) -> (f32, IndexSet<Node>) {
 	}
 
 	// grad test all simultaneously
-	let (diff, expected_diff) =
-		grad_numeric_test_inner(&config.loss, &input_values, inputs.clone(), &grads, config.step_size);
+	let (diff, expected_diff) = grad_numeric_test_inner(
+		&config.loss,
+		&input_values,
+		inputs.clone(),
+		&grads,
+		config.step_size,
+		config.check_accumulation,
+	);
 	let error = expected_diff - diff;
 	let rel_error = (error.abs() / diff.abs().max(expected_diff.abs())) as f32;
 	if let Some(tolerance) = inputs.iter().fold(Some(0.0f32), |max, i| {
@@ -306,10 +326,12 @@ fn grad_numeric_test_inner(
 	tested_inputs: IndexSet<Node>,
 	grads: &IndexMap<Node, Node>,
 	step_size: f32,
+	check_accumulation: bool,
 ) -> (f64, f64) {
 	// first call with grads and y as outputs
 	let outputs = tested_inputs.iter().map(|n| &grads[n]).chain(::std::iter::once(loss));
 	let results = ExecutionPlan::new(input_values.clone(), outputs)
+		.check_accumulation(check_accumulation)
 		.execute()
 		.unwrap_or_else(|err| panic!("Call to exec() failed in numeric test.\n{:#?}", err));
 
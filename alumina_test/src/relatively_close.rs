@@ -7,29 +7,264 @@ pub trait RelClose<A: Float + Debug> {
 	/// `(*x - *y).abs() <= tol * y.abs()`
 	/// rather than
 	/// `(*x - *y).abs() <= tol`
+	///
+	/// NaN is never close to anything, see `all_relatively_close_nan(..)`.
 	fn all_relatively_close<S2, E2>(&self, rhs: &ArrayBase<S2, E2>, tol: A) -> bool
 	where
 		A: Float,
 		S2: Data<Elem = A>,
 		E2: Dimension;
+
+	/// As `all_relatively_close(..)`, but NaN and infinite values are handled explicitly.
+	///
+	/// If `equal_nan` is true a NaN is close to a NaN, otherwise any NaN fails. An infinite value is only close to an
+	/// infinite value of the same sign, and an unexpected infinity is reported separately from a tolerance failure.
+	fn all_relatively_close_nan<S2, E2>(&self, rhs: &ArrayBase<S2, E2>, tol: A, equal_nan: bool) -> bool
+	where
+		A: Float,
+		S2: Data<Elem = A>,
+		E2: Dimension;
 }
 
 impl<A: Float + Debug, S1: Data<Elem = A>, E1: Dimension> RelClose<A> for ArrayBase<S1, E1> {
 	fn all_relatively_close<S2, E2>(&self, rhs: &ArrayBase<S2, E2>, tol: A) -> bool
+	where
+		S2: Data<Elem = A>,
+		E2: Dimension,
+	{
+		self.all_relatively_close_nan(rhs, tol, false)
+	}
+
+	fn all_relatively_close_nan<S2, E2>(&self, rhs: &ArrayBase<S2, E2>, tol: A, equal_nan: bool) -> bool
 	where
 		S2: Data<Elem = A>,
 		E2: Dimension,
 	{
 		!Zip::from(self)
 			.and(rhs.broadcast(self.raw_dim()).expect("Broadcast failed"))
-			.fold_while((), |_, &x, &y| {
-				if (x - y).abs() <= tol * y.abs().max(A::one()) {
-					FoldWhile::Continue(())
-				} else {
+			.fold_while((), |_, &x, &y| match compare(x, y, tol, equal_nan) {
+				None => FoldWhile::Continue(()),
+				Some(Mismatch::NaN) => {
+					eprintln!("NaN mismatch, found: {:?}, expected: {:?}", x, y);
+					FoldWhile::Done(())
+				},
+				Some(Mismatch::Inf) => {
+					eprintln!("unexpected infinity, found: {:?}, expected: {:?}", x, y);
+					FoldWhile::Done(())
+				},
+				Some(Mismatch::Tolerance(_)) => {
 					eprintln!("tolerance failed, found: {:?}, expect within {:?} of {:?}", x, tol, y);
 					FoldWhile::Done(())
-				}
+				},
 			})
 			.is_done()
 	}
 }
+
+/// The reason a found value is not relatively close to an expected value.
+enum Mismatch<A> {
+	/// Exactly one value is NaN, or both are and NaNs are not considered equal.
+	NaN,
+
+	/// At least one value is infinite, and they are not equal.
+	Inf,
+
+	/// Both values are finite, with this error relative to `max(|expected|, 1)`.
+	Tolerance(A),
+}
+
+fn compare<A: Float>(x: A, y: A, tol: A, equal_nan: bool) -> Option<Mismatch<A>> {
+	if x.is_nan() || y.is_nan() {
+		if equal_nan && x.is_nan() && y.is_nan() {
+			None
+		} else {
+			Some(Mismatch::NaN)
+		}
+	} else if x.is_infinite() || y.is_infinite() {
+		if x == y {
+			None
+		} else {
+			Some(Mismatch::Inf)
+		}
+	} else if (x - y).abs() <= tol * y.abs().max(A::one()) {
+		None
+	} else {
+		Some(Mismatch::Tolerance((x - y).abs() / y.abs().max(A::one())))
+	}
+}
+
+/// Checks the same condition as `all_relatively_close_nan(..)`, returning a description of the shapes, the worst
+/// mismatch, and the tolerance if it fails.
+///
+/// The worst mismatch is the first NaN or infinity mismatch if there is one, otherwise the element with the largest
+/// error relative to `max(|expected|, 1)`.
+pub fn rel_close_failure<A, S1, E1, S2, E2>(
+	got: &ArrayBase<S1, E1>,
+	expected: &ArrayBase<S2, E2>,
+	tol: A,
+	equal_nan: bool,
+) -> Option<String>
+where
+	A: Float + Debug,
+	S1: Data<Elem = A>,
+	E1: Dimension,
+	S2: Data<Elem = A>,
+	E2: Dimension,
+{
+	let got = got.view().into_dyn();
+	let expected_view = expected.view().into_dyn();
+	let expected_broadcast = match expected_view.broadcast(got.raw_dim()) {
+		Some(arr) => arr,
+		None => {
+			return Some(format!(
+				"expected shape {:?} could not be broadcast to the shape found {:?}",
+				expected.shape(),
+				got.shape()
+			))
+		},
+	};
+
+	let mut worst: Option<(IxDyn, A, A, Mismatch<A>)> = None;
+	for ((idx, &x), &y) in got.indexed_iter().zip(expected_broadcast.iter()) {
+		let mismatch = match compare(x, y, tol, equal_nan) {
+			Some(mismatch) => mismatch,
+			None => continue,
+		};
+		let is_worse = match (&worst, &mismatch) {
+			(None, _) => true,
+			(Some((_, _, _, Mismatch::Tolerance(worst_error))), Mismatch::Tolerance(rel_error)) => {
+				rel_error > worst_error
+			},
+			(Some((_, _, _, Mismatch::Tolerance(_))), _) => true,
+			(Some(_), _) => false,
+		};
+		if is_worse {
+			worst = Some((idx, x, y, mismatch));
+		}
+	}
+
+	worst.map(|(idx, x, y, mismatch)| {
+		let prefix = format!("shape found {:?}, shape expected {:?}", got.shape(), expected.shape());
+		match mismatch {
+			Mismatch::NaN => format!(
+				"{}, NaN mismatch at index {:?}: found {:?}, expected {:?}",
+				prefix,
+				idx.slice(),
+				x,
+				y
+			),
+			Mismatch::Inf => format!(
+				"{}, unexpected infinity at index {:?}: found {:?}, expected {:?}",
+				prefix,
+				idx.slice(),
+				x,
+				y
+			),
+			Mismatch::Tolerance(rel_error) => format!(
+				"{}, worst mismatch at index {:?}: found {:?}, expected {:?}, relative error {:?} > tolerance {:?}",
+				prefix,
+				idx.slice(),
+				x,
+				y,
+				rel_error,
+				tol
+			),
+		}
+	})
+}
+
+/// Asserts that an array is relatively close to an expected array, see `RelClose::all_relatively_close(..)`.
+///
+/// The expected array is broadcast to the shape of the first array. On failure the panic message includes the shapes,
+/// the worst mismatching element, and the tolerance. NaNs can be treated as equal by passing `equal_nan = true` as a
+/// fourth argument.
+///
+/// ```rust
+/// # use alumina_test::assert_rel_close;
+/// # use ndarray::{arr0, arr1};
+/// assert_rel_close!(arr1(&[1.0, 1.0 + 1e-7]), arr0(1.0), 1e-6);
+/// ```
+#[macro_export]
+macro_rules! assert_rel_close {
+	($got:expr, $expected:expr, $tol:expr $(,)?) => {
+		$crate::assert_rel_close!($got, $expected, $tol, equal_nan = false)
+	};
+	($got:expr, $expected:expr, $tol:expr, equal_nan = $equal_nan:expr $(,)?) => {
+		if let Some(failure) = $crate::relatively_close::rel_close_failure(&$got, &$expected, $tol, $equal_nan) {
+			panic!(
+				"assertion failed: `assert_rel_close!({}, {})`\n{}",
+				stringify!($got),
+				stringify!($expected),
+				failure
+			);
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::RelClose;
+	use ndarray::{arr0, arr1, arr2};
+
+	#[test]
+	fn assert_rel_close_passes() {
+		assert_rel_close!(
+			arr2(&[[1.0, 2.0], [3.0, 4.0]]),
+			arr2(&[[1.0, 2.0], [3.0, 4.0 + 1e-7]]),
+			1e-6
+		);
+		assert_rel_close!(arr1(&[0.5f32, 0.5]), arr0(0.5f32), ::std::f32::EPSILON);
+	}
+
+	#[test]
+	#[should_panic(
+		expected = "shape found [2, 2], shape expected [2, 2], worst mismatch at index [1, 0]: found 3.5, expected 3.0"
+	)]
+	fn assert_rel_close_reports_worst() {
+		assert_rel_close!(arr2(&[[1.0, 2.1], [3.5, 4.0]]), arr2(&[[1.0, 2.0], [3.0, 4.0]]), 1e-3);
+	}
+
+	#[test]
+	#[should_panic(expected = "tolerance 0.001")]
+	fn assert_rel_close_reports_tolerance() {
+		assert_rel_close!(arr1(&[1.0, 2.1]), arr1(&[1.0, 2.0]), 1e-3);
+	}
+
+	#[test]
+	#[should_panic(expected = "could not be broadcast")]
+	fn assert_rel_close_reports_shapes() {
+		assert_rel_close!(arr1(&[1.0, 2.0]), arr1(&[1.0, 2.0, 3.0]), 1e-3);
+	}
+
+	#[test]
+	fn nan_equal_nan() {
+		let nan = f32::NAN;
+		assert!(arr1(&[1.0, nan]).all_relatively_close_nan(&arr1(&[1.0, nan]), 1e-6, true));
+		assert!(!arr1(&[1.0, nan]).all_relatively_close_nan(&arr1(&[1.0, nan]), 1e-6, false));
+		assert!(!arr1(&[1.0, nan]).all_relatively_close(&arr1(&[1.0, nan]), 1e-6));
+		assert!(!arr1(&[1.0, nan]).all_relatively_close_nan(&arr1(&[1.0, 2.0]), 1e-6, true));
+		assert!(!arr1(&[1.0, 2.0]).all_relatively_close_nan(&arr1(&[1.0, nan]), 1e-6, true));
+		assert_rel_close!(arr1(&[1.0, nan]), arr1(&[1.0, nan]), 1e-6, equal_nan = true);
+	}
+
+	#[test]
+	fn finite_vs_inf() {
+		let inf = f32::INFINITY;
+		assert!(!arr1(&[1.0, 2.0]).all_relatively_close(&arr1(&[1.0, inf]), 1e-6));
+		assert!(!arr1(&[1.0, inf]).all_relatively_close(&arr1(&[1.0, 2.0]), 1e-6));
+		assert!(!arr1(&[1.0, -inf]).all_relatively_close(&arr1(&[1.0, inf]), 1e-6));
+		assert!(arr1(&[1.0, inf, -inf]).all_relatively_close(&arr1(&[1.0, inf, -inf]), 1e-6));
+	}
+
+	#[test]
+	#[should_panic(expected = "NaN mismatch at index [1]: found NaN, expected NaN")]
+	fn assert_rel_close_reports_nan() {
+		assert_rel_close!(arr1(&[1.0, f32::NAN]), arr1(&[1.0, f32::NAN]), 1e-6);
+	}
+
+	#[test]
+	#[should_panic(expected = "unexpected infinity at index [2]: found inf, expected 3.0")]
+	fn assert_rel_close_reports_inf_before_tolerance() {
+		assert_rel_close!(arr1(&[1.5, 2.0, f32::INFINITY]), arr1(&[1.0, 2.0, 3.0]), 1e-3);
+	}
+}
@@ -0,0 +1,93 @@
+//! Compares repeated backward passes of a small network with fresh allocations for every array, and with a
+//! `BufferPool` reusing the arrays of earlier passes.
+//!
+//! Before benchmarking, the number of arrays allocated over several pooled passes is printed, along with the number
+//! of arrays the passes require, all of which are allocated without the pool.
+//!
+//! `cargo bench --bench buffer_pool`
+use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use indexmap::{IndexMap, IndexSet};
+
+use alumina::{
+	core::exec::{BufferPool, ExecutionPlan},
+	core::grad::Grad,
+	core::graph::Node,
+	core::init::gaussian,
+	core::subgraph::{execution_subgraph, SubGraph},
+	ops::{elementwise::tanh::tanh, nn::matmul::matmul, reduce::reduce_sum::reduce_sum},
+};
+
+const PASSES: usize = 10;
+
+fn buffer_pool_benchmark(c: &mut Criterion) {
+	report_allocations();
+	c.bench_function("backward_fresh", |b| backward_bench(b, false));
+	c.bench_function("backward_pooled", |b| backward_bench(b, true));
+}
+
+/// Returns the gradients of a two layer network, and the subgraph which calculates them.
+fn network() -> (IndexSet<Node>, SubGraph) {
+	let input = Node::new(&[64, 256]).set_name("input").set_init(gaussian(0.0, 1.0));
+	let weights1 = Node::new(&[256, 256]).set_name("weights1").set_init(gaussian(0.0, 0.1));
+	let weights2 = Node::new(&[256, 16]).set_name("weights2").set_init(gaussian(0.0, 0.1));
+
+	let hidden = tanh(matmul(&input, &weights1).unwrap()).unwrap();
+	let loss = reduce_sum(tanh(matmul(&hidden, &weights2).unwrap()).unwrap(), &[], false).unwrap();
+
+	input.init_value();
+	weights1.init_value();
+	weights2.init_value();
+
+	let grads: IndexSet<Node> = Grad::of(&loss)
+		.wrt(&[&weights1, &weights2])
+		.build()
+		.unwrap()
+		.into_iter()
+		.map(|(_node, grad)| grad)
+		.collect();
+	let subgraph = execution_subgraph(&[] as &[&Node], &grads, false).unwrap();
+	(grads, subgraph)
+}
+
+fn report_allocations() {
+	let (grads, subgraph) = network();
+
+	let mut pool = BufferPool::new();
+	for _ in 0..PASSES {
+		let results = ExecutionPlan::new(IndexMap::<Node, _>::new(), &grads)
+			.subgraph(Some(&subgraph))
+			.buffer_pool(Some(&mut pool))
+			.execute()
+			.unwrap();
+		for (_node, value) in results {
+			pool.recycle(value);
+		}
+	}
+
+	println!(
+		"{} passes allocated {} arrays with the pool, and {} without",
+		PASSES,
+		pool.allocations(),
+		pool.allocations() + pool.reuses()
+	);
+}
+
+fn backward_bench(b: &mut Bencher<'_>, pooled: bool) {
+	let (grads, subgraph) = network();
+
+	let mut pool = BufferPool::new();
+	b.iter(|| {
+		let mut plan = ExecutionPlan::new(IndexMap::<Node, _>::new(), &grads).subgraph(Some(&subgraph));
+		if pooled {
+			let results = plan.buffer_pool(Some(&mut pool)).execute().unwrap();
+			for (_node, value) in results {
+				pool.recycle(value);
+			}
+		} else {
+			plan.execute().unwrap();
+		}
+	});
+}
+
+criterion_group!(benches, buffer_pool_benchmark);
+criterion_main!(benches);
@@ -1,14 +1,21 @@
-use criterion::{criterion_group, criterion_main, Bencher, Criterion};
-use indexmap::{indexset, IndexMap, IndexSet};
+use criterion::{criterion_group, criterion_main, BatchSize, Bencher, Criterion};
+use indexmap::{indexmap, indexset, IndexMap, IndexSet};
 
 use alumina::{
 	core::base_ops::{dummy::DummyOp, OpSpecification},
+	core::errors::GradientError,
 	core::exec::ExecutionPlan,
-	core::grad::Grad,
-	core::graph::{Node, NodeTag},
+	core::grad::{Grad, GradientContext},
+	core::graph::{Node, NodeID, NodeTag},
 	core::init::gaussian,
 	core::subgraph::{execution_subgraph, SubGraph},
-	ops::elementwise::relu::relu,
+	ops::elementwise::{
+		elementwise_single::{BinaryElementwise, BinaryFunc},
+		min::{MinBack, MinBackBoth},
+		mish::MishBack,
+		relu::relu,
+		silu::SiluBack,
+	},
 };
 
 fn elementwise_benchmark(c: &mut Criterion) {
@@ -19,6 +26,16 @@ fn elementwise_benchmark(c: &mut Criterion) {
 	// c.bench_function("backward_dummy_small", dummy_small_bench);
 	// c.bench_function("backward_dummy", dummy_bench);
 	c.bench_function("backward_relu", backward_relu_bench);
+
+	// backward Ops reusing the forward output, against recalculating the forward function from the input
+	c.bench_function("backward_silu", backward_silu_bench);
+	c.bench_function("backward_silu_recompute", backward_silu_recompute_bench);
+	c.bench_function("backward_mish", backward_mish_bench);
+	c.bench_function("backward_mish_recompute", backward_mish_recompute_bench);
+
+	// both min input grads in a single pass, against a pass for each input
+	c.bench_function("backward_min_two_pass", backward_min_two_pass_bench);
+	c.bench_function("backward_min_single_pass", backward_min_single_pass_bench);
 }
 
 /// Set value of all inputs using initialisers
@@ -112,5 +129,178 @@ fn backward_relu_bench(b: &mut Bencher<'_>) {
 	})
 }
 
+/// Execute a backward Op which takes the forward input, forward output, and output grad
+fn backward_reuse_bench<F>(b: &mut Bencher<'_>, build: F)
+where
+	F: FnOnce(&Node, &Node, &Node, &Node),
+{
+	let input = Node::new(&[1031, 1033]).set_name("input").set_init(gaussian(0.0, 1.0));
+	let output = Node::new(&[1031, 1033]).set_name("output").set_init(gaussian(0.0, 1.0));
+	let output_grad = Node::new(&[1031, 1033])
+		.set_name("output_grad")
+		.set_init(gaussian(0.0, 1.0));
+	let input_grad = Node::new(&[1031, 1033]).set_name("input_grad");
+	build(&input, &output, &output_grad, &input_grad);
+
+	let exec_subgraph = setup(&input_grad, indexset![&input, &output, &output_grad]);
+	b.iter(|| {
+		ExecutionPlan::new(IndexMap::<Node, _>::new(), indexset![input_grad.clone()])
+			.subgraph(Some(&exec_subgraph))
+			.execute()
+			.unwrap()
+	})
+}
+
+/// Execute a backward Op which takes only the forward input and output grad
+fn backward_recompute_bench<F: BinaryFunc + Default>(b: &mut Bencher<'_>) {
+	let input = Node::new(&[1031, 1033]).set_name("input").set_init(gaussian(0.0, 1.0));
+	let output_grad = Node::new(&[1031, 1033])
+		.set_name("output_grad")
+		.set_init(gaussian(0.0, 1.0));
+	let input_grad = Node::new(&[1031, 1033]).set_name("input_grad");
+	BinaryElementwise::<F>::new_default(&input, &output_grad, &input_grad)
+		.build()
+		.unwrap();
+
+	let exec_subgraph = setup(&input_grad, indexset![&input, &output_grad]);
+	b.iter(|| {
+		ExecutionPlan::new(IndexMap::<Node, _>::new(), indexset![input_grad.clone()])
+			.subgraph(Some(&exec_subgraph))
+			.execute()
+			.unwrap()
+	})
+}
+
+fn backward_silu_bench(b: &mut Bencher<'_>) {
+	backward_reuse_bench(b, |input, output, output_grad, input_grad| {
+		SiluBack::new_default(input, output, output_grad, input_grad)
+			.build()
+			.unwrap();
+	});
+}
+
+fn backward_silu_recompute_bench(b: &mut Bencher<'_>) {
+	backward_recompute_bench::<SiluRecomputeBackFunc>(b);
+}
+
+fn backward_mish_bench(b: &mut Bencher<'_>) {
+	backward_reuse_bench(b, |input, output, output_grad, input_grad| {
+		MishBack::new_default(input, output, output_grad, input_grad)
+			.build()
+			.unwrap();
+	});
+}
+
+fn backward_mish_recompute_bench(b: &mut Bencher<'_>) {
+	backward_recompute_bench::<MishRecomputeBackFunc>(b);
+}
+
+/// Execute backward Ops which take both inputs of min and the output grad, and produce the grads of both inputs
+///
+/// The output grad is supplied fresh for each iteration, as it would be by the forward pass, so that it can be taken
+/// without copying.
+fn backward_min_bench<F>(b: &mut Bencher<'_>, build: F)
+where
+	F: FnOnce(&Node, &Node, &Node, &Node, &Node),
+{
+	let input1 = Node::new(&[1024, 1024]).set_name("input1").set_init(gaussian(0.0, 1.0));
+	let input2 = Node::new(&[1024, 1024]).set_name("input2").set_init(gaussian(0.0, 1.0));
+	let output_grad = Node::new(&[1024, 1024])
+		.set_name("output_grad")
+		.set_init(gaussian(0.0, 1.0));
+	let input1_grad = Node::new(&[1024, 1024]).set_name("input1_grad");
+	let input2_grad = Node::new(&[1024, 1024]).set_name("input2_grad");
+	build(&input1, &input2, &output_grad, &input1_grad, &input2_grad);
+
+	input1.init_value();
+	input2.init_value();
+	let output_grad_value = output_grad.init_array().unwrap();
+	let exec_subgraph = execution_subgraph(&[&output_grad], &[&input1_grad, &input2_grad], false).unwrap();
+	b.iter_batched(
+		|| indexmap![output_grad.clone() => output_grad_value.to_shared()],
+		|inputs| {
+			ExecutionPlan::new(inputs, indexset![input1_grad.clone(), input2_grad.clone()])
+				.subgraph(Some(&exec_subgraph))
+				.execute()
+				.unwrap()
+		},
+		BatchSize::LargeInput,
+	)
+}
+
+fn backward_min_two_pass_bench(b: &mut Bencher<'_>) {
+	backward_min_bench(b, |input1, input2, output_grad, input1_grad, input2_grad| {
+		MinBack::new_default(input1, input2, output_grad, input1_grad)
+			.build()
+			.unwrap();
+		MinBack::new_default(input2, input1, output_grad, input2_grad)
+			.build()
+			.unwrap();
+	});
+}
+
+fn backward_min_single_pass_bench(b: &mut Bencher<'_>) {
+	backward_min_bench(b, |input1, input2, output_grad, input1_grad, input2_grad| {
+		MinBackBoth::new_default(input1, input2, output_grad, input1_grad, input2_grad)
+			.build()
+			.unwrap();
+	});
+}
+
+/// input1 = input of silu
+/// input2 = grad of output of silu
+#[derive(Clone, Debug, Default)]
+struct SiluRecomputeBackFunc {}
+
+impl BinaryFunc for SiluRecomputeBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		let sigmoid = 1.0 / (1.0 + (-input1).exp());
+		input2 * sigmoid * (1.0 + input1 * (1.0 - sigmoid))
+	}
+
+	fn type_name(&self) -> &'static str {
+		"SiluRecomputeBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
+/// input1 = input of mish
+/// input2 = grad of output of mish
+#[derive(Clone, Debug, Default)]
+struct MishRecomputeBackFunc {}
+
+impl BinaryFunc for MishRecomputeBackFunc {
+	#[inline]
+	fn calc(&self, input1: f32, input2: f32) -> f32 {
+		let t = (input1.max(0.0) + (-input1.abs()).exp().ln_1p()).tanh();
+		let sigmoid = 1.0 / (1.0 + (-input1).exp());
+		input2 * (t + input1 * (1.0 - t * t) * sigmoid)
+	}
+
+	fn type_name(&self) -> &'static str {
+		"MishRecomputeBackward"
+	}
+
+	fn grad(
+		&self,
+		_ctx: &mut GradientContext,
+		_input1: &NodeID,
+		_input2: &NodeID,
+		_output: &NodeID,
+	) -> Result<(), GradientError> {
+		Err(GradientError::Unimplemented)
+	}
+}
+
 criterion_group!(benches, elementwise_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Compares the cost of building a graph with many Ops, with and without generating unique names for each output node
+//! and Op.
+//!
+//! `cargo bench --bench graph_build`
+use criterion::{criterion_group, criterion_main, BatchSize, Bencher, Criterion};
+
+use alumina::{
+	core::graph::Node,
+	ops::elementwise::min::{min, min_unnamed},
+};
+
+const OPS: usize = 10_000;
+
+fn graph_build_benchmark(c: &mut Criterion) {
+	let mut group = c.benchmark_group("graph_build");
+	group.sample_size(10);
+	group.bench_function("build_min_named_10k", build_min_named_bench);
+	group.bench_function("build_min_unnamed_10k", build_min_unnamed_bench);
+	group.finish();
+}
+
+fn inputs() -> (Node, Node) {
+	let input1 = Node::new(&[16]).set_name("input1");
+	let input2 = Node::new(&[16]).set_name("input2");
+	(input1, input2)
+}
+
+fn build_min_named_bench(b: &mut Bencher<'_>) {
+	b.iter_batched(
+		inputs,
+		|(input1, input2)| {
+			for _ in 0..OPS {
+				min(&input1, &input2).unwrap();
+			}
+			input1
+		},
+		BatchSize::PerIteration,
+	)
+}
+
+fn build_min_unnamed_bench(b: &mut Bencher<'_>) {
+	b.iter_batched(
+		inputs,
+		|(input1, input2)| {
+			for _ in 0..OPS {
+				min_unnamed(&input1, &input2).unwrap();
+			}
+			input1
+		},
+		BatchSize::PerIteration,
+	)
+}
+
+criterion_group!(benches, graph_build_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! Compares the scalar and SIMD inner loops of `MulDiv`, which are selected by the `simd` feature.
+//!
+//! Save the scalar results as a baseline, then compare the SIMD build against it:
+//!
+//! `cargo bench --bench muldiv -- --save-baseline scalar`
+//!
+//! `cargo +nightly bench --features simd --bench muldiv -- --baseline scalar`
+use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use indexmap::{indexset, IndexMap};
+
+use alumina::{
+	core::exec::ExecutionPlan, core::graph::Node, core::init::gaussian, core::subgraph::execution_subgraph,
+	ops::math::muldiv::muldiv,
+};
+
+fn muldiv_benchmark(c: &mut Criterion) {
+	c.bench_function("forward_muldiv", muldiv_bench);
+}
+
+fn muldiv_bench(b: &mut Bencher<'_>) {
+	let input = Node::new(&[256, 1024]).set_name("input").set_init(gaussian(0.0, 1.0));
+	let output = muldiv(&input).unwrap();
+
+	input.init_value();
+	let exec_subgraph = execution_subgraph(&[] as &[&Node], &[&output], false).unwrap();
+	b.iter(|| {
+		ExecutionPlan::new(IndexMap::<Node, _>::new(), indexset![output.clone()])
+			.subgraph(Some(&exec_subgraph))
+			.execute()
+			.unwrap()
+	})
+}
+
+criterion_group!(benches, muldiv_benchmark);
+criterion_main!(benches);
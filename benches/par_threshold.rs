@@ -0,0 +1,44 @@
+//! Compares execution of `Min` and `MulDiv` on tiny arrays with every Op split over the rayon thread pool, and with
+//! the default `par_threshold()` running them serially on the calling thread.
+//!
+//! `cargo bench --bench par_threshold`
+use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use indexmap::{indexset, IndexMap};
+
+use alumina::{
+	core::exec::{ExecutionPlan, DEFAULT_PAR_THRESHOLD},
+	core::graph::Node,
+	core::init::gaussian,
+	core::subgraph::execution_subgraph,
+	ops::{elementwise::min::min, math::muldiv::muldiv},
+};
+
+fn par_threshold_benchmark(c: &mut Criterion) {
+	c.bench_function("tiny_always_parallel", |b| tiny_bench(b, 0));
+	c.bench_function("tiny_default_threshold", |b| tiny_bench(b, DEFAULT_PAR_THRESHOLD));
+}
+
+fn tiny_bench(b: &mut Bencher<'_>, par_threshold: usize) {
+	let input1 = Node::new(&[4, 16]).set_name("input1").set_init(gaussian(0.0, 1.0));
+	let input2 = Node::new(&[4, 16]).set_name("input2").set_init(gaussian(0.0, 1.0));
+
+	// a chain of small Ops, as found in the scalar parts of a loss or optimiser graph
+	let mut output = min(&input1, &input2).unwrap();
+	for _ in 0..16 {
+		output = min(muldiv(&output).unwrap(), &input2).unwrap();
+	}
+
+	input1.init_value();
+	input2.init_value();
+	let exec_subgraph = execution_subgraph(&[] as &[&Node], &[&output], false).unwrap();
+	b.iter(|| {
+		ExecutionPlan::new(IndexMap::<Node, _>::new(), indexset![output.clone()])
+			.subgraph(Some(&exec_subgraph))
+			.par_threshold(Some(par_threshold))
+			.execute()
+			.unwrap()
+	})
+}
+
+criterion_group!(benches, par_threshold_benchmark);
+criterion_main!(benches);
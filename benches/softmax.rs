@@ -0,0 +1,33 @@
+//! Compares the default and online forward passes of `Softmax` on a long axis.
+//!
+//! `cargo bench --bench softmax`
+use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use indexmap::{indexset, IndexMap};
+
+use alumina::{
+	core::base_ops::OpSpecification, core::exec::ExecutionPlan, core::graph::Node, core::init::gaussian,
+	core::subgraph::execution_subgraph, ops::nn::softmax::Softmax,
+};
+
+fn softmax_benchmark(c: &mut Criterion) {
+	c.bench_function("forward_softmax_long_axis", |b| softmax_bench(b, false));
+	c.bench_function("forward_softmax_long_axis_online", |b| softmax_bench(b, true));
+}
+
+fn softmax_bench(b: &mut Bencher<'_>, online: bool) {
+	let logits = Node::new(&[8, 1 << 20]).set_name("logits").set_init(gaussian(0.0, 1.0));
+	let output = Node::new(&[8, 1 << 20]).set_name("output");
+	Softmax::new(&logits, &output, 1).online(online).build().unwrap();
+
+	logits.init_value();
+	let exec_subgraph = execution_subgraph(&[] as &[&Node], &[&output], false).unwrap();
+	b.iter(|| {
+		ExecutionPlan::new(IndexMap::<Node, _>::new(), indexset![output.clone()])
+			.subgraph(Some(&exec_subgraph))
+			.execute()
+			.unwrap()
+	})
+}
+
+criterion_group!(benches, softmax_benchmark);
+criterion_main!(benches);
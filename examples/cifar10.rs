@@ -6,8 +6,8 @@ use alumina::{
 	ops::{
 		nn::conv::Padding,
 		panicking::{
-			add_n, argmax, avg_pool, conv, equal, ibias, l2, leaky_relu, linear, reduce_mean, reduce_sum, scale,
-			softmax_cross_entropy,
+			add_n, argmax, avg_pool, conv, equal, ibias, l2, leaky_relu_with_slope, linear, reduce_mean, reduce_sum,
+			scale, softmax_cross_entropy,
 		},
 	},
 	opt::{adam::Adam, every_n_steps, max_steps, nth_step, print_step_data, GradientOptimiser},
@@ -24,18 +24,19 @@ fn main() -> Result<(), Error> {
 	let input = Node::new(&[-1, 32, 32, 3]).set_name("input");
 	let labels = Node::new(&[-1, 10]).set_name("labels");
 
-	let layer1 = leaky_relu(ibias(conv(&input, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer1");
-	let layer2 = leaky_relu(ibias(conv(layer1, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer2");
-	let layer3 = leaky_relu(ibias(conv(layer2, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer3");
-	let layer4 = leaky_relu(ibias(conv(layer3, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer4");
+	let layer1 = leaky_relu_with_slope(ibias(conv(&input, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer1");
+	let layer2 = leaky_relu_with_slope(ibias(conv(layer1, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer2");
+	let layer3 = leaky_relu_with_slope(ibias(conv(layer2, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer3");
+	let layer4 = leaky_relu_with_slope(ibias(conv(layer3, 32, &[3, 3], Padding::Valid), &[-1]), 0.1).set_name("layer4");
 	let pool1 = avg_pool(&layer4, &[1, 2, 2, 1]).set_name("pool1");
-	let layer5 = leaky_relu(ibias(conv(pool1, 64, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer5");
-	let layer6 = leaky_relu(ibias(conv(layer5, 64, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer6");
-	let layer7 = leaky_relu(ibias(conv(layer6, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer7");
+	let layer5 = leaky_relu_with_slope(ibias(conv(pool1, 64, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer5");
+	let layer6 = leaky_relu_with_slope(ibias(conv(layer5, 64, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer6");
+	let layer7 = leaky_relu_with_slope(ibias(conv(layer6, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer7");
 	let pool2 = avg_pool(&layer7, &[1, 2, 2, 1]).set_name("pool2");
-	let layer8 = leaky_relu(ibias(conv(pool2, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer8");
-	let layer9 = leaky_relu(ibias(conv(layer8, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer9");
-	let layer10 = leaky_relu(ibias(conv(layer9, 256, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer10");
+	let layer8 = leaky_relu_with_slope(ibias(conv(pool2, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer8");
+	let layer9 = leaky_relu_with_slope(ibias(conv(layer8, 128, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer9");
+	let layer10 =
+		leaky_relu_with_slope(ibias(conv(layer9, 256, &[3, 3], Padding::Same), &[-1]), 0.1).set_name("layer10");
 
 	let logits = add_n(&[
 		linear(reduce_mean(layer4, &[1, 2], false), 10, msra(1.0)),